@@ -0,0 +1,26 @@
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    // headers of the RocksDB sources built by librocksdb-sys
+    let rocksdb_dir = PathBuf::from(
+        env::var("DEP_ROCKSDB_CARGO_MANIFEST_DIR").expect("librocksdb-sys must be a dependency"),
+    )
+    .join("rocksdb");
+    let mut build = cc::Build::new();
+    build
+        .cpp(true)
+        .file("src/event_listener.cc")
+        .file("src/options_ext.cc")
+        .include(rocksdb_dir.join("include"))
+        .include(&rocksdb_dir);
+    if build.get_compiler().is_like_msvc() {
+        build.flag("/std:c++17").flag("/GR-");
+    } else {
+        // RocksDB is built without RTTI
+        build.flag("-std=c++17").flag("-fno-rtti");
+    }
+    build.compile("rocksdict_ext");
+    println!("cargo:rerun-if-changed=src/event_listener.cc");
+    println!("cargo:rerun-if-changed=src/options_ext.cc");
+}
@@ -8,6 +8,7 @@ __all__ = ["Rdict",
            "AccessType",
            "WriteOptions",
            "Snapshot",
+           "ConsistentView",
            "RdictIter",
            "Options",
            "ReadOptions",
@@ -34,8 +35,27 @@ __all__ = ["Rdict",
            "BottommostLevelCompaction",
            "KeyEncodingType",
            "DbClosedError",
+           "DbFrozenError",
+           "RocksDBError",
+           "CorruptionError",
+           "BusyError",
+           "TransactionConflictError",
+           "TryAgainError",
+           "TimedOutError",
+           "NotSupportedError",
+           "IOErrorNoSpace",
            "WriteBufferManager",
-           "Checkpoint"]
+           "Checkpoint",
+           "RetryPolicy",
+           "TransactionDb",
+           "TransactionDbOptions",
+           "OptimisticTransactionDb",
+           "Transaction",
+           "copy_range",
+           "split",
+           "merge_dbs",
+           "migrate_key_codec",
+           "build_info"]
 
 Rdict.__enter__ = lambda self: self
 Rdict.__exit__ = lambda self, exc_type, exc_val, exc_tb: self.close()
@@ -0,0 +1,87 @@
+"""Fixed-width integer key helpers for raw mode.
+
+In raw mode, keys are compared as bytes. Integers encoded with these
+helpers sort in numeric order, so range scans over integer keys
+work as expected.
+
+Example:
+    ::
+
+        from rocksdict import Rdict, Options
+        from rocksdict.keys import u64_be, from_u64_be
+
+        db = Rdict("./temp_int_keys", Options(raw_mode=True))
+        for i in [3, 1, 256, 2]:
+            db[u64_be(i)] = b""
+        assert [from_u64_be(k) for k in db.keys()] == [1, 2, 3, 256]
+        db.close()
+        Rdict.destroy("./temp_int_keys")
+"""
+
+import struct
+
+__all__ = ["u32_be",
+           "u64_be",
+           "i32_be",
+           "i64_be",
+           "from_u32_be",
+           "from_u64_be",
+           "from_i32_be",
+           "from_i64_be"]
+
+_U32 = struct.Struct(">I")
+_U64 = struct.Struct(">Q")
+_I32_SIGN = 1 << 31
+_I64_SIGN = 1 << 63
+
+
+def u32_be(i: int) -> bytes:
+    """Encode an unsigned integer in `[0, 2**32)` as 4 big-endian bytes."""
+    return _U32.pack(i)
+
+
+def u64_be(i: int) -> bytes:
+    """Encode an unsigned integer in `[0, 2**64)` as 8 big-endian bytes."""
+    return _U64.pack(i)
+
+
+def i32_be(i: int) -> bytes:
+    """Encode a signed integer in `[-2**31, 2**31)` as 4 bytes.
+
+    The sign bit is flipped so that negative numbers sort before
+    positive numbers in bytewise order.
+    """
+    if not -_I32_SIGN <= i < _I32_SIGN:
+        raise struct.error("i32_be argument out of range")
+    return _U32.pack(i + _I32_SIGN)
+
+
+def i64_be(i: int) -> bytes:
+    """Encode a signed integer in `[-2**63, 2**63)` as 8 bytes.
+
+    The sign bit is flipped so that negative numbers sort before
+    positive numbers in bytewise order.
+    """
+    if not -_I64_SIGN <= i < _I64_SIGN:
+        raise struct.error("i64_be argument out of range")
+    return _U64.pack(i + _I64_SIGN)
+
+
+def from_u32_be(b: bytes) -> int:
+    """Decode bytes produced by `u32_be`."""
+    return _U32.unpack(b)[0]
+
+
+def from_u64_be(b: bytes) -> int:
+    """Decode bytes produced by `u64_be`."""
+    return _U64.unpack(b)[0]
+
+
+def from_i32_be(b: bytes) -> int:
+    """Decode bytes produced by `i32_be`."""
+    return _U32.unpack(b)[0] - _I32_SIGN
+
+
+def from_i64_be(b: bytes) -> int:
+    """Decode bytes produced by `i64_be`."""
+    return _U64.unpack(b)[0] - _I64_SIGN
@@ -1,4 +1,5 @@
-from typing import Any, Union, List, Iterator, Tuple, Dict, overload, Callable
+import os
+from typing import Any, Union, List, Iterable, Iterator, Tuple, Dict, overload, Callable
 
 __all__ = ["Rdict",
            "RdictIter",
@@ -28,12 +29,32 @@ __all__ = ["Rdict",
            "ColumnFamily",
            "AccessType",
            "Snapshot",
+           "ConsistentView",
            "CompactOptions",
            "BottommostLevelCompaction",
            "KeyEncodingType",
            "DbClosedError",
+           "DbFrozenError",
+           "RocksDBError",
+           "CorruptionError",
+           "BusyError",
+           "TransactionConflictError",
+           "TryAgainError",
+           "TimedOutError",
+           "NotSupportedError",
+           "IOErrorNoSpace",
            "WriteBufferManager",
-           "Checkpoint"]
+           "Checkpoint",
+           "RetryPolicy",
+           "TransactionDb",
+           "TransactionDbOptions",
+           "OptimisticTransactionDb",
+           "Transaction",
+           "copy_range",
+           "split",
+           "merge_dbs",
+           "migrate_key_codec",
+           "build_info"]
 
 class DataBlockIndexType:
     @staticmethod
@@ -171,8 +192,15 @@ class MemtableFactory:
 
 class Options:
     def __init__(self, raw_mode: bool = False) -> None: ...
+    def validate(self) -> None: ...
+    def set_value_checksums(self, enabled: bool) -> None: ...
+    def set_key_codec(self, key_codec: str) -> None: ...
+    def set_strict_types(self, enabled: bool) -> None: ...
+    def set_version_history(self, max_versions: int) -> None: ...
     @staticmethod
     def load_latest(path: str, env: Env = Env(), ignore_unknown_options: bool = False, cache: Cache = Cache(8 * 1024 * 1024)) -> Tuple[Options, Dict[str, Options]]: ...
+    @staticmethod
+    def load_from_file(options_file: str, env: Env = Env(), ignore_unknown_options: bool = False, cache: Cache = Cache(8 * 1024 * 1024)) -> Tuple[Options, Dict[str, Options]]: ...
     def create_if_missing(self, create_if_missing: bool) -> None: ...
     def create_missing_column_families(self, create_missing_cfs: bool) -> None: ...
     def enable_statistics(self) -> None: ...
@@ -194,7 +222,9 @@ class Options:
     def set_compaction_readahead_size(self, compaction_readahead_size: int) -> None: ...
     def set_compaction_style(self, style: DBCompactionStyle) -> None: ...
     def set_compression_options(self, w_bits: int, level: int, strategy: int, max_dict_bytes: int) -> None: ...
-    def set_compression_per_level(self,level_types: list) -> None: ...
+    def set_compression_per_level(self, level_types: List[DBCompressionType]) -> None: ...
+    @staticmethod
+    def compression_supported(t: DBCompressionType) -> bool: ...
     def set_compression_type(self, t: DBCompressionType) -> None: ...
     def set_cuckoo_table_factory(self, factory: CuckooTableOptions) -> None: ...
     def set_db_log_dir(self, path: str) -> None: ...
@@ -209,6 +239,7 @@ class Options:
     def set_error_if_exists(self, enabled: bool) -> None: ...
     def set_fifo_compaction_options(self, fco: FifoCompactOptions) -> None: ...
     def set_hard_pending_compaction_bytes_limit(self, limit: int) -> None: ...
+    def set_delayed_write_rate(self, rate: int) -> None: ...
     def set_inplace_update_locks(self, num: int) -> None: ...
     def set_inplace_update_support(self, enabled: bool) -> None: ...
     def set_is_fd_close_on_exec(self, enabled: bool) -> None: ...
@@ -244,6 +275,8 @@ class Options:
     def set_num_levels(self, n: int) -> None: ...
     def set_optimize_filters_for_hits(self, optimize_for_hits: bool) -> None: ...
     def set_paranoid_checks(self, enabled: bool) -> None: ...
+    def set_best_efforts_recovery(self, enabled: bool) -> None: ...
+    def set_file_checksum_gen_factory(self, name: Union[str, None]) -> None: ...
     def set_plain_table_factory(self, options: PlainTableFactoryOptions) -> None: ...
     def set_prefix_extractor(self, prefix_extractor: SliceTransform) -> None: ...
     def set_ratelimiter(self, rate_bytes_per_sec: int, refill_period_us: int, fairness: int) -> None: ...
@@ -255,6 +288,7 @@ class Options:
     def set_soft_pending_compaction_bytes_limit(self, limit: int) -> None: ...
     def set_stats_dump_period_sec(self, period: int) -> None: ...
     def set_stats_persist_period_sec(self, period: int) -> None: ...
+    def set_persist_stats_to_disk(self, enabled: bool) -> None: ...
     def set_table_cache_num_shard_bits(self, nbits: int) -> None: ...
     def set_target_file_size_base(self, size: int) -> None: ...
     def set_target_file_size_multiplier(self, multiplier: int) -> None: ...
@@ -390,10 +424,14 @@ class Rdict:
     def __init__(self, path: str,
                  options: Union[Options, None] = None,
                  column_families: Union[Dict[str, Options], None] = None,
-                 access_type: AccessType = AccessType.read_write()) -> None: ...
+                 access_type: AccessType = AccessType.read_write(),
+                 retry_policy: Union[RetryPolicy, None] = None,
+                 skip_wal_recovery: bool = False) -> None: ...
     def __enter__(self) -> Rdict: ...
     def set_dumps(self, dumps: Callable[[Any], bytes]) -> None: ...
     def set_loads(self, dumps: Callable[[bytes], Any]) -> None: ...
+    def set_value_encryption(self, key: Union[bytes, None]) -> None: ...
+    def set_value_compression(self, threshold: Union[int, None] = 1024, level: int = 3) -> None: ...
     def set_read_options(self, read_opt: ReadOptions) -> None: ...
     def set_write_options(self, write_opt: WriteOptions) -> None: ...
     def __contains__(self, key: Union[str, int, float, bytes, bool]) -> bool: ...
@@ -403,7 +441,17 @@ class Rdict:
     def get(self,
             key: Union[str, int, float, bytes, bool, List[Union[str, int, float, bytes, bool]]],
             default: Any = None,
-            read_opt: Union[ReadOptions, None] = None) -> Any | None: ...
+            read_opt: Union[ReadOptions, None] = None,
+            sorted_input: bool = False,
+            raw: bool = False) -> Any | None: ...
+    def get_into(self,
+                 key: Union[str, int, float, bytes, bool],
+                 buffer: Any,
+                 read_opt: Union[ReadOptions, None] = None) -> int | None: ...
+    def put_numpy(self,
+                  keys: Any,
+                  values: Any,
+                  write_opt: Union[WriteOptions, None] = None) -> int: ...
     def get_entity(self,
                    key: Union[str, int, float, bytes, bool, List[Union[str, int, float, bytes, bool]]],
                    default: Any = None,
@@ -418,78 +466,273 @@ class Rdict:
                    values: List[Any],
                    write_opt: Union[WriteOptions, None] = None) -> None: ...
     def delete(self, key: Union[str, int, float, bytes, bool], write_opt: Union[WriteOptions, None] = None) -> None: ...
+    def update_atomic(self,
+                      key: Union[str, int, float, bytes, bool],
+                      func: Callable[[Any], Any],
+                      default: Any = None,
+                      write_opt: Union[WriteOptions, None] = None) -> Any: ...
+    def put_if_absent(self,
+                      key: Union[str, int, float, bytes, bool],
+                      value: Any,
+                      write_opt: Union[WriteOptions, None] = None) -> bool: ...
+    def compare_and_set(self,
+                        key: Union[str, int, float, bytes, bool],
+                        expected: Any,
+                        new: Any,
+                        write_opt: Union[WriteOptions, None] = None) -> bool: ...
+    def delete_if(self,
+                  key: Union[str, int, float, bytes, bool],
+                  expected: Any,
+                  write_opt: Union[WriteOptions, None] = None) -> bool: ...
+    def wait_for(self, key: Union[str, int, float, bytes, bool], timeout: Union[float, None] = None) -> Any: ...
     def key_may_exist(self,
                       key: Union[str, int, float, bytes, bool],
                       fetch: bool = False,
                       read_opt = None) -> Union[bool, Tuple[bool, Any]]: ...
     def iter(self, read_opt: Union[ReadOptions, None] = None) -> RdictIter: ...
+    def pooled_iter(self) -> RdictIter: ...
     def items(self, backwards: bool = False,
               from_key: Union[str, int, float, bytes, bool, None] = None,
-              read_opt: Union[ReadOptions, None] = None) -> RdictItems: ...
+              read_opt: Union[ReadOptions, None] = None,
+              skip: int = 0,
+              limit: Union[int, None] = None,
+              start: Union[str, int, float, bytes, bool, None] = None,
+              stop: Union[str, int, float, bytes, bool, None] = None,
+              inclusive: Tuple[bool, bool] = (True, False),
+              raw: bool = False) -> RdictItems: ...
     def keys(self, backwards: bool = False,
              from_key: Union[str, int, float, bytes, bool, None] = None,
-             read_opt: Union[ReadOptions, None] = None) -> RdictKeys: ...
+             read_opt: Union[ReadOptions, None] = None,
+             skip: int = 0,
+             limit: Union[int, None] = None,
+             start: Union[str, int, float, bytes, bool, None] = None,
+             stop: Union[str, int, float, bytes, bool, None] = None,
+             inclusive: Tuple[bool, bool] = (True, False)) -> RdictKeys: ...
     def values(self, backwards: bool = False,
                from_key: Union[str, int, float, bytes, bool, None] = None,
-               read_opt: Union[ReadOptions, None] = None) -> RdictValues: ...
+               read_opt: Union[ReadOptions, None] = None,
+               skip: int = 0,
+               limit: Union[int, None] = None,
+               start: Union[str, int, float, bytes, bool, None] = None,
+               stop: Union[str, int, float, bytes, bool, None] = None,
+               inclusive: Tuple[bool, bool] = (True, False)) -> RdictValues: ...
     def columns(self, backwards: bool = False,
                 from_key: Union[str, int, float, bytes, bool, None] = None,
-                read_opt: Union[ReadOptions, None] = None) -> RdictColumns: ...
+                read_opt: Union[ReadOptions, None] = None,
+                skip: int = 0,
+                limit: Union[int, None] = None) -> RdictColumns: ...
     def entities(self, backwards: bool = False,
                 from_key: Union[str, int, float, bytes, bool, None] = None,
-                read_opt: Union[ReadOptions, None] = None) -> RdictEntities: ...
-    def ingest_external_file(self, paths: List[str], opts: IngestExternalFileOptions = IngestExternalFileOptions()) -> None: ...
+                read_opt: Union[ReadOptions, None] = None,
+                skip: int = 0,
+                limit: Union[int, None] = None) -> RdictEntities: ...
+    def first(self, read_opt: Union[ReadOptions, None] = None) -> Union[Tuple[Any, Any], None]: ...
+    def last(self, read_opt: Union[ReadOptions, None] = None) -> Union[Tuple[Any, Any], None]: ...
+    def peekitem(self, index: int = -1, read_opt: Union[ReadOptions, None] = None) -> Tuple[Any, Any]: ...
+    def ingest_external_file(self,
+                             paths: Union[List[str], Dict[str, List[str]]],
+                             opts: IngestExternalFileOptions = IngestExternalFileOptions(),
+                             progress: Union[Callable[[int, int], None], None] = None,
+                             chunk_size: Union[int, None] = None) -> List[Dict[str, Any]]: ...
+    def ingest_from(self,
+                    src: Rdict,
+                    begin: Any = None,
+                    end: Any = None,
+                    file_size: int = 64 * 1024 * 1024) -> int: ...
     def get_column_family(self, name: str) -> Rdict: ...
     def get_column_family_handle(self, name: str) -> ColumnFamily: ...
     def drop_column_family(self, name: str) -> None: ...
     def create_column_family(self, name: str, options: Options = Options()) -> Rdict: ...
     def write(self, write_batch: WriteBatch, write_opt: Union[WriteOptions, None] = None) -> None: ...
     def delete_range(self,
-                     begin: Union[str, int, float, bytes, bool],
-                     end: Union[str, int, float, bytes, bool],
+                     begin: Union[str, int, float, bytes, bool, None],
+                     end: Union[str, int, float, bytes, bool, None],
                      write_opt: Union[WriteOptions, None] = None) -> None: ...
+    def truncate_range(self,
+                       begin: Union[str, int, float, bytes, bool],
+                       end: Union[str, int, float, bytes, bool],
+                       compact: bool = True,
+                       write_opt: Union[WriteOptions, None] = None) -> int: ...
+    def count_range(self,
+                    begin: Union[str, int, float, bytes, bool, None] = None,
+                    end: Union[str, int, float, bytes, bool, None] = None,
+                    read_opt: Union[ReadOptions, None] = None) -> int: ...
+    def verify_values(self,
+                      begin: Union[str, int, float, bytes, bool, None] = None,
+                      end: Union[str, int, float, bytes, bool, None] = None) -> Dict[str, Any]: ...
+    def map_values(self,
+                   func: Callable[[Any], Any],
+                   begin: Union[str, int, float, bytes, bool, None] = None,
+                   end: Union[str, int, float, bytes, bool, None] = None,
+                   batch_size: int = 1000,
+                   write_opt: Union[WriteOptions, None] = None) -> int: ...
+    def delete_where(self,
+                     predicate: Union[Callable[[Any, Any], bool], None] = None,
+                     key_prefix: Union[str, bytes, None] = None,
+                     value_filter: Any = None,
+                     batch_size: int = 1000,
+                     write_opt: Union[WriteOptions, None] = None) -> int: ...
+    def atomic(self) -> RdictAtomic: ...
+    def buffered_writer(self,
+                        max_batch: int = 10000,
+                        max_bytes: int = 16777216,
+                        flush_interval: float = 0.1,
+                        write_opt: Union[WriteOptions, None] = None) -> RdictBufferedWriter: ...
     def snapshot(self) -> Snapshot: ...
+    def consistent_view(self) -> ConsistentView: ...
     def path(self) -> str: ...
-    def set_options(self, options: Dict[str, str]) -> None: ...
+    def set_options(self, options: Dict[str, str]) -> Dict[str, Union[str, None]]: ...
+    def set_cf_option_int(self, name: str, value: int) -> Union[int, None]: ...
+    def set_cf_option_float(self, name: str, value: float) -> Union[float, None]: ...
+    def set_cf_option_bool(self, name: str, value: bool) -> Union[bool, None]: ...
+    def get_options(self) -> Dict[str, str]: ...
     def property_value(self, name: str) -> Union[str, None]: ...
     def property_int_value(self, name: str) -> Union[int, None]: ...
     def latest_sequence_number(self) -> int: ...
+    def latest_sequence_for_key(self, key: Union[str, int, float, bytes, bool], seq_number: int) -> Union[int, None]: ...
+    def ship_updates_since(self, seq_number: int) -> RdictUpdates: ...
+    def apply_updates(self,
+                      updates: Iterable[Tuple[int, bytes]],
+                      write_opt: Union[WriteOptions, None] = None) -> Union[int, None]: ...
+    def apply_batch_bytes(self, data: bytes, write_opt: Union[WriteOptions, None] = None) -> None: ...
     def live_files(self) -> List[Dict[str, Any]]: ...
+    def recent_jobs(self, limit: int = 100) -> List[Dict[str, Any]]: ...
+    def statistics(self) -> Union[str, None]: ...
+    def stats_history(self,
+                      start_time: int = 0,
+                      end_time: Union[int, None] = None) -> Dict[int, Dict[str, int]]: ...
+    def io_stats(self) -> Dict[str, Any]: ...
+    def file_handle_stats(self) -> Dict[str, Any]: ...
+    def enable_latency_tracking(self) -> None: ...
+    def disable_latency_tracking(self) -> None: ...
+    def latency_stats(self, percentiles: List[float] = [50.0, 95.0, 99.0]) -> Dict[str, Dict[str, Any]]: ...
+    def get_live_files_checksums(self) -> Dict[str, str]: ...
+    @staticmethod
+    def verify_file_checksums(path: str, checksums: Dict[str, str]) -> None: ...
+    def get_sorted_wal_files(self) -> List[Dict[str, Any]]: ...
+    def purge_wal_files(self, up_to_seq: int) -> List[Dict[str, Any]]: ...
+    def table_properties(self, level: Union[int, None] = None) -> Dict[str, Union[int, float, str]]: ...
+    def write_stall_info(self) -> Dict[str, Union[int, bool]]: ...
+    def count_prefixes(self, prefix_len: int) -> Dict[bytes, int]: ...
+    def prefetch_range(self,
+                       begin: Union[str, int, float, bytes, bool, None],
+                       end: Union[str, int, float, bytes, bool, None],
+                       what: str = "data",
+                       max_bytes_per_sec: Union[int, None] = None) -> Dict[str, int]: ...
+    def clone_to(self,
+                 dst_path: str,
+                 options: Union[Options, None] = None,
+                 column_families: Union[Dict[str, Options], None] = None) -> None: ...
+    def export_archive(self, archive: str) -> None: ...
+    @staticmethod
+    def import_archive(archive: str, dst_path: str) -> None: ...
     def compact_range(self, begin: Union[str, int, float, bytes, bool, None],
                       end: Union[str, int, float, bytes, bool, None],
                       compact_opt: CompactOptions = CompactOptions()) -> None: ...
+    def compact_all(self, compact_opt: Union[CompactOptions, None] = None,
+                    wait: bool = True, parallel: int = 1) -> List[str]: ...
     def try_catch_up_with_primary(self) -> None: ...
     def cancel_all_background(self, wait: bool) -> None: ...
-    def close(self) -> None: ...
+    def close(self, force: bool = False) -> None: ...
+    def reopen(self,
+               options: Union[Options, None] = None,
+               access_type: Union[AccessType, None] = None) -> None: ...
+    def open_handles(self) -> Dict[str, int]: ...
     def __exit__(self, exc_type, exc_val, exc_tb) -> None: ...
     def flush(self, wait: bool = True) -> None: ...
+    def flush_all(self, wait: bool = True) -> List[str]: ...
     def flush_wal(self, sync: bool = True) -> None: ...
+    def freeze(self) -> None: ...
+    def unfreeze(self) -> None: ...
+    def is_frozen(self) -> bool: ...
+    def set_auto_flush(self, interval_secs: Union[float, None], flush_wal: bool = False) -> None: ...
+    @staticmethod
+    def temp(prefix: Union[str, None] = None,
+             cleanup: bool = True,
+             options: Union[Options, None] = None) -> Rdict: ...
+    @staticmethod
+    def from_uri(uri: Union[str, None] = None) -> Rdict: ...
+    @staticmethod
+    def open_checkpoint(checkpoint_path: str, read_only: bool = True) -> Rdict: ...
     @staticmethod
-    def destroy(path: str, options: Options = Options()) -> None: ...
+    def open_external(path: str,
+                      raw_mode: bool = True,
+                      options: Union[Options, None] = None,
+                      column_families: Union[Dict[str, Options], None] = None,
+                      access_type: AccessType = AccessType.read_only()) -> Rdict: ...
     @staticmethod
-    def repair(path: str, options: Options = Options()) -> None: ...
+    def destroy(path: str,
+                options: Options = Options(),
+                dry_run: bool = False,
+                column_family: Union[str, None] = None) -> Union[List[str], None]: ...
+    @staticmethod
+    def repair(path: str,
+               options: Union[Options, None] = None,
+               column_families: Union[Dict[str, Options], None] = None) -> None: ...
     @staticmethod
     def list_cf(path: str, options: Options = Options()) -> List[str]: ...
 
 class RdictItems(Iterator[Tuple[Union[str, int, float, bytes, bool], Any]]):
     def __iter__(self) -> RdictItems: ...
     def __next__(self) -> Tuple[Union[str, int, float, bytes, bool], Any]: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> RdictItems: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
 
 class RdictKeys(Iterator[Union[str, int, float, bytes, bool]]):
     def __iter__(self) -> RdictKeys: ...
     def __next__(self) -> Union[str, int, float, bytes, bool]: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> RdictKeys: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
 
 class RdictValues(Iterator[Any]):
     def __iter__(self) -> RdictValues: ...
     def __next__(self) -> Any: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> RdictValues: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
 
 class RdictColumns(Iterator[Any]):
     def __iter__(self) -> RdictValues: ...
     def __next__(self) -> List[Tuple[Any, Any]]: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> RdictColumns: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
 
 class RdictEntities(Iterator[Tuple[Union[str, int, float, bytes, bool], List[Tuple[Any, Any]]]]):
     def __iter__(self) -> RdictEntities: ...
     def __next__(self) -> Tuple[Union[str, int, float, bytes, bool], List[Tuple[Any, Any]]]: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> RdictEntities: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
+
+class RdictUpdates(Iterator[Tuple[int, bytes]]):
+    def __iter__(self) -> RdictUpdates: ...
+    def __next__(self) -> Tuple[int, bytes]: ...
+
+class RdictAtomic:
+    def __enter__(self) -> RdictAtomic: ...
+    def __exit__(self, exc_type: Any, exc_value: Any, traceback: Any) -> bool: ...
+    def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
+    def __getitem__(self, key: Union[str, int, float, bytes, bool]) -> Any: ...
+    def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def __contains__(self, key: Union[str, int, float, bytes, bool]) -> bool: ...
+    def put(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
+    def delete(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def get(self, key: Union[str, int, float, bytes, bool], default: Any = None) -> Any: ...
+    def commit(self) -> None: ...
+    def rollback(self) -> None: ...
+
+class RdictBufferedWriter:
+    def __enter__(self) -> RdictBufferedWriter: ...
+    def __exit__(self, exc_type: Any, exc_value: Any, traceback: Any) -> bool: ...
+    def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
+    def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def put(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
+    def delete(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def flush(self) -> None: ...
+    def close(self) -> None: ...
 
 class RdictIter:
     def valid(self) -> bool: ...
@@ -503,6 +746,20 @@ class RdictIter:
     def key(self) -> Any: ...
     def value(self) -> Any: ...
     def columns(self) -> List[Tuple[Any, Any]]: ...
+    def get_chunk_keys(self,
+                       chunk_size: int = 1000,
+                       backwards: bool = False,
+                       stop_key: Union[str, int, float, bytes, bool, None] = None,
+                       max_bytes: Union[int, None] = None) -> List[Any]: ...
+    def get_chunk_items(self,
+                        chunk_size: int = 1000,
+                        backwards: bool = False,
+                        stop_key: Union[str, int, float, bytes, bool, None] = None,
+                        max_bytes: Union[int, None] = None) -> List[Tuple[Any, Any]]: ...
+    def get_chunk_keys_np(self, chunk_size: int = 1000, dtype: Any = None, backwards: bool = False) -> Any: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> RdictIter: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
 
 class IngestExternalFileOptions:
     def __init__(self) -> None: ...
@@ -513,16 +770,29 @@ class IngestExternalFileOptions:
     def set_ingest_behind(self, v: bool) -> None: ...
 
 class SstFileWriter:
-    def __init__(self, options: Options = Options()) -> None: ...
+    def __init__(self,
+                 options: Options = Options(),
+                 compat: Union[str, None] = None,
+                 table_options: Union[BlockBasedOptions, None] = None,
+                 compression: Union[DBCompressionType, None] = None,
+                 block_size: Union[int, None] = None) -> None: ...
     def set_dumps(self, dumps: Callable[[Any], bytes]) -> None: ...
-    def open(self, path: str) -> None: ...
+    def open(self, path: Union[str, os.PathLike]) -> None: ...
     def finish(self) -> None: ...
     def file_size(self) -> int: ...
     def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
     def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def write_sorted(self,
+                     pairs: Iterable[Tuple[Any, Any]],
+                     progress: Union[Callable[[int], None], None] = None,
+                     chunk_size: int = 10000) -> int: ...
 
 class WriteBatch:
-    def __init__(self, raw_mode: bool = False) -> None: ...
+    def __init__(self,
+                 raw_mode: bool = False,
+                 strict_types: bool = False,
+                 key_codec: Union[str, None] = None,
+                 value_checksums: bool = False) -> None: ...
     def __len__(self) -> int: ...
     def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
     def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
@@ -539,8 +809,8 @@ class WriteBatch:
                    values: List[Any]) -> None: ...
     def delete(self, key: Union[str, int, float, bytes, bool],
                column_family: Union[ColumnFamily, None] = None) -> None: ...
-    def delete_range(self, begin: Union[str, int, float, bytes, bool],
-                     end: Union[str, int, float, bytes, bool],
+    def delete_range(self, begin: Union[str, int, float, bytes, bool, None],
+                     end: Union[str, int, float, bytes, bool, None],
                      column_family: Union[ColumnFamily, None] = None) -> None: ...
     def clear(self) -> None: ...
 
@@ -557,17 +827,52 @@ class AccessType:
     def with_ttl(duration: int) -> AccessType: ...
 
 class Snapshot:
+    @overload
     def __getitem__(self, key: Union[str, int, float, bytes, bool]) -> Any: ...
+    @overload
+    def __getitem__(self, key: List[Union[str, int, float, bytes, bool]]) -> List[Any]: ...
+    @overload
+    def get(self,
+            key: Union[str, int, float, bytes, bool],
+            default: Any = None,
+            read_opt: Union[ReadOptions, None] = None,
+            sorted_input: bool = False) -> Any: ...
+    @overload
+    def get(self,
+            key: List[Union[str, int, float, bytes, bool]],
+            default: Any = None,
+            read_opt: Union[ReadOptions, None] = None,
+            sorted_input: bool = False) -> List[Any]: ...
+    def key_may_exist(self,
+                      key: Union[str, int, float, bytes, bool],
+                      fetch: bool = False) -> Union[bool, Tuple[bool, Any]]: ...
     def iter(self, read_opt: Union[ReadOptions, None] = None) -> RdictIter: ...
     def items(self, backwards: bool = False,
               from_key: Union[str, int, float, bytes, bool, None] = None,
-              read_opt: Union[ReadOptions, None] = None) -> RdictItems: ...
+              read_opt: Union[ReadOptions, None] = None,
+              skip: int = 0,
+              limit: Union[int, None] = None) -> RdictItems: ...
     def keys(self, backwards: bool = False,
              from_key: Union[str, int, float, bytes, bool, None] = None,
-             read_opt: Union[ReadOptions, None] = None) -> RdictKeys: ...
+             read_opt: Union[ReadOptions, None] = None,
+             skip: int = 0,
+             limit: Union[int, None] = None) -> RdictKeys: ...
     def values(self, backwards: bool = False,
                from_key: Union[str, int, float, bytes, bool, None] = None,
-               read_opt: Union[ReadOptions, None] = None) -> RdictValues: ...
+               read_opt: Union[ReadOptions, None] = None,
+               skip: int = 0,
+               limit: Union[int, None] = None) -> RdictValues: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> Snapshot: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
+
+class ConsistentView:
+    def column_family(self, name: str = "default") -> Snapshot: ...
+    def __getitem__(self, name: str) -> Snapshot: ...
+    def sequence_number(self) -> int: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> ConsistentView: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
 
 class BottommostLevelCompaction:
     @staticmethod
@@ -604,7 +909,159 @@ class KeyEncodingType:
 
 class Checkpoint:
     def __init__(self, db: Rdict) -> None: ...
-    def create_checkpoint(self, path: str) -> None: ...
+    def create_checkpoint(self, path: Union[str, os.PathLike]) -> None: ...
+    @staticmethod
+    def list(path_root: Union[str, os.PathLike]) -> List[str]: ...
+
+class RetryPolicy:
+    def __init__(self,
+                 max_attempts: int = 3,
+                 initial_backoff: float = 0.01,
+                 max_backoff: float = 1.0,
+                 backoff_multiplier: float = 2.0) -> None: ...
+    @property
+    def max_attempts(self) -> int: ...
+    @property
+    def initial_backoff(self) -> float: ...
+    @property
+    def max_backoff(self) -> float: ...
+    @property
+    def backoff_multiplier(self) -> float: ...
+
+class TransactionDbOptions:
+    def __init__(self) -> None: ...
+    @property
+    def txn_lock_timeout(self) -> int: ...
+    @txn_lock_timeout.setter
+    def txn_lock_timeout(self, v: int) -> None: ...
+    @property
+    def default_lock_timeout(self) -> int: ...
+    @default_lock_timeout.setter
+    def default_lock_timeout(self, v: int) -> None: ...
+    @property
+    def max_num_locks(self) -> int: ...
+    @max_num_locks.setter
+    def max_num_locks(self, v: int) -> None: ...
+    @property
+    def num_stripes(self) -> int: ...
+    @num_stripes.setter
+    def num_stripes(self, v: int) -> None: ...
+    @property
+    def deadlock_detect(self) -> bool: ...
+    @deadlock_detect.setter
+    def deadlock_detect(self, v: bool) -> None: ...
+    @property
+    def deadlock_detect_depth(self) -> int: ...
+    @deadlock_detect_depth.setter
+    def deadlock_detect_depth(self, v: int) -> None: ...
+    @property
+    def expiration(self) -> int: ...
+    @expiration.setter
+    def expiration(self, v: int) -> None: ...
+
+class TransactionDb:
+    def __init__(self,
+                 path: str,
+                 options: Union[Options, None] = None,
+                 column_families: Union[Dict[str, Options], None] = None,
+                 txn_db_options: Union[TransactionDbOptions, None] = None) -> None: ...
+    def __enter__(self) -> TransactionDb: ...
+    def __exit__(self, exc_type: Any, exc_value: Any, traceback: Any) -> bool: ...
+    def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
+    def __getitem__(self, key: Union[str, int, float, bytes, bool]) -> Any: ...
+    def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def __contains__(self, key: Union[str, int, float, bytes, bool]) -> bool: ...
+    def begin_transaction(self,
+                          write_opt: Union[WriteOptions, None] = None,
+                          set_snapshot: bool = False) -> Transaction: ...
+    def prepared_transactions(self) -> Dict[str, Transaction]: ...
+    def put(self, key: Union[str, int, float, bytes, bool], value: Any, column_family: Union[str, None] = None) -> None: ...
+    def get(self, key: Union[str, int, float, bytes, bool], default: Any = None, column_family: Union[str, None] = None) -> Any: ...
+    def delete(self, key: Union[str, int, float, bytes, bool], column_family: Union[str, None] = None) -> None: ...
+    def create_column_family(self, name: str, options: Options = Options()) -> None: ...
+    def close(self) -> None: ...
+
+class OptimisticTransactionDb(TransactionDb):
+    def __init__(self,
+                 path: str,
+                 options: Union[Options, None] = None,
+                 column_families: Union[Dict[str, Options], None] = None) -> None: ...
+
+class Transaction:
+    def __enter__(self) -> Transaction: ...
+    def __exit__(self, exc_type: Any, exc_value: Any, traceback: Any) -> bool: ...
+    def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
+    def __getitem__(self, key: Union[str, int, float, bytes, bool]) -> Any: ...
+    def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def __contains__(self, key: Union[str, int, float, bytes, bool]) -> bool: ...
+    def put(self, key: Union[str, int, float, bytes, bool], value: Any, column_family: Union[str, None] = None) -> None: ...
+    def get(self, key: Union[str, int, float, bytes, bool], default: Any = None, column_family: Union[str, None] = None) -> Any: ...
+    def delete(self, key: Union[str, int, float, bytes, bool], column_family: Union[str, None] = None) -> None: ...
+    def get_for_update(self,
+                       key: Union[str, int, float, bytes, bool],
+                       default: Any = None,
+                       column_family: Union[str, None] = None,
+                       exclusive: bool = True) -> Any: ...
+    def set_snapshot(self) -> None: ...
+    def set_savepoint(self) -> None: ...
+    def rollback_to_savepoint(self) -> None: ...
+    def set_name(self, name: str) -> None: ...
+    @property
+    def name(self) -> Union[str, None]: ...
+    def prepare(self) -> None: ...
+    def commit(self) -> None: ...
+    def rollback(self) -> None: ...
 
 class DbClosedError(Exception):
     """Raised when accessing a closed database instance."""
+
+class DbFrozenError(Exception):
+    """Raised when writing to a database instance frozen by `Rdict.freeze`."""
+
+class RocksDBError(Exception):
+    """Base class of errors returned by RocksDB."""
+
+class CorruptionError(RocksDBError):
+    """Raised when RocksDB detects data corruption."""
+
+class BusyError(RocksDBError):
+    """Raised when a resource is busy, for example on write conflicts."""
+
+class TransactionConflictError(BusyError):
+    """Raised when a transaction cannot commit because of a conflicting write, and can be retried."""
+
+class TryAgainError(RocksDBError):
+    """Raised when an operation failed transiently and can be retried."""
+
+class TimedOutError(RocksDBError):
+    """Raised when an operation timed out, for example waiting for a lock."""
+
+class NotSupportedError(RocksDBError):
+    """Raised when an operation is not supported, for example by a read-only database."""
+
+class IOErrorNoSpace(RocksDBError):
+    """Raised when an IO operation failed because the disk is full."""
+
+def copy_range(src: Rdict,
+               dst: Rdict,
+               begin: Union[str, int, float, bytes, bool, None] = None,
+               end: Union[str, int, float, bytes, bool, None] = None,
+               batch_size: int = 1000,
+               use_ingest: bool = False) -> int: ...
+
+def split(src_path: str,
+          dst_paths: List[str],
+          boundaries: List[Union[str, int, float, bytes, bool]],
+          file_size: int = 64 * 1024 * 1024) -> List[int]: ...
+
+def merge_dbs(src_paths: List[str],
+              dst_path: str,
+              conflict: str = "newest",
+              file_size: int = 64 * 1024 * 1024) -> int: ...
+
+def migrate_key_codec(src_path: str,
+                      dst_path: str,
+                      key_codec: str = "ordered",
+                      batch_size: int = 10000) -> int: ...
+
+def build_info() -> Dict[str, Any]: ...
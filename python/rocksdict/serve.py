@@ -0,0 +1,274 @@
+"""Serve a database read-only over TCP.
+
+One process opens the database and answers `get`, `multi_get` and
+`scan` requests from other processes, which can be written in any
+language. Run it with::
+
+    python -m rocksdict.serve ./db_path --port 7070
+
+Protocol:
+    Each request and response is a frame: a 4-byte big-endian length
+    followed by a UTF-8 JSON object of that length. Requests are::
+
+        {"op": "get", "key": K}
+        {"op": "multi_get", "keys": [K, ...]}
+        {"op": "scan", "start": K or null, "stop": K or null, "limit": N or null}
+
+    Responses are ``{"ok": true, "result": ...}`` or
+    ``{"ok": false, "error": "message"}``. `get` returns the value or
+    null, `multi_get` a list of values, and `scan` a list of
+    ``[key, value]`` pairs with `start` included and `stop` excluded.
+
+    Frames longer than `MAX_FRAME_BYTES` are answered with an error
+    and the connection is closed. `scan` returns at most
+    `max_scan_items` pairs of the server, whatever the `limit`: continue
+    a larger scan from the last key returned, as `Client.scan` does.
+
+    `str`, `int`, `float` and `bool` keys and values are plain JSON.
+    `bytes` are written as ``{"$bytes": "<base64>"}``, and other values
+    as ``{"$repr": "<python repr>"}``.
+
+Example:
+    ::
+
+        from rocksdict import Rdict
+        from rocksdict.serve import RdictServer, Client
+        from threading import Thread
+
+        db = Rdict("./temp_serve")
+        db["key"] = "value"
+        db.close()
+
+        server = RdictServer("./temp_serve", port=0)
+        Thread(target=server.serve_forever, daemon=True).start()
+        with Client("127.0.0.1", server.port) as client:
+            assert client.get("key") == "value"
+        server.shutdown()
+        server.server_close()
+"""
+
+import argparse
+import base64
+import json
+import socket
+import socketserver
+import struct
+from itertools import islice
+from typing import Any, List, Optional, Tuple
+
+from .rocksdict import AccessType, Rdict
+
+__all__ = ["RdictServer", "Client", "main", "MAX_FRAME_BYTES"]
+
+_LENGTH = struct.Struct(">I")
+
+MAX_FRAME_BYTES = 64 * 1024 * 1024
+"""Largest payload of a frame read by `read_frame`."""
+
+
+def _to_json(obj: Any) -> Any:
+    if isinstance(obj, bytes):
+        return {"$bytes": base64.b64encode(obj).decode("ascii")}
+    if obj is None or isinstance(obj, (str, int, float, bool)):
+        return obj
+    return {"$repr": repr(obj)}
+
+
+def _from_json(obj: Any) -> Any:
+    if isinstance(obj, dict) and "$bytes" in obj:
+        return base64.b64decode(obj["$bytes"])
+    return obj
+
+
+def _read_exact(stream: Any, n: int) -> Optional[bytearray]:
+    data = bytearray(n)
+    view = memoryview(data)
+    read = 0
+    while read < n:
+        count = stream.readinto(view[read:])
+        if not count:
+            return None
+        read += count
+    return data
+
+
+def read_frame(stream: Any, max_bytes: int = MAX_FRAME_BYTES) -> Optional[Any]:
+    """Read a JSON frame, returning `None` at end of stream.
+
+    Raises:
+        ValueError: if the frame is longer than `max_bytes`, or is not JSON.
+    """
+    header = _read_exact(stream, _LENGTH.size)
+    if header is None:
+        return None
+    length = _LENGTH.unpack(header)[0]
+    if length > max_bytes:
+        raise ValueError(f"frame of {length} bytes exceeds {max_bytes} bytes")
+    payload = _read_exact(stream, length)
+    if payload is None:
+        return None
+    return json.loads(payload.decode("utf-8"))
+
+
+def write_frame(stream: Any, obj: Any) -> None:
+    """Write a JSON frame."""
+    payload = json.dumps(obj).encode("utf-8")
+    stream.write(_LENGTH.pack(len(payload)) + payload)
+    stream.flush()
+
+
+class _Handler(socketserver.StreamRequestHandler):
+    server: "RdictServer"
+
+    def handle(self) -> None:
+        while True:
+            try:
+                request = read_frame(self.rfile)
+            except ValueError as e:
+                write_frame(self.wfile, {"ok": False, "error": f"invalid request: {e}"})
+                return
+            if request is None:
+                return
+            try:
+                result = self.server.execute(request)
+                write_frame(self.wfile, {"ok": True, "result": result})
+            except Exception as e:
+                write_frame(self.wfile, {"ok": False, "error": str(e)})
+
+
+class RdictServer(socketserver.ThreadingTCPServer):
+    """A TCP server answering read requests on a database opened read-only.
+
+    Args:
+        path: path of the database.
+        host: address to listen on.
+        port: port to listen on, `0` to pick a free port.
+        access_type: how to open the database, read-only by default.
+        max_scan_items: most pairs returned by a `scan` request.
+    """
+
+    daemon_threads = True
+    allow_reuse_address = True
+
+    def __init__(self,
+                 path: str,
+                 host: str = "127.0.0.1",
+                 port: int = 7070,
+                 access_type: Optional[AccessType] = None,
+                 max_scan_items: int = 1000) -> None:
+        if max_scan_items <= 0:
+            raise ValueError("max_scan_items must be positive")
+        self.max_scan_items = max_scan_items
+        self.db = Rdict(path, access_type=access_type or AccessType.read_only())
+        try:
+            super().__init__((host, port), _Handler)
+        except BaseException:
+            self.db.close()
+            raise
+
+    @property
+    def port(self) -> int:
+        """The port the server listens on."""
+        return self.server_address[1]
+
+    def execute(self, request: Any) -> Any:
+        """Execute a decoded request and return the JSON result."""
+        op = request.get("op")
+        if op == "get":
+            return _to_json(self.db.get(_from_json(request["key"])))
+        if op == "multi_get":
+            keys = [_from_json(k) for k in request["keys"]]
+            return [_to_json(v) for v in self.db.get(keys)]
+        if op == "scan":
+            items = self.db.items(start=_from_json(request.get("start")),
+                                  stop=_from_json(request.get("stop")))
+            limit = request.get("limit")
+            if limit is None or limit > self.max_scan_items:
+                limit = self.max_scan_items
+            return [[_to_json(k), _to_json(v)] for k, v in islice(items, limit)]
+        raise ValueError(f"unknown op: {op!r}")
+
+    def server_close(self) -> None:
+        super().server_close()
+        self.db.close()
+
+
+class Client:
+    """A minimal Python client of `RdictServer`.
+
+    Args:
+        host: address of the server.
+        port: port of the server.
+    """
+
+    def __init__(self, host: str = "127.0.0.1", port: int = 7070) -> None:
+        self._sock = socket.create_connection((host, port))
+        self._stream = self._sock.makefile("rwb")
+
+    def _call(self, request: Any) -> Any:
+        write_frame(self._stream, request)
+        response = read_frame(self._stream)
+        if response is None:
+            raise ConnectionError("server closed the connection")
+        if not response["ok"]:
+            raise RuntimeError(response["error"])
+        return response["result"]
+
+    def get(self, key: Any) -> Any:
+        return _from_json(self._call({"op": "get", "key": _to_json(key)}))
+
+    def multi_get(self, keys: List[Any]) -> List[Any]:
+        result = self._call({"op": "multi_get", "keys": [_to_json(k) for k in keys]})
+        return [_from_json(v) for v in result]
+
+    def scan(self,
+             start: Any = None,
+             stop: Any = None,
+             limit: Optional[int] = None) -> List[Tuple[Any, Any]]:
+        """Scan from `start` to `stop`, in as many requests as the
+        `max_scan_items` of the server requires."""
+        pairs: List[Tuple[Any, Any]] = []
+        while limit is None or len(pairs) < limit:
+            # a continued scan starts at the last key returned
+            page_limit = None if limit is None else limit - len(pairs) + bool(pairs)
+            result = self._call({"op": "scan",
+                                 "start": _to_json(start),
+                                 "stop": _to_json(stop),
+                                 "limit": page_limit})
+            page = [(_from_json(k), _from_json(v)) for k, v in result]
+            if pairs and page and page[0][0] == pairs[-1][0]:
+                page = page[1:]
+            if not page:
+                break
+            pairs.extend(page)
+            start = pairs[-1][0]
+        return pairs
+
+    def close(self) -> None:
+        self._stream.close()
+        self._sock.close()
+
+    def __enter__(self) -> "Client":
+        return self
+
+    def __exit__(self, *args: Any) -> None:
+        self.close()
+
+
+def main() -> None:
+    parser = argparse.ArgumentParser(prog="python -m rocksdict.serve",
+                                     description="Serve a database read-only over TCP.")
+    parser.add_argument("path", help="path of the database")
+    parser.add_argument("--host", default="127.0.0.1", help="address to listen on")
+    parser.add_argument("--port", type=int, default=7070, help="port to listen on")
+    args = parser.parse_args()
+    with RdictServer(args.path, args.host, args.port) as server:
+        print(f"serving {args.path} on {args.host}:{server.port}")
+        try:
+            server.serve_forever()
+        except KeyboardInterrupt:
+            pass
+
+
+if __name__ == "__main__":
+    main()
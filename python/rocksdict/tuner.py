@@ -0,0 +1,240 @@
+"""Tune options of an open database to its workload.
+
+`AutoTuner` watches write stalls, compaction debt and the block cache
+hit rate of a database, and adjusts options that can be changed while
+the database is open, within bounds given for each of them. Options
+are raised while writes stall, and lowered back one step at a time
+after a while without stalls. Every change is logged to the
+``rocksdict.tuner`` logger and recorded in `AutoTuner.changes`.
+
+Only column family options can be changed on an open database, so the
+number of background jobs and rate limits are not tuned.
+
+Example:
+    ::
+
+        from rocksdict import Rdict, Options, Cache, BlockBasedOptions
+        from rocksdict.tuner import AutoTuner
+
+        cache = Cache(64 * 1024 * 1024)
+        table = BlockBasedOptions()
+        table.set_block_cache(cache)
+        opt = Options()
+        opt.set_block_based_table_factory(table)
+        opt.enable_statistics()
+        db = Rdict("./temp_tuner", opt)
+
+        with AutoTuner(db,
+                       max_write_buffer_number=(2, 8),
+                       cache=cache,
+                       cache_capacity=(64 << 20, 1 << 30)) as tuner:
+            for i in range(1_000_000):
+                db[i] = i
+        for change in tuner.changes:
+            print(change)
+"""
+
+import logging
+import threading
+import time
+from typing import Dict, List, NamedTuple, Optional, Tuple
+
+from .rocksdict import Cache, Rdict
+
+__all__ = ["AutoTuner", "Change"]
+
+logger = logging.getLogger("rocksdict.tuner")
+
+_CACHE_CAPACITY = "cache_capacity"
+
+# lookups needed between two adjustments to compute a cache hit rate
+_MIN_CACHE_LOOKUPS = 100
+
+
+class Change(NamedTuple):
+    """An option changed by `AutoTuner`."""
+
+    time: float
+    option: str
+    old: int
+    new: int
+    reason: str
+
+
+def _ticker(statistics: str, name: str) -> int:
+    # tickers are dumped as "rocksdb.block.cache.hit COUNT : 123"
+    for line in statistics.splitlines():
+        if line.startswith(name + " "):
+            return int(line.rsplit(":", 1)[1])
+    return 0
+
+
+class AutoTuner:
+    """Adjust options of a database within bounds.
+
+    Call `step` periodically, or `start` a background thread calling it
+    every `interval` seconds. Options given as `None` are not tuned.
+    Options outside of their bounds are moved into them when the tuner
+    is created.
+
+    Args:
+        db: the `Rdict` to tune, options of its column family are changed.
+        interval: seconds between two adjustments of the background thread.
+        max_write_buffer_number: ``(min, max)`` number of memtables,
+            raised while writes stall on memtable flushes.
+        soft_pending_compaction_bytes_limit: ``(min, max)`` compaction
+            debt before writes are delayed, raised while writes stall on
+            compaction. Keep `max` below `hard_pending_compaction_bytes_limit`.
+        cache: block cache of the database, raised while its hit rate is
+            low. Requires `Options.enable_statistics`.
+        cache_capacity: ``(min, max)`` capacity of `cache` in bytes,
+            which is set to `min` when the tuner is created.
+        target_cache_hit_rate: hit rate of `cache` below which it is raised.
+        calm_steps: number of adjustments without write stalls before
+            an option is lowered back by one step.
+    """
+
+    def __init__(self,
+                 db: Rdict,
+                 interval: float = 10.0,
+                 max_write_buffer_number: Optional[Tuple[int, int]] = None,
+                 soft_pending_compaction_bytes_limit: Optional[Tuple[int, int]] = None,
+                 cache: Optional[Cache] = None,
+                 cache_capacity: Optional[Tuple[int, int]] = None,
+                 target_cache_hit_rate: float = 0.9,
+                 calm_steps: int = 6) -> None:
+        if (cache is None) != (cache_capacity is None):
+            raise ValueError("cache and cache_capacity must be given together")
+        self.db = db
+        self.interval = interval
+        self.cache = cache
+        self.target_cache_hit_rate = target_cache_hit_rate
+        self.calm_steps = calm_steps
+        self.changes: List[Change] = []
+        self._bounds: Dict[str, Tuple[int, int]] = {}
+        self._current: Dict[str, int] = {}
+        self._calm = 0
+        self._cache_lookups = (0, 0)
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+        options = db.get_options()
+        for name, bounds in [("max_write_buffer_number", max_write_buffer_number),
+                             ("soft_pending_compaction_bytes_limit",
+                              soft_pending_compaction_bytes_limit)]:
+            if bounds is not None:
+                self._add(name, bounds, int(options[name]))
+        if cache_capacity is not None:
+            self._add(_CACHE_CAPACITY, cache_capacity, None)
+
+    def _add(self, option: str, bounds: Tuple[int, int], current: Optional[int]) -> None:
+        low, high = bounds
+        if not 0 < low <= high:
+            raise ValueError(f"invalid bounds of {option}: {bounds}")
+        self._bounds[option] = bounds
+        if current is None:
+            if option == _CACHE_CAPACITY and self.cache is not None:
+                self.cache.set_capacity(low)
+            self._current[option] = low
+        else:
+            self._current[option] = current
+            self._set(option, current, "out of bounds", [])
+
+    def _set(self, option: str, value: int, reason: str, changes: List[Change]) -> None:
+        low, high = self._bounds[option]
+        value = max(low, min(high, value))
+        old = self._current[option]
+        if value == old:
+            return
+        if option == _CACHE_CAPACITY:
+            self.cache.set_capacity(value)
+        else:
+            self.db.set_options({option: str(value)})
+        self._current[option] = value
+        change = Change(time.time(), option, old, value, reason)
+        logger.info("%s: %d -> %d (%s)", option, old, value, reason)
+        self.changes.append(change)
+        changes.append(change)
+
+    def _cache_hit_rate(self) -> Optional[float]:
+        statistics = self.db.statistics()
+        if statistics is None:
+            return None
+        hits = _ticker(statistics, "rocksdb.block.cache.hit")
+        misses = _ticker(statistics, "rocksdb.block.cache.miss")
+        last_hits, last_misses = self._cache_lookups
+        lookups = hits - last_hits + misses - last_misses
+        if lookups < _MIN_CACHE_LOOKUPS:
+            return None
+        self._cache_lookups = (hits, misses)
+        return (hits - last_hits) / lookups
+
+    def step(self) -> List[Change]:
+        """Adjust the options once.
+
+        Returns:
+            the changes made.
+        """
+        changes: List[Change] = []
+        info = self.db.write_stall_info()
+        stalled = info["is_write_stopped"] or info["is_write_delayed"]
+        self._calm = 0 if stalled else self._calm + 1
+        calm = self._calm >= self.calm_steps
+        if "max_write_buffer_number" in self._bounds:
+            current = self._current["max_write_buffer_number"]
+            immutable = info["num_immutable_mem_table"]
+            if stalled and immutable >= current - 1:
+                self._set("max_write_buffer_number", current + 1,
+                          "writes stall on memtable flushes", changes)
+            elif calm and immutable == 0:
+                self._set("max_write_buffer_number", current - 1,
+                          "no write stalls", changes)
+        if "soft_pending_compaction_bytes_limit" in self._bounds:
+            current = self._current["soft_pending_compaction_bytes_limit"]
+            debt = info["estimate_pending_compaction_bytes"]
+            if stalled and debt >= current * 4 // 5:
+                self._set("soft_pending_compaction_bytes_limit", current * 2,
+                          "writes stall on compaction debt", changes)
+            elif calm and debt < current // 4:
+                self._set("soft_pending_compaction_bytes_limit", current // 2,
+                          "low compaction debt", changes)
+        if _CACHE_CAPACITY in self._bounds:
+            hit_rate = self._cache_hit_rate()
+            if hit_rate is not None and hit_rate < self.target_cache_hit_rate:
+                self._set(_CACHE_CAPACITY, self._current[_CACHE_CAPACITY] * 3 // 2,
+                          f"block cache hit rate {hit_rate:.2f}", changes)
+        # lower options one step per calm period
+        if calm:
+            self._calm = 0
+        return changes
+
+    def _run(self) -> None:
+        while not self._stop.wait(self.interval):
+            try:
+                self.step()
+            except Exception:
+                logger.exception("auto-tuning stopped")
+                return
+
+    def start(self) -> None:
+        """Adjust the options every `interval` seconds in a background thread."""
+        if self._thread is not None:
+            raise RuntimeError("the tuner is already started")
+        self._stop.clear()
+        self._thread = threading.Thread(target=self._run,
+                                        name="rocksdict-auto-tuner",
+                                        daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop the background thread, before closing the database."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join()
+            self._thread = None
+
+    def __enter__(self) -> "AutoTuner":
+        self.start()
+        return self
+
+    def __exit__(self, *args: object) -> None:
+        self.stop()
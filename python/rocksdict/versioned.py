@@ -0,0 +1,191 @@
+"""Keep the last versions of each key.
+
+`VersionedRdict` stores each write as a new version of its key,
+stamped with a timestamp, and keeps the newest `max_versions`
+versions of each key. Older versions are dropped by compactions.
+Reads see the latest version, or the version at a given time.
+
+Versions are stored in a raw mode database, under the key followed by
+its timestamp inverted, so that the versions of a key are adjacent and
+sorted newest first. Timestamps are integers, the nanoseconds since the
+epoch of `time.time_ns` by default.
+
+Example:
+    ::
+
+        from rocksdict.versioned import VersionedRdict
+
+        db = VersionedRdict("./temp_versioned", max_versions=3)
+        db.put("price", 10, ts=100)
+        db.put("price", 12, ts=200)
+        assert db["price"] == 12
+        assert db.get("price", as_of=150) == 10
+        assert [v.value for v in db.history("price")] == [12, 10]
+        db.close()
+"""
+
+import pickle
+import struct
+import time
+from typing import Any, Iterator, List, NamedTuple, Optional, Union
+
+from .rocksdict import Options, Rdict
+
+__all__ = ["VersionedRdict", "Version"]
+
+_VERSION = struct.Struct(">Q")
+_MAX_TIMESTAMP = (1 << 64) - 1
+# zero bytes of keys are escaped, so that the end of a key sorts
+# before any longer key sharing its bytes
+_ESCAPED_ZERO = b"\x00\xff"
+_KEY_END = b"\x00\x00"
+# pickled values are never empty
+_DELETED = b""
+
+
+class Version(NamedTuple):
+    """A version of a key, as returned by `VersionedRdict.history`."""
+
+    timestamp: int
+    value: Any
+    deleted: bool
+
+
+def _encode_key(key: Union[str, bytes]) -> bytes:
+    if isinstance(key, str):
+        tag, data = b"s", key.encode("utf-8")
+    elif isinstance(key, bytes):
+        tag, data = b"b", key
+    else:
+        raise TypeError(f"key must be str or bytes, not {type(key).__name__}")
+    return tag + data.replace(b"\x00", _ESCAPED_ZERO) + _KEY_END
+
+
+def _encode_timestamp(ts: Optional[int]) -> bytes:
+    if ts is None:
+        ts = time.time_ns()
+    if not 0 <= ts <= _MAX_TIMESTAMP:
+        raise ValueError(f"timestamp out of range: {ts}")
+    return _VERSION.pack(_MAX_TIMESTAMP - ts)
+
+
+class VersionedRdict:
+    """A dict keeping the newest `max_versions` versions of each key.
+
+    Keys are str or bytes, and values are pickled. Deleting a key writes
+    a deletion as its newest version, which counts as one of the versions
+    kept.
+
+    Args:
+        path: path of the database.
+        max_versions: number of versions kept for each key.
+        options: raw mode `Options` of the database, which are changed to
+            drop older versions on compaction. Defaults to
+            ``Options(raw_mode=True)``.
+    """
+
+    def __init__(self,
+                 path: str,
+                 max_versions: int = 10,
+                 options: Optional[Options] = None) -> None:
+        if options is None:
+            options = Options(raw_mode=True)
+        options.set_version_history(max_versions)
+        self.max_versions = max_versions
+        # the filter is not stored in the OPTIONS file, pass it to the
+        # default column family explicitly instead of its loaded options
+        self._db = Rdict(path, options, column_families={"default": options})
+
+    def put(self, key: Union[str, bytes], value: Any, ts: Optional[int] = None) -> None:
+        """Write a new version of `key`.
+
+        Args:
+            key: the key.
+            value: the value, which must be picklable.
+            ts: timestamp of the version, defaults to the current time.
+                A version with the same timestamp is replaced.
+        """
+        self._db.put(_encode_key(key) + _encode_timestamp(ts), pickle.dumps(value))
+
+    def delete(self, key: Union[str, bytes], ts: Optional[int] = None) -> None:
+        """Write a deletion as a new version of `key`.
+
+        Args:
+            key: the key.
+            ts: timestamp of the deletion, defaults to the current time.
+        """
+        self._db.put(_encode_key(key) + _encode_timestamp(ts), _DELETED)
+
+    def _versions(self, key: Union[str, bytes]) -> Iterator[Version]:
+        # versions past max_versions are ignored until compacted away,
+        # so that reads do not depend on compactions
+        prefix = _encode_key(key)
+        it = self._db.iter()
+        it.seek(prefix)
+        for _ in range(self.max_versions):
+            if not it.valid() or not it.key().startswith(prefix):
+                return
+            ts = _MAX_TIMESTAMP - _VERSION.unpack(it.key()[len(prefix):])[0]
+            value = it.value()
+            if value == _DELETED:
+                yield Version(ts, None, True)
+            else:
+                yield Version(ts, pickle.loads(value), False)
+            it.next()
+
+    def history(self, key: Union[str, bytes]) -> List[Version]:
+        """Versions of `key`, newest first.
+
+        Returns:
+            at most `max_versions` versions, including deletions.
+        """
+        return list(self._versions(key))
+
+    def get(self,
+            key: Union[str, bytes],
+            default: Any = None,
+            as_of: Optional[int] = None) -> Any:
+        """Value of `key`, or `default` if missing or deleted.
+
+        Args:
+            key: the key.
+            default: returned if the key has no version.
+            as_of: read the newest version with a timestamp at most
+                `as_of`, instead of the newest version.
+        """
+        for version in self._versions(key):
+            if as_of is None or version.timestamp <= as_of:
+                return default if version.deleted else version.value
+        return default
+
+    def __getitem__(self, key: Union[str, bytes]) -> Any:
+        for version in self._versions(key):
+            if version.deleted:
+                break
+            return version.value
+        raise KeyError(key)
+
+    def __setitem__(self, key: Union[str, bytes], value: Any) -> None:
+        self.put(key, value)
+
+    def __delitem__(self, key: Union[str, bytes]) -> None:
+        self.delete(key)
+
+    def __contains__(self, key: Union[str, bytes]) -> bool:
+        for version in self._versions(key):
+            return not version.deleted
+        return False
+
+    def compact(self) -> None:
+        """Compact the database, dropping versions past `max_versions`."""
+        self._db.compact_range(None, None)
+
+    def close(self) -> None:
+        """Close the database."""
+        self._db.close()
+
+    def __enter__(self) -> "VersionedRdict":
+        return self
+
+    def __exit__(self, *args: object) -> None:
+        self.close()
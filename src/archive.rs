@@ -0,0 +1,60 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Zstandard frame magic number.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// A unique, not yet existing path next to `path`.
+pub(crate) fn sibling_temp_path(path: &Path, tag: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}-{}-{}", tag, std::process::id(), nanos));
+    path.with_file_name(name)
+}
+
+/// Pack the files of the (flat) directory `dir` into a tar archive.
+///
+/// The archive is zstd compressed if its extension is `.zst`.
+pub(crate) fn pack_dir(dir: &Path, archive: &Path) -> io::Result<()> {
+    let file = BufWriter::new(File::create(archive)?);
+    let compressed = archive.extension().is_some_and(|ext| ext == "zst");
+    let result = if compressed {
+        append_files(zstd::Encoder::new(file, 0)?, dir)
+            .and_then(|encoder| encoder.finish())
+            .and_then(|mut file| file.flush())
+    } else {
+        append_files(file, dir).and_then(|mut file| file.flush())
+    };
+    if result.is_err() {
+        fs::remove_file(archive).ok();
+    }
+    result
+}
+
+fn append_files<W: Write>(writer: W, dir: &Path) -> io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        if entry.file_type()?.is_file() {
+            builder.append_path_with_name(entry.path(), entry.file_name())?;
+        }
+    }
+    builder.into_inner()
+}
+
+/// Unpack a tar archive, optionally zstd compressed, into `dst`.
+pub(crate) fn unpack(archive: &Path, dst: &Path) -> io::Result<()> {
+    let mut file = BufReader::new(File::open(archive)?);
+    fs::create_dir_all(dst)?;
+    if file.fill_buf()?.starts_with(&ZSTD_MAGIC) {
+        tar::Archive::new(zstd::Decoder::with_buffer(file)?).unpack(dst)
+    } else {
+        tar::Archive::new(file).unpack(dst)
+    }
+}
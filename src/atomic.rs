@@ -0,0 +1,200 @@
+use crate::encoder::encode_key;
+use crate::exceptions::to_py_err;
+use crate::Rdict;
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use rocksdb::{UnboundColumnFamily, WriteBatch, DEFAULT_COLUMN_FAMILY_NAME};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A group of writes to the current column family committed atomically.
+///
+/// Created by `Rdict.atomic()`. Writes are buffered in a `WriteBatch`
+/// and indexed, so that reads through this object see its own pending
+/// writes on top of the database. Used as a context manager, the writes
+/// are committed when the block exits normally, and discarded if it
+/// raises an exception.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict
+///
+///         db = Rdict("./temp_path")
+///         db["alice"] = 100
+///         with db.atomic() as tx:
+///             tx["alice"] = tx["alice"] - 30
+///             tx["bob"] = tx.get("bob", 0) + 30
+///         assert db["alice"] == 70 and db["bob"] == 30
+///
+/// Notes:
+///     This is not a transaction: reads are not isolated from
+///     concurrent writers, and no conflicts are detected.
+#[pyclass]
+pub(crate) struct RdictAtomic {
+    rdict: Py<Rdict>,
+    cf: Arc<UnboundColumnFamily>,
+    /// `None` once committed or rolled back.
+    batch: Option<WriteBatch>,
+    /// Encoded values of pending writes, `None` for pending deletes.
+    pending: HashMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl RdictAtomic {
+    pub(crate) fn new(rdict: &Bound<Rdict>) -> PyResult<Self> {
+        let cf = {
+            let rdict = rdict.borrow();
+            match &rdict.column_family {
+                None => {
+                    rdict
+                        .get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                        .cf
+                }
+                Some(cf) => cf.clone(),
+            }
+        };
+        Ok(RdictAtomic {
+            rdict: rdict.clone().unbind(),
+            cf,
+            batch: Some(WriteBatch::default()),
+            pending: HashMap::new(),
+        })
+    }
+
+    fn batch(&mut self) -> PyResult<&mut WriteBatch> {
+        self.batch.as_mut().ok_or_else(finished_error)
+    }
+}
+
+fn finished_error() -> PyErr {
+    PyValueError::new_err("atomic block already committed or rolled back")
+}
+
+#[pymethods]
+impl RdictAtomic {
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    /// Commit on normal exit, roll back if an exception was raised.
+    #[pyo3(signature = (exc_type, _exc_value, _traceback))]
+    fn __exit__(
+        &mut self,
+        exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+        py: Python,
+    ) -> PyResult<bool> {
+        if self.batch.is_some() {
+            match exc_type {
+                None => self.commit(py)?,
+                Some(_) => self.rollback(),
+            }
+        }
+        Ok(false)
+    }
+
+    fn __setitem__(
+        &mut self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        py: Python,
+    ) -> PyResult<()> {
+        self.put(key, value, py)
+    }
+
+    fn __getitem__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
+        match self.get(key, None, py)? {
+            Some(value) => Ok(value),
+            None => Err(PyKeyError::new_err(format!("key {key} not found"))),
+        }
+    }
+
+    fn __delitem__(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.delete(key, py)
+    }
+
+    fn __contains__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<bool> {
+        Ok(self.get(key, None, py)?.is_some())
+    }
+
+    /// Buffer inserting a key value.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     value: the value.
+    fn put(&mut self, key: &Bound<PyAny>, value: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        let (key, value) = {
+            let rdict = self.rdict.borrow(py);
+            let key = encode_key(key, rdict.opt_py.key_codec())?.to_vec();
+            let value = rdict.dump_value(&key, value)?.to_vec();
+            (key, value)
+        };
+        let cf = self.cf.clone();
+        self.batch()?.put_cf(&cf, &key, &value);
+        self.pending.insert(key, Some(value));
+        Ok(())
+    }
+
+    /// Buffer deleting a key.
+    ///
+    /// Args:
+    ///     key: the key.
+    fn delete(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        let key = encode_key(key, self.rdict.borrow(py).opt_py.key_codec())?.to_vec();
+        let cf = self.cf.clone();
+        self.batch()?.delete_cf(&cf, &key);
+        self.pending.insert(key, None);
+        Ok(())
+    }
+
+    /// Get the value of a key, including the pending writes of this block.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     default: the default value to return if key not found.
+    #[pyo3(signature = (key, default = None))]
+    fn get(
+        &self,
+        key: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        py: Python,
+    ) -> PyResult<Option<PyObject>> {
+        let rdict = self.rdict.borrow(py);
+        let key = encode_key(key, rdict.opt_py.key_codec())?;
+        let value = match self.pending.get(&*key) {
+            Some(Some(value)) => Some(rdict.load_value(py, &key, value)?),
+            Some(None) => None,
+            None => match rdict
+                .get_db()?
+                .get_pinned_cf_opt(&self.cf, &key, &rdict.read_opt)
+                .map_err(to_py_err)?
+            {
+                Some(slice) => Some(rdict.load_value(py, &key, slice.as_ref())?),
+                None => None,
+            },
+        };
+        Ok(value.or_else(|| default.map(|default| default.to_object(py))))
+    }
+
+    /// Atomically write all buffered writes to the database.
+    fn commit(&mut self, py: Python) -> PyResult<()> {
+        {
+            let batch = self.batch.as_ref().ok_or_else(finished_error)?;
+            let rdict = self.rdict.borrow(py);
+            let db = rdict.get_db()?;
+            // a failed write has no effect, so each attempt writes a copy of the batch
+            rdict.write_with_retry(|| {
+                db.write_opt(WriteBatch::from_data(batch.data()), &rdict.write_opt)
+            })?;
+        }
+        self.rollback();
+        Ok(())
+    }
+
+    /// Discard all buffered writes.
+    fn rollback(&mut self) {
+        self.batch = None;
+        self.pending.clear();
+    }
+}
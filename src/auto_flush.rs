@@ -0,0 +1,95 @@
+use crate::db_reference::DbReferenceHolder;
+use crate::exceptions::to_py_err;
+use pyo3::prelude::*;
+use rocksdb::UnboundColumnFamily;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A background thread flushing the memtable of a column family on a timer.
+///
+/// Created by `Rdict.set_auto_flush`. Stops when dropped.
+pub(crate) struct AutoFlush {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+    error: Arc<Mutex<Option<rocksdb::Error>>>,
+    pub(crate) interval: Duration,
+    pub(crate) flush_wal: bool,
+}
+
+impl AutoFlush {
+    /// Flush `cf`, or the default column family if `None`, every `interval`.
+    pub(crate) fn start(
+        db: &DbReferenceHolder,
+        cf: Option<Arc<UnboundColumnFamily>>,
+        interval: Duration,
+        flush_wal: bool,
+    ) -> PyResult<Self> {
+        let (stop, receiver) = mpsc::channel();
+        let error = Arc::new(Mutex::new(None));
+        let thread = {
+            let db = db.with_kind("auto flush");
+            let error = error.clone();
+            thread::Builder::new()
+                .name("rocksdict-auto-flush".to_string())
+                .spawn(move || run(db, cf, receiver, interval, flush_wal, error))?
+        };
+        Ok(AutoFlush {
+            stop: Some(stop),
+            thread: Some(thread),
+            error,
+            interval,
+            flush_wal,
+        })
+    }
+
+    /// Stop the background thread, waiting for a running flush,
+    /// and raise the last error of the thread.
+    pub(crate) fn stop(&mut self) -> PyResult<()> {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+        match self.error.lock().unwrap().take() {
+            None => Ok(()),
+            Some(e) => Err(to_py_err(e)),
+        }
+    }
+}
+
+impl Drop for AutoFlush {
+    fn drop(&mut self) {
+        self.stop().ok();
+    }
+}
+
+/// Loop of the background thread, until stopped.
+fn run(
+    db: DbReferenceHolder,
+    cf: Option<Arc<UnboundColumnFamily>>,
+    receiver: Receiver<()>,
+    interval: Duration,
+    flush_wal: bool,
+    error: Arc<Mutex<Option<rocksdb::Error>>>,
+) {
+    // the cloned reference keeps the database open until the thread exits
+    let Some(inner) = db.get() else {
+        return;
+    };
+    while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(interval) {
+        let wal_result = if flush_wal {
+            inner.flush_wal(true)
+        } else {
+            Ok(())
+        };
+        let result = match &cf {
+            None => inner.flush(),
+            Some(cf) => inner.flush_cf(cf),
+        };
+        // keep flushing after errors, and raise the last one to the user
+        if let Err(e) = wal_result.and(result) {
+            *error.lock().unwrap() = Some(e);
+        }
+    }
+}
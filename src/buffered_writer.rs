@@ -0,0 +1,286 @@
+use crate::db_reference::DbReferenceHolder;
+use crate::encoder::{encode_key, encode_value, KeyCodec, ValueCodec};
+use crate::exceptions::{to_py_err, DbClosedError, DbFrozenError, RocksDBError};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rocksdb::{UnboundColumnFamily, WriteBatch, WriteOptions};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+enum Op {
+    Put(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
+    /// Write everything queued so far, then reply.
+    Flush(mpsc::Sender<()>),
+}
+
+/// Error of the background thread, raised by later calls.
+#[derive(Clone)]
+enum WriteError {
+    RocksDB(rocksdb::Error),
+    Frozen,
+}
+
+/// Settings of the background thread.
+pub(crate) struct WriterConfig {
+    pub max_batch: usize,
+    pub max_bytes: usize,
+    pub flush_interval: Duration,
+}
+
+/// Writes queued by a background thread into write batches.
+///
+/// Created by `Rdict.buffered_writer()`. `put` and `delete` only encode
+/// the key and value and add them to a queue, and a background thread
+/// writes the queue into the current column family in batches, so that
+/// producers are not slowed down by each write (or by write stalls,
+/// until the queue is full). A batch is written once it reaches
+/// `max_batch` updates or `max_bytes` bytes, or `flush_interval` seconds
+/// after its first update.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict
+///
+///         db = Rdict("./temp_path")
+///         with db.buffered_writer(max_batch=1000) as writer:
+///             for i in range(100_000):
+///                 writer[i] = i
+///         # all updates are written after the with block
+///         assert db[99_999] == 99_999
+///
+/// Notes:
+///     Updates are not visible to reads until written by the background
+///     thread; call `flush()` to wait for them. Errors of the background
+///     thread are raised by the next call to `put`, `delete`, `flush`
+///     or `close`. Close the writer before closing the database.
+#[pyclass]
+pub(crate) struct RdictBufferedWriter {
+    sender: Option<SyncSender<Op>>,
+    thread: Option<JoinHandle<()>>,
+    error: Arc<Mutex<Option<WriteError>>>,
+    dumps: PyObject,
+    codec: ValueCodec,
+    key_codec: KeyCodec,
+    strict_types: bool,
+}
+
+impl RdictBufferedWriter {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        db: &DbReferenceHolder,
+        cf: Arc<UnboundColumnFamily>,
+        write_opt: WriteOptions,
+        config: WriterConfig,
+        dumps: PyObject,
+        codec: ValueCodec,
+        key_codec: KeyCodec,
+        strict_types: bool,
+    ) -> PyResult<Self> {
+        if db.get().is_none() {
+            return Err(DbClosedError::new_err("DB instance already closed"));
+        }
+        let (sender, receiver) = mpsc::sync_channel(config.max_batch.max(1));
+        let error = Arc::new(Mutex::new(None));
+        let thread = {
+            let db = db.with_kind("buffered writer");
+            let error = error.clone();
+            thread::Builder::new()
+                .name("rocksdict-buffered-writer".to_string())
+                .spawn(move || run(db, cf, receiver, write_opt, config, error))?
+        };
+        Ok(RdictBufferedWriter {
+            sender: Some(sender),
+            thread: Some(thread),
+            error,
+            dumps,
+            codec,
+            key_codec,
+            strict_types,
+        })
+    }
+
+    fn check_error(&self) -> PyResult<()> {
+        match &*self.error.lock().unwrap() {
+            None => Ok(()),
+            Some(WriteError::RocksDB(e)) => Err(to_py_err(e.clone())),
+            Some(WriteError::Frozen) => Err(DbFrozenError::new_err("database is frozen")),
+        }
+    }
+
+    fn send(&self, op: Op, py: Python) -> PyResult<()> {
+        self.check_error()?;
+        let sender = self
+            .sender
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("buffered writer already closed"))?;
+        let closed = || RocksDBError::new_err("buffered writer thread stopped");
+        match sender.try_send(op) {
+            Ok(()) => Ok(()),
+            // the queue is full, wait for the background thread without the GIL
+            Err(TrySendError::Full(op)) => {
+                py.allow_threads(|| sender.send(op)).map_err(|_| closed())
+            }
+            Err(TrySendError::Disconnected(_)) => Err(closed()),
+        }
+    }
+}
+
+#[pymethods]
+impl RdictBufferedWriter {
+    /// Queue inserting a key value.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     value: the value.
+    fn put(&self, key: &Bound<PyAny>, value: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        let value = self
+            .codec
+            .encode(
+                &key,
+                encode_value(
+                    value,
+                    &self.dumps,
+                    self.key_codec.is_raw(),
+                    self.strict_types,
+                )?,
+            )?
+            .to_vec();
+        self.send(Op::Put(key, value), py)
+    }
+
+    /// Queue deleting a key.
+    ///
+    /// Args:
+    ///     key: the key.
+    fn delete(&self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        self.send(Op::Delete(key), py)
+    }
+
+    fn __setitem__(&self, key: &Bound<PyAny>, value: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.put(key, value, py)
+    }
+
+    fn __delitem__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.delete(key, py)
+    }
+
+    /// Block until all queued updates are written.
+    fn flush(&self, py: Python) -> PyResult<()> {
+        let (reply, done) = mpsc::channel();
+        self.send(Op::Flush(reply), py)?;
+        py.allow_threads(|| done.recv())
+            .map_err(|_| RocksDBError::new_err("buffered writer thread stopped"))?;
+        self.check_error()
+    }
+
+    /// Write all queued updates and stop the background thread.
+    fn close(&mut self, py: Python) -> PyResult<()> {
+        drop(self.sender.take());
+        if let Some(thread) = self.thread.take() {
+            py.allow_threads(|| thread.join())
+                .map_err(|_| RocksDBError::new_err("buffered writer thread panicked"))?;
+        }
+        self.check_error()
+    }
+
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type, _exc_value, _traceback))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+        py: Python,
+    ) -> PyResult<bool> {
+        self.close(py)?;
+        Ok(false)
+    }
+}
+
+impl Drop for RdictBufferedWriter {
+    fn drop(&mut self) {
+        // the background thread writes the remaining updates and exits
+        drop(self.sender.take());
+    }
+}
+
+/// Loop of the background thread, until the writer is closed or dropped.
+fn run(
+    db: DbReferenceHolder,
+    cf: Arc<UnboundColumnFamily>,
+    receiver: Receiver<Op>,
+    write_opt: WriteOptions,
+    config: WriterConfig,
+    error: Arc<Mutex<Option<WriteError>>>,
+) {
+    let mut batch = WriteBatch::default();
+    let mut bytes = 0;
+    let mut deadline: Option<Instant> = None;
+    // the cloned reference keeps the database open until the thread exits
+    let Some(inner) = db.get() else {
+        return;
+    };
+    let write = |batch: &mut WriteBatch| {
+        // updates are dropped after an error, which is raised to the user
+        if batch.is_empty() || error.lock().unwrap().is_some() {
+            batch.clear();
+            return;
+        }
+        let Some(_writing) = db.begin_write() else {
+            batch.clear();
+            *error.lock().unwrap() = Some(WriteError::Frozen);
+            return;
+        };
+        match inner.write_opt(std::mem::take(batch), &write_opt) {
+            Ok(()) => db.notify_writes(),
+            Err(e) => *error.lock().unwrap() = Some(WriteError::RocksDB(e)),
+        }
+    };
+    loop {
+        let op = match deadline {
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+        };
+        let reply = match op {
+            Ok(Op::Put(key, value)) => {
+                bytes += key.len() + value.len();
+                batch.put_cf(&cf, key, value);
+                None
+            }
+            Ok(Op::Delete(key)) => {
+                bytes += key.len();
+                batch.delete_cf(&cf, key);
+                None
+            }
+            Ok(Op::Flush(reply)) => Some(reply),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => {
+                write(&mut batch);
+                return;
+            }
+        };
+        if deadline.is_none() && !batch.is_empty() {
+            deadline = Some(Instant::now() + config.flush_interval);
+        }
+        let due = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if reply.is_some() || due || batch.len() >= config.max_batch || bytes >= config.max_bytes {
+            write(&mut batch);
+            bytes = 0;
+            deadline = None;
+        }
+        if let Some(reply) = reply {
+            reply.send(()).ok();
+        }
+    }
+}
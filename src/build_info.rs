@@ -0,0 +1,94 @@
+use crate::exceptions::{to_py_err, RocksDBError};
+use crate::options::{compression_type_name, compression_type_supported};
+use crate::rdict::latest_options;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rocksdb::{DBCompressionType, Options, DB};
+use std::fs;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const COMPRESSION_TYPES: [DBCompressionType; 7] = [
+    DBCompressionType::None,
+    DBCompressionType::Snappy,
+    DBCompressionType::Zlib,
+    DBCompressionType::Bz2,
+    DBCompressionType::Lz4,
+    DBCompressionType::Lz4hc,
+    DBCompressionType::Zstd,
+];
+
+/// Facts about this build of rocksdict and of the RocksDB linked in.
+///
+/// Include them in bug reports, or check them when deploying, for
+/// example that a wheel supports the compression types in use.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import build_info
+///
+///         info = build_info()
+///         assert "zstd" in info["compression_types"]
+///
+/// Returns:
+///     a dict with the keys:
+///
+///     - ``rocksdict_version`` (str): version of the Rust crate.
+///     - ``rocksdb_version`` (str): version of the RocksDB linked in, as
+///       written to the OPTIONS files of databases.
+///     - ``compression_types`` (List[str]): names of the compression
+///       types supported, as in `DBCompressionType`.
+///     - ``io_uring`` (bool): whether RocksDB was built with io_uring,
+///       used by `ReadOptions.set_async_io` on Linux.
+///     - ``jemalloc`` (bool): whether RocksDB was built with jemalloc.
+///     - ``target`` (str): operating system and architecture the build
+///       targets, such as ``"macos-aarch64"``.
+#[pyfunction]
+pub(crate) fn build_info(py: Python) -> PyResult<PyObject> {
+    let compression_types = COMPRESSION_TYPES
+        .into_iter()
+        .filter(|t| compression_type_supported(*t))
+        .map(compression_type_name)
+        .collect::<Vec<_>>();
+    let result = PyDict::new_bound(py);
+    result.set_item("rocksdict_version", env!("CARGO_PKG_VERSION"))?;
+    result.set_item("rocksdb_version", rocksdb_version(py)?)?;
+    result.set_item("compression_types", compression_types)?;
+    result.set_item("io_uring", cfg!(feature = "io-uring"))?;
+    result.set_item("jemalloc", cfg!(feature = "jemalloc"))?;
+    result.set_item(
+        "target",
+        format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+    )?;
+    Ok(result.to_object(py))
+}
+
+/// The version of RocksDB, read once from the OPTIONS file of a
+/// temporary database, since the C API does not expose it.
+fn rocksdb_version(py: Python) -> PyResult<String> {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    if let Some(version) = VERSION.get() {
+        return Ok(version.clone());
+    }
+    let dir = std::env::temp_dir().join(format!(
+        "rocksdict-build-info-{}-{}",
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default()
+    ));
+    let version = py.allow_threads(|| {
+        let mut opt = Options::default();
+        opt.create_if_missing(true);
+        let db = DB::open(&opt, &dir).map_err(to_py_err)?;
+        drop(db);
+        latest_options(&dir, "Version").map_err(PyErr::from)
+    });
+    fs::remove_dir_all(&dir).ok();
+    let version = version?
+        .remove("rocksdb_version")
+        .ok_or_else(|| RocksDBError::new_err("rocksdb_version not found in OPTIONS file"))?;
+    Ok(VERSION.get_or_init(|| version).clone())
+}
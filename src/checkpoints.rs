@@ -2,9 +2,11 @@ use crate::{
     db_reference::DbReference,
     ffi_try, ffi_try_impl,
     util::{error_message, to_cpath},
-    Rdict, RocksDictConfig,
+    Rdict, RocksDictConfig, ROCKSDICT_CONFIG_FILE,
 };
 use pyo3::{exceptions::PyException, prelude::*};
+use std::fs;
+use std::path::PathBuf;
 
 /// Database's checkpoint object.
 /// Used to create checkpoints of the specified DB from time to time.
@@ -52,8 +54,8 @@ impl CheckpointPy {
 
     /// Creates new physical DB checkpoint in directory specified by `path`.
     #[pyo3(signature = (path))]
-    pub fn create_checkpoint(&self, path: &str) -> PyResult<()> {
-        let cpath = to_cpath(path)?;
+    pub fn create_checkpoint(&self, path: PathBuf) -> PyResult<()> {
+        let cpath = to_cpath(&path)?;
 
         /// Undocumented parameter for `ffi::rocksdb_checkpoint_create` function. Zero by default.
         const LOG_SIZE_FOR_FLUSH: u64 = 0_u64;
@@ -66,9 +68,45 @@ impl CheckpointPy {
             ));
         }
 
-        self.db_config.save_to_dir(path)?;
+        self.db_config.save_to_dir(&path)?;
         Ok(())
     }
+
+    /// List checkpoints created in subdirectories of `path_root`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Checkpoint
+    ///
+    ///         db = Rdict("./temp_db")
+    ///         checkpoint = Checkpoint(db)
+    ///         checkpoint.create_checkpoint("./temp_checkpoints/1")
+    ///         checkpoint.create_checkpoint("./temp_checkpoints/2")
+    ///         assert Checkpoint.list("./temp_checkpoints") == [
+    ///             "./temp_checkpoints/1",
+    ///             "./temp_checkpoints/2",
+    ///         ]
+    ///
+    /// Args:
+    ///     path_root (str): directory containing checkpoints.
+    ///
+    /// Returns:
+    ///     sorted paths of the subdirectories that contain both a database
+    ///     and the rocksdict config saved with the checkpoint.
+    #[staticmethod]
+    #[pyo3(signature = (path_root))]
+    pub fn list(path_root: PathBuf) -> PyResult<Vec<PathBuf>> {
+        let mut checkpoints = Vec::new();
+        for entry in fs::read_dir(path_root)? {
+            let path = entry?.path();
+            if path.join("CURRENT").is_file() && path.join(ROCKSDICT_CONFIG_FILE).is_file() {
+                checkpoints.push(path);
+            }
+        }
+        checkpoints.sort();
+        Ok(checkpoints)
+    }
 }
 
 impl Drop for CheckpointPy {
@@ -0,0 +1,23 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Compute the crc32c checksum of a whole file.
+pub(crate) fn file_crc32c(path: &Path) -> io::Result<u32> {
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; 1 << 20];
+    let mut crc = 0u32;
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => return Ok(crc),
+            Ok(n) => crc = crc32c::crc32c_append(crc, &buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Format a checksum the way it is returned to python.
+pub(crate) fn format_checksum(crc: u32) -> String {
+    format!("{crc:08x}")
+}
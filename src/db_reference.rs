@@ -1,22 +1,178 @@
+use crate::key_locks::{KeyGuard, KeyLocks};
+use crate::util::TempDir;
 use rocksdb::{DBWithThreadMode, MultiThreaded};
-use std::sync::Arc;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::Duration;
 
 /// The type of a reference to a [rocksdb::DB] that is passed around the library.
 pub(crate) type DbReference = Arc<DBWithThreadMode<MultiThreaded>>;
 
+/// Paths and weak references of all databases opened by this process,
+/// transactional or not.
+static OPEN_DBS: Mutex<Vec<(PathBuf, Weak<dyn Any + Send + Sync>)>> = Mutex::new(Vec::new());
+
 /// A wrapper around [DbReference] that cancels all background work when dropped.
 ///
 /// All users of [rocksdb::DB] should use this wrapper instead to avoid keeping background threads
 /// alive after the database is dropped.
-#[derive(Clone)]
 pub(crate) struct DbReferenceHolder {
     inner: Option<DbReference>,
+    /// What holds this reference, reported by `Rdict.open_handles`.
+    kind: &'static str,
+    /// Open references of all handles of the database.
+    handles: Arc<OpenHandles>,
+    /// Directory removed after the database is dropped, for temporary databases.
+    temp_dir: Option<Arc<TempDir>>,
+    /// Wakes up `wait_for` callers after writes through any handle of the database.
+    writes: Arc<WriteNotifier>,
+    /// Locks of keys under atomic read-modify-write operations.
+    key_locks: Arc<KeyLocks>,
+    /// Rejects writes through any handle of the database once frozen.
+    write_gate: Arc<WriteGate>,
 }
 
 impl DbReferenceHolder {
     pub fn new(db: DBWithThreadMode<MultiThreaded>) -> Self {
+        let path = db.path().to_path_buf();
+        let db = register_open(&path, db);
+        let handles = Arc::new(OpenHandles::default());
+        handles.add(DATABASE);
         Self {
-            inner: Some(Arc::new(db)),
+            inner: Some(db),
+            kind: DATABASE,
+            handles,
+            temp_dir: None,
+            writes: Arc::default(),
+            key_locks: Arc::default(),
+            write_gate: Arc::default(),
+        }
+    }
+
+    /// Another reference to the database, held by a handle of `kind`.
+    pub fn with_kind(&self, kind: &'static str) -> Self {
+        if self.inner.is_some() {
+            self.handles.add(kind);
+        }
+        Self {
+            inner: self.inner.clone(),
+            kind,
+            handles: self.handles.clone(),
+            temp_dir: self.temp_dir.clone(),
+            writes: self.writes.clone(),
+            key_locks: self.key_locks.clone(),
+            write_gate: self.write_gate.clone(),
+        }
+    }
+
+    /// Register a handle to be released by `release_handles`.
+    pub fn register_release(&self, handle: Weak<dyn ReleaseHandle>) {
+        let mut releasable = self.handles.releasable.lock().unwrap();
+        releasable.retain(|handle| handle.strong_count() > 0);
+        releasable.push(handle);
+    }
+
+    /// Release all registered handles, such as iterators and snapshots,
+    /// together with their references to the database.
+    ///
+    /// Blocks until the handles are not in use, so must be called without the GIL.
+    pub fn release_handles(&self) {
+        let releasable = std::mem::take(&mut *self.handles.releasable.lock().unwrap());
+        for handle in releasable.iter().filter_map(Weak::upgrade) {
+            handle.release();
+        }
+    }
+
+    /// Number of references to the database other than this one, by kind.
+    pub fn open_handles(&self) -> HashMap<&'static str, usize> {
+        let mut counts = self.handles.counts.lock().unwrap().clone();
+        let Some(db) = &self.inner else {
+            return counts;
+        };
+        if let Some(count) = counts.get_mut(self.kind) {
+            *count -= 1;
+        }
+        // references not held by a DbReferenceHolder, such as checkpoints
+        let holders: usize = counts.values().sum::<usize>() + 1;
+        let other = Arc::strong_count(db).saturating_sub(holders);
+        if other > 0 {
+            *counts.entry("other").or_default() += other;
+        }
+        counts.retain(|_, count| *count > 0);
+        counts
+    }
+
+    /// Lock `key` of column family `cf` against other atomic read-modify-write operations.
+    pub fn lock_key(&self, cf: usize, key: &[u8]) -> KeyGuard {
+        self.key_locks.lock(cf, key)
+    }
+
+    /// Start watching for writes to the database.
+    pub fn watch_writes(&self) -> WriteWatch {
+        WriteWatch::new(self.writes.clone())
+    }
+
+    /// Wake up threads watching for writes, to be called after each successful write.
+    pub fn notify_writes(&self) {
+        self.writes.notify();
+    }
+
+    /// Hold off `set_frozen` until the returned guard is dropped,
+    /// or `None` if the database is frozen.
+    pub fn begin_write(&self) -> Option<WriteGuard> {
+        let mut state = self.write_gate.state.lock().unwrap();
+        if state.frozen {
+            return None;
+        }
+        state.writes += 1;
+        Some(WriteGuard(self.write_gate.clone()))
+    }
+
+    /// Reject or accept writes through any handle of the database.
+    ///
+    /// Freezing blocks until writes in progress are done, so must be called without the GIL.
+    pub fn set_frozen(&self, frozen: bool) {
+        let mut state = self.write_gate.state.lock().unwrap();
+        state.frozen = frozen;
+        if frozen {
+            let idle = &self.write_gate.idle;
+            let _state = idle.wait_while(state, |s| s.writes > 0).unwrap();
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.write_gate.state.lock().unwrap().frozen
+    }
+
+    /// Remove `dir` once all references to the database are closed.
+    pub fn remove_on_close(&mut self, dir: PathBuf) {
+        self.temp_dir = Some(Arc::new(TempDir::from_path(dir)));
+    }
+
+    /// Close the database shared by `holders`, to be opened again by `reopen`.
+    ///
+    /// `holders` must be all references to the database. Unlike `close`,
+    /// they still count as open handles.
+    pub fn close_shared(holders: &mut [&mut DbReferenceHolder]) {
+        for holder in holders.iter_mut() {
+            if let Some(db) = holder.inner.take().and_then(Arc::into_inner) {
+                db.cancel_all_background_work(true);
+            }
+        }
+        // the database is dropped by now
+    }
+
+    /// Share the reopened `db` with `holders` closed by `close_shared`.
+    pub fn reopen(holders: &mut [&mut DbReferenceHolder], db: DBWithThreadMode<MultiThreaded>) {
+        let path = db.path().to_path_buf();
+        let db = register_open(&path, db);
+        for holder in holders.iter_mut() {
+            holder.inner = Some(db.clone());
         }
     }
 
@@ -25,9 +181,20 @@ impl DbReferenceHolder {
     }
 
     pub fn close(&mut self) {
-        if let Some(db) = self.inner.take().and_then(Arc::into_inner) {
-            db.cancel_all_background_work(true);
+        if let Some(db) = self.inner.take() {
+            self.handles.remove(self.kind);
+            if let Some(db) = Arc::into_inner(db) {
+                db.cancel_all_background_work(true);
+            }
         }
+        // the database is dropped by now if this is the last reference
+        drop(self.temp_dir.take());
+    }
+}
+
+impl Clone for DbReferenceHolder {
+    fn clone(&self) -> Self {
+        self.with_kind(self.kind)
     }
 }
 
@@ -36,3 +203,256 @@ impl Drop for DbReferenceHolder {
         self.close();
     }
 }
+
+/// Kind of the reference held by the `Rdict` that opened the database.
+const DATABASE: &str = "database";
+
+/// A handle that `Rdict.close(force=True)` releases before closing the database.
+pub(crate) trait ReleaseHandle: Send + Sync {
+    /// Release the handle and its reference to the database, at most once.
+    fn release(&self);
+}
+
+/// Open references of all handles of a database.
+#[derive(Default)]
+struct OpenHandles {
+    /// Number of open [DbReferenceHolder]s of each kind.
+    counts: Mutex<HashMap<&'static str, usize>>,
+    /// Handles released by a forced close.
+    releasable: Mutex<Vec<Weak<dyn ReleaseHandle>>>,
+}
+
+impl OpenHandles {
+    fn add(&self, kind: &'static str) {
+        *self.counts.lock().unwrap().entry(kind).or_default() += 1;
+    }
+
+    fn remove(&self, kind: &'static str) {
+        let mut counts = self.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(kind) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(kind);
+            }
+        }
+    }
+}
+
+/// Writes in progress, and whether new writes are rejected.
+///
+/// Writers never wait on the gate, so that a writer holding the GIL
+/// cannot block a frozen database waiting for another writer.
+#[derive(Default)]
+pub(crate) struct WriteGate {
+    state: Mutex<WriteGateState>,
+    idle: Condvar,
+}
+
+#[derive(Default)]
+struct WriteGateState {
+    frozen: bool,
+    writes: usize,
+}
+
+/// A write in progress, returned by `DbReferenceHolder::begin_write`.
+pub(crate) struct WriteGuard(Arc<WriteGate>);
+
+impl Drop for WriteGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        state.writes -= 1;
+        if state.writes == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
+
+/// Counts writes to a database while threads are waiting for them.
+#[derive(Default)]
+pub(crate) struct WriteNotifier {
+    watchers: AtomicUsize,
+    generation: Mutex<u64>,
+    written: Condvar,
+}
+
+impl WriteNotifier {
+    fn notify(&self) {
+        // writes stay cheap when nobody is watching
+        if self.watchers.load(Ordering::SeqCst) > 0 {
+            *self.generation.lock().unwrap() += 1;
+            self.written.notify_all();
+        }
+    }
+}
+
+/// Registration of a thread waiting for writes to a database.
+///
+/// Writes completed after the watch is created are never missed:
+/// check the database after creating the watch, then `wait`.
+pub(crate) struct WriteWatch {
+    notifier: Arc<WriteNotifier>,
+    generation: u64,
+}
+
+impl WriteWatch {
+    fn new(notifier: Arc<WriteNotifier>) -> Self {
+        notifier.watchers.fetch_add(1, Ordering::SeqCst);
+        let generation = *notifier.generation.lock().unwrap();
+        Self {
+            notifier,
+            generation,
+        }
+    }
+
+    /// Wait at most `timeout` for a write since the last call.
+    ///
+    /// Returns whether any write happened.
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        let generation = self.notifier.generation.lock().unwrap();
+        let (generation, _) = self
+            .notifier
+            .written
+            .wait_timeout_while(generation, timeout, |g| *g == self.generation)
+            .unwrap();
+        let written = *generation != self.generation;
+        self.generation = *generation;
+        written
+    }
+}
+
+impl Drop for WriteWatch {
+    fn drop(&mut self) {
+        self.notifier.watchers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Share `db` opened at `path`, remembering it is open for `is_open_in_process`
+/// until all references to it are dropped.
+pub(crate) fn register_open<T: Send + Sync + 'static>(path: &Path, db: T) -> Arc<T> {
+    let path = canonical_path(path);
+    let db = Arc::new(db);
+    let weak: Weak<dyn Any + Send + Sync> = Arc::downgrade(&db) as _;
+    let mut open_dbs = OPEN_DBS.lock().unwrap();
+    open_dbs.retain(|(_, db)| db.strong_count() > 0);
+    open_dbs.push((path, weak));
+    db
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Whether the database at `path` is still open in this process.
+///
+/// A database stays open as long as any `Rdict`, column family,
+/// iterator, or snapshot instance referencing it is alive, and a
+/// transactional database as long as it or any of its transactions is.
+pub(crate) fn is_open_in_process(path: &Path) -> bool {
+    let path = canonical_path(path);
+    OPEN_DBS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|(p, db)| db.strong_count() > 0 && *p == path)
+}
+
+/// Whether the `LOCK` file of the database at `path` is held by another process.
+///
+/// Must not be called while this process holds the lock: closing
+/// any file descriptor of the `LOCK` file releases POSIX record locks
+/// of the whole process.
+#[cfg(unix)]
+pub(crate) fn is_locked_by_other_process(path: &Path) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    let lock_file = match fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path.join("LOCK"))
+    {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as _;
+    lock.l_whence = libc::SEEK_SET as _;
+    if unsafe { libc::fcntl(lock_file.as_raw_fd(), libc::F_GETLK, &mut lock) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(lock.l_type != libc::F_UNLCK as _)
+}
+
+/// Whether the `LOCK` file of the database at `path` is held by another process.
+///
+/// RocksDB opens the `LOCK` file without sharing on Windows,
+/// so opening it fails with a sharing violation while it is held.
+#[cfg(windows)]
+pub(crate) fn is_locked_by_other_process(path: &Path) -> io::Result<bool> {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    match fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path.join("LOCK"))
+    {
+        Ok(_) => Ok(false),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => Ok(true),
+        Err(e) => Err(e),
+    }
+}
+
+/// The `LOCK` file of a database, locked the way RocksDB locks it.
+/// Other processes cannot open the database until it is dropped.
+pub(crate) struct DbLock {
+    _file: fs::File,
+}
+
+/// Lock the database at `path`, or return `None` if its `LOCK` file
+/// is held by another process.
+///
+/// Must not be called while this process holds the lock, see
+/// `is_locked_by_other_process`.
+#[cfg(unix)]
+pub(crate) fn try_lock_db(path: &Path) -> io::Result<Option<DbLock>> {
+    use std::os::unix::io::AsRawFd;
+
+    let lock_file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path.join("LOCK"))?;
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as _;
+    lock.l_whence = libc::SEEK_SET as _;
+    if unsafe { libc::fcntl(lock_file.as_raw_fd(), libc::F_SETLK, &lock) } == -1 {
+        let e = io::Error::last_os_error();
+        return match e.raw_os_error() {
+            Some(libc::EACCES | libc::EAGAIN) => Ok(None),
+            _ => Err(e),
+        };
+    }
+    Ok(Some(DbLock { _file: lock_file }))
+}
+
+/// Lock the database at `path`, or return `None` if its `LOCK` file
+/// is held by another process.
+#[cfg(windows)]
+pub(crate) fn try_lock_db(path: &Path) -> io::Result<Option<DbLock>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    match fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .share_mode(0)
+        .open(path.join("LOCK"))
+    {
+        Ok(file) => Ok(Some(DbLock { _file: file })),
+        Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
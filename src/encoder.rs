@@ -1,8 +1,50 @@
-use num_bigint::BigInt;
-use pyo3::exceptions::{PyException, PyKeyError, PyValueError};
+use crate::exceptions::CorruptionError;
+use crate::options::rocksdict_compare;
+use crate::rdict::{
+    COMPARATOR_BYTEWISE, COMPARATOR_ROCKSDICT, KEY_CODEC_COMPOSITE, KEY_CODEC_ORDERED,
+    KEY_CODEC_RAW, KEY_CODEC_ROCKSDICT, KEY_CODEC_TYPED, KEY_CODEC_U64_BE,
+};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use num_bigint::{BigInt, Sign};
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{PyException, PyKeyError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyBool, PyBytes, PyFloat, PyInt, PyString};
+use pyo3::types::{PyBool, PyBytes, PyFloat, PyInt, PyString, PyTuple};
 use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Type byte of values encrypted by `ValueCodec`, followed by the nonce
+/// and the AES-GCM ciphertext of the encoded value.
+///
+/// The ciphertext is authenticated together with the encoded key and the
+/// name of the column family, see `associated_data`, so that a value cannot
+/// be moved to another key or column family without failing to decrypt.
+const ENCRYPTED_TYPE: u8 = 7;
+
+/// Size of AES-GCM nonces.
+const NONCE_SIZE: usize = 12;
+
+/// Size of AES-GCM authentication tags.
+const TAG_SIZE: usize = 16;
+
+/// Type byte of pickled values.
+const PICKLE_TYPE: u8 = 6;
+
+/// Flag bit in the type byte of values compressed by `ValueCodec`.
+const COMPRESSED_FLAG: u8 = 0x80;
+
+/// Flag bit in the type byte of values followed by their crc32c checksum.
+const CHECKSUM_FLAG: u8 = 0x40;
+
+/// Size of value checksums.
+const CHECKSUM_SIZE: usize = 4;
+
+/// Sign bit of the IEEE 754 bits of floats.
+const SIGN_BIT: u64 = 1 << 63;
 
 pub(crate) enum ValueTypes<'a, 'b> {
     Bytes(&'a [u8]),
@@ -13,6 +55,74 @@ pub(crate) enum ValueTypes<'a, 'b> {
     Any(&'a Bound<'b, PyAny>),
 }
 
+/// Encoded bytes of a key or value.
+///
+/// In raw mode, bytes are borrowed from `bytes` objects, or held through
+/// the python buffer protocol (`memoryview`, `bytearray`, numpy arrays, etc.),
+/// which pins the underlying memory without copying.
+pub(crate) enum EncodedBytes<'a> {
+    Borrowed(&'a [u8]),
+    Owned(Vec<u8>),
+    Buffer(PyBuffer<u8>),
+}
+
+impl Deref for EncodedBytes<'_> {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        match self {
+            EncodedBytes::Borrowed(bytes) => bytes,
+            EncodedBytes::Owned(bytes) => bytes,
+            EncodedBytes::Buffer(buffer) => {
+                if buffer.len_bytes() == 0 {
+                    &[]
+                } else {
+                    // SAFETY: the buffer is C-contiguous (checked in `from_buffer`),
+                    // and the exporter keeps the memory alive and unresized
+                    // until the buffer is released on drop.
+                    unsafe {
+                        std::slice::from_raw_parts(
+                            buffer.buf_ptr() as *const u8,
+                            buffer.len_bytes(),
+                        )
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AsRef<[u8]> for EncodedBytes<'_> {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<EncodedBytes<'_>> for Vec<u8> {
+    fn from(bytes: EncodedBytes<'_>) -> Self {
+        match bytes {
+            EncodedBytes::Owned(bytes) => bytes,
+            other => other.to_vec(),
+        }
+    }
+}
+
+impl EncodedBytes<'_> {
+    /// Try to view any object exporting the buffer protocol as bytes,
+    /// copying only if the buffer is not C-contiguous.
+    #[inline(always)]
+    fn from_buffer(obj: &Bound<PyAny>) -> Option<PyResult<Self>> {
+        let buffer = PyBuffer::<u8>::get_bound(obj).ok()?;
+        if buffer.is_c_contiguous() {
+            Some(Ok(EncodedBytes::Buffer(buffer)))
+        } else {
+            Some(buffer.to_vec(obj.py()).map(EncodedBytes::Owned))
+        }
+    }
+}
+
 #[inline(always)]
 pub(crate) fn encoding_byte(v_type: &ValueTypes) -> u8 {
     match v_type {
@@ -25,24 +135,145 @@ pub(crate) fn encoding_byte(v_type: &ValueTypes) -> u8 {
     }
 }
 
+/// How keys are encoded into bytes, recorded as `key_codec` in `rocksdict-config.json`.
+///
+/// - `Raw`: keys are bytes, stored as is.
+/// - `Rocksdict`: keys are prefixed with their type byte, and int keys
+///   are stored as signed big-endian bytes, ordered by the `rocksdict` comparator.
+/// - `Ordered`: like `Rocksdict`, but int and float keys are stored so that
+///   bytewise order is their numeric order, see `encode_ordered_int`.
+/// - `U64Be`: keys are ints in `[0, 2**64)`, stored as 8 big-endian bytes
+///   without type byte, as `rocksdict.keys.u64_be` does.
+/// - `Composite`: keys are tuples, stored as the concatenation of their
+///   elements, see `encode_composite_key`.
+///
+/// Column families can use another codec than the database, recorded
+/// in `column_family_key_codecs` of `rocksdict-config.json`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum KeyCodec {
+    Raw,
+    Rocksdict,
+    Ordered,
+    U64Be,
+    Composite,
+}
+
+/// Key codecs of a database, and of its column families using another one.
+#[derive(Clone)]
+pub(crate) struct KeyCodecs {
+    /// codec of the database, used by column families not listed
+    pub(crate) default: KeyCodec,
+    pub(crate) column_families: HashMap<String, KeyCodec>,
+}
+
+impl KeyCodecs {
+    pub(crate) fn new(default: KeyCodec) -> Self {
+        KeyCodecs {
+            default,
+            column_families: HashMap::new(),
+        }
+    }
+
+    /// Key codec of the column family `name`.
+    pub(crate) fn get(&self, name: &str) -> KeyCodec {
+        self.column_families
+            .get(name)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+impl From<bool> for KeyCodec {
+    /// The default key codec of `raw_mode`.
+    fn from(raw_mode: bool) -> Self {
+        if raw_mode {
+            KeyCodec::Raw
+        } else {
+            KeyCodec::Rocksdict
+        }
+    }
+}
+
+impl KeyCodec {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            KeyCodec::Raw => KEY_CODEC_RAW,
+            KeyCodec::Rocksdict => KEY_CODEC_ROCKSDICT,
+            KeyCodec::Ordered => KEY_CODEC_ORDERED,
+            KeyCodec::U64Be => KEY_CODEC_U64_BE,
+            KeyCodec::Composite => KEY_CODEC_COMPOSITE,
+        }
+    }
+
+    /// Parse a codec name, `typed` being an alias of `rocksdict`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            KEY_CODEC_RAW => Some(KeyCodec::Raw),
+            KEY_CODEC_ROCKSDICT | KEY_CODEC_TYPED => Some(KeyCodec::Rocksdict),
+            KEY_CODEC_ORDERED => Some(KeyCodec::Ordered),
+            KEY_CODEC_U64_BE => Some(KeyCodec::U64Be),
+            KEY_CODEC_COMPOSITE => Some(KeyCodec::Composite),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_raw(self) -> bool {
+        self == KeyCodec::Raw
+    }
+
+    /// Name of the comparator ordering keys encoded by this codec.
+    pub(crate) fn comparator_name(self) -> &'static str {
+        match self {
+            KeyCodec::Rocksdict => COMPARATOR_ROCKSDICT,
+            _ => COMPARATOR_BYTEWISE,
+        }
+    }
+
+    /// The key order of the database, as a function of encoded keys.
+    pub(crate) fn compare(self) -> fn(&[u8], &[u8]) -> Ordering {
+        match self {
+            KeyCodec::Rocksdict => rocksdict_compare,
+            _ => <[u8]>::cmp,
+        }
+    }
+}
+
 #[inline(always)]
-pub(crate) fn encode_key<'a>(key: &'a Bound<PyAny>, raw_mode: bool) -> PyResult<Cow<'a, [u8]>> {
-    if raw_mode {
+pub(crate) fn encode_key<'a>(
+    key: &'a Bound<PyAny>,
+    key_codec: impl Into<KeyCodec>,
+) -> PyResult<EncodedBytes<'a>> {
+    let key_codec = key_codec.into();
+    if key_codec == KeyCodec::Raw {
         return if let Ok(value) = key.downcast::<PyBytes>() {
-            Ok(Cow::Borrowed(value.as_bytes()))
+            Ok(EncodedBytes::Borrowed(value.as_bytes()))
+        } else if let Some(bytes) = EncodedBytes::from_buffer(key) {
+            bytes
         } else {
-            Err(PyKeyError::new_err("raw mode only support bytes"))
+            Err(PyKeyError::new_err(
+                "raw mode only support bytes or bytes-like objects",
+            ))
         };
     }
+    match key_codec {
+        KeyCodec::U64Be => return encode_u64_be_key(key).map(EncodedBytes::Owned),
+        KeyCodec::Composite => return encode_composite_key(key).map(EncodedBytes::Owned),
+        _ => {}
+    }
     let bytes = py_to_value_types(key)?;
     let type_encoding = encoding_byte(&bytes);
     let owned_bytes = match bytes {
         ValueTypes::Bytes(value) => Ok(concat_type_encoding(type_encoding, value)),
         ValueTypes::String(value) => Ok(concat_type_encoding(type_encoding, value.as_bytes())),
+        ValueTypes::Int(value) if key_codec == KeyCodec::Ordered => Ok(encode_ordered_int(&value)),
         ValueTypes::Int(value) => Ok(concat_type_encoding(
             type_encoding,
             &value.to_signed_bytes_be()[..],
         )),
+        ValueTypes::Float(value) if key_codec == KeyCodec::Ordered => {
+            Ok(encode_ordered_float(value))
+        }
         ValueTypes::Float(value) => Ok(concat_type_encoding(
             type_encoding,
             &value.to_be_bytes()[..],
@@ -55,7 +286,258 @@ pub(crate) fn encode_key<'a>(key: &'a Bound<PyAny>, raw_mode: bool) -> PyResult<
             "Only support `string`, `int`, `float`, `bool`, and `bytes` as keys",
         )),
     }?;
-    Ok(Cow::Owned(owned_bytes))
+    Ok(EncodedBytes::Owned(owned_bytes))
+}
+
+/// Encode an int key of the `u64_be` key codec.
+fn encode_u64_be_key(key: &Bound<PyAny>) -> PyResult<Vec<u8>> {
+    if key.is_instance_of::<PyBool>() || !key.is_instance_of::<PyInt>() {
+        return Err(PyTypeError::new_err(
+            "key codec `u64_be` only supports int keys",
+        ));
+    }
+    let value: u64 = key.extract().map_err(|_| {
+        PyValueError::new_err("key codec `u64_be` only supports ints in [0, 2**64)")
+    })?;
+    Ok(value.to_be_bytes().to_vec())
+}
+
+/// Encode a tuple key of the `composite` key codec.
+///
+/// Each element is encoded with its type byte, `str` and `bytes` elements
+/// with each `0x00` byte escaped as `0x00 0xFF` and followed by a `0x00`
+/// terminator, and int and float elements as in the `ordered` key codec.
+/// The bytewise order of encoded keys is thus the order of tuples,
+/// elements of different types being ordered by type.
+fn encode_composite_key(key: &Bound<PyAny>) -> PyResult<Vec<u8>> {
+    let tuple = key
+        .downcast::<PyTuple>()
+        .map_err(|_| PyTypeError::new_err("key codec `composite` only supports tuple keys"))?;
+    let mut output = Vec::new();
+    for element in tuple.iter() {
+        let element = py_to_value_types(&element)?;
+        let type_encoding = encoding_byte(&element);
+        match element {
+            ValueTypes::Bytes(value) => escape_composite_element(&mut output, type_encoding, value),
+            ValueTypes::String(value) => {
+                escape_composite_element(&mut output, type_encoding, value.as_bytes())
+            }
+            ValueTypes::Int(value) => output.extend(encode_ordered_int(&value)),
+            ValueTypes::Float(value) => output.extend(encode_ordered_float(value)),
+            ValueTypes::Bool(value) => output.extend([type_encoding, value as u8]),
+            ValueTypes::Any(_) => {
+                return Err(PyTypeError::new_err(
+                    "Only support `string`, `int`, `float`, `bool`, and `bytes` in tuple keys",
+                ))
+            }
+        }
+    }
+    Ok(output)
+}
+
+fn escape_composite_element(output: &mut Vec<u8>, type_encoding: u8, value: &[u8]) {
+    output.push(type_encoding);
+    for &b in value {
+        output.push(b);
+        if b == 0x00 {
+            output.push(0xFF);
+        }
+    }
+    output.push(0x00);
+}
+
+fn decode_composite_key(py: Python, bytes: &[u8]) -> PyResult<PyObject> {
+    let invalid = || CorruptionError::new_err("invalid composite key");
+    let mut elements = Vec::new();
+    let mut rest = bytes;
+    while let Some((&type_encoding, payload)) = rest.split_first() {
+        match type_encoding {
+            1 | 2 => {
+                let mut value = Vec::new();
+                let mut i = 0;
+                loop {
+                    match payload.get(i) {
+                        Some(&0x00) if payload.get(i + 1) == Some(&0xFF) => {
+                            value.push(0x00);
+                            i += 2;
+                        }
+                        Some(&0x00) => break,
+                        Some(&b) => {
+                            value.push(b);
+                            i += 1;
+                        }
+                        None => return Err(invalid()),
+                    }
+                }
+                rest = &payload[i + 1..];
+                elements.push(if type_encoding == 1 {
+                    PyBytes::new_bound(py, &value).to_object(py)
+                } else {
+                    String::from_utf8(value)
+                        .map_err(|_| invalid())?
+                        .to_object(py)
+                });
+            }
+            3 => {
+                let len = payload.get(1..5).ok_or_else(invalid)?;
+                let mut len: [u8; 4] = len.try_into().unwrap();
+                if payload[0] == 0x00 {
+                    len = len.map(|b| !b);
+                }
+                let end = 5 + u32::from_be_bytes(len) as usize;
+                let element = payload.get(..end).ok_or_else(invalid)?;
+                elements.push(decode_ordered_int(element)?.to_object(py));
+                rest = &payload[end..];
+            }
+            4 => {
+                let element = payload.get(..8).ok_or_else(invalid)?;
+                elements.push(decode_ordered_float(element)?.into_py(py));
+                rest = &payload[8..];
+            }
+            5 => {
+                let (&value, payload) = payload.split_first().ok_or_else(invalid)?;
+                elements.push(PyBool::new_bound(py, value != 0).to_object(py));
+                rest = payload;
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(PyTuple::new_bound(py, elements).to_object(py))
+}
+
+/// Encode an int key of the `ordered` key codec.
+///
+/// The type byte `3` is followed by a sign byte, `0x00` for negative
+/// and `0x01` for non-negative ints, the byte length of the magnitude
+/// as a 4-byte big-endian integer, and the big-endian magnitude without
+/// leading zeros (empty for `0`). The length and magnitude of negative
+/// ints are bitwise inverted, so that the bytewise order of encoded keys
+/// is the numeric order of ints of any size.
+fn encode_ordered_int(value: &BigInt) -> Vec<u8> {
+    let negative = value.sign() == Sign::Minus;
+    let magnitude = match value.sign() {
+        Sign::NoSign => Vec::new(),
+        _ => value.magnitude().to_bytes_be(),
+    };
+    let mut output = Vec::with_capacity(magnitude.len() + 6);
+    output.push(3);
+    output.push(if negative { 0x00 } else { 0x01 });
+    let len = (magnitude.len() as u32).to_be_bytes();
+    if negative {
+        output.extend(len.iter().map(|b| !b));
+        output.extend(magnitude.iter().map(|b| !b));
+    } else {
+        output.extend_from_slice(&len);
+        output.extend_from_slice(&magnitude);
+    }
+    output
+}
+
+/// Encode a float key of the `ordered` key codec.
+///
+/// The type byte `4` is followed by the big-endian IEEE 754 bits, with
+/// the sign bit flipped for positive floats and all bits inverted for
+/// negative floats, so that the bytewise order of encoded keys is the
+/// numeric order of floats (`-0.0` sorts before `0.0`, and `nan` after `inf`).
+fn encode_ordered_float(value: f64) -> Vec<u8> {
+    let bits = value.to_bits();
+    let bits = if bits & SIGN_BIT == 0 {
+        bits ^ SIGN_BIT
+    } else {
+        !bits
+    };
+    concat_type_encoding(4, &bits.to_be_bytes())
+}
+
+fn decode_ordered_int(payload: &[u8]) -> PyResult<BigInt> {
+    let invalid = || CorruptionError::new_err("invalid ordered int key");
+    let (&sign, rest) = payload.split_first().ok_or_else(invalid)?;
+    if rest.len() < 4 || sign > 1 {
+        return Err(invalid());
+    }
+    let (len, magnitude) = rest.split_at(4);
+    let negative = sign == 0x00;
+    let mut len: [u8; 4] = len.try_into().unwrap();
+    if negative {
+        len = len.map(|b| !b);
+    }
+    if u32::from_be_bytes(len) as usize != magnitude.len() {
+        return Err(invalid());
+    }
+    if negative {
+        let magnitude = magnitude.iter().map(|b| !b).collect::<Vec<_>>();
+        Ok(BigInt::from_bytes_be(Sign::Minus, &magnitude))
+    } else {
+        Ok(BigInt::from_bytes_be(Sign::Plus, magnitude))
+    }
+}
+
+fn decode_ordered_float(payload: &[u8]) -> PyResult<f64> {
+    let bits: [u8; 8] = payload
+        .try_into()
+        .map_err(|_| CorruptionError::new_err("invalid ordered float key"))?;
+    let bits = u64::from_be_bytes(bits);
+    let bits = if bits & SIGN_BIT != 0 {
+        bits ^ SIGN_BIT
+    } else {
+        !bits
+    };
+    Ok(f64::from_bits(bits))
+}
+
+/// Re-encode a key encoded with the key codec `from` to the key codec `to`
+/// of non-raw mode, without going through Python objects.
+pub(crate) fn transcode_key(key: &[u8], from: KeyCodec, to: KeyCodec) -> PyResult<Vec<u8>> {
+    match key.split_first() {
+        Some((3, payload)) if from != to => {
+            let value = match from {
+                KeyCodec::Ordered => decode_ordered_int(payload)?,
+                _ => BigInt::from_signed_bytes_be(payload),
+            };
+            Ok(match to {
+                KeyCodec::Ordered => encode_ordered_int(&value),
+                _ => concat_type_encoding(3, &value.to_signed_bytes_be()),
+            })
+        }
+        Some((4, payload)) if from != to => {
+            let value = match from {
+                KeyCodec::Ordered => decode_ordered_float(payload)?,
+                _ => f64::from_be_bytes(
+                    payload
+                        .try_into()
+                        .map_err(|_| CorruptionError::new_err("invalid float key"))?,
+                ),
+            };
+            Ok(match to {
+                KeyCodec::Ordered => encode_ordered_float(value),
+                _ => concat_type_encoding(4, &value.to_be_bytes()),
+            })
+        }
+        _ => Ok(key.to_vec()),
+    }
+}
+
+/// Decode a key encoded by `encode_key` with `key_codec`.
+#[inline(always)]
+pub(crate) fn decode_key(
+    py: Python,
+    bytes: &[u8],
+    loads: &PyObject,
+    key_codec: impl Into<KeyCodec>,
+) -> PyResult<PyObject> {
+    let key_codec = key_codec.into();
+    match (key_codec, bytes.split_first()) {
+        (KeyCodec::Ordered, Some((3, payload))) => Ok(decode_ordered_int(payload)?.to_object(py)),
+        (KeyCodec::Ordered, Some((4, payload))) => Ok(decode_ordered_float(payload)?.into_py(py)),
+        (KeyCodec::U64Be, _) => {
+            let value: [u8; 8] = bytes
+                .try_into()
+                .map_err(|_| CorruptionError::new_err("invalid u64_be key"))?;
+            Ok(u64::from_be_bytes(value).into_py(py))
+        }
+        (KeyCodec::Composite, _) => decode_composite_key(py, bytes),
+        _ => decode_value(py, bytes, loads, key_codec.is_raw()),
+    }
 }
 
 ///
@@ -68,12 +550,17 @@ pub(crate) fn encode_value<'a>(
     value: &'a Bound<PyAny>,
     dumps: &PyObject,
     raw_mode: bool,
-) -> PyResult<Cow<'a, [u8]>> {
+    strict_types: bool,
+) -> PyResult<EncodedBytes<'a>> {
     if raw_mode {
         if let Ok(value) = value.downcast::<PyBytes>() {
-            Ok(Cow::Borrowed(value.as_bytes()))
+            Ok(EncodedBytes::Borrowed(value.as_bytes()))
+        } else if let Some(bytes) = EncodedBytes::from_buffer(value) {
+            bytes
         } else {
-            Err(PyValueError::new_err("raw mode only support bytes"))
+            Err(PyValueError::new_err(
+                "raw mode only support bytes or bytes-like objects",
+            ))
         }
     } else {
         let bytes = py_to_value_types(value)?;
@@ -90,6 +577,13 @@ pub(crate) fn encode_value<'a>(
             ValueTypes::Bool(value) => {
                 concat_type_encoding(type_encoding, if value { &[1u8] } else { &[0u8] })
             }
+            ValueTypes::Any(value) if strict_types => {
+                return Err(PyTypeError::new_err(format!(
+                    "strict typing refuses to pickle a value of type `{}`, \
+                     only str, int, float, bool and bytes are allowed",
+                    value.get_type().name()?
+                )))
+            }
             ValueTypes::Any(value) => {
                 let py = value.py();
                 let pickle_bytes = dumps.call1(py, (value,))?;
@@ -97,7 +591,7 @@ pub(crate) fn encode_value<'a>(
                 concat_type_encoding(type_encoding, bytes)
             }
         };
-        Ok(Cow::Owned(owned_bytes))
+        Ok(EncodedBytes::Owned(owned_bytes))
     }
 }
 
@@ -155,11 +649,302 @@ pub(crate) fn decode_value(
             }
             5 => Ok(PyBool::new_bound(py, bytes[1] != 0).to_object(py)),
             6 => loads.call1(py, (PyBytes::new_bound(py, &bytes[1..]),)),
+            ENCRYPTED_TYPE => Err(PyValueError::new_err(
+                "value is encrypted, set the key with `Rdict.set_value_encryption`",
+            )),
             _ => Err(PyException::new_err("Unknown value type")),
         },
     }
 }
 
+/// Stages applied to encoded values in non-raw mode, after `encode_value`
+/// and before `decode_value`.
+///
+/// Values written without a stage are decoded as is, so stages can be
+/// enabled on existing databases.
+#[derive(Clone)]
+pub(crate) struct ValueCodec {
+    raw_mode: bool,
+    checksums: bool,
+    compression: Option<Compression>,
+    encryption: Option<Arc<Encryption>>,
+}
+
+/// Encryption of values, see `Rdict.set_value_encryption`.
+struct Encryption {
+    cipher: Aes256Gcm,
+    /// Key of `cipher`, to tell whether two codecs encrypt alike.
+    key: Vec<u8>,
+    /// Column family of the encrypted values, authenticated with them.
+    column_family: String,
+}
+
+impl Encryption {
+    /// Data authenticated together with the value of `key`: the length of
+    /// the column family name as 4 little-endian bytes, the name, then the key.
+    fn associated_data(&self, key: &[u8]) -> Vec<u8> {
+        let name = self.column_family.as_bytes();
+        let mut aad = Vec::with_capacity(4 + name.len() + key.len());
+        aad.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        aad.extend_from_slice(name);
+        aad.extend_from_slice(key);
+        aad
+    }
+}
+
+/// Compression of pickled values, see `Rdict.set_value_compression`.
+#[derive(Clone, Copy, PartialEq)]
+struct Compression {
+    threshold: usize,
+    level: i32,
+}
+
+impl ValueCodec {
+    /// A codec without stages, which cannot be added in raw mode,
+    /// besides the checksums recorded in the rocksdict config.
+    pub(crate) fn new(raw_mode: bool, checksums: bool) -> Self {
+        ValueCodec {
+            raw_mode,
+            checksums: checksums && !raw_mode,
+            compression: None,
+            encryption: None,
+        }
+    }
+
+    /// Append checksums to written values, see `Options.set_value_checksums`.
+    pub(crate) fn set_checksums(&mut self, checksums: bool) {
+        self.checksums = checksums && !self.raw_mode;
+    }
+
+    /// Whether checksums are appended to written values.
+    pub(crate) fn has_checksums(&self) -> bool {
+        self.checksums
+    }
+
+    fn check_typed(&self) -> PyResult<()> {
+        if self.raw_mode {
+            return Err(PyValueError::new_err(
+                "value codecs are not supported in raw mode",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Compress pickled values of at least `threshold` bytes with zstd,
+    /// or stop compressing if `None`.
+    pub(crate) fn set_compression(&mut self, threshold: Option<usize>, level: i32) -> PyResult<()> {
+        self.check_typed()?;
+        if !zstd::compression_level_range().contains(&level) {
+            return Err(PyValueError::new_err(format!(
+                "invalid zstd compression level {level}"
+            )));
+        }
+        self.compression = threshold.map(|threshold| Compression { threshold, level });
+        Ok(())
+    }
+
+    /// Whether values stored by `other` are stored alike by this codec,
+    /// so that they can be copied as is to the same keys.
+    ///
+    /// Encrypted values can only be copied within the same column family name.
+    pub(crate) fn same_stages(&self, other: &ValueCodec) -> bool {
+        let encryption = |codec: &ValueCodec| {
+            codec
+                .encryption
+                .as_ref()
+                .map(|e| (e.key.clone(), e.column_family.clone()))
+        };
+        self.raw_mode == other.raw_mode
+            && self.checksums == other.checksums
+            && self.compression == other.compression
+            && encryption(self) == encryption(other)
+    }
+
+    /// Whether values are encrypted.
+    pub(crate) fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    /// Encrypt values of the column family `column_family` with AES-256-GCM
+    /// using `key`, or stop encrypting if `None`.
+    pub(crate) fn set_encryption_key(
+        &mut self,
+        key: Option<&[u8]>,
+        column_family: &str,
+    ) -> PyResult<()> {
+        self.check_typed()?;
+        self.encryption = match key {
+            None => None,
+            Some(key) => Some(Arc::new(Encryption {
+                cipher: Aes256Gcm::new_from_slice(key).map_err(|_| {
+                    PyValueError::new_err(format!(
+                        "encryption key must be 32 bytes, got {} bytes",
+                        key.len()
+                    ))
+                })?,
+                key: key.to_vec(),
+                column_family: column_family.to_string(),
+            })),
+        };
+        Ok(())
+    }
+
+    /// Apply the stages to a value encoded by `encode_value`,
+    /// to be stored at the encoded `key`.
+    pub(crate) fn encode<'a>(
+        &self,
+        key: &[u8],
+        value: EncodedBytes<'a>,
+    ) -> PyResult<EncodedBytes<'a>> {
+        let mut value = value;
+        if let Some(compression) = self.compression {
+            if value.first() == Some(&PICKLE_TYPE) && value.len() > compression.threshold {
+                let compressed = zstd::bulk::compress(&value[1..], compression.level)
+                    .map_err(|e| PyValueError::new_err(format!("failed to compress value: {e}")))?;
+                // keep incompressible values as is
+                if compressed.len() + 1 < value.len() {
+                    value = EncodedBytes::Owned(concat_type_encoding(
+                        PICKLE_TYPE | COMPRESSED_FLAG,
+                        &compressed,
+                    ));
+                }
+            }
+        }
+        if self.checksums && !value.is_empty() {
+            let mut output = Vec::with_capacity(value.len() + CHECKSUM_SIZE);
+            output.push(value[0] | CHECKSUM_FLAG);
+            output.extend_from_slice(&value[1..]);
+            output.extend_from_slice(&crc32c::crc32c(&output).to_le_bytes());
+            value = EncodedBytes::Owned(output);
+        }
+        let Some(encryption) = &self.encryption else {
+            return Ok(value);
+        };
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = encryption.associated_data(key);
+        let ciphertext = encryption
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &value[..],
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| PyValueError::new_err("failed to encrypt value"))?;
+        let mut output = Vec::with_capacity(1 + NONCE_SIZE + ciphertext.len());
+        output.push(ENCRYPTED_TYPE);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(EncodedBytes::Owned(output))
+    }
+
+    /// Undo the stages of a value stored at the encoded `key`, before `decode_value`.
+    pub(crate) fn decode<'a>(&self, key: &[u8], bytes: &'a [u8]) -> PyResult<Cow<'a, [u8]>> {
+        if self.raw_mode {
+            return Ok(Cow::Borrowed(bytes));
+        }
+        let bytes = strip_checksum(self.decrypt(key, bytes)?)?;
+        // compressed values are decoded even if compression is disabled
+        if bytes.first() == Some(&(PICKLE_TYPE | COMPRESSED_FLAG)) {
+            let pickle = zstd::stream::decode_all(&bytes[1..]).map_err(|e| {
+                CorruptionError::new_err(format!("failed to decompress value: {e}"))
+            })?;
+            return Ok(Cow::Owned(concat_type_encoding(PICKLE_TYPE, &pickle)));
+        }
+        Ok(bytes)
+    }
+
+    /// Check the checksum of a value stored at the encoded `key`,
+    /// returning whether it has one.
+    ///
+    /// Encrypted values are decrypted first, and only have a checksum
+    /// if the encryption key is set.
+    pub(crate) fn verify(&self, key: &[u8], bytes: &[u8]) -> PyResult<bool> {
+        if self.raw_mode {
+            return Ok(false);
+        }
+        let bytes = self.decrypt(key, bytes)?;
+        let has_checksum = bytes.first().is_some_and(|b| b & CHECKSUM_FLAG != 0);
+        strip_checksum(bytes)?;
+        Ok(has_checksum)
+    }
+
+    fn decrypt<'a>(&self, key: &[u8], bytes: &'a [u8]) -> PyResult<Cow<'a, [u8]>> {
+        match (&self.encryption, bytes.first()) {
+            (Some(encryption), Some(&ENCRYPTED_TYPE)) => {
+                if bytes.len() < 1 + NONCE_SIZE + TAG_SIZE {
+                    return Err(CorruptionError::new_err("encrypted value is truncated"));
+                }
+                let (nonce, ciphertext) = bytes[1..].split_at(NONCE_SIZE);
+                let aad = encryption.associated_data(key);
+                encryption
+                    .cipher
+                    .decrypt(
+                        Nonce::from_slice(nonce),
+                        Payload {
+                            msg: ciphertext,
+                            aad: &aad,
+                        },
+                    )
+                    .map(Cow::Owned)
+                    .map_err(|_| {
+                        CorruptionError::new_err(
+                            "failed to decrypt value, the key is wrong, or the value is \
+                             corrupted or was moved from another key or column family",
+                        )
+                    })
+            }
+            _ => Ok(Cow::Borrowed(bytes)),
+        }
+    }
+}
+
+/// Verify and remove the checksum of a value, if it has one.
+fn strip_checksum(bytes: Cow<[u8]>) -> PyResult<Cow<[u8]>> {
+    match bytes.first() {
+        Some(b) if b & CHECKSUM_FLAG != 0 => {
+            if bytes.len() < 1 + CHECKSUM_SIZE {
+                return Err(CorruptionError::new_err("value is truncated"));
+            }
+            let (value, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+            if crc32c::crc32c(value).to_le_bytes() != checksum {
+                return Err(CorruptionError::new_err("value checksum mismatch"));
+            }
+            let mut output = value.to_vec();
+            output[0] &= !CHECKSUM_FLAG;
+            Ok(Cow::Owned(output))
+        }
+        _ => Ok(bytes),
+    }
+}
+
+/// The smallest encoded key greater than the encoded `key` in the key order of the database.
+pub(crate) fn key_successor(key: &[u8], key_codec: impl Into<KeyCodec>) -> Vec<u8> {
+    // int keys are compared numerically by the rocksdict comparator
+    if key_codec.into() == KeyCodec::Rocksdict && key.first() == Some(&3) {
+        let next = BigInt::from_signed_bytes_be(&key[1..]) + 1;
+        return concat_type_encoding(3, &next.to_signed_bytes_be());
+    }
+    let mut successor = Vec::with_capacity(key.len() + 1);
+    successor.extend_from_slice(key);
+    successor.push(0);
+    successor
+}
+
+/// An encoded key greater than all encoded keys, for ranges open at the end.
+///
+/// In non-raw mode all type tags are below `0xFF`, so `[0xFF]` is larger than
+/// any key. `u64_be` keys have no type tag, but are 8 bytes long, so nine `0xFF`
+/// bytes are larger than any key. Raw keys have no such upper bound.
+pub(crate) fn max_encoded_key(key_codec: impl Into<KeyCodec>) -> Option<Vec<u8>> {
+    match key_codec.into() {
+        KeyCodec::Raw => None,
+        KeyCodec::U64Be => Some(vec![u8::MAX; 9]),
+        _ => Some(vec![u8::MAX]),
+    }
+}
+
 #[inline(always)]
 fn concat_type_encoding(encoding: u8, payload: &[u8]) -> Vec<u8> {
     let mut output = Vec::with_capacity(payload.len() + 1);
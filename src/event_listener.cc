@@ -0,0 +1,149 @@
+// Event listener reporting flush and compaction jobs to `src/jobs.rs`,
+// since the C API of RocksDB does not expose event listeners.
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <string>
+
+#include "rocksdb/listener.h"
+#include "rocksdb/options.h"
+
+// the layout of `rocksdb_options_t` in `db/c.cc`
+struct rocksdb_options_t {
+  rocksdb::Options rep;
+};
+
+extern "C" {
+
+// mirrored by `JobEvent` in `src/jobs.rs`
+struct rocksdict_job_event_t {
+  // 0 for flush, 1 for compaction
+  int job_type;
+  // 0 when the job begins, 1 when it completes
+  int completed;
+  int job_id;
+  const char* cf_name;
+  size_t cf_name_len;
+  const char* reason;
+  // empty if the job succeeded
+  const char* status;
+  size_t status_len;
+  // flush jobs
+  const char* file_path;
+  size_t file_path_len;
+  uint64_t num_entries;
+  uint64_t num_deletions;
+  uint64_t data_size;
+  int triggered_writes_slowdown;
+  int triggered_writes_stop;
+  // compaction jobs
+  int base_input_level;
+  int output_level;
+  uint64_t num_input_files;
+  uint64_t num_output_files;
+  uint64_t num_input_records;
+  uint64_t num_output_records;
+  uint64_t total_input_bytes;
+  uint64_t total_output_bytes;
+};
+
+typedef void (*rocksdict_job_callback_t)(void* state,
+                                         const rocksdict_job_event_t* event);
+typedef void (*rocksdict_destroy_t)(void* state);
+
+}  // extern "C"
+
+namespace {
+
+class JobListener : public rocksdb::EventListener {
+ public:
+  JobListener(void* state, rocksdict_job_callback_t on_event,
+              rocksdict_destroy_t destroy)
+      : state_(state), on_event_(on_event), destroy_(destroy) {}
+
+  ~JobListener() override { destroy_(state_); }
+
+  const char* Name() const override { return "RocksDictJobListener"; }
+
+  void OnFlushBegin(rocksdb::DB*, const rocksdb::FlushJobInfo& info) override {
+    Flush(info, false);
+  }
+
+  void OnFlushCompleted(rocksdb::DB*,
+                        const rocksdb::FlushJobInfo& info) override {
+    Flush(info, true);
+  }
+
+  void OnCompactionBegin(rocksdb::DB*,
+                         const rocksdb::CompactionJobInfo& info) override {
+    Compaction(info, false);
+  }
+
+  void OnCompactionCompleted(rocksdb::DB*,
+                             const rocksdb::CompactionJobInfo& info) override {
+    Compaction(info, true);
+  }
+
+ private:
+  void Flush(const rocksdb::FlushJobInfo& info, bool completed) {
+    rocksdict_job_event_t event{};
+    event.job_type = 0;
+    event.completed = completed;
+    event.job_id = info.job_id;
+    event.cf_name = info.cf_name.data();
+    event.cf_name_len = info.cf_name.size();
+    event.reason = rocksdb::GetFlushReasonString(info.flush_reason);
+    event.status = "";
+    event.file_path = info.file_path.data();
+    event.file_path_len = info.file_path.size();
+    event.num_entries = info.table_properties.num_entries;
+    event.num_deletions = info.table_properties.num_deletions;
+    event.data_size = info.table_properties.data_size;
+    event.triggered_writes_slowdown = info.triggered_writes_slowdown;
+    event.triggered_writes_stop = info.triggered_writes_stop;
+    on_event_(state_, &event);
+  }
+
+  void Compaction(const rocksdb::CompactionJobInfo& info, bool completed) {
+    std::string status = info.status.ok() ? "" : info.status.ToString();
+    rocksdict_job_event_t event{};
+    event.job_type = 1;
+    event.completed = completed;
+    event.job_id = info.job_id;
+    event.cf_name = info.cf_name.data();
+    event.cf_name_len = info.cf_name.size();
+    event.reason = rocksdb::GetCompactionReasonString(info.compaction_reason);
+    event.status = status.data();
+    event.status_len = status.size();
+    event.file_path = "";
+    event.base_input_level = info.base_input_level;
+    event.output_level = info.output_level;
+    event.num_input_files = info.input_files.size();
+    event.num_output_files = info.output_files.size();
+    event.num_input_records = info.stats.num_input_records;
+    event.num_output_records = info.stats.num_output_records;
+    event.total_input_bytes = info.stats.total_input_bytes;
+    event.total_output_bytes = info.stats.total_output_bytes;
+    on_event_(state_, &event);
+  }
+
+  void* state_;
+  rocksdict_job_callback_t on_event_;
+  rocksdict_destroy_t destroy_;
+};
+
+}  // namespace
+
+extern "C" {
+
+// Add a listener calling `on_event` with `state` for each flush and
+// compaction job, and `destroy` with `state` once the listener is dropped.
+void rocksdict_options_add_job_listener(rocksdb_options_t* opt, void* state,
+                                        rocksdict_job_callback_t on_event,
+                                        rocksdict_destroy_t destroy) {
+  opt->rep.listeners.push_back(
+      std::make_shared<JobListener>(state, on_event, destroy));
+}
+
+}  // extern "C"
@@ -1,5 +1,6 @@
 use pyo3::create_exception;
 use pyo3::exceptions::PyException;
+use pyo3::PyErr;
 
 create_exception!(
     rocksdict,
@@ -7,3 +8,89 @@ create_exception!(
     PyException,
     "Raised when accessing a closed database instance."
 );
+
+create_exception!(
+    rocksdict,
+    DbFrozenError,
+    PyException,
+    "Raised when writing to a database instance frozen by `Rdict.freeze`."
+);
+
+create_exception!(
+    rocksdict,
+    RocksDBError,
+    PyException,
+    "Base class of errors returned by RocksDB."
+);
+
+create_exception!(
+    rocksdict,
+    CorruptionError,
+    RocksDBError,
+    "Raised when RocksDB detects data corruption."
+);
+
+create_exception!(
+    rocksdict,
+    BusyError,
+    RocksDBError,
+    "Raised when a resource is busy, for example on write conflicts."
+);
+
+create_exception!(
+    rocksdict,
+    TransactionConflictError,
+    BusyError,
+    "Raised when a transaction cannot commit because of a conflicting write, and can be retried."
+);
+
+create_exception!(
+    rocksdict,
+    TryAgainError,
+    RocksDBError,
+    "Raised when an operation failed transiently and can be retried."
+);
+
+create_exception!(
+    rocksdict,
+    TimedOutError,
+    RocksDBError,
+    "Raised when an operation timed out, for example waiting for a lock."
+);
+
+create_exception!(
+    rocksdict,
+    NotSupportedError,
+    RocksDBError,
+    "Raised when an operation is not supported, for example by a read-only database."
+);
+
+create_exception!(
+    rocksdict,
+    IOErrorNoSpace,
+    RocksDBError,
+    "Raised when an IO operation failed because the disk is full."
+);
+
+/// Convert a RocksDB status message into the matching exception class.
+///
+/// RocksDB status messages start with the status code,
+/// for example `Corruption: block checksum mismatch`.
+pub(crate) fn status_to_py_err(message: String) -> PyErr {
+    match message.split(':').next().unwrap_or("") {
+        "Corruption" => CorruptionError::new_err(message),
+        "Resource busy" => BusyError::new_err(message),
+        "Operation failed. Try again." => TryAgainError::new_err(message),
+        "Operation timed out" => TimedOutError::new_err(message),
+        "Not implemented" => NotSupportedError::new_err(message),
+        "IO error" if message.contains("No space left on device") => {
+            IOErrorNoSpace::new_err(message)
+        }
+        _ => RocksDBError::new_err(message),
+    }
+}
+
+/// Convert a `rocksdb::Error` into the matching exception class.
+pub(crate) fn to_py_err(e: rocksdb::Error) -> PyErr {
+    status_to_py_err(e.into_string())
+}
@@ -1,64 +1,268 @@
-use crate::db_reference::DbReferenceHolder;
-use crate::encoder::{decode_value, encode_key};
-use crate::exceptions::DbClosedError;
+use crate::db_reference::{DbReferenceHolder, ReleaseHandle};
+use crate::encoder::{decode_key, decode_value, encode_key, KeyCodec, ValueCodec};
+use crate::exceptions::{status_to_py_err, DbClosedError};
+use crate::latency::{LatencyTracker, Operation};
 use crate::util::error_message;
 use crate::{ReadOpt, ReadOptionsPy};
 use core::slice;
 use libc::{c_char, c_uchar, size_t};
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use pyo3::types::{PyList, PyTuple};
+use pyo3::types::{PyBytes, PyList, PyTuple};
 use rocksdb::{AsColumnFamilyRef, Iterable as _, UnboundColumnFamily};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::ptr::null_mut;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Keys read by the first chunk of `RdictKeys`, doubled for each chunk
+/// up to `MAX_KEY_CHUNK`, so that short scans do not read ahead much.
+const MIN_KEY_CHUNK: usize = 16;
+const MAX_KEY_CHUNK: usize = 1024;
+
+/// Idle iterators kept by each `Rdict` for `Rdict.pooled_iter`.
+const MAX_POOLED_ITERS: usize = 8;
 
 #[pyclass]
-#[allow(dead_code)]
 pub(crate) struct RdictIter {
-    /// iterator must keep a reference count of DB to keep DB alive.
-    pub(crate) db: DbReferenceHolder,
+    /// The C iterator, shared with the database so that a forced close can release it.
+    state: Arc<IterHandle>,
+
+    /// use pickle loads to convert bytes to pyobjects
+    pub(crate) loads: PyObject,
 
-    pub(crate) inner: *mut librocksdb_sys::rocksdb_iterator_t,
+    /// stages applied to values, such as encryption
+    pub(crate) codec: ValueCodec,
+
+    pub(crate) raw_mode: bool,
+
+    /// how keys are encoded, see `Options.set_key_codec`
+    pub(crate) key_codec: KeyCodec,
+
+    /// records the latency of each item of `Rdict` iterators, if enabled
+    pub(crate) latency: Option<Arc<LatencyTracker>>,
+
+    /// where the C iterator returns when closed, for `Rdict.pooled_iter`
+    pool: Option<Weak<IterPool>>,
+}
+
+/// A C iterator with the references it needs, `None` once closed.
+struct IterHandle(Mutex<Option<IterState>>);
+
+struct IterState {
+    inner: *mut librocksdb_sys::rocksdb_iterator_t,
 
     /// When iterate_upper_bound is set, the inner C iterator keeps a pointer to the upper bound
     /// inside `_readopts`. Storing this makes sure the upper bound is always alive when the
     /// iterator is being used.
-    pub(crate) readopts: ReadOpt,
+    _readopts: ReadOpt,
 
-    /// use pickle loads to convert bytes to pyobjects
-    pub(crate) loads: PyObject,
+    /// iterator must keep a reference count of DB to keep DB alive.
+    db: DbReferenceHolder,
+}
 
-    pub(crate) raw_mode: bool,
+/// The C iterator is only used while holding the lock of its `IterHandle`.
+unsafe impl Send for IterState {}
+
+impl Drop for IterState {
+    fn drop(&mut self) {
+        unsafe {
+            librocksdb_sys::rocksdb_iter_destroy(self.inner);
+        }
+    }
+}
+
+/// Where a chunk read by `IterHandle::read_chunk` ends before its count.
+#[derive(Clone, Copy, Default)]
+struct ChunkLimit<'a> {
+    /// encoded key before which the chunk ends, in the order of `compare`.
+    stop_key: Option<(&'a [u8], fn(&[u8], &[u8]) -> Ordering)>,
+    /// maximum total size of the keys and values read.
+    max_bytes: Option<usize>,
+}
+
+impl IterHandle {
+    /// Copy up to `count` keys from the current position, moving forward
+    /// or `backwards`, without copying their values.
+    ///
+    /// Returns `None` if the iterator is closed.
+    fn read_keys(&self, count: usize, backwards: bool) -> Option<Vec<Vec<u8>>> {
+        let chunk = self.read_chunk(count, backwards, false, ChunkLimit::default())?;
+        Some(chunk.into_iter().map(|(key, _)| key).collect())
+    }
+
+    /// Copy up to `count` keys, and their values if `with_values`, from the
+    /// current position, stopping early at the `limit`.
+    ///
+    /// The iterator is left at the first entry not read. The first entry is
+    /// always read when `max_bytes` is smaller than it, so that each chunk
+    /// moves the iterator.
+    ///
+    /// Returns `None` if the iterator is closed.
+    fn read_chunk(
+        &self,
+        count: usize,
+        backwards: bool,
+        with_values: bool,
+        limit: ChunkLimit,
+    ) -> Option<Vec<(Vec<u8>, Vec<u8>)>> {
+        let state = self.0.lock().unwrap();
+        let inner = state.as_ref()?.inner;
+        let mut chunk = Vec::with_capacity(count.min(MAX_KEY_CHUNK));
+        let mut total_bytes = 0;
+        while chunk.len() < count && unsafe { librocksdb_sys::rocksdb_iter_valid(inner) } != 0 {
+            let (key, value) = unsafe {
+                let mut key_len: size_t = 0;
+                let key_ptr = librocksdb_sys::rocksdb_iter_key(inner, &mut key_len);
+                let key = slice::from_raw_parts(key_ptr as *const c_uchar, key_len);
+                let value: &[u8] = if with_values {
+                    let mut val_len: size_t = 0;
+                    let val_ptr = librocksdb_sys::rocksdb_iter_value(inner, &mut val_len);
+                    slice::from_raw_parts(val_ptr as *const c_uchar, val_len)
+                } else {
+                    &[]
+                };
+                (key, value)
+            };
+            if let Some((stop_key, compare)) = limit.stop_key {
+                let past = match compare(key, stop_key) {
+                    Ordering::Less => backwards,
+                    Ordering::Equal => true,
+                    Ordering::Greater => !backwards,
+                };
+                if past {
+                    break;
+                }
+            }
+            total_bytes += key.len() + value.len();
+            if limit.max_bytes.is_some_and(|max| total_bytes > max) && !chunk.is_empty() {
+                break;
+            }
+            chunk.push((key.to_vec(), value.to_vec()));
+            unsafe {
+                if backwards {
+                    librocksdb_sys::rocksdb_iter_prev(inner);
+                } else {
+                    librocksdb_sys::rocksdb_iter_next(inner);
+                }
+            }
+        }
+        Some(chunk)
+    }
+}
+
+impl IterHandle {
+    /// Wrap an open C iterator, released with the other handles of its database.
+    fn register(state: IterState) -> Arc<Self> {
+        let handle = Arc::new(IterHandle(Mutex::new(None)));
+        let release: Weak<dyn ReleaseHandle> = Arc::downgrade(&handle);
+        state.db.register_release(release);
+        *handle.0.lock().unwrap() = Some(state);
+        handle
+    }
+}
+
+/// Idle C iterators of an `Rdict`, reused by `Rdict.pooled_iter`.
+///
+/// Idle iterators stay registered with their database, so that closing
+/// or reopening it releases them like iterators in use.
+#[derive(Default)]
+pub(crate) struct IterPool(Mutex<Vec<Arc<IterHandle>>>);
+
+impl IterPool {
+    /// Take an idle iterator, refreshed to read the latest state of the
+    /// database, or `None` if there is no open one.
+    fn take(&self) -> Option<Arc<IterHandle>> {
+        loop {
+            let handle = self.0.lock().unwrap().pop()?;
+            let refreshed = handle.0.lock().unwrap().as_ref().is_some_and(|state| {
+                let mut err: *mut c_char = null_mut();
+                unsafe {
+                    librocksdb_sys::rocksdb_iter_refresh(state.inner, &mut err);
+                }
+                if err.is_null() {
+                    true
+                } else {
+                    // drop the iterator and try the next one
+                    error_message(err);
+                    false
+                }
+            });
+            if refreshed {
+                return Some(handle);
+            }
+        }
+    }
+
+    /// Keep the C iterator of a closed `RdictIter` for reuse,
+    /// unless the pool is full.
+    fn put(&self, state: IterState) {
+        let mut idle = self.0.lock().unwrap();
+        if idle.len() < MAX_POOLED_ITERS {
+            idle.push(IterHandle::register(state));
+        }
+    }
+
+    /// Drop all idle iterators with their references to the database.
+    pub(crate) fn clear(&self) {
+        let idle = std::mem::take(&mut *self.0.lock().unwrap());
+        drop(idle);
+    }
+}
+
+impl ReleaseHandle for IterHandle {
+    fn release(&self) {
+        let state = self.0.lock().unwrap().take();
+        drop(state);
+    }
+}
+
+impl Drop for RdictIter {
+    fn drop(&mut self) {
+        // return pooled iterators that were not closed
+        if self.pool.is_some() {
+            self.close();
+        }
+    }
 }
 
 #[pyclass]
 pub(crate) struct RdictItems {
     inner: RdictIter,
     backwards: bool,
+    remaining: Option<usize>,
 }
 
 #[pyclass]
 pub(crate) struct RdictKeys {
     inner: RdictIter,
     backwards: bool,
+    remaining: Option<usize>,
+    /// keys read ahead in the last chunk, not yet returned.
+    pending: VecDeque<PyObject>,
+    /// size of the last chunk read.
+    chunk_size: usize,
 }
 
 #[pyclass]
 pub(crate) struct RdictValues {
     inner: RdictIter,
     backwards: bool,
+    remaining: Option<usize>,
 }
 
 #[pyclass]
 pub(crate) struct RdictColumns {
     inner: RdictIter,
     backwards: bool,
+    remaining: Option<usize>,
 }
 
 #[pyclass]
 pub(crate) struct RdictEntities {
     inner: RdictIter,
     backwards: bool,
+    remaining: Option<usize>,
 }
 
 impl RdictIter {
@@ -67,30 +271,185 @@ impl RdictIter {
         cf: &Option<Arc<UnboundColumnFamily>>,
         readopts: ReadOptionsPy,
         pickle_loads: &PyObject,
-        raw_mode: bool,
+        codec: &ValueCodec,
+        key_codec: KeyCodec,
         py: Python,
     ) -> PyResult<Self> {
-        let readopts = readopts.to_read_opt(raw_mode, py)?;
+        let readopts = readopts.to_read_opt(key_codec, py)?;
+        Self::from_read_opt(db, cf, readopts, pickle_loads, codec, key_codec)
+    }
+
+    /// Decode keys and values as in raw mode, returning the bytes stored.
+    pub(crate) fn into_raw(mut self) -> Self {
+        self.raw_mode = true;
+        self.key_codec = KeyCodec::Raw;
+        self.codec = ValueCodec::new(true, false);
+        self
+    }
 
+    /// Create an iterator from already converted read options.
+    pub(crate) fn from_read_opt(
+        db: &DbReferenceHolder,
+        cf: &Option<Arc<UnboundColumnFamily>>,
+        readopts: ReadOpt,
+        pickle_loads: &PyObject,
+        codec: &ValueCodec,
+        key_codec: KeyCodec,
+    ) -> PyResult<Self> {
         let db_inner = db
             .get()
             .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))?
             .inner();
 
-        Ok(RdictIter {
-            db: db.clone(),
-            inner: unsafe {
-                match cf {
-                    None => librocksdb_sys::rocksdb_create_iterator(db_inner, readopts.0),
-                    Some(cf) => {
-                        librocksdb_sys::rocksdb_create_iterator_cf(db_inner, readopts.0, cf.inner())
-                    }
+        let inner = unsafe {
+            match cf {
+                None => librocksdb_sys::rocksdb_create_iterator(db_inner, readopts.0),
+                Some(cf) => {
+                    librocksdb_sys::rocksdb_create_iterator_cf(db_inner, readopts.0, cf.inner())
                 }
-            },
-            readopts,
+            }
+        };
+        let state = IterHandle::register(IterState {
+            inner,
+            _readopts: readopts,
+            db: db.with_kind("iterator"),
+        });
+        Ok(Self::from_handle(state, pickle_loads, codec, key_codec))
+    }
+
+    fn from_handle(
+        state: Arc<IterHandle>,
+        pickle_loads: &PyObject,
+        codec: &ValueCodec,
+        key_codec: KeyCodec,
+    ) -> Self {
+        RdictIter {
+            state,
             loads: pickle_loads.clone(),
-            raw_mode,
+            codec: codec.clone(),
+            raw_mode: key_codec.is_raw(),
+            key_codec,
+            latency: None,
+            pool: None,
+        }
+    }
+
+    /// Reuse an idle iterator of `pool`, or create one with default
+    /// read options, which returns to `pool` when closed.
+    pub(crate) fn pooled(
+        pool: &Arc<IterPool>,
+        db: &DbReferenceHolder,
+        cf: &Option<Arc<UnboundColumnFamily>>,
+        pickle_loads: &PyObject,
+        codec: &ValueCodec,
+        key_codec: KeyCodec,
+        py: Python,
+    ) -> PyResult<Self> {
+        let mut iter = match py.allow_threads(|| pool.take()) {
+            Some(state) => Self::from_handle(state, pickle_loads, codec, key_codec),
+            None => Self::new(
+                db,
+                cf,
+                ReadOptionsPy::default(py)?,
+                pickle_loads,
+                codec,
+                key_codec,
+                py,
+            )?,
+        };
+        iter.pool = Some(Arc::downgrade(pool));
+        Ok(iter)
+    }
+
+    /// Time the items yielded by `Rdict` iterators over this iterator.
+    pub(crate) fn with_latency(mut self, latency: &Arc<LatencyTracker>) -> Self {
+        self.latency = Some(latency.clone());
+        self
+    }
+
+    /// Run `f` on the C iterator, or return `None` if the iterator is closed.
+    ///
+    /// `f` must not call into Python, which could close the iterator.
+    fn with_inner<R>(
+        &self,
+        f: impl FnOnce(*mut librocksdb_sys::rocksdb_iterator_t) -> R,
+    ) -> Option<R> {
+        self.state
+            .0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|state| f(state.inner))
+    }
+
+    /// Copy of the current key, or `None` if the iterator is not valid.
+    fn key_bytes(&self) -> Option<Vec<u8>> {
+        self.with_inner(|inner| unsafe {
+            if librocksdb_sys::rocksdb_iter_valid(inner) == 0 {
+                return None;
+            }
+            let mut key_len: size_t = 0;
+            let key_ptr = librocksdb_sys::rocksdb_iter_key(inner, &mut key_len) as *const c_uchar;
+            Some(slice::from_raw_parts(key_ptr, key_len).to_vec())
         })
+        .flatten()
+    }
+
+    /// Read and decode up to `count` keys, see `IterHandle::read_keys`.
+    ///
+    /// Keys are read with the GIL released, and are empty if the
+    /// iterator is closed.
+    fn key_chunk(&self, count: usize, backwards: bool, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = &self.state;
+        let keys = py
+            .allow_threads(|| state.read_keys(count, backwards))
+            .unwrap_or_default();
+        keys.iter()
+            .map(|key| decode_key(py, key, &self.loads, self.key_codec))
+            .collect()
+    }
+
+    /// Read up to `count` entries with the GIL released, see
+    /// `IterHandle::read_chunk`. Empty if the iterator is closed.
+    fn raw_chunk(
+        &self,
+        count: usize,
+        backwards: bool,
+        with_values: bool,
+        stop_key: Option<&Bound<PyAny>>,
+        max_bytes: Option<usize>,
+        py: Python,
+    ) -> PyResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        let stop_key = stop_key
+            .map(|key| encode_key(key, self.key_codec))
+            .transpose()?;
+        let limit = ChunkLimit {
+            stop_key: stop_key
+                .as_deref()
+                .map(|key| (key, self.key_codec.compare())),
+            max_bytes,
+        };
+        let state = &self.state;
+        Ok(py
+            .allow_threads(|| state.read_chunk(count, backwards, with_values, limit))
+            .unwrap_or_default())
+    }
+
+    fn is_closed(&self) -> bool {
+        self.with_inner(|_| ()).is_none()
+    }
+
+    /// Copy of the current value, or `None` if the iterator is not valid.
+    fn value_bytes(&self) -> Option<Vec<u8>> {
+        self.with_inner(|inner| unsafe {
+            if librocksdb_sys::rocksdb_iter_valid(inner) == 0 {
+                return None;
+            }
+            let mut val_len: size_t = 0;
+            let val_ptr = librocksdb_sys::rocksdb_iter_value(inner, &mut val_len) as *const c_uchar;
+            Some(slice::from_raw_parts(val_ptr, val_len).to_vec())
+        })
+        .flatten()
     }
 }
 
@@ -104,7 +463,8 @@ impl RdictIter {
     /// return an error when `valid` is `true`.
     #[inline]
     pub fn valid(&self) -> bool {
-        unsafe { librocksdb_sys::rocksdb_iter_valid(self.inner) != 0 }
+        self.with_inner(|inner| unsafe { librocksdb_sys::rocksdb_iter_valid(inner) != 0 })
+            .unwrap_or(false)
     }
 
     /// Returns an error `Result` if the iterator has encountered an error
@@ -113,12 +473,17 @@ impl RdictIter {
     ///
     /// Performing a seek will discard the current status.
     pub fn status(&self) -> PyResult<()> {
-        let mut err: *mut c_char = null_mut();
-        unsafe {
-            librocksdb_sys::rocksdb_iter_get_error(self.inner, &mut err);
-        }
+        let err = self
+            .with_inner(|inner| {
+                let mut err: *mut c_char = null_mut();
+                unsafe {
+                    librocksdb_sys::rocksdb_iter_get_error(inner, &mut err);
+                }
+                err
+            })
+            .ok_or_else(|| DbClosedError::new_err("iterator already closed"))?;
         if !err.is_null() {
-            Err(PyException::new_err(error_message(err)))
+            Err(status_to_py_err(error_message(err)))
         } else {
             Ok(())
         }
@@ -149,9 +514,9 @@ impl RdictIter {
     ///         del iter, db
     ///         Rdict.destroy(path, Options())
     pub fn seek_to_first(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_seek_to_first(self.inner);
-        }
+        self.with_inner(|inner| unsafe {
+            librocksdb_sys::rocksdb_iter_seek_to_first(inner);
+        });
     }
 
     /// Seeks to the last key in the database.
@@ -179,9 +544,9 @@ impl RdictIter {
     ///         del iter, db
     ///         Rdict.destroy(path, Options())
     pub fn seek_to_last(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_seek_to_last(self.inner);
-        }
+        self.with_inner(|inner| unsafe {
+            librocksdb_sys::rocksdb_iter_seek_to_last(inner);
+        });
     }
 
     /// Seeks to the specified key or the first key that lexicographically follows it.
@@ -205,14 +570,14 @@ impl RdictIter {
     ///         del iter, db
     ///         Rdict.destroy(path, Options())
     pub fn seek(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
-        let key = encode_key(key, self.raw_mode)?;
-        unsafe {
+        let key = encode_key(key, self.key_codec)?;
+        self.with_inner(|inner| unsafe {
             librocksdb_sys::rocksdb_iter_seek(
-                self.inner,
+                inner,
                 key.as_ptr() as *const c_char,
                 key.len() as size_t,
             );
-        }
+        });
         Ok(())
     }
 
@@ -238,64 +603,49 @@ impl RdictIter {
     ///         del iter, db
     ///         Rdict.destroy(path, Options())
     pub fn seek_for_prev(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
-        let key = encode_key(key, self.raw_mode)?;
-        unsafe {
+        let key = encode_key(key, self.key_codec)?;
+        self.with_inner(|inner| unsafe {
             librocksdb_sys::rocksdb_iter_seek_for_prev(
-                self.inner,
+                inner,
                 key.as_ptr() as *const c_char,
                 key.len() as size_t,
             );
-        }
+        });
         Ok(())
     }
 
     /// Seeks to the next key.
     pub fn next(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_next(self.inner);
-        }
+        self.with_inner(|inner| unsafe {
+            librocksdb_sys::rocksdb_iter_next(inner);
+        });
     }
 
     /// Seeks to the previous key.
     pub fn prev(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_prev(self.inner);
-        }
+        self.with_inner(|inner| unsafe {
+            librocksdb_sys::rocksdb_iter_prev(inner);
+        });
     }
 
     /// Returns the current key.
     pub fn key(&self, py: Python) -> PyResult<PyObject> {
-        if self.valid() {
-            // Safety Note: This is safe as all methods that may invalidate the buffer returned
-            // take `&mut self`, so borrow checker will prevent use of buffer after seek.
-            unsafe {
-                let mut key_len: size_t = 0;
-                let key_len_ptr: *mut size_t = &mut key_len;
-                let key_ptr =
-                    librocksdb_sys::rocksdb_iter_key(self.inner, key_len_ptr) as *const c_uchar;
-                let key = slice::from_raw_parts(key_ptr, key_len);
-                Ok(decode_value(py, key, &self.loads, self.raw_mode)?)
-            }
-        } else {
-            Ok(py.None())
+        match self.key_bytes() {
+            Some(key) => decode_key(py, &key, &self.loads, self.key_codec),
+            None => Ok(py.None()),
         }
     }
 
     /// Returns the current value.
     pub fn value(&self, py: Python) -> PyResult<PyObject> {
-        if self.valid() {
-            // Safety Note: This is safe as all methods that may invalidate the buffer returned
-            // take `&mut self`, so borrow checker will prevent use of buffer after seek.
-            unsafe {
-                let mut val_len: size_t = 0;
-                let val_len_ptr: *mut size_t = &mut val_len;
-                let val_ptr =
-                    librocksdb_sys::rocksdb_iter_value(self.inner, val_len_ptr) as *const c_uchar;
-                let value = slice::from_raw_parts(val_ptr, val_len);
-                Ok(decode_value(py, value, &self.loads, self.raw_mode)?)
-            }
-        } else {
-            Ok(py.None())
+        match (self.key_bytes(), self.value_bytes()) {
+            (Some(key), Some(value)) => decode_value(
+                py,
+                &self.codec.decode(&key, &value)?,
+                &self.loads,
+                self.raw_mode,
+            ),
+            _ => Ok(py.None()),
         }
     }
 
@@ -307,48 +657,299 @@ impl RdictIter {
     ///    with default column name (empty bytes/string).
     ///    None or default value if the key does not exist.
     pub fn columns(&self, py: Python) -> PyResult<PyObject> {
-        if self.valid() {
-            let columns = unsafe {
-                rocksdb::WideColumns::from_c(librocksdb_sys::rocksdb_iter_columns(self.inner))
-            };
-            let result = PyList::empty_bound(py);
-            for column in columns.iter() {
-                let name = decode_value(py, column.name, &self.loads, self.raw_mode)?;
-                let value = decode_value(py, column.value, &self.loads, self.raw_mode)?;
-                result.append(PyTuple::new_bound(py, [name, value]))?;
+        let key = self.key_bytes().unwrap_or_default();
+        let columns = self
+            .with_inner(|inner| unsafe {
+                if librocksdb_sys::rocksdb_iter_valid(inner) == 0 {
+                    return None;
+                }
+                let columns =
+                    rocksdb::WideColumns::from_c(librocksdb_sys::rocksdb_iter_columns(inner));
+                let columns = columns
+                    .iter()
+                    .map(|column| (column.name.to_vec(), column.value.to_vec()))
+                    .collect::<Vec<_>>();
+                Some(columns)
+            })
+            .flatten();
+        match columns {
+            Some(columns) => {
+                let result = PyList::empty_bound(py);
+                for (name, value) in columns {
+                    let name = decode_value(py, &name, &self.loads, self.raw_mode)?;
+                    let value = decode_value(
+                        py,
+                        &self.codec.decode(&key, &value)?,
+                        &self.loads,
+                        self.raw_mode,
+                    )?;
+                    result.append(PyTuple::new_bound(py, [name, value]))?;
+                }
+                Ok(result.to_object(py))
             }
-            Ok(result.to_object(py))
-        } else {
-            Ok(py.None())
+            None => Ok(py.None()),
         }
     }
-}
 
-impl Drop for RdictIter {
-    fn drop(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_destroy(self.inner);
+    /// Destroy the iterator and release its reference to the database.
+    ///
+    /// Long scans pin memtables and SST files, and the database stays
+    /// open while any iterator is alive. Close iterators, or use them in
+    /// a with block, instead of waiting for the garbage collector. A
+    /// closed iterator is never valid, and `status` raises `DbClosedError`.
+    /// Iterators of `Rdict.pooled_iter` return to the pool instead.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         with db.iter() as it:
+    ///             it.seek_to_first()
+    ///             while it.valid():
+    ///                 print(it.key())
+    ///                 it.next()
+    pub fn close(&mut self) {
+        let state = self.state.0.lock().unwrap().take();
+        match (state, self.pool.as_ref().and_then(Weak::upgrade)) {
+            (Some(state), Some(pool)) => pool.put(state),
+            (state, _) => drop(state),
         }
     }
-}
 
-unsafe impl Send for RdictIter {}
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type, _exc_value, _traceback))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+    ) -> bool {
+        self.close();
+        false
+    }
+
+    /// Read the next chunk of keys, without reading their values.
+    ///
+    /// Reads up to `chunk_size` keys starting from the current position,
+    /// and leaves the iterator at the key after the chunk. Keys are read
+    /// with the GIL released, and values are never copied or decoded,
+    /// which makes existence scans much faster than `key` and `next`
+    /// calls over column families with large values.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         it = db.iter()
+    ///         it.seek_to_first()
+    ///         while keys := it.get_chunk_keys(1000):
+    ///             print(len(keys))
+    ///
+    /// Args:
+    ///     chunk_size: maximum number of keys to read.
+    ///     backwards: move to the previous keys instead of the next ones.
+    ///     stop_key: end the chunk before this key, see `get_chunk_items`.
+    ///     max_bytes: maximum total size of the encoded keys, see
+    ///         `get_chunk_items`.
+    ///
+    /// Returns:
+    ///     a list of keys, empty if the iterator is not valid.
+    ///
+    /// Notes:
+    ///     The RocksDB C API does not expose `allow_unprepared_value`,
+    ///     so with BlobDB the iterator still fetches the blob of each
+    ///     key it visits, though the blob is never copied.
+    #[pyo3(signature = (chunk_size = 1000, backwards = false, stop_key = None, max_bytes = None))]
+    pub fn get_chunk_keys(
+        &mut self,
+        chunk_size: usize,
+        backwards: bool,
+        stop_key: Option<&Bound<PyAny>>,
+        max_bytes: Option<usize>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let chunk = self.raw_chunk(chunk_size, backwards, false, stop_key, max_bytes, py)?;
+        self.status()?;
+        let keys = chunk
+            .iter()
+            .map(|(key, _)| decode_key(py, key, &self.loads, self.key_codec))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(PyList::new_bound(py, keys).to_object(py))
+    }
+
+    /// Read the next chunk of `(key, value)` pairs.
+    ///
+    /// Reads up to `chunk_size` items starting from the current position,
+    /// and leaves the iterator at the item after the chunk. Items are
+    /// copied with the GIL released, then decoded.
+    ///
+    /// The chunk also ends before `stop_key`, and before its keys and
+    /// values exceed `max_bytes` in total, which bounds the memory of
+    /// each chunk when the sizes of values vary. The first item is always
+    /// read even if it alone exceeds `max_bytes`, so that only an
+    /// exhausted iterator, or one at `stop_key`, returns an empty chunk.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         it = db.iter()
+    ///         it.seek("user:")
+    ///         # at most 1000 items and about 16 MiB per chunk
+    ///         while items := it.get_chunk_items(
+    ///             1000, stop_key="user;", max_bytes=16 << 20
+    ///         ):
+    ///             for key, value in items:
+    ///                 print(key, value)
+    ///
+    /// Args:
+    ///     chunk_size: maximum number of items to read.
+    ///     backwards: move to the previous items instead of the next ones.
+    ///     stop_key: end the chunk before this key, which is not included.
+    ///         It is an upper bound when moving forward, and a lower
+    ///         bound when moving backwards.
+    ///     max_bytes: maximum total size of the keys and values read,
+    ///         as stored in the database.
+    ///
+    /// Returns:
+    ///     a list of `(key, value)` tuples, empty if the iterator is not
+    ///     valid or is at `stop_key`.
+    #[pyo3(signature = (chunk_size = 1000, backwards = false, stop_key = None, max_bytes = None))]
+    pub fn get_chunk_items(
+        &mut self,
+        chunk_size: usize,
+        backwards: bool,
+        stop_key: Option<&Bound<PyAny>>,
+        max_bytes: Option<usize>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let chunk = self.raw_chunk(chunk_size, backwards, true, stop_key, max_bytes, py)?;
+        self.status()?;
+        let result = PyList::empty_bound(py);
+        for (key, value) in chunk {
+            let value = decode_value(
+                py,
+                &self.codec.decode(&key, &value)?,
+                &self.loads,
+                self.raw_mode,
+            )?;
+            let key = decode_key(py, &key, &self.loads, self.key_codec)?;
+            result.append(PyTuple::new_bound(py, [key, value]))?;
+        }
+        Ok(result.to_object(py))
+    }
+
+    /// Read the next chunk of fixed-width keys as a single numpy array.
+    ///
+    /// Reads up to `chunk_size` keys starting from the current position,
+    /// and leaves the iterator at the key after the chunk. Keys are read
+    /// with the GIL released, and their encoded bytes are concatenated
+    /// and viewed with `dtype`, so no Python object is created per key.
+    ///
+    /// Keys are read as stored with the `"raw"` and `"u64_be"` key
+    /// codecs. With the `"rocksdict"` and `"ordered"` key codecs only
+    /// `bytes` keys are supported, without their type tag. `"composite"`
+    /// keys are not supported.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///         import numpy as np
+    ///
+    ///         db = Rdict("./temp_path", Options(raw_mode=True))
+    ///         for i in range(100):
+    ///             db[i.to_bytes(8, "big")] = b"v"
+    ///         it = db.iter()
+    ///         it.seek_to_first()
+    ///         keys = it.get_chunk_keys_np(64, dtype=">u8")
+    ///         assert np.array_equal(keys, np.arange(64))
+    ///
+    /// Args:
+    ///     chunk_size: maximum number of keys to read.
+    ///     dtype: numpy dtype of each key, whose item size must be
+    ///         the length of the keys. Big-endian unsigned 64-bit integers
+    ///         by default, which keep the byte order of the keys.
+    ///     backwards: move to the previous keys instead of the next ones.
+    ///
+    /// Returns:
+    ///     a one-dimensional numpy array, empty if the iterator is not valid.
+    ///
+    /// Raises:
+    ///     ValueError: if a key is not supported by the key codec, or its
+    ///         length is not the item size of `dtype`.
+    #[pyo3(signature = (chunk_size = 1000, dtype = None, backwards = false))]
+    pub fn get_chunk_keys_np(
+        &mut self,
+        chunk_size: usize,
+        dtype: Option<&Bound<PyAny>>,
+        backwards: bool,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let numpy = py.import_bound("numpy")?;
+        let dtype = match dtype {
+            Some(dtype) => numpy.getattr("dtype")?.call1((dtype,))?,
+            None => numpy.getattr("dtype")?.call1((">u8",))?,
+        };
+        let item_size: usize = dtype.getattr("itemsize")?.extract()?;
+        let key_codec = self.key_codec;
+        let state = &self.state;
+        let buffer = py.allow_threads(|| {
+            let keys = state.read_keys(chunk_size, backwards).unwrap_or_default();
+            let mut buffer = Vec::with_capacity(item_size * keys.len());
+            for key in &keys {
+                let key = match (key_codec, key.split_first()) {
+                    (KeyCodec::Raw | KeyCodec::U64Be, _) => &key[..],
+                    // strip the type tag of bytes keys
+                    (KeyCodec::Rocksdict | KeyCodec::Ordered, Some((1, key))) => key,
+                    (KeyCodec::Composite, _) => {
+                        return Err(PyValueError::new_err(
+                            "composite keys cannot be read as numpy arrays",
+                        ))
+                    }
+                    _ => {
+                        return Err(PyValueError::new_err(
+                            "only bytes keys can be read as numpy arrays",
+                        ))
+                    }
+                };
+                if key.len() != item_size {
+                    return Err(PyValueError::new_err(format!(
+                        "key of {} bytes does not match dtype item size {item_size}",
+                        key.len()
+                    )));
+                }
+                buffer.extend_from_slice(key);
+            }
+            Ok(buffer)
+        })?;
+        self.status()?;
+        let bytes = PyBytes::new_bound(py, &buffer);
+        Ok(numpy
+            .getattr("frombuffer")?
+            .call1((bytes, dtype))?
+            .to_object(py))
+    }
+}
 
 macro_rules! impl_iter {
     ($iter_name: ident, $($field: ident),*) => {
-        #[pymethods]
-        impl $iter_name {
-            fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
-                slf
-            }
+        impl_iter!(@methods $iter_name);
 
-            fn __next__(mut slf: PyRefMut<Self>, py: Python) -> PyResult<Option<PyObject>> {
-                if slf.inner.valid() {
-                    $(let $field = slf.inner.$field(py)?;)*
-                    if slf.backwards {
-                        slf.inner.prev();
+        impl $iter_name {
+            fn next_item(&mut self, py: Python) -> PyResult<Option<PyObject>> {
+                if self.remaining == Some(0) {
+                    return Ok(None);
+                }
+                if self.inner.valid() {
+                    if let Some(remaining) = self.remaining.as_mut() {
+                        *remaining -= 1;
+                    }
+                    $(let $field = self.inner.$field(py)?;)*
+                    if self.backwards {
+                        self.inner.prev();
                     } else {
-                        slf.inner.next();
+                        self.inner.next();
                     }
                     Ok(Some(($($field),*).to_object(py)))
                 } else {
@@ -356,9 +957,50 @@ macro_rules! impl_iter {
                 }
             }
         }
+    };
+    (@methods $iter_name: ident $(, $extra: ident)*) => {
+        #[pymethods]
+        impl $iter_name {
+            fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+                slf
+            }
+
+            /// Close the underlying iterator, see `RdictIter.close`.
+            fn close(&mut self) {
+                self.inner.close();
+            }
+
+            fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+                slf
+            }
+
+            #[pyo3(signature = (_exc_type, _exc_value, _traceback))]
+            fn __exit__(
+                &mut self,
+                _exc_type: Option<&Bound<PyAny>>,
+                _exc_value: Option<&Bound<PyAny>>,
+                _traceback: Option<&Bound<PyAny>>,
+            ) -> bool {
+                self.inner.close();
+                false
+            }
+
+            fn __next__(mut slf: PyRefMut<Self>, py: Python) -> PyResult<Option<PyObject>> {
+                let latency = slf.inner.latency.clone();
+                let _timer = latency.as_ref().and_then(|l| l.timer(Operation::Scan));
+                slf.next_item(py)
+            }
+        }
 
         impl $iter_name {
-            pub(crate) fn new(inner: RdictIter, backwards: bool, from_key: Option<&Bound<PyAny>>) -> PyResult<Self> {
+            pub(crate) fn new(
+                inner: RdictIter,
+                backwards: bool,
+                from_key: Option<&Bound<PyAny>>,
+                skip: usize,
+                limit: Option<usize>,
+                py: Python,
+            ) -> PyResult<Self> {
                 let mut inner = inner;
                 if let Some(from_key) = from_key {
                     if backwards {
@@ -373,16 +1015,70 @@ macro_rules! impl_iter {
                         inner.seek_to_first();
                     }
                 }
+                // skip without decoding, and with GIL released
+                if skip > 0 {
+                    py.allow_threads(|| {
+                        for _ in 0..skip {
+                            if !inner.valid() {
+                                break;
+                            }
+                            if backwards {
+                                inner.prev();
+                            } else {
+                                inner.next();
+                            }
+                        }
+                    });
+                }
                 Ok(Self {
                     inner,
                     backwards,
+                    remaining: limit,
+                    $($extra: Default::default(),)*
                 })
             }
         }
     };
 }
 
-impl_iter!(RdictKeys, key);
+impl RdictItems {
+    /// Yield the bytes stored, see `RdictIter::into_raw`.
+    pub(crate) fn into_raw(mut self) -> Self {
+        self.inner = self.inner.into_raw();
+        self
+    }
+}
+
+impl RdictKeys {
+    /// Return keys from chunks read ahead by `RdictIter::key_chunk`,
+    /// which never reads values.
+    fn next_item(&mut self, py: Python) -> PyResult<Option<PyObject>> {
+        if self.inner.is_closed() {
+            return Ok(None);
+        }
+        if self.pending.is_empty() {
+            self.chunk_size = (self.chunk_size * 2).clamp(MIN_KEY_CHUNK, MAX_KEY_CHUNK);
+            let count = match self.remaining {
+                Some(remaining) => remaining.min(self.chunk_size),
+                None => self.chunk_size,
+            };
+            if count == 0 {
+                return Ok(None);
+            }
+            let keys = self.inner.key_chunk(count, self.backwards, py)?;
+            self.pending.extend(keys);
+        }
+        let key = self.pending.pop_front();
+        if key.is_some() {
+            if let Some(remaining) = self.remaining.as_mut() {
+                *remaining -= 1;
+            }
+        }
+        Ok(key)
+    }
+}
+
+impl_iter!(@methods RdictKeys, pending, chunk_size);
 impl_iter!(RdictValues, value);
 impl_iter!(RdictItems, key, value);
 impl_iter!(RdictColumns, columns);
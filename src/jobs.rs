@@ -0,0 +1,224 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of jobs kept by a [JobHistory].
+pub(crate) const JOB_HISTORY_CAPACITY: usize = 1000;
+
+/// A flush or compaction job reported by the event listener in
+/// `src/event_listener.cc`, where it is defined as `rocksdict_job_event_t`.
+#[repr(C)]
+struct JobEvent {
+    job_type: c_int,
+    completed: c_int,
+    job_id: c_int,
+    cf_name: *const c_char,
+    cf_name_len: usize,
+    reason: *const c_char,
+    status: *const c_char,
+    status_len: usize,
+    file_path: *const c_char,
+    file_path_len: usize,
+    num_entries: u64,
+    num_deletions: u64,
+    data_size: u64,
+    triggered_writes_slowdown: c_int,
+    triggered_writes_stop: c_int,
+    base_input_level: c_int,
+    output_level: c_int,
+    num_input_files: u64,
+    num_output_files: u64,
+    num_input_records: u64,
+    num_output_records: u64,
+    total_input_bytes: u64,
+    total_output_bytes: u64,
+}
+
+extern "C" {
+    fn rocksdict_options_add_job_listener(
+        opt: *mut librocksdb_sys::rocksdb_options_t,
+        state: *mut c_void,
+        on_event: unsafe extern "C" fn(*mut c_void, *const JobEvent),
+        destroy: unsafe extern "C" fn(*mut c_void),
+    );
+}
+
+/// The most recent flush and compaction jobs of a database,
+/// recorded by an event listener as they begin and complete.
+#[derive(Default)]
+pub(crate) struct JobHistory {
+    state: Mutex<JobHistoryState>,
+}
+
+#[derive(Default)]
+struct JobHistoryState {
+    /// Jobs, oldest first, at most [JOB_HISTORY_CAPACITY] of them.
+    jobs: VecDeque<Map<String, Value>>,
+    /// Sequence numbers of running jobs by type and id.
+    running: HashMap<(&'static str, c_int), u64>,
+    /// Sequence number of the oldest job in `jobs`.
+    first: u64,
+}
+
+impl JobHistory {
+    /// Record the jobs of the database opened with `opt` in this history.
+    pub(crate) fn listen(self: &Arc<Self>, opt: &mut rocksdb::Options) {
+        let state = Arc::into_raw(self.clone()) as *mut c_void;
+        unsafe {
+            rocksdict_options_add_job_listener(
+                opt.inner() as *mut librocksdb_sys::rocksdb_options_t,
+                state,
+                on_job_event,
+                drop_job_history,
+            )
+        }
+    }
+
+    /// The most recent `limit` jobs, oldest first.
+    pub(crate) fn recent(&self, limit: usize) -> Vec<Map<String, Value>> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let skip = state.jobs.len().saturating_sub(limit);
+        state.jobs.iter().skip(skip).cloned().collect()
+    }
+
+    fn record(&self, event: &JobEvent) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        let job_type = if event.job_type == 0 {
+            "flush"
+        } else {
+            "compaction"
+        };
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = &mut *state;
+        if event.completed == 0 {
+            let mut job = Map::new();
+            job.insert("type".into(), job_type.into());
+            job.insert("job".into(), event.job_id.into());
+            job.insert(
+                "cf_name".into(),
+                unsafe { lossy(event.cf_name, event.cf_name_len) }.into(),
+            );
+            job.insert("reason".into(), unsafe { c_str(event.reason) }.into());
+            job.insert("start_micros".into(), now.into());
+            if state.jobs.len() == JOB_HISTORY_CAPACITY {
+                state.jobs.pop_front();
+                state.first += 1;
+            }
+            let seq = state.first + state.jobs.len() as u64;
+            state.running.insert((job_type, event.job_id), seq);
+            state.jobs.push_back(job);
+            // jobs evicted before completing are never completed
+            let first = state.first;
+            state.running.retain(|_, seq| *seq >= first);
+            return;
+        }
+        let Some(seq) = state.running.remove(&(job_type, event.job_id)) else {
+            return;
+        };
+        let Some(job) = state.jobs.get_mut((seq - state.first) as usize) else {
+            return;
+        };
+        let start_micros = job
+            .get("start_micros")
+            .and_then(Value::as_u64)
+            .unwrap_or(now);
+        job.insert("end_micros".into(), now.into());
+        job.insert(
+            "duration_micros".into(),
+            now.saturating_sub(start_micros).into(),
+        );
+        let status = unsafe { lossy(event.status, event.status_len) };
+        job.insert(
+            "status".into(),
+            if status.is_empty() {
+                "OK".into()
+            } else {
+                status.into()
+            },
+        );
+        if event.job_type == 0 {
+            let file_path = unsafe { lossy(event.file_path, event.file_path_len) };
+            job.insert("file_path".into(), file_path.into());
+            job.insert("num_entries".into(), event.num_entries.into());
+            job.insert("num_deletions".into(), event.num_deletions.into());
+            job.insert("data_size".into(), event.data_size.into());
+            job.insert(
+                "triggered_writes_slowdown".into(),
+                (event.triggered_writes_slowdown != 0).into(),
+            );
+            job.insert(
+                "triggered_writes_stop".into(),
+                (event.triggered_writes_stop != 0).into(),
+            );
+        } else {
+            job.insert("base_input_level".into(), event.base_input_level.into());
+            job.insert("output_level".into(), event.output_level.into());
+            job.insert("num_input_files".into(), event.num_input_files.into());
+            job.insert("num_output_files".into(), event.num_output_files.into());
+            job.insert("num_input_records".into(), event.num_input_records.into());
+            job.insert("num_output_records".into(), event.num_output_records.into());
+            job.insert("total_input_bytes".into(), event.total_input_bytes.into());
+            job.insert("total_output_bytes".into(), event.total_output_bytes.into());
+        }
+    }
+}
+
+unsafe extern "C" fn on_job_event(state: *mut c_void, event: *const JobEvent) {
+    let history = &*(state as *const JobHistory);
+    // never unwind into RocksDB
+    let _ = std::panic::catch_unwind(AssertUnwindSafe(|| history.record(&*event)));
+}
+
+unsafe extern "C" fn drop_job_history(state: *mut c_void) {
+    drop(Arc::from_raw(state as *const JobHistory));
+}
+
+unsafe fn lossy(ptr: *const c_char, len: usize) -> String {
+    if ptr.is_null() || len == 0 {
+        return String::new();
+    }
+    String::from_utf8_lossy(std::slice::from_raw_parts(ptr as *const u8, len)).into_owned()
+}
+
+unsafe fn c_str(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+/// Convert a json value into python objects.
+pub(crate) fn json_to_py(value: &Value, py: Python) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.to_object(py),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => n.to_object(py),
+            (None, Some(n)) => n.to_object(py),
+            _ => n.as_f64().unwrap_or(f64::NAN).to_object(py),
+        },
+        Value::String(s) => s.to_object(py),
+        Value::Array(values) => {
+            let list = PyList::empty_bound(py);
+            for v in values {
+                list.append(json_to_py(v, py)?)?;
+            }
+            list.to_object(py)
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new_bound(py);
+            for (k, v) in map {
+                dict.set_item(k, json_to_py(v, py)?)?;
+            }
+            dict.to_object(py)
+        }
+    })
+}
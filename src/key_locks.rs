@@ -0,0 +1,40 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Locks on individual keys, serializing read-modify-write operations
+/// (`update_atomic`, `put_if_absent`, ...) on the same key of a database.
+#[derive(Default)]
+pub(crate) struct KeyLocks {
+    /// `(column family handle, key)` pairs currently locked.
+    locked: Mutex<HashSet<(usize, Vec<u8>)>>,
+    released: Condvar,
+}
+
+impl KeyLocks {
+    /// Block until `key` of column family `cf` is unlocked, then lock it.
+    pub fn lock(self: &Arc<Self>, cf: usize, key: &[u8]) -> KeyGuard {
+        let entry = (cf, key.to_vec());
+        let mut locked = self.locked.lock().unwrap();
+        while locked.contains(&entry) {
+            locked = self.released.wait(locked).unwrap();
+        }
+        locked.insert(entry.clone());
+        KeyGuard {
+            locks: self.clone(),
+            entry,
+        }
+    }
+}
+
+/// Holds the lock on a key until dropped.
+pub(crate) struct KeyGuard {
+    locks: Arc<KeyLocks>,
+    entry: (usize, Vec<u8>),
+}
+
+impl Drop for KeyGuard {
+    fn drop(&mut self) {
+        self.locks.locked.lock().unwrap().remove(&self.entry);
+        self.locks.released.notify_all();
+    }
+}
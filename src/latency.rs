@@ -0,0 +1,181 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Values below `1 << (SUB_BITS + 1)` nanoseconds have a bucket each, larger
+/// values share `1 << SUB_BITS` buckets per power of two, so that
+/// percentiles are within about 6% of the recorded latencies.
+const SUB_BITS: u32 = 3;
+const BUCKETS: usize = ((64 - SUB_BITS as usize) << SUB_BITS) + (1 << SUB_BITS);
+
+/// Operations timed by `LatencyTracker`.
+#[derive(Clone, Copy)]
+pub(crate) enum Operation {
+    Get,
+    Put,
+    Delete,
+    Scan,
+}
+
+impl Operation {
+    pub(crate) const ALL: [Operation; 4] = [
+        Operation::Get,
+        Operation::Put,
+        Operation::Delete,
+        Operation::Scan,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Operation::Get => "get",
+            Operation::Put => "put",
+            Operation::Delete => "delete",
+            Operation::Scan => "scan",
+        }
+    }
+}
+
+/// Latencies of the operations of a database, recorded while enabled.
+///
+/// Shared by the column families of the database, and updated without
+/// locks, so that tracking costs two clock reads per operation.
+pub(crate) struct LatencyTracker {
+    enabled: AtomicBool,
+    histograms: [Histogram; 4],
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        LatencyTracker {
+            enabled: AtomicBool::new(false),
+            histograms: std::array::from_fn(|_| Histogram::default()),
+        }
+    }
+}
+
+impl LatencyTracker {
+    /// Start recording, dropping the latencies recorded before.
+    pub(crate) fn enable(&self) {
+        for histogram in &self.histograms {
+            histogram.clear();
+        }
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn disable(&self) {
+        self.enabled.store(false, Ordering::Relaxed);
+    }
+
+    /// Time `op` until the returned timer is dropped, if enabled.
+    pub(crate) fn timer(&self, op: Operation) -> Option<Timer<'_>> {
+        self.enabled.load(Ordering::Relaxed).then(|| Timer {
+            start: Instant::now(),
+            histogram: &self.histograms[op as usize],
+        })
+    }
+
+    pub(crate) fn histogram(&self, op: Operation) -> &Histogram {
+        &self.histograms[op as usize]
+    }
+}
+
+/// Records the time elapsed since its creation when dropped.
+pub(crate) struct Timer<'a> {
+    start: Instant,
+    histogram: &'a Histogram,
+}
+
+impl Drop for Timer<'_> {
+    fn drop(&mut self) {
+        self.histogram.record(self.start.elapsed());
+    }
+}
+
+/// A log-linear histogram of latencies in nanoseconds.
+pub(crate) struct Histogram {
+    buckets: [AtomicU64; BUCKETS],
+    sum: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            sum: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    fn record(&self, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.buckets[bucket_index(nanos)].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(nanos, Ordering::Relaxed);
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+        self.sum.store(0, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count(&self) -> u64 {
+        self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).sum()
+    }
+
+    pub(crate) fn sum(&self) -> u64 {
+        self.sum.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn max(&self) -> u64 {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    /// The latency in nanoseconds below which `percentile` percent of the
+    /// recorded latencies fall, or `None` if none was recorded.
+    pub(crate) fn percentile(&self, percentile: f64) -> Option<u64> {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((percentile / 100.0 * total as f64).ceil() as u64).clamp(1, total);
+        let mut seen = 0;
+        for (index, count) in counts.into_iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let (low, high) = bucket_bounds(index);
+                return Some((low + (high - low) / 2).min(self.max()));
+            }
+        }
+        Some(self.max())
+    }
+}
+
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < 1 << (SUB_BITS + 1) {
+        return nanos as usize;
+    }
+    let msb = 63 - nanos.leading_zeros();
+    let shift = msb - SUB_BITS;
+    ((shift as usize) << SUB_BITS) + (nanos >> shift) as usize
+}
+
+/// The range of nanoseconds counted by a bucket, upper bound excluded.
+fn bucket_bounds(index: usize) -> (u64, u64) {
+    if index < 1 << (SUB_BITS + 1) {
+        return (index as u64, index as u64 + 1);
+    }
+    let shift = (index >> SUB_BITS) - 1;
+    let mantissa = (index & ((1 << SUB_BITS) - 1)) as u64 + (1 << SUB_BITS);
+    let low = mantissa << shift;
+    (low, low.saturating_add(1 << shift))
+}
@@ -1,21 +1,43 @@
+mod archive;
+mod atomic;
+mod auto_flush;
+mod buffered_writer;
+mod build_info;
 mod checkpoints;
+mod checksum;
 mod db_reference;
 mod encoder;
 mod exceptions;
 mod iter;
+mod jobs;
+mod key_locks;
+mod latency;
 mod options;
 mod rdict;
+mod replication;
+mod retry;
 mod snapshot;
 mod sst_file_writer;
+mod transaction;
 mod util;
+mod version_history;
+mod wal;
 mod write_batch;
 
+use crate::atomic::RdictAtomic;
+use crate::buffered_writer::RdictBufferedWriter;
+use crate::build_info::build_info;
 use crate::exceptions::*;
 use crate::iter::*;
 use crate::options::*;
 use crate::rdict::*;
-use crate::snapshot::Snapshot;
+use crate::replication::RdictUpdates;
+use crate::retry::RetryPolicyPy;
+use crate::snapshot::{ConsistentView, Snapshot};
 use crate::sst_file_writer::*;
+use crate::transaction::{
+    OptimisticTransactionDbPy, TransactionDbOptionsPy, TransactionDbPy, TransactionPy,
+};
 use crate::write_batch::*;
 use checkpoints::CheckpointPy;
 use pyo3::prelude::*;
@@ -136,20 +158,50 @@ fn rocksdict(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<RdictKeys>()?;
     m.add_class::<RdictColumns>()?;
     m.add_class::<RdictEntities>()?;
+    m.add_class::<RdictUpdates>()?;
+    m.add_class::<RdictAtomic>()?;
+    m.add_class::<RdictBufferedWriter>()?;
     m.add_class::<IngestExternalFileOptionsPy>()?;
     m.add_class::<SstFileWriterPy>()?;
     m.add_class::<WriteBatchPy>()?;
     m.add_class::<ColumnFamilyPy>()?;
     m.add_class::<AccessType>()?;
     m.add_class::<Snapshot>()?;
+    m.add_class::<ConsistentView>()?;
     m.add_class::<CompactOptionsPy>()?;
     m.add_class::<BottommostLevelCompactionPy>()?;
     m.add_class::<ChecksumTypePy>()?;
     m.add_class::<KeyEncodingTypePy>()?;
     m.add_class::<WriteBufferManagerPy>()?;
     m.add_class::<CheckpointPy>()?;
+    m.add_class::<RetryPolicyPy>()?;
+    m.add_class::<TransactionDbPy>()?;
+    m.add_class::<OptimisticTransactionDbPy>()?;
+    m.add_class::<TransactionPy>()?;
+    m.add_class::<TransactionDbOptionsPy>()?;
+
+    m.add_function(wrap_pyfunction!(copy_range, m)?)?;
+    m.add_function(wrap_pyfunction!(split, m)?)?;
+    m.add_function(wrap_pyfunction!(merge_dbs, m)?)?;
+    m.add_function(wrap_pyfunction!(migrate_key_codec, m)?)?;
+    m.add_function(wrap_pyfunction!(build_info, m)?)?;
 
     m.add("DbClosedError", py.get_type_bound::<DbClosedError>())?;
+    m.add("DbFrozenError", py.get_type_bound::<DbFrozenError>())?;
+    m.add("RocksDBError", py.get_type_bound::<RocksDBError>())?;
+    m.add("CorruptionError", py.get_type_bound::<CorruptionError>())?;
+    m.add("BusyError", py.get_type_bound::<BusyError>())?;
+    m.add(
+        "TransactionConflictError",
+        py.get_type_bound::<TransactionConflictError>(),
+    )?;
+    m.add("TryAgainError", py.get_type_bound::<TryAgainError>())?;
+    m.add("TimedOutError", py.get_type_bound::<TimedOutError>())?;
+    m.add(
+        "NotSupportedError",
+        py.get_type_bound::<NotSupportedError>(),
+    )?;
+    m.add("IOErrorNoSpace", py.get_type_bound::<IOErrorNoSpace>())?;
 
     Ok(())
 }
@@ -1,16 +1,33 @@
-use crate::encoder::encode_key;
-use crate::rdict::{RocksDictConfig, ROCKSDICT_CONFIG_FILE};
+use crate::encoder::{encode_key, KeyCodec};
+use crate::exceptions::to_py_err;
+use crate::rdict::{RocksDictConfig, COMPARATOR_BYTEWISE, ROCKSDICT_CONFIG_FILE};
+use crate::util::error_message;
+use crate::version_history::VersionHistoryFactory;
+use crate::{ffi_try, ffi_try_impl};
 use libc::{c_char, c_uchar, size_t};
 use num_bigint::BigInt;
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::{PyException, PyFileNotFoundError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyList, PyTuple};
 use rocksdb::*;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::ffi::c_double;
+use std::ffi::{c_double, CString};
+use std::fs;
 use std::os::raw::{c_int, c_uint};
 use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// setters of `src/options_ext.cc`, not exposed by the C API
+extern "C" {
+    fn rocksdict_options_set_persist_stats_to_disk(
+        opt: *mut librocksdb_sys::rocksdb_options_t,
+        v: c_uchar,
+    );
+}
 
 /// Database-wide options around performance and behavior.
 ///
@@ -54,7 +71,97 @@ use std::path::{Path, PathBuf};
 pub(crate) struct OptionsPy {
     pub(crate) inner_opt: Options,
     pub(crate) raw_mode: bool,
+    /// Key codec set by `Options.set_key_codec`, the default one of `raw_mode` if `None`.
+    pub(crate) key_codec: Option<KeyCodec>,
+    pub(crate) value_checksums: bool,
+    pub(crate) strict_types: bool,
     pub(crate) prefix_extractor: Option<SliceTransformType>,
+    pub(crate) settings: OptionSettings,
+}
+
+/// Options cross-checked by `Options.validate`, as set on an `Options` instance.
+///
+/// Options loaded from an OPTIONS file are unknown, and assumed to be
+/// RocksDB defaults.
+#[derive(Clone, Copy)]
+pub(crate) struct OptionSettings {
+    table_factory: &'static str,
+    /// Options of the last block-based table factory set, even if replaced since.
+    block_based: Option<BlockBasedSettings>,
+    memtable_factory: &'static str,
+    allow_concurrent_memtable_write: bool,
+    unordered_write: bool,
+    enable_pipelined_write: bool,
+    allow_mmap_reads: bool,
+    allow_mmap_writes: bool,
+    use_direct_reads: bool,
+    use_direct_io_for_flush_and_compaction: bool,
+    persist_stats_to_disk: bool,
+}
+
+impl Default for OptionSettings {
+    fn default() -> Self {
+        OptionSettings {
+            table_factory: "BlockBasedTable",
+            block_based: None,
+            memtable_factory: "SkipList",
+            allow_concurrent_memtable_write: true,
+            unordered_write: false,
+            enable_pipelined_write: false,
+            allow_mmap_reads: false,
+            allow_mmap_writes: false,
+            use_direct_reads: false,
+            use_direct_io_for_flush_and_compaction: false,
+            persist_stats_to_disk: false,
+        }
+    }
+}
+
+impl OptionSettings {
+    /// Incompatible combinations of the options, which fail or are ignored at open.
+    fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(block_based) = &self.block_based {
+            if block_based.partition_filters {
+                if self.table_factory != "BlockBasedTable" {
+                    errors.push(format!(
+                        "partitioned filters of BlockBasedOptions are not supported by {}",
+                        self.table_factory
+                    ));
+                } else if !block_based.two_level_index {
+                    errors.push(
+                        "partitioned filters require BlockBasedIndexType.two_level_index_search()"
+                            .to_string(),
+                    );
+                }
+            }
+        }
+        if self.table_factory == "CuckooTable" && !self.allow_mmap_reads {
+            errors.push("CuckooTable requires allow_mmap_reads".to_string());
+        }
+        if self.unordered_write && self.enable_pipelined_write {
+            errors.push("unordered_write is incompatible with enable_pipelined_write".to_string());
+        }
+        if self.unordered_write && !self.allow_concurrent_memtable_write {
+            errors.push("unordered_write requires allow_concurrent_memtable_write".to_string());
+        }
+        if self.allow_concurrent_memtable_write && self.memtable_factory != "SkipList" {
+            errors.push(format!(
+                "{} memtable does not support allow_concurrent_memtable_write",
+                self.memtable_factory
+            ));
+        }
+        if self.allow_mmap_reads && self.use_direct_reads {
+            errors.push("allow_mmap_reads is incompatible with use_direct_reads".to_string());
+        }
+        if self.allow_mmap_writes && self.use_direct_io_for_flush_and_compaction {
+            errors.push(
+                "allow_mmap_writes is incompatible with use_direct_io_for_flush_and_compaction"
+                    .to_string(),
+            );
+        }
+        errors
+    }
 }
 
 /// Optionally disable WAL or sync for this write.
@@ -202,7 +309,14 @@ pub(crate) struct MemtableFactoryPy(MemtableFactory);
 
 /// For configuring block-based file storage.
 #[pyclass(name = "BlockBasedOptions")]
-pub(crate) struct BlockBasedOptionsPy(BlockBasedOptions);
+pub(crate) struct BlockBasedOptionsPy(pub(crate) BlockBasedOptions, BlockBasedSettings);
+
+/// Options of `BlockBasedOptions` cross-checked by `Options.validate`.
+#[derive(Clone, Copy, Default)]
+struct BlockBasedSettings {
+    partition_filters: bool,
+    two_level_index: bool,
+}
 
 /// Configuration of cuckoo-based storage.
 #[pyclass(name = "CuckooTableOptions")]
@@ -313,7 +427,7 @@ pub(crate) struct DBPathPy {
 ///         opt.set_compression_type(DBCompressionType.snappy())
 ///
 #[pyclass(name = "DBCompressionType")]
-pub(crate) struct DBCompressionTypePy(DBCompressionType);
+pub(crate) struct DBCompressionTypePy(pub(crate) DBCompressionType);
 
 /// This is to be treated as an enum.
 ///
@@ -513,6 +627,24 @@ unsafe impl Send for CompactOptionsPy {}
 unsafe impl Sync for CompactOptionsPy {}
 
 impl OptionsPy {
+    /// How keys are encoded, see `Options.set_key_codec`.
+    #[inline]
+    pub(crate) fn key_codec(&self) -> KeyCodec {
+        self.key_codec.unwrap_or(KeyCodec::from(self.raw_mode))
+    }
+
+    /// Set the key codec, the comparator of the codec, and raw mode if the codec is `raw`.
+    pub(crate) fn set_key_codec_inner(&mut self, key_codec: KeyCodec) {
+        match key_codec {
+            KeyCodec::Rocksdict => OptionsPy::set_rocksdict_comparator(&mut self.inner_opt),
+            // keep the builtin comparator, which is faster than a callback
+            // and lets other RocksDB tools open the database
+            _ => OptionsPy::set_bytewise_comparator(&mut self.inner_opt),
+        }
+        self.raw_mode = key_codec.is_raw();
+        self.key_codec = Some(key_codec);
+    }
+
     /// function that sets prefix extractor according to slice transform type
     fn set_prefix_extractor_inner(
         options: &mut Options,
@@ -541,27 +673,73 @@ impl OptionsPy {
         ignore_unknown_options: bool,
         cache: CachePy,
     ) -> PyResult<(OptionsPy, HashMap<String, OptionsPy>)> {
-        let mut config_path = PathBuf::from(path);
-        config_path.push(ROCKSDICT_CONFIG_FILE);
-        let rocksdict_config = RocksDictConfig::load(config_path).unwrap_or_default();
-        let raw_mode = rocksdict_config.raw_mode;
-        let slice_transforms = rocksdict_config.prefix_extractors;
         let load_result = Options::load_latest(path, env.0, ignore_unknown_options, cache.0);
+        OptionsPy::compose_loaded_options(Path::new(path), load_result)
+    }
+
+    /// load options from a specific OPTIONS file, and config files in the same directory
+    pub fn load_from_file_inner(
+        options_file: &str,
+        env: EnvPy,
+        ignore_unknown_options: bool,
+        cache: CachePy,
+    ) -> PyResult<(OptionsPy, HashMap<String, OptionsPy>)> {
+        let options_file = Path::new(options_file);
+        if !options_file.is_file() {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "OPTIONS file {} not found",
+                options_file.display()
+            )));
+        }
+        let db_dir = options_file.parent().unwrap_or_else(|| Path::new("."));
+        // the C API can only load the latest OPTIONS file of a directory,
+        // so copy the requested file into an otherwise empty directory.
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let tmp_dir = std::env::temp_dir().join(format!(
+            "rocksdict-options-{}-{}",
+            std::process::id(),
+            nanos
+        ));
+        fs::create_dir_all(&tmp_dir)?;
+        let load_result = match fs::copy(options_file, tmp_dir.join("OPTIONS-000001")) {
+            Ok(_) => Options::load_latest(&tmp_dir, env.0, ignore_unknown_options, cache.0),
+            Err(e) => {
+                fs::remove_dir_all(&tmp_dir).ok();
+                return Err(e.into());
+            }
+        };
+        fs::remove_dir_all(&tmp_dir).ok();
+        OptionsPy::compose_loaded_options(db_dir, load_result)
+    }
+
+    /// convert loaded rocksdb options to `OptionsPy` using the rocksdict config in `config_dir`
+    fn compose_loaded_options(
+        config_dir: &Path,
+        load_result: Result<(Options, Vec<ColumnFamilyDescriptor>), Error>,
+    ) -> PyResult<(OptionsPy, HashMap<String, OptionsPy>)> {
+        let config_path = config_dir.join(ROCKSDICT_CONFIG_FILE);
+        let rocksdict_config = RocksDictConfig::load_or_default(config_path)?;
+        let key_codecs = rocksdict_config.key_codecs();
+        let slice_transforms = rocksdict_config.prefix_extractors;
         let (options, column_families) = match load_result {
             Ok(d) => d,
-            Err(e) => return Err(PyException::new_err(e.to_string())),
+            Err(e) => return Err(to_py_err(e)),
         };
-        let options = OptionsPy::compose_options_py(
+        let mut options = OptionsPy::compose_options_py(
             options,
-            raw_mode,
+            key_codecs.default,
             slice_transforms.get(DEFAULT_COLUMN_FAMILY_NAME).cloned(),
         )?;
+        options.value_checksums = rocksdict_config.value_checksums;
         let column_families: PyResult<HashMap<_, _>> = column_families
             .into_iter()
             .map(|c| {
                 let opt = OptionsPy::compose_options_py(
                     c.options,
-                    raw_mode,
+                    key_codecs.get(&c.name),
                     slice_transforms.get(&c.name).cloned(),
                 );
                 match opt {
@@ -573,39 +751,93 @@ impl OptionsPy {
         Ok((options, column_families?))
     }
 
-    /// convert `Options` into `OptionsPy` based on `raw_mode` and `prefix_extractor`
+    /// convert `Options` into `OptionsPy` based on `key_codec` and `prefix_extractor`
     fn compose_options_py(
         opt: Options,
-        raw_mode: bool,
+        key_codec: KeyCodec,
         prefix_extractor: Option<SliceTransformType>,
     ) -> PyResult<OptionsPy> {
         let mut opt = opt;
-        if !raw_mode {
-            OptionsPy::set_rocksdict_comparator(&mut opt);
-        }
         if let Some(slice_transform) = &prefix_extractor {
             OptionsPy::set_prefix_extractor_inner(&mut opt, slice_transform)?
         }
-        let options = OptionsPy {
+        let mut options = OptionsPy {
             inner_opt: opt,
-            raw_mode,
+            raw_mode: key_codec.is_raw(),
+            key_codec: Some(key_codec),
+            value_checksums: false,
+            strict_types: false,
             prefix_extractor,
+            settings: OptionSettings::default(),
         };
+        if !key_codec.is_raw() {
+            options.set_key_codec_inner(key_codec);
+        }
         Ok(options)
     }
 
     fn set_rocksdict_comparator(opt: &mut Options) {
-        opt.set_comparator(
-            "rocksdict",
-            Box::new(|v1, v2| {
-                if let (Some(3), Some(3)) = (v1.first(), v2.first()) {
-                    BigInt::from_signed_bytes_be(&v1[1..])
-                        .cmp(&BigInt::from_signed_bytes_be(&v2[1..]))
-                } else {
-                    v1.cmp(v2)
-                }
-            }),
-        );
+        opt.set_comparator("rocksdict", Box::new(rocksdict_compare));
+    }
+
+    /// Restore the builtin bytewise comparator, replacing a custom one.
+    fn set_bytewise_comparator(opt: &mut Options) {
+        // the C API only sets comparators with callbacks,
+        // select the builtin one by name through an option string
+        let option = CString::new(format!("comparator={COMPARATOR_BYTEWISE}")).unwrap();
+        let inner = opt.inner() as *mut librocksdb_sys::rocksdb_options_t;
+        let mut err: *mut c_char = null_mut();
+        unsafe {
+            librocksdb_sys::rocksdb_get_options_from_string(
+                inner,
+                option.as_ptr(),
+                inner,
+                &mut err,
+            );
+        }
+        // the builtin comparator is always registered
+        assert!(err.is_null(), "{}", error_message(err));
+    }
+}
+
+/// Whether RocksDB was built with `t`, found once per type by opening
+/// an in-memory database with it, which fails for codecs not linked in.
+pub(crate) fn compression_type_supported(t: DBCompressionType) -> bool {
+    static SUPPORTED: OnceLock<Mutex<HashMap<&'static str, bool>>> = OnceLock::new();
+    let mut supported = SUPPORTED.get_or_init(Default::default).lock().unwrap();
+    *supported
+        .entry(compression_type_name(t))
+        .or_insert_with(|| {
+            let Ok(env) = Env::mem_env() else {
+                return false;
+            };
+            let mut opt = Options::default();
+            opt.set_env(&env);
+            opt.create_if_missing(true);
+            opt.set_compression_type(t);
+            DB::open(&opt, "/rocksdict-compression-probe").is_ok()
+        })
+}
+
+pub(crate) fn compression_type_name(t: DBCompressionType) -> &'static str {
+    match t {
+        DBCompressionType::None => "none",
+        DBCompressionType::Snappy => "snappy",
+        DBCompressionType::Zlib => "zlib",
+        DBCompressionType::Bz2 => "bz2",
+        DBCompressionType::Lz4 => "lz4",
+        DBCompressionType::Lz4hc => "lz4hc",
+        DBCompressionType::Zstd => "zstd",
+    }
+}
+
+/// Key order of non-raw mode: int keys are compared numerically,
+/// and other keys bytewise.
+pub(crate) fn rocksdict_compare(v1: &[u8], v2: &[u8]) -> Ordering {
+    if let (Some(3), Some(3)) = (v1.first(), v2.first()) {
+        BigInt::from_signed_bytes_be(&v1[1..]).cmp(&BigInt::from_signed_bytes_be(&v2[1..]))
+    } else {
+        v1.cmp(v2)
     }
 }
 
@@ -623,8 +855,202 @@ impl OptionsPy {
         OptionsPy {
             inner_opt: opt,
             raw_mode,
+            key_codec: None,
+            value_checksums: false,
+            strict_types: false,
             prefix_extractor: None,
+            settings: OptionSettings::default(),
+        }
+    }
+
+    /// Whether `set_persist_stats_to_disk` enabled persisting statistics.
+    pub(crate) fn persist_stats_to_disk(&self) -> bool {
+        self.settings.persist_stats_to_disk
+    }
+
+    /// Check the options for incompatible combinations before opening a database.
+    ///
+    /// Checks table factories, memtable factories, and write and IO
+    /// modes set on this instance, such as partitioned filters without
+    /// a two-level index, or `unordered_write` with
+    /// `enable_pipelined_write`. RocksDB checks all options again when
+    /// a database is opened.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Options, MemtableFactory
+    ///
+    ///         opts = Options()
+    ///         opts.set_memtable_factory(MemtableFactory.vector())
+    ///         # raises ValueError, vector memtables do not support concurrent writes
+    ///         opts.validate()
+    ///
+    /// Raises:
+    ///     ValueError: listing all incompatible combinations found.
+    pub fn validate(&self) -> PyResult<()> {
+        let errors = self.settings.errors();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(errors.join("; ")))
+        }
+    }
+
+    /// Append a crc32c checksum to each value written in non-raw mode.
+    ///
+    /// The setting is recorded in `rocksdict-config.json`, and applies to
+    /// all column families. Checksums are verified when values are read,
+    /// raising `CorruptionError` on a mismatch, so that corrupted values
+    /// are told apart from values that the `loads` function cannot decode.
+    /// Values written without checksums remain readable. Use
+    /// `Rdict.verify_values` to scan a range of values.
+    ///
+    /// `SstFileWriter` appends checksums when created with these options,
+    /// and `WriteBatch` when created with `value_checksums=True`, which
+    /// `Rdict.write` requires once checksums are enabled.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_value_checksums(True)
+    ///         db = Rdict("./temp_path", opt)
+    ///         db["key"] = "value"
+    ///         assert db.verify_values()["corrupted"] == []
+    pub fn set_value_checksums(&mut self, enabled: bool) {
+        self.value_checksums = enabled
+    }
+
+    /// Set how keys are encoded in non-raw mode.
+    ///
+    /// - `"rocksdict"` (default, also named `"typed"`): int keys are stored
+    ///   as signed big-endian bytes, and ordered numerically by the custom
+    ///   `rocksdict` comparator.
+    /// - `"ordered"`: int keys are stored with a sign byte and a fixed-width
+    ///   4-byte length prefix, and float keys with their sign bit flipped,
+    ///   so that keys are ordered bytewise, and other RocksDB tools can
+    ///   open the database with the default comparator.
+    /// - `"u64_be"`: keys are ints in `[0, 2**64)`, stored as 8 big-endian
+    ///   bytes, as `rocksdict.keys.u64_be` does.
+    /// - `"composite"`: keys are tuples of `str`, `int`, `float`, `bool`
+    ///   and `bytes`, ordered element by element.
+    /// - `"raw"`: keys and values are bytes, as with `raw_mode=True`.
+    ///
+    /// With `"rocksdict"` and `"ordered"`, keys of different types are
+    /// ordered by type: all `bytes` keys, then `str`, `int`, `float` and
+    /// `bool` keys. With `"ordered"`, ints of any size and floats (including
+    /// `-inf`, `inf` and `nan`) are in numeric order within their type.
+    ///
+    /// The codec is recorded in `rocksdict-config.json`, and cannot be
+    /// changed on an existing database. Use `migrate_key_codec` to copy
+    /// an existing database into a new one with another codec.
+    ///
+    /// Column families use the codec of the database, unless their
+    /// options passed to `Rdict.create_column_family` or as
+    /// `column_families` set one, which is recorded for each column
+    /// family. This allows a database to hold both raw column families,
+    /// readable by other RocksDB tools, and column families of Python keys.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_key_codec("ordered")
+    ///         db = Rdict("./temp_path", opt)
+    ///         db[2**100] = "big"
+    ///         db[-1] = "negative"
+    ///         assert list(db.keys()) == [-1, 2**100]
+    ///
+    ///         raw_opt = Options()
+    ///         raw_opt.set_key_codec("raw")
+    ///         raw = db.create_column_family("raw", raw_opt)
+    ///         raw[b"key"] = b"value"
+    ///
+    ///         pair_opt = Options()
+    ///         pair_opt.set_key_codec("composite")
+    ///         pairs = db.create_column_family("pairs", pair_opt)
+    ///         pairs[("alice", 2)] = "second"
+    ///         pairs[("alice", 10)] = "tenth"
+    ///         assert list(pairs.keys()) == [("alice", 2), ("alice", 10)]
+    ///
+    /// Args:
+    ///     key_codec (str): `"rocksdict"`, `"typed"`, `"ordered"`,
+    ///         `"u64_be"`, `"composite"` or `"raw"`.
+    ///
+    /// Raises:
+    ///     ValueError: if `raw_mode` is True and `key_codec` is not `"raw"`,
+    ///         or `key_codec` is unknown.
+    pub fn set_key_codec(&mut self, key_codec: &str) -> PyResult<()> {
+        match KeyCodec::from_name(key_codec) {
+            Some(codec) if self.raw_mode && !codec.is_raw() => Err(PyValueError::new_err(
+                "keys are not encoded in raw mode, set raw_mode=False to choose a key codec",
+            )),
+            Some(codec) => {
+                self.set_key_codec_inner(codec);
+                Ok(())
+            }
+            None => Err(PyValueError::new_err(format!(
+                "unknown key codec `{key_codec}`, expected `rocksdict`, `typed`, `ordered`, \
+                 `u64_be`, `composite` or `raw`"
+            ))),
+        }
+    }
+
+    /// Raise `TypeError` instead of pickling values that are not
+    /// `str`, `int`, `float`, `bool` or `bytes`.
+    ///
+    /// Only these types are encoded in a way that can be read without
+    /// Python. This applies to values written through the `Rdict` opened
+    /// with these options, its column families and buffered writers, and
+    /// to `SstFileWriter` instances created with these options. It is not
+    /// recorded in the database, so it must be set each time it is opened.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_strict_types(True)
+    ///         db = Rdict("./temp_path", opt)
+    ///         db["key"] = "value"
+    ///         # raises TypeError
+    ///         db["list"] = [1, 2, 3]
+    pub fn set_strict_types(&mut self, enabled: bool) {
+        self.strict_types = enabled
+    }
+
+    /// Keep the newest `max_versions` versions of each key on compaction.
+    ///
+    /// Keys must end with an 8-byte version, which sorts newer versions
+    /// first. Versions of a key past the newest `max_versions` are
+    /// dropped by compactions, so older versions remain readable until
+    /// compacted. The filter is not recorded in the OPTIONS file, and
+    /// must be set each time the database is opened.
+    /// Used by `rocksdict.versioned.VersionedRdict`.
+    ///
+    /// Args:
+    ///     max_versions (int): number of versions to keep, at least 1.
+    ///
+    /// Raises:
+    ///     ValueError: if `raw_mode` is False, or `max_versions` is 0.
+    pub fn set_version_history(&mut self, max_versions: usize) -> PyResult<()> {
+        if !self.raw_mode {
+            return Err(PyValueError::new_err(
+                "version history requires raw_mode=True",
+            ));
         }
+        if max_versions == 0 {
+            return Err(PyValueError::new_err("max_versions must be at least 1"));
+        }
+        self.inner_opt
+            .set_compaction_filter_factory(VersionHistoryFactory { max_versions });
+        Ok(())
     }
 
     /// Load latest options from the rocksdb path
@@ -656,6 +1082,54 @@ impl OptionsPy {
         Ok(returned_tuple.to_object(py))
     }
 
+    /// Load options from a specific OPTIONS file
+    ///
+    /// Useful for restoring a known-good historical configuration,
+    /// for example after a bad `set_options` call. The rocksdict
+    /// config (raw mode and prefix extractors) is read from the
+    /// directory containing the OPTIONS file.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Options, Rdict
+    ///
+    ///         opt, cols = Options.load_from_file("./db_path/OPTIONS-000007")
+    ///         db = Rdict("./db_path", opt, cols)
+    ///
+    /// Args:
+    ///     options_file: path to the OPTIONS file.
+    ///     env: Env
+    ///     ignore_unknown_options: whether to ignore unknown options.
+    ///     cache: Cache
+    ///
+    /// Returns a tuple, where the first item is `Options`
+    /// and the second item is a `Dict` of column families.
+    #[staticmethod]
+    #[pyo3(signature = (
+        options_file,
+        env = EnvPy::default().unwrap(),
+        ignore_unknown_options = false,
+        cache = CachePy::new_lru_cache(8 * 1024 * 1204)
+    ))]
+    pub fn load_from_file(
+        options_file: &str,
+        env: EnvPy,
+        ignore_unknown_options: bool,
+        cache: CachePy,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let (options, column_families) =
+            OptionsPy::load_from_file_inner(options_file, env, ignore_unknown_options, cache)?;
+        let options = Py::new(py, options)?;
+        let columns = PyDict::new_bound(py);
+        for (name, opt) in column_families {
+            columns.set_item(name, Py::new(py, opt)?)?
+        }
+        let returned_tuple = PyTuple::new_bound(py, [options.to_object(py), columns.to_object(py)]);
+        Ok(returned_tuple.to_object(py))
+    }
+
     /// By default, RocksDB uses only one background thread for flush and
     /// compaction. Calling this function will set it up such that total of
     /// `total_threads` is used. Good value for `total_threads` is the number of
@@ -747,6 +1221,86 @@ impl OptionsPy {
         self.inner_opt.set_paranoid_checks(enabled)
     }
 
+    /// Enable/disable best-efforts recovery.
+    ///
+    /// If true, a database with missing or truncated SST or blob files,
+    /// such as an incomplete copy, is opened at the most recent point in
+    /// time whose files are all available, instead of failing, so that
+    /// the remaining data can be salvaged. Unlike `Rdict.repair`, the
+    /// database is opened in place without rewriting it, but recent
+    /// writes are lost with the missing files, and the WAL is not
+    /// recovered. Other corruptions are not handled, use `Rdict.repair`
+    /// for them.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_best_efforts_recovery(True)
+    ///         db = Rdict("./temp_damaged", opt)
+    ///
+    /// Default: false
+    pub fn set_best_efforts_recovery(&mut self, enabled: bool) -> PyResult<()> {
+        // not exposed by the C API, set it through an option string
+        let option = CString::new(format!("best_efforts_recovery={enabled}")).unwrap();
+        let inner = self.inner_opt.inner() as *mut librocksdb_sys::rocksdb_options_t;
+        unsafe {
+            ffi_try!(librocksdb_sys::rocksdb_get_options_from_string(
+                inner,
+                option.as_ptr(),
+                inner,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set the factory generating full file checksums of SST and blob files.
+    ///
+    /// With a factory, RocksDB computes the checksum of each file it
+    /// creates, records it in the MANIFEST, and verifies it when the file
+    /// is copied or ingested, for example by `ingest_external_file` or by
+    /// backups. The only supported factory is `"crc32c"`, whose checksums
+    /// match the ones returned by `Rdict.get_live_files_checksums`.
+    /// Pass `None` to stop generating checksums.
+    ///
+    /// Only files created after setting the factory have checksums.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_file_checksum_gen_factory("crc32c")
+    ///         db = Rdict("./temp_path", opt)
+    ///
+    /// Default: None
+    #[pyo3(signature = (name))]
+    pub fn set_file_checksum_gen_factory(&mut self, name: Option<&str>) -> PyResult<()> {
+        let factory = match name {
+            Some("crc32c") => "FileChecksumGenCrc32cFactory",
+            None => "nullptr",
+            Some(name) => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown file checksum gen factory: {name}, expected \"crc32c\""
+                )))
+            }
+        };
+        // not exposed by the C API, set it through an option string
+        let option = CString::new(format!("file_checksum_gen_factory={factory}")).unwrap();
+        let inner = self.inner_opt.inner() as *mut librocksdb_sys::rocksdb_options_t;
+        unsafe {
+            ffi_try!(librocksdb_sys::rocksdb_get_options_from_string(
+                inner,
+                option.as_ptr(),
+                inner,
+            ));
+        }
+        Ok(())
+    }
+
     /// A list of paths where SST files can be put into, with its target size.
     /// Newer data is placed into paths specified earlier in the vector while
     /// older data gradually moves to paths specified later in the vector.
@@ -786,7 +1340,7 @@ impl OptionsPy {
             db_paths.push(
                 match DBPath::new(&path.borrow().path, path.borrow().target_size) {
                     Ok(p) => p,
-                    Err(e) => return Err(PyException::new_err(e.into_string())),
+                    Err(e) => return Err(to_py_err(e)),
                 },
             );
         }
@@ -822,9 +1376,11 @@ impl OptionsPy {
     /// are cases where most lower levels would like to use quick compression
     /// algorithms while the higher levels (which have more data) use
     /// compression algorithms that have better compression but could
-    /// be slower. This array, if non-empty, should have an entry for
-    /// each level of the database; these override the value specified in
-    /// the previous field 'compression'.
+    /// be slower. This list, if non-empty, overrides the value specified
+    /// by `set_compression_type` for each level of the database.
+    ///
+    /// A list shorter than `num_levels` is extended by repeating its last
+    /// entry, so set `num_levels` first.
     ///
     /// Example:
     ///     ::
@@ -832,23 +1388,70 @@ impl OptionsPy {
     ///         from rocksdict import Options, DBCompressionType
     ///
     ///         opts = Options()
+    ///         # no compression for L0 and L1, zstd for the other levels
     ///         opts.set_compression_per_level([
     ///             DBCompressionType.none(),
     ///             DBCompressionType.none(),
-    ///             DBCompressionType.snappy(),
-    ///             DBCompressionType.snappy(),
-    ///             DBCompressionType.snappy()
+    ///             DBCompressionType.zstd(),
     ///         ])
+    ///
+    /// Args:
+    ///     level_types: a list of `DBCompressionType`, starting from L0.
+    ///
+    /// Raises:
+    ///     ValueError: if the list is longer than `num_levels`, or if one of
+    ///         the compression types is not supported by this build, see
+    ///         `Options.compression_supported`.
     pub fn set_compression_per_level(&mut self, level_types: &Bound<PyList>) -> PyResult<()> {
-        let mut result = Vec::with_capacity(level_types.len());
+        let num_levels = unsafe {
+            librocksdb_sys::rocksdb_options_get_num_levels(
+                self.inner_opt.inner() as *mut librocksdb_sys::rocksdb_options_t
+            )
+        } as usize;
+        if level_types.len() > num_levels {
+            return Err(PyValueError::new_err(format!(
+                "{} compression types for {num_levels} levels",
+                level_types.len()
+            )));
+        }
+        let mut result = Vec::with_capacity(num_levels);
         for py_any in level_types.iter() {
             let level_type: &Bound<DBCompressionTypePy> = py_any.downcast()?;
-            result.push(level_type.borrow().0)
+            let level_type = level_type.borrow().0;
+            if !result.contains(&level_type) && !compression_type_supported(level_type) {
+                return Err(PyValueError::new_err(format!(
+                    "{} compression is not supported by this build of RocksDB",
+                    compression_type_name(level_type)
+                )));
+            }
+            result.push(level_type)
+        }
+        if let Some(&last) = result.last() {
+            result.resize(num_levels, last);
         }
         self.inner_opt.set_compression_per_level(&result);
         Ok(())
     }
 
+    /// Whether this build of RocksDB supports a compression type.
+    ///
+    /// Use it to detect missing codecs, such as zstd or lz4, before
+    /// writing data: opening a database with an unsupported compression
+    /// type fails.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Options, DBCompressionType
+    ///
+    ///         opts = Options()
+    ///         if Options.compression_supported(DBCompressionType.zstd()):
+    ///             opts.set_compression_type(DBCompressionType.zstd())
+    #[staticmethod]
+    pub fn compression_supported(t: &DBCompressionTypePy) -> bool {
+        compression_type_supported(t.0)
+    }
+
     /// Maximum size of dictionaries used to prime the compression library.
     /// Enabling dictionary can improve compression ratios when there are
     /// repetitions across data blocks.
@@ -1077,6 +1680,7 @@ impl OptionsPy {
     ///
     /// Default: true
     pub fn set_allow_concurrent_memtable_write(&mut self, allow: bool) {
+        self.settings.allow_concurrent_memtable_write = allow;
         self.inner_opt.set_allow_concurrent_memtable_write(allow)
     }
 
@@ -1111,6 +1715,7 @@ impl OptionsPy {
     ///
     /// Default: false
     pub fn set_use_direct_reads(&mut self, enabled: bool) {
+        self.settings.use_direct_reads = enabled;
         self.inner_opt.set_use_direct_reads(enabled)
     }
 
@@ -1124,6 +1729,7 @@ impl OptionsPy {
     ///
     /// Default: false
     pub fn set_use_direct_io_for_flush_and_compaction(&mut self, enabled: bool) {
+        self.settings.use_direct_io_for_flush_and_compaction = enabled;
         self.inner_opt
             .set_use_direct_io_for_flush_and_compaction(enabled)
     }
@@ -1340,6 +1946,7 @@ impl OptionsPy {
     ///
     /// Default: false
     pub fn set_unordered_write(&mut self, unordered: bool) {
+        self.settings.unordered_write = unordered;
         self.inner_opt.set_unordered_write(unordered)
     }
 
@@ -1507,6 +2114,7 @@ impl OptionsPy {
     ///
     /// Default: false
     pub fn set_enable_pipelined_write(&mut self, value: bool) {
+        self.settings.enable_pipelined_write = value;
         self.inner_opt.set_enable_pipelined_write(value)
     }
 
@@ -1526,6 +2134,11 @@ impl OptionsPy {
     ///         opts.set_allow_concurrent_memtable_write(false)
     ///         opts.set_memtable_factory(factory)
     pub fn set_memtable_factory(&mut self, factory: &MemtableFactoryPy) {
+        self.settings.memtable_factory = match factory.0 {
+            MemtableFactory::Vector => "Vector",
+            MemtableFactory::HashSkipList { .. } => "HashSkipList",
+            MemtableFactory::HashLinkList { .. } => "HashLinkList",
+        };
         self.inner_opt.set_memtable_factory(match factory.0 {
             MemtableFactory::Vector => MemtableFactory::Vector,
             MemtableFactory::HashSkipList {
@@ -1544,6 +2157,8 @@ impl OptionsPy {
     }
 
     pub fn set_block_based_table_factory(&mut self, factory: &BlockBasedOptionsPy) {
+        self.settings.table_factory = "BlockBasedTable";
+        self.settings.block_based = Some(factory.1);
         self.inner_opt.set_block_based_table_factory(&factory.0)
     }
 
@@ -1567,6 +2182,7 @@ impl OptionsPy {
     ///
     ///         opts.set_cuckoo_table_factory(factory_opts)
     pub fn set_cuckoo_table_factory(&mut self, factory: &CuckooTableOptionsPy) {
+        self.settings.table_factory = "CuckooTable";
         self.inner_opt.set_cuckoo_table_factory(&factory.0)
     }
 
@@ -1592,6 +2208,7 @@ impl OptionsPy {
     ///
     ///         opts.set_plain_table_factory(factory_opts)
     pub fn set_plain_table_factory(&mut self, options: &PlainTableFactoryOptionsPy) {
+        self.settings.table_factory = "PlainTable";
         self.inner_opt
             .set_plain_table_factory(&options.to_opt(self.raw_mode))
     }
@@ -1624,6 +2241,10 @@ impl OptionsPy {
         self.inner_opt.set_wal_recovery_mode(mode.0)
     }
 
+    /// Collect statistics of tickers and histograms.
+    ///
+    /// Each database opened with these options gets its own statistics,
+    /// which can be read with `Rdict.statistics()`.
     pub fn enable_statistics(&mut self) {
         self.inner_opt.enable_statistics()
     }
@@ -1646,6 +2267,35 @@ impl OptionsPy {
         self.inner_opt.set_stats_persist_period_sec(period)
     }
 
+    /// Persist statistics every `stats_persist_period_sec` to the hidden
+    /// column family `___rocksdb_stats_history___` of the database,
+    /// instead of keeping them in memory, so that they survive restarts.
+    ///
+    /// Statistics must be enabled with `enable_statistics`. Read the
+    /// persisted statistics with `Rdict.stats_history`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.enable_statistics()
+    ///         opt.set_stats_persist_period_sec(60)
+    ///         opt.set_persist_stats_to_disk(True)
+    ///         db = Rdict("./temp_path", opt)
+    ///
+    /// Default: false
+    pub fn set_persist_stats_to_disk(&mut self, enabled: bool) {
+        unsafe {
+            rocksdict_options_set_persist_stats_to_disk(
+                self.inner_opt.inner() as *mut librocksdb_sys::rocksdb_options_t,
+                c_uchar::from(enabled),
+            );
+        }
+        self.settings.persist_stats_to_disk = enabled;
+    }
+
     /// When set to true, reading SST files will opt out of the filesystem's
     /// readahead. Setting this to false may improve sequential iteration
     /// performance.
@@ -1762,6 +2412,7 @@ impl OptionsPy {
     ///
     /// Default: false
     pub fn set_allow_mmap_writes(&mut self, is_enabled: bool) {
+        self.settings.allow_mmap_writes = is_enabled;
         self.inner_opt.set_allow_mmap_writes(is_enabled)
     }
 
@@ -1769,6 +2420,7 @@ impl OptionsPy {
     ///
     /// Default: false
     pub fn set_allow_mmap_reads(&mut self, is_enabled: bool) {
+        self.settings.allow_mmap_reads = is_enabled;
         self.inner_opt.set_allow_mmap_reads(is_enabled)
     }
 
@@ -1875,6 +2527,27 @@ impl OptionsPy {
             .set_hard_pending_compaction_bytes_limit(limit)
     }
 
+    /// Sets the rate in bytes per second that writes are slowed down to
+    /// when a write stall condition is triggered (too many level 0 files,
+    /// memtables, or pending compaction bytes).
+    ///
+    /// If 0, it is inferred from the rate limiter if one is set,
+    /// and is 16MB/s otherwise. The configured value is reported by
+    /// `Rdict.write_stall_info()`.
+    ///
+    /// Default: 0
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Options
+    ///
+    ///         options = Options()
+    ///         options.set_delayed_write_rate(8 * 1024 * 1024)
+    pub fn set_delayed_write_rate(&mut self, rate: u64) {
+        self.inner_opt.set_delayed_write_rate(rate)
+    }
+
     /// Sets the size of one block in arena memory allocation.
     ///
     /// If <= 0, a proper value is automatically calculated (usually 1/10 of
@@ -1984,10 +2657,26 @@ impl OptionsPy {
     /// entries in any "N" consecutive entries, or the ratio of tombstone
     /// entries >= deletion_ratio.
     ///
-    /// `window_size`: is the sliding window size "N"
-    /// `num_dels_trigger`: is the deletion trigger "D"
-    /// `deletion_ratio`: if <= 0 or > 1, disable triggering compaction based on
-    /// deletion ratio.
+    /// Useful for delete-heavy workloads, where scans would otherwise
+    /// slow down skipping over tombstones until regular compactions
+    /// reach them.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         # compact files with 50 deletions in any 128 consecutive
+    ///         # entries, or with at least 50% tombstones
+    ///         opt.add_compact_on_deletion_collector_factory(128, 50, 0.5)
+    ///         db = Rdict("./temp_path", opt)
+    ///
+    /// Args:
+    ///     window_size: is the sliding window size "N"
+    ///     num_dels_trigger: is the deletion trigger "D"
+    ///     deletion_ratio: if <= 0 or > 1, disable triggering compaction based on
+    ///         deletion ratio.
     pub fn add_compact_on_deletion_collector_factory(
         &mut self,
         window_size: size_t,
@@ -2142,7 +2831,7 @@ impl ReadOptionsPy {
 
     /// Sets the lower bound for an iterator.
     pub fn set_iterate_lower_bound(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
-        self.iterate_upper_bound = key.to_object(py);
+        self.iterate_lower_bound = key.to_object(py);
         Ok(())
     }
 
@@ -2238,6 +2927,8 @@ impl ReadOptionsPy {
     /// Asynchronously prefetch some data.
     ///
     /// Used for sequential reads and internal automatic prefetching.
+    /// Batched gets do not read asynchronously, since the coroutine
+    /// MultiGet of RocksDB is not built.
     ///
     /// Default: `false`
     pub fn set_async_io(&mut self, v: bool) {
@@ -2246,15 +2937,20 @@ impl ReadOptionsPy {
 }
 
 impl ReadOptionsPy {
-    pub(crate) fn to_read_options(&self, raw_mode: bool, py: Python) -> PyResult<ReadOptions> {
+    pub(crate) fn to_read_options(
+        &self,
+        key_codec: impl Into<KeyCodec>,
+        py: Python,
+    ) -> PyResult<ReadOptions> {
+        let key_codec = key_codec.into();
         let mut opt = ReadOptions::default();
         opt.fill_cache(self.fill_cache);
         if !self.iterate_lower_bound.is_none(py) {
-            let lower_bound = encode_key(self.iterate_lower_bound.bind(py), raw_mode)?;
+            let lower_bound = encode_key(self.iterate_lower_bound.bind(py), key_codec)?;
             opt.set_iterate_lower_bound(lower_bound);
         }
         if !self.iterate_upper_bound.is_none(py) {
-            let upper_bound = encode_key(self.iterate_upper_bound.bind(py), raw_mode)?;
+            let upper_bound = encode_key(self.iterate_upper_bound.bind(py), key_codec)?;
             opt.set_iterate_upper_bound(upper_bound);
         }
         opt.set_prefix_same_as_start(self.prefix_same_as_start);
@@ -2270,29 +2966,20 @@ impl ReadOptionsPy {
         Ok(opt)
     }
 
-    pub(crate) fn to_read_opt(&self, raw_mode: bool, py: Python) -> PyResult<ReadOpt> {
+    pub(crate) fn to_read_opt(
+        &self,
+        key_codec: impl Into<KeyCodec>,
+        py: Python,
+    ) -> PyResult<ReadOpt> {
+        let key_codec = key_codec.into();
         let opt = unsafe { ReadOpt(librocksdb_sys::rocksdb_readoptions_create()) };
         if !self.iterate_lower_bound.is_none(py) {
-            let lower_bound = encode_key(self.iterate_lower_bound.bind(py), raw_mode)?;
-
-            unsafe {
-                librocksdb_sys::rocksdb_readoptions_set_iterate_lower_bound(
-                    opt.0,
-                    lower_bound.as_ptr() as *const c_char,
-                    lower_bound.len() as size_t,
-                );
-            }
+            let lower_bound = encode_key(self.iterate_lower_bound.bind(py), key_codec)?;
+            opt.set_iterate_lower_bound(&lower_bound);
         }
         if !self.iterate_upper_bound.is_none(py) {
-            let upper_bound = encode_key(self.iterate_upper_bound.bind(py), raw_mode)?;
-
-            unsafe {
-                librocksdb_sys::rocksdb_readoptions_set_iterate_upper_bound(
-                    opt.0,
-                    upper_bound.as_ptr() as *const c_char,
-                    upper_bound.len() as size_t,
-                );
-            }
+            let upper_bound = encode_key(self.iterate_upper_bound.bind(py), key_codec)?;
+            opt.set_iterate_upper_bound(&upper_bound);
         }
         unsafe {
             librocksdb_sys::rocksdb_readoptions_set_fill_cache(opt.0, self.fill_cache as c_uchar);
@@ -2331,6 +3018,32 @@ impl ReadOptionsPy {
     }
 }
 
+impl ReadOpt {
+    /// Sets the (inclusive) lower bound of iterators using an encoded key.
+    /// The key is copied.
+    pub(crate) fn set_iterate_lower_bound(&self, key: &[u8]) {
+        unsafe {
+            librocksdb_sys::rocksdb_readoptions_set_iterate_lower_bound(
+                self.0,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+            );
+        }
+    }
+
+    /// Sets the (exclusive) upper bound of iterators using an encoded key.
+    /// The key is copied.
+    pub(crate) fn set_iterate_upper_bound(&self, key: &[u8]) {
+        unsafe {
+            librocksdb_sys::rocksdb_readoptions_set_iterate_upper_bound(
+                self.0,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+            );
+        }
+    }
+}
+
 unsafe impl Send for ReadOpt {}
 
 unsafe impl Sync for ReadOpt {}
@@ -2367,7 +3080,7 @@ impl MemtableFactoryPy {
 impl BlockBasedOptionsPy {
     #[new]
     pub fn default() -> Self {
-        BlockBasedOptionsPy(BlockBasedOptions::default())
+        BlockBasedOptionsPy(BlockBasedOptions::default(), BlockBasedSettings::default())
     }
 
     /// Approximate size of user data packed per block. Note that the
@@ -2396,6 +3109,7 @@ impl BlockBasedOptionsPy {
     /// Use partitioned full filters for each SST file. This option is
     /// incompatible with block-based filters.
     pub fn set_partition_filters(&mut self, size: bool) {
+        self.1.partition_filters = size;
         self.0.set_partition_filters(size)
     }
 
@@ -2434,6 +3148,7 @@ impl BlockBasedOptionsPy {
     ///         block_opts.set_index_type(BlockBasedIndexType.hash_search())
     ///         opts.set_block_based_table_factory(block_opts)
     pub fn set_index_type(&mut self, index_type: &BlockBasedIndexTypePy) {
+        self.1.two_level_index = matches!(index_type.0, BlockBasedIndexType::TwoLevelIndexSearch);
         self.0.set_index_type(match index_type.0 {
             BlockBasedIndexType::BinarySearch => BlockBasedIndexType::BinarySearch,
             BlockBasedIndexType::HashSearch => BlockBasedIndexType::HashSearch,
@@ -2870,7 +3585,7 @@ impl EnvPy {
     pub fn default() -> PyResult<Self> {
         match Env::new() {
             Ok(env) => Ok(EnvPy(env)),
-            Err(e) => Err(PyException::new_err(e.into_string())),
+            Err(e) => Err(to_py_err(e)),
         }
     }
 
@@ -2880,7 +3595,7 @@ impl EnvPy {
     pub fn mem_env() -> PyResult<Self> {
         match Env::mem_env() {
             Ok(env) => Ok(EnvPy(env)),
-            Err(e) => Err(PyException::new_err(e.into_string())),
+            Err(e) => Err(to_py_err(e)),
         }
     }
 
@@ -0,0 +1,17 @@
+// Setters of options the C API of RocksDB does not expose.
+
+#include "rocksdb/options.h"
+
+// the layout of `rocksdb_options_t` in `db/c.cc`
+struct rocksdb_options_t {
+  rocksdb::Options rep;
+};
+
+extern "C" {
+
+void rocksdict_options_set_persist_stats_to_disk(rocksdb_options_t* opt,
+                                                 unsigned char v) {
+  opt->rep.persist_stats_to_disk = v;
+}
+
+}  // extern "C"
@@ -1,34 +1,69 @@
-use crate::db_reference::{DbReference, DbReferenceHolder};
-use crate::encoder::{decode_value, encode_key, encode_value};
-use crate::exceptions::DbClosedError;
-use crate::iter::{RdictItems, RdictKeys, RdictValues};
+use crate::archive::{pack_dir, sibling_temp_path, unpack};
+use crate::atomic::RdictAtomic;
+use crate::auto_flush::AutoFlush;
+use crate::buffered_writer::{RdictBufferedWriter, WriterConfig};
+use crate::checkpoints::CheckpointPy;
+use crate::checksum::{file_crc32c, format_checksum};
+use crate::db_reference::{
+    is_locked_by_other_process, is_open_in_process, try_lock_db, DbReference, DbReferenceHolder,
+    WriteGuard,
+};
+use crate::encoder::{
+    decode_key, decode_value, encode_key, encode_value, key_successor, max_encoded_key,
+    transcode_key, EncodedBytes, KeyCodec, KeyCodecs, ValueCodec,
+};
+use crate::exceptions::{
+    status_to_py_err, to_py_err, BusyError, CorruptionError, DbClosedError, DbFrozenError,
+    NotSupportedError, RocksDBError,
+};
+use crate::iter::{IterPool, RdictItems, RdictKeys, RdictValues};
+use crate::jobs::{json_to_py, JobHistory};
+use crate::key_locks::KeyGuard;
+use crate::latency::{LatencyTracker, Operation};
 use crate::options::{CachePy, EnvPy, SliceTransformType};
+use crate::replication::{
+    batch_column_family_ids, combine_batches, latest_key_sequence, RdictUpdates,
+};
+use crate::retry::{with_retry, RetryPolicyPy};
+use crate::snapshot::ConsistentView;
+use crate::util::{path_to_str, TempDir};
+use crate::wal::{delete_live_wal_files, purge_wal_files, sorted_wal_files, WalFile};
 use crate::{
     CompactOptionsPy, FlushOptionsPy, IngestExternalFileOptionsPy, OptionsPy, RdictColumns,
     RdictEntities, RdictIter, ReadOptionsPy, Snapshot, WriteBatchPy, WriteOptionsPy,
 };
-use pyo3::exceptions::{PyException, PyKeyError};
+use core::slice;
+use libc::{c_void, size_t};
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{
+    PyException, PyFileExistsError, PyFileNotFoundError, PyIndexError, PyKeyError, PyTimeoutError,
+    PyTypeError, PyUserWarning, PyValueError,
+};
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList, PyTuple};
+use pyo3::types::{
+    PyBytes, PyDict, PyList, PyMemoryView, PyTuple, PyWeakrefMethods, PyWeakrefReference,
+};
 use rocksdb::{
-    ColumnFamilyDescriptor, FlushOptions, Iterable as _, LiveFile, ReadOptions,
-    UnboundColumnFamily, WriteOptions, DEFAULT_COLUMN_FAMILY_NAME,
+    AsColumnFamilyRef, BottommostLevelCompaction, ColumnFamilyDescriptor, CompactOptions,
+    FlushOptions, IngestExternalFileOptions, Iterable as _, LiveFile, ReadOptions, SstFileWriter,
+    UnboundColumnFamily, WriteBatch, WriteOptions, DEFAULT_COLUMN_FAMILY_NAME,
 };
 use serde::{Deserialize, Serialize};
-use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub const ROCKSDICT_CONFIG_FILE: &str = "rocksdict-config.json";
 /// 8MB default LRU cache size
 pub const DEFAULT_LRU_CACHE_SIZE: usize = 8 * 1024 * 1024;
 
-pub fn config_file(path: &str) -> PathBuf {
-    let mut config_path = PathBuf::from(path);
+pub fn config_file<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut config_path = path.as_ref().to_path_buf();
     config_path.push(ROCKSDICT_CONFIG_FILE);
     config_path
 }
@@ -65,7 +100,16 @@ type DB = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
 ///     access_type (AccessType): there are four access types:
 ///         ReadWrite, ReadOnly, WithTTL, and Secondary, use
 ///         AccessType class to create.
-#[pyclass(name = "Rdict")]
+///     retry_policy (RetryPolicy): retry writes and `try_catch_up_with_primary`
+///         on transient `Busy` and `TryAgain` errors. No retry by default.
+///     skip_wal_recovery (bool): delete the WAL files before opening,
+///         instead of replaying them. This silently and permanently
+///         discards all writes not flushed to SST files yet, and all
+///         prepared but uncommitted two-phase commit transactions.
+///         Makes reopening after a crash faster, for disposable data
+///         such as caches. Not supported for read-only and secondary
+///         access types.
+#[pyclass(name = "Rdict", weakref)]
 pub(crate) struct Rdict {
     pub(crate) write_opt: WriteOptions,
     pub(crate) flush_opt: FlushOptionsPy,
@@ -78,6 +122,22 @@ pub(crate) struct Rdict {
     pub(crate) opt_py: OptionsPy,
     pub(crate) access_type: AccessType,
     pub(crate) slice_transforms: Arc<RwLock<HashMap<String, SliceTransformType>>>,
+    /// Key codecs of the database and its column families, shared by the column families.
+    pub(crate) key_codecs: Arc<RwLock<KeyCodecs>>,
+    pub(crate) retry_policy: Option<RetryPolicyPy>,
+    /// Weak references of the column family `Rdict`s sharing the database, for `reopen`.
+    pub(crate) cf_rdicts: Arc<Mutex<Vec<Py<PyWeakrefReference>>>>,
+    pub(crate) auto_flush: Option<AutoFlush>,
+    /// Stages applied to the encoded values of this column family, such as encryption.
+    pub(crate) codec: ValueCodec,
+    /// Latencies of operations, shared by the column families of the database.
+    pub(crate) latency: Arc<LatencyTracker>,
+    /// Recent flush and compaction jobs, shared by the column families of the database.
+    pub(crate) jobs: Arc<JobHistory>,
+    /// Opened by `Rdict.open_external`, which never writes `rocksdict-config.json`.
+    pub(crate) external: bool,
+    /// Idle iterators of this column family, reused by `pooled_iter`.
+    pub(crate) iter_pool: Arc<IterPool>,
     // drop DB last
     pub(crate) db: DbReferenceHolder,
 }
@@ -109,29 +169,191 @@ pub(crate) struct Rdict {
 #[pyclass(name = "AccessType")]
 pub(crate) struct AccessType(AccessTypeInner);
 
+/// Current version of `rocksdict-config.json`.
+///
+/// Bump this whenever the meaning of stored data changes, and add
+/// a migration step to `RocksDictConfig::migrate`.
+pub const ROCKSDICT_CONFIG_VERSION: u32 = 1;
+pub const SERIALIZER_PICKLE: &str = "pickle";
+/// environment variable read by `Rdict.from_uri` when no uri is passed
+const ROCKSDICT_URI_ENV: &str = "ROCKSDICT_URI";
+pub const COMPARATOR_BYTEWISE: &str = "leveldb.BytewiseComparator";
+pub const COMPARATOR_ROCKSDICT: &str = "rocksdict";
+pub const KEY_CODEC_RAW: &str = "raw";
+pub const KEY_CODEC_ROCKSDICT: &str = "rocksdict";
+pub const KEY_CODEC_ORDERED: &str = "ordered";
+pub const KEY_CODEC_U64_BE: &str = "u64_be";
+pub const KEY_CODEC_COMPOSITE: &str = "composite";
+/// alias of `KEY_CODEC_ROCKSDICT` accepted by `Options.set_key_codec`
+pub const KEY_CODEC_TYPED: &str = "typed";
+/// column family of statistics persisted by `Options.set_persist_stats_to_disk`
+pub const STATS_HISTORY_COLUMN_FAMILY: &str = "___rocksdb_stats_history___";
+
+/// Config stored alongside the database in `rocksdict-config.json`.
+///
+/// Unknown fields are ignored when loading, so that configs written by
+/// newer releases with the same `version` can still be read. Configs with
+/// a newer `version` are rejected, and legacy configs without a `version`
+/// are migrated on load.
 #[derive(Serialize, Deserialize)]
 pub struct RocksDictConfig {
+    /// config format version, 0 for legacy configs without version
+    #[serde(default)]
+    pub version: u32,
     pub raw_mode: bool,
     // mapping from column families to SliceTransformType
     pub prefix_extractors: HashMap<String, SliceTransformType>,
+    /// serializer used for arbitrary python objects in non-raw mode
+    #[serde(default)]
+    pub serializer: String,
+    /// name of the comparator of the database
+    #[serde(default)]
+    pub comparator: String,
+    /// how keys and values are encoded into bytes
+    #[serde(default)]
+    pub key_codec: String,
+    /// whether values are written with checksums, see `Options.set_value_checksums`
+    #[serde(default)]
+    pub value_checksums: bool,
+    /// key codecs of the column families not using `key_codec`,
+    /// whose values are raw bytes if their codec is `raw`
+    #[serde(default)]
+    pub column_family_key_codecs: HashMap<String, String>,
 }
 
 impl Default for RocksDictConfig {
     fn default() -> Self {
-        Self {
-            raw_mode: true,
-            prefix_extractors: Default::default(),
-        }
+        RocksDictConfig::new(true, Default::default())
     }
 }
 
 impl RocksDictConfig {
+    pub fn new(raw_mode: bool, prefix_extractors: HashMap<String, SliceTransformType>) -> Self {
+        Self {
+            version: ROCKSDICT_CONFIG_VERSION,
+            raw_mode,
+            prefix_extractors,
+            serializer: SERIALIZER_PICKLE.to_string(),
+            comparator: if raw_mode {
+                COMPARATOR_BYTEWISE
+            } else {
+                COMPARATOR_ROCKSDICT
+            }
+            .to_string(),
+            key_codec: if raw_mode {
+                KEY_CODEC_RAW
+            } else {
+                KEY_CODEC_ROCKSDICT
+            }
+            .to_string(),
+            value_checksums: false,
+            column_family_key_codecs: HashMap::new(),
+        }
+    }
+
+    /// Record the key codec, and the comparator it orders keys with.
+    pub fn with_key_codec(mut self, key_codec: KeyCodec) -> Self {
+        self.key_codec = key_codec.name().to_string();
+        self.comparator = key_codec.comparator_name().to_string();
+        self
+    }
+
+    /// Record the key codecs of the database and of its column families.
+    pub fn with_key_codecs(self, key_codecs: &KeyCodecs) -> Self {
+        let mut config = self.with_key_codec(key_codecs.default);
+        config.column_family_key_codecs = key_codecs
+            .column_families
+            .iter()
+            .map(|(name, codec)| (name.clone(), codec.name().to_string()))
+            .collect();
+        config
+    }
+
+    /// The key codecs recorded, the default codec of `raw_mode` if unknown.
+    pub(crate) fn key_codecs(&self) -> KeyCodecs {
+        let mut key_codecs = KeyCodecs::new(
+            KeyCodec::from_name(&self.key_codec).unwrap_or(KeyCodec::from(self.raw_mode)),
+        );
+        for (name, codec) in &self.column_family_key_codecs {
+            if let Some(codec) = KeyCodec::from_name(codec) {
+                key_codecs.column_families.insert(name.clone(), codec);
+            }
+        }
+        key_codecs
+    }
+
+    /// Record whether values are written with checksums.
+    pub fn with_value_checksums(mut self, value_checksums: bool) -> Self {
+        self.value_checksums = value_checksums;
+        self
+    }
+
     pub fn load<P: AsRef<Path>>(path: P) -> PyResult<Self> {
-        let config_file = fs::File::options().read(true).open(path)?;
-        match serde_json::from_reader(config_file) {
-            Ok(c) => Ok(c),
-            Err(e) => Err(PyException::new_err(e.to_string())),
+        let config_file = fs::File::options().read(true).open(path.as_ref())?;
+        let config: Self = match serde_json::from_reader(config_file) {
+            Ok(c) => c,
+            Err(e) => return Err(CorruptionError::new_err(e.to_string())),
+        };
+        config.migrate(path.as_ref())
+    }
+
+    /// Load config if it exists, otherwise return the default config.
+    ///
+    /// Unlike `load(..).unwrap_or_default()`, this fails
+    /// if an existing config cannot be read or migrated.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> PyResult<Self> {
+        if path.as_ref().exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Upgrade a loaded config to `ROCKSDICT_CONFIG_VERSION`.
+    fn migrate(self, path: &Path) -> PyResult<Self> {
+        let config = match self.version {
+            // legacy config: only `raw_mode` and `prefix_extractors` were stored
+            0 => RocksDictConfig::new(self.raw_mode, self.prefix_extractors),
+            ROCKSDICT_CONFIG_VERSION => self,
+            version => {
+                return Err(NotSupportedError::new_err(format!(
+                    "{} has version {version}, but this rocksdict release supports up to version \
+                     {ROCKSDICT_CONFIG_VERSION}, please upgrade rocksdict to open this database",
+                    path.display()
+                )))
+            }
+        };
+        let supported = match KeyCodec::from_name(&config.key_codec) {
+            Some(codec) => {
+                codec.is_raw() == config.raw_mode && codec.comparator_name() == config.comparator
+            }
+            None => false,
+        };
+        if !supported {
+            return Err(PyValueError::new_err(format!(
+                "{} records key codec `{}` with comparator `{}`, which is not supported with raw_mode={}",
+                path.display(),
+                config.key_codec,
+                config.comparator,
+                config.raw_mode
+            )));
+        }
+        for (name, codec) in &config.column_family_key_codecs {
+            if KeyCodec::from_name(codec).is_none() {
+                return Err(NotSupportedError::new_err(format!(
+                    "{} records unsupported key codec `{codec}` for column family `{name}`",
+                    path.display(),
+                )));
+            }
         }
+        if config.serializer != SERIALIZER_PICKLE {
+            return Err(NotSupportedError::new_err(format!(
+                "{} records unsupported serializer `{}`",
+                path.display(),
+                config.serializer
+            )));
+        }
+        Ok(config)
     }
 
     pub fn save<P: AsRef<Path>>(&self, path: P) -> PyResult<()> {
@@ -146,7 +368,7 @@ impl RocksDictConfig {
         }
     }
 
-    pub fn save_to_dir(&self, dir: &str) -> PyResult<()> {
+    pub fn save_to_dir(&self, dir: &Path) -> PyResult<()> {
         self.save(config_file(dir))
     }
 }
@@ -154,48 +376,306 @@ impl RocksDictConfig {
 impl Rdict {
     #[inline]
     pub(crate) fn config(&self) -> RocksDictConfig {
-        RocksDictConfig {
-            raw_mode: self.opt_py.raw_mode,
-            prefix_extractors: self.slice_transforms.read().unwrap().clone(),
-        }
+        let key_codecs = self.key_codecs.read().unwrap();
+        RocksDictConfig::new(
+            key_codecs.default.is_raw(),
+            self.slice_transforms.read().unwrap().clone(),
+        )
+        .with_key_codecs(&key_codecs)
+        .with_value_checksums(self.opt_py.value_checksums)
     }
 
     fn dump_config(&self) -> PyResult<()> {
+        if self.external {
+            return Ok(());
+        }
         self.config().save_to_dir(&self.path()?)
     }
 
-    #[inline]
-    pub(crate) fn get_db(&self) -> PyResult<&DbReference> {
+    /// Lock `key` of `cf` for an atomic read-modify-write, releasing the GIL while waiting.
+    fn lock_key(&self, cf: &Arc<UnboundColumnFamily>, key: &[u8], py: Python) -> KeyGuard {
+        let cf = cf.inner() as usize;
+        py.allow_threads(|| self.db.lock_key(cf, key))
+    }
+
+    /// Encode a value of the encoded `key` with the dumps function and the value codec.
+    pub(crate) fn dump_value<'a>(
+        &self,
+        key: &[u8],
+        value: &'a Bound<PyAny>,
+    ) -> PyResult<EncodedBytes<'a>> {
+        self.codec.encode(
+            key,
+            encode_value(
+                value,
+                &self.dumps,
+                self.opt_py.raw_mode,
+                self.opt_py.strict_types,
+            )?,
+        )
+    }
+
+    /// Decode a value stored at the encoded `key` with the value codec and the loads function.
+    pub(crate) fn load_value(&self, py: Python, key: &[u8], bytes: &[u8]) -> PyResult<PyObject> {
+        decode_value(
+            py,
+            &self.codec.decode(key, bytes)?,
+            &self.loads,
+            self.opt_py.raw_mode,
+        )
+    }
+
+    /// Whether `key` of `cf` exists with a value equal to `expected`.
+    fn value_equals(
+        &self,
+        db: &DB,
+        cf: &Arc<UnboundColumnFamily>,
+        key: &[u8],
+        expected: &Bound<PyAny>,
+        py: Python,
+    ) -> PyResult<bool> {
+        match db
+            .get_pinned_cf_opt(cf, key, &self.read_opt)
+            .map_err(to_py_err)?
+        {
+            Some(slice) => self
+                .load_value(py, key, slice.as_ref())?
+                .bind(py)
+                .eq(expected),
+            None => Ok(false),
+        }
+    }
+
+    /// Hold off `freeze` until the returned guard is dropped,
+    /// raising `DbFrozenError` if the database is frozen.
+    pub(crate) fn begin_write(&self) -> PyResult<WriteGuard> {
         self.db
-            .get()
-            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))
+            .begin_write()
+            .ok_or_else(|| DbFrozenError::new_err("database is frozen"))
     }
-}
 
-#[pymethods]
-impl Rdict {
-    /// Create a new database or open an existing one.
-    ///
-    /// If Options are not provided:
-    /// - first, attempt to read from the path
-    /// - if failed to read from the path, use default
-    #[new]
-    #[pyo3(signature = (
-        path,
-        options = None,
-        column_families = None,
-        access_type = AccessType::read_write()
-    ))]
-    fn new(
+    /// Run a write with the retry policy, waking up `wait_for` callers once it succeeds.
+    pub(crate) fn write_with_retry<T>(
+        &self,
+        f: impl FnMut() -> Result<T, rocksdb::Error>,
+    ) -> PyResult<T> {
+        let _writing = self.begin_write()?;
+        let result = with_retry(self.retry_policy.as_ref(), f)?;
+        self.db.notify_writes();
+        Ok(result)
+    }
+
+    /// Create an iterator over the keys between `start` and `stop`.
+    fn range_iter(
+        &self,
+        read_opt: Option<&ReadOptionsPy>,
+        start: Option<&Bound<PyAny>>,
+        stop: Option<&Bound<PyAny>>,
+        inclusive: (bool, bool),
+        py: Python,
+    ) -> PyResult<RdictIter> {
+        if start.is_none() && stop.is_none() {
+            return self.iter(read_opt, py);
+        }
+        let key_codec = self.opt_py.key_codec();
+        let readopts = match read_opt {
+            None => ReadOptionsPy::default(py)?.to_read_opt(key_codec, py)?,
+            Some(opt) => opt.to_read_opt(key_codec, py)?,
+        };
+        // RocksDB iterator bounds are [lower, upper)
+        if let Some(start) = start {
+            let start = encode_key(start, key_codec)?;
+            if inclusive.0 {
+                readopts.set_iterate_lower_bound(&start);
+            } else {
+                readopts.set_iterate_lower_bound(&key_successor(&start, key_codec));
+            }
+        }
+        if let Some(stop) = stop {
+            let stop = encode_key(stop, key_codec)?;
+            if inclusive.1 {
+                readopts.set_iterate_upper_bound(&key_successor(&stop, key_codec));
+            } else {
+                readopts.set_iterate_upper_bound(&stop);
+            }
+        }
+        Ok(RdictIter::from_read_opt(
+            &self.db,
+            &self.column_family,
+            readopts,
+            &self.loads,
+            &self.codec,
+            key_codec,
+        )?
+        .with_latency(&self.latency))
+    }
+
+    /// Open the database at `path`, returning it with its options,
+    /// prefix extractors, key codecs and job history.
+    fn open_db(
+        path: &str,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+        access_type: &AccessType,
+    ) -> PyResult<(
+        DB,
+        OptionsPy,
+        HashMap<String, SliceTransformType>,
+        KeyCodecs,
+        Arc<JobHistory>,
+    )> {
+        let (options, cfs, prefix_extractors, key_codecs) =
+            Rdict::prepare_open(path, options, column_families)?;
+        let (db, jobs) = Rdict::open_cf_descriptors(path, &options, cfs, access_type)?;
+        Ok((db, options, prefix_extractors, key_codecs, jobs))
+    }
+
+    /// Open the database at `path` created by other tools, with the key codec
+    /// of `raw_mode`, without reading or writing `rocksdict-config.json`.
+    fn open_external_db(
         path: &str,
+        raw_mode: bool,
         options: Option<OptionsPy>,
         column_families: Option<HashMap<String, OptionsPy>>,
+        access_type: &AccessType,
+    ) -> PyResult<(DB, OptionsPy, KeyCodecs, Arc<JobHistory>)> {
+        if !Path::new(path).join("CURRENT").is_file() {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "no database found at {path}"
+            )));
+        }
+        // the default comparator orders keys as the `ordered` key codec does
+        let key_codec = if raw_mode {
+            KeyCodec::Raw
+        } else {
+            KeyCodec::Ordered
+        };
+        let (mut options, mut column_families) = match (options, column_families) {
+            (Some(opt), Some(cols)) => (opt, cols),
+            (opt, cols) => {
+                let (opt_loaded, cols_loaded) = match OptionsPy::load_latest_inner(
+                    path,
+                    EnvPy::default()?,
+                    false,
+                    CachePy::new_lru_cache(DEFAULT_LRU_CACHE_SIZE),
+                ) {
+                    Ok(loaded) => loaded,
+                    // databases without OPTIONS file
+                    Err(_) => {
+                        let opt = OptionsPy::new(true);
+                        let names = DB::list_cf(&opt.inner_opt, path).map_err(to_py_err)?;
+                        let cols = names.into_iter().map(|name| (name, opt.clone())).collect();
+                        (opt, cols)
+                    }
+                };
+                (opt.unwrap_or(opt_loaded), cols.unwrap_or(cols_loaded))
+            }
+        };
+        for opt in std::iter::once(&mut options).chain(column_families.values_mut()) {
+            if opt.key_codec() != key_codec {
+                opt.set_key_codec_inner(key_codec);
+            }
+        }
+        column_families
+            .entry(DEFAULT_COLUMN_FAMILY_NAME.to_string())
+            .or_insert_with(|| options.clone());
+        let cfs = column_families
+            .into_iter()
+            .map(|(name, opt)| ColumnFamilyDescriptor::new(name, opt.inner_opt))
+            .collect::<Vec<_>>();
+        let (db, jobs) = Rdict::open_cf_descriptors(path, &options, cfs, access_type)?;
+        Ok((db, options, KeyCodecs::new(key_codec), jobs))
+    }
+
+    /// Open the database at `path` with the column families `cfs`,
+    /// returning it with the history of its jobs.
+    fn open_cf_descriptors(
+        path: &str,
+        options: &OptionsPy,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        access_type: &AccessType,
+    ) -> PyResult<(DB, Arc<JobHistory>)> {
+        // listen on a copy, so that databases opened with
+        // the same options do not share the history
+        let jobs = Arc::new(JobHistory::default());
+        let mut opt_inner = options.inner_opt.clone();
+        jobs.listen(&mut opt_inner);
+        let opt_inner = &opt_inner;
+        let db = match &access_type.0 {
+            AccessTypeInner::ReadWrite => DB::open_cf_descriptors(opt_inner, path, cfs),
+            AccessTypeInner::ReadOnly {
+                error_if_log_file_exist,
+            } => DB::open_cf_descriptors_read_only(opt_inner, path, cfs, *error_if_log_file_exist),
+            AccessTypeInner::Secondary { secondary_path } => {
+                DB::open_cf_descriptors_as_secondary(opt_inner, path, secondary_path, cfs)
+            }
+            AccessTypeInner::WithTTL { ttl } => {
+                DB::open_cf_descriptors_with_ttl(opt_inner, path, cfs, *ttl)
+            }
+        }
+        .map_err(to_py_err)?;
+        Ok((db, jobs))
+    }
+
+    /// Create the `Rdict` of the default column family of an opened database.
+    #[allow(clippy::too_many_arguments)]
+    fn with_db(
+        db: DB,
+        jobs: Arc<JobHistory>,
+        options: OptionsPy,
+        prefix_extractors: HashMap<String, SliceTransformType>,
+        key_codecs: KeyCodecs,
         access_type: AccessType,
+        retry_policy: Option<RetryPolicyPy>,
+        external: bool,
         py: Python,
     ) -> PyResult<Self> {
         let pickle = PyModule::import_bound(py, "pickle")?.to_object(py);
+        let r_opt = ReadOptionsPy::default(py)?;
+        let w_opt = WriteOptionsPy::new();
+        Ok(Rdict {
+            db: DbReferenceHolder::new(db),
+            write_opt: (&w_opt).into(),
+            flush_opt: FlushOptionsPy::new(),
+            read_opt: r_opt.to_read_options(options.key_codec(), py)?,
+            loads: pickle.getattr(py, "loads")?,
+            dumps: pickle.getattr(py, "dumps")?,
+            write_opt_py: w_opt,
+            read_opt_py: r_opt,
+            column_family: None,
+            codec: ValueCodec::new(options.raw_mode, options.value_checksums),
+            opt_py: options,
+            access_type,
+            slice_transforms: Arc::new(RwLock::new(prefix_extractors)),
+            key_codecs: Arc::new(RwLock::new(key_codecs)),
+            retry_policy,
+            cf_rdicts: Arc::default(),
+            auto_flush: None,
+            latency: Arc::default(),
+            jobs,
+            external,
+            iter_pool: Arc::default(),
+        })
+    }
+
+    /// Resolve the options and column families to open the database at
+    /// `path` with, passed ones taking precedence over the latest OPTIONS
+    /// file, and save the rocksdict config of the database.
+    pub(crate) fn prepare_open(
+        path: &str,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+    ) -> PyResult<(
+        OptionsPy,
+        Vec<ColumnFamilyDescriptor>,
+        HashMap<String, SliceTransformType>,
+        KeyCodecs,
+    )> {
         // create db path if missing
         fs::create_dir_all(path).map_err(|e| PyException::new_err(e.to_string()))?;
+        // fail early on an incompatible rocksdict config, instead of overwriting it
+        let config_path = config_file(path);
+        let existing_config = RocksDictConfig::load_or_default(&config_path)?;
         // load options
         let options_loaded = OptionsPy::load_latest_inner(
             path,
@@ -214,26 +694,55 @@ impl Rdict {
             (Err(_), Some(opt), cols) => (opt, cols),
             (Err(_), None, cols) => (OptionsPy::new(false), cols),
         };
-        // save slice transforms types in rocksdict config
-        let config_path = config_file(path);
-        let mut prefix_extractors = HashMap::new();
-        if let Some(slice_transform) = &options.prefix_extractor {
-            prefix_extractors.insert(
-                DEFAULT_COLUMN_FAMILY_NAME.to_string(),
-                slice_transform.clone(),
-            );
-        }
-        if let Some(cf) = &column_families {
-            for (name, opt) in cf.iter() {
-                if let Some(slice_transform) = &opt.prefix_extractor {
-                    prefix_extractors.insert(name.clone(), slice_transform.clone());
+        // give each database its own statistics, instead of sharing them
+        // with all databases opened with copies of the same options
+        let mut options = options;
+        if options.inner_opt.get_statistics().is_some() {
+            options.inner_opt.enable_statistics();
+        }
+        // the `ordered` and raw key codecs share the bytewise comparator,
+        // so RocksDB cannot tell them apart when opening the database
+        let key_codec = options.key_codec();
+        if existing_config.key_codec != key_codec.name()
+            && existing_config.comparator == key_codec.comparator_name()
+            && Path::new(path).join("CURRENT").is_file()
+        {
+            return Err(PyValueError::new_err(format!(
+                "database at {path} uses key codec `{}`, but the options use key codec `{}`",
+                existing_config.key_codec,
+                key_codec.name()
+            )));
+        }
+        // open the column family of persisted statistics, which RocksDB
+        // creates with the bytewise comparator, to read it back
+        let mut column_families = column_families;
+        if options.persist_stats_to_disk() {
+            column_families
+                .get_or_insert_with(HashMap::new)
+                .entry(STATS_HISTORY_COLUMN_FAMILY.to_string())
+                .or_insert_with(|| OptionsPy::new(true));
+        }
+        // column families without a key codec of their own keep the recorded one
+        let recorded_key_codecs = existing_config.key_codecs();
+        let key_codecs = collect_key_codecs(&options, &column_families, &recorded_key_codecs);
+        for (name, cf_key_codec) in &key_codecs.column_families {
+            if let Some(recorded) = recorded_key_codecs.column_families.get(name) {
+                if recorded != cf_key_codec
+                    && recorded.comparator_name() == cf_key_codec.comparator_name()
+                {
+                    return Err(PyValueError::new_err(format!(
+                        "column family `{name}` uses key codec `{}`, but its options use key codec `{}`",
+                        recorded.name(),
+                        cf_key_codec.name()
+                    )));
                 }
             }
         }
-        let rocksdict_config = RocksDictConfig {
-            raw_mode: options.raw_mode,
-            prefix_extractors: prefix_extractors.clone(),
-        };
+        // save slice transforms types in rocksdict config
+        let prefix_extractors = collect_prefix_extractors(&options, &column_families);
+        let rocksdict_config = RocksDictConfig::new(options.raw_mode, prefix_extractors.clone())
+            .with_key_codecs(&key_codecs)
+            .with_value_checksums(options.value_checksums);
         rocksdict_config.save(config_path)?;
         let opt_inner = &options.inner_opt;
         // define column families
@@ -246,9 +755,13 @@ impl Rdict {
             }
             Some(mut cf) => {
                 let mut has_default_cf = false;
-                // check options_raw_mode for column families
+                // set the key codec and raw_mode of column families
                 for (cf_name, cf_opt) in cf.iter_mut() {
-                    cf_opt.raw_mode = options.raw_mode;
+                    let cf_key_codec = key_codecs.get(cf_name);
+                    cf_opt.raw_mode = cf_key_codec.is_raw();
+                    if cf_opt.key_codec() != cf_key_codec {
+                        cf_opt.set_key_codec_inner(cf_key_codec);
+                    }
                     if cf_name.as_str() == DEFAULT_COLUMN_FAMILY_NAME {
                         has_default_cf = true;
                     }
@@ -267,36 +780,125 @@ impl Rdict {
                 cfs
             }
         };
-        // open db
-        let db = match &access_type.0 {
-            AccessTypeInner::ReadWrite => DB::open_cf_descriptors(opt_inner, path, cfs),
-            AccessTypeInner::ReadOnly {
-                error_if_log_file_exist,
-            } => DB::open_cf_descriptors_read_only(opt_inner, path, cfs, *error_if_log_file_exist),
-            AccessTypeInner::Secondary { secondary_path } => {
-                DB::open_cf_descriptors_as_secondary(opt_inner, path, secondary_path, cfs)
+        Ok((options, cfs, prefix_extractors, key_codecs))
+    }
+
+    /// Options of this `Rdict`, with the key codec of the column family `name`.
+    fn column_family_options(&self, name: &str) -> OptionsPy {
+        let key_codec = self.key_codecs.read().unwrap().get(name);
+        let mut options = self.opt_py.clone();
+        if options.key_codec() != key_codec {
+            options.set_key_codec_inner(key_codec);
+        }
+        options
+    }
+
+    /// Set the dynamic option `name` of the current column family with
+    /// `set_options`, returning its effective value.
+    fn set_cf_option(&self, name: &str, value: String, py: Python) -> PyResult<Option<String>> {
+        let mut effective = self.set_options(HashMap::from([(name.to_string(), value)]), py)?;
+        Ok(effective.remove(name).flatten())
+    }
+
+    #[inline]
+    pub(crate) fn get_db(&self) -> PyResult<&DbReference> {
+        self.db
+            .get()
+            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))
+    }
+
+    /// Names and handles of all column families of the database,
+    /// skipping those dropped since listed.
+    fn all_column_families(&self) -> PyResult<(Vec<String>, Vec<Arc<UnboundColumnFamily>>)> {
+        let db = self.get_db()?;
+        let mut names = DB::list_cf(&self.opt_py.inner_opt, db.path()).map_err(to_py_err)?;
+        let mut cfs = Vec::with_capacity(names.len());
+        names.retain(|name| match unsafe { db.cf_handle_unbounded(name) } {
+            Some(cf) => {
+                cfs.push(cf);
+                true
             }
-            AccessTypeInner::WithTTL { ttl } => {
-                DB::open_cf_descriptors_with_ttl(opt_inner, path, cfs, *ttl)
+            None => false,
+        });
+        Ok((names, cfs))
+    }
+
+    /// Name of the current column family.
+    pub(crate) fn column_family_name(&self) -> String {
+        match &self.column_family {
+            None => DEFAULT_COLUMN_FAMILY_NAME.to_string(),
+            Some(cf) => unsafe {
+                let mut len: size_t = 0;
+                let name =
+                    librocksdb_sys::rocksdb_column_family_handle_get_name(cf.inner(), &mut len);
+                let result = String::from_utf8_lossy(slice::from_raw_parts(name as *const u8, len))
+                    .into_owned();
+                librocksdb_sys::rocksdb_free(name as *mut c_void);
+                result
+            },
+        }
+    }
+}
+
+#[pymethods]
+impl Rdict {
+    /// Create a new database or open an existing one.
+    ///
+    /// If Options are not provided:
+    /// - first, attempt to read from the path
+    /// - if failed to read from the path, use default
+    #[new]
+    #[pyo3(signature = (
+        path,
+        options = None,
+        column_families = None,
+        access_type = AccessType::read_write(),
+        retry_policy = None,
+        skip_wal_recovery = false
+    ))]
+    fn new(
+        path: &str,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+        access_type: AccessType,
+        retry_policy: Option<RetryPolicyPy>,
+        skip_wal_recovery: bool,
+        py: Python,
+    ) -> PyResult<Self> {
+        if skip_wal_recovery {
+            if let AccessTypeInner::ReadOnly { .. } | AccessTypeInner::Secondary { .. } =
+                &access_type.0
+            {
+                return Err(PyValueError::new_err(
+                    "skip_wal_recovery is not supported for read-only or secondary access",
+                ));
+            }
+            ensure_not_in_use(path)?;
+            // a new database has no WAL to skip
+            if Path::new(path).join("CURRENT").is_file() {
+                let wal_dir = wal_dir(Path::new(path))?;
+                // hold the LOCK while deleting, so that no other process
+                // opens the database and writes to its WAL meanwhile, and
+                // the GIL, so that no other thread opens it either
+                let _lock = try_lock_db(Path::new(path))?.ok_or_else(|| {
+                    BusyError::new_err(format!("database at {path} is locked by another process"))
+                })?;
+                delete_live_wal_files(&wal_dir)?;
             }
         }
-        .map_err(|e| PyException::new_err(e.to_string()))?;
-        let r_opt = ReadOptionsPy::default(py)?;
-        let w_opt = WriteOptionsPy::new();
-        Ok(Rdict {
-            db: DbReferenceHolder::new(db),
-            write_opt: (&w_opt).into(),
-            flush_opt: FlushOptionsPy::new(),
-            read_opt: r_opt.to_read_options(options.raw_mode, py)?,
-            loads: pickle.getattr(py, "loads")?,
-            dumps: pickle.getattr(py, "dumps")?,
-            write_opt_py: w_opt,
-            read_opt_py: r_opt,
-            column_family: None,
-            opt_py: options.clone(),
+        let (db, options, prefix_extractors, key_codecs, jobs) =
+            Rdict::open_db(path, options, column_families, &access_type)?;
+        Rdict::with_db(
+            db,
+            jobs,
+            options,
+            prefix_extractors,
+            key_codecs,
             access_type,
-            slice_transforms: Arc::new(RwLock::new(prefix_extractors)),
-        })
+            retry_policy,
+            false,
+            py,
+        )
     }
 
     /// set custom dumps function
@@ -309,20 +911,105 @@ impl Rdict {
         self.loads = loads
     }
 
-    /// Optionally disable WAL or sync for this write.
+    /// Encrypt the values of the current column family with AES-256-GCM.
+    ///
+    /// Values are encrypted before being written and decrypted after
+    /// being read, independently of the `Env` of the database, so that
+    /// sensitive column families can be protected on their own. Keys
+    /// are not encrypted. Values written before remain readable, so
+    /// encryption can be enabled on an existing database.
+    ///
+    /// The encryption key is not stored: set it again after opening the
+    /// database, and on each column family `Rdict`, since column families
+    /// do not share their keys.
+    ///
+    /// Each value is authenticated together with its key and the name of
+    /// its column family, so an encrypted value copied to another key or
+    /// column family fails to decrypt instead of being read as the value
+    /// stored there. For the same reason, `copy_range` and `ingest_from`
+    /// refuse to copy values between column families with different
+    /// encryption keys or names, while `split` and `merge_dbs` keep the
+    /// keys and column family names, so that the copied values remain
+    /// readable with the same encryption key.
+    ///
+    /// Notes:
+    ///     Only supported in non-raw mode. Values written by `WriteBatch`
+    ///     and `SstFileWriter` are not encrypted, and names of wide columns
+    ///     are not encrypted.
     ///
     /// Example:
     ///     ::
     ///
-    ///         from rocksdict import Rdict, Options, WriteBatch, WriteOptions
+    ///         import os
+    ///         from rocksdict import Rdict
     ///
-    ///         path = "_path_for_rocksdb_storageY1"
-    ///         db = Rdict(path)
+    ///         db = Rdict("./temp_path")
+    ///         secrets = db.create_column_family("secrets")
+    ///         key = os.urandom(32)
+    ///         secrets.set_value_encryption(key)
+    ///         secrets["token"] = "secret value"
+    ///         assert secrets["token"] == "secret value"
     ///
-    ///         # set write options
-    ///         write_options = WriteOptions()
-    ///         write_options.set_sync(False)
-    ///         write_options.disable_wal(True)
+    /// Args:
+    ///     key (bytes): a 32 bytes encryption key, or `None` to write new
+    ///         values unencrypted.
+    ///
+    /// Raises:
+    ///     ValueError: if the key is not 32 bytes, or in raw mode.
+    #[pyo3(signature = (key))]
+    fn set_value_encryption(&mut self, key: Option<&[u8]>) -> PyResult<()> {
+        let column_family = self.column_family_name();
+        self.codec.set_encryption_key(key, &column_family)
+    }
+
+    /// Compress large pickled values of the current column family with zstd.
+    ///
+    /// Pickle output is often highly compressible, and compressing each
+    /// large value on its own also removes the redundancy that block
+    /// compression misses when a value spans several blocks. Compressed
+    /// values are flagged in their type byte, and are read back
+    /// transparently even after compression is disabled. Values of other
+    /// types (`str`, `bytes`, `int`, ...) are not compressed.
+    ///
+    /// Like `set_value_encryption`, the setting is not stored and is
+    /// specific to each column family `Rdict`. Values are compressed
+    /// before being encrypted.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db.set_value_compression(threshold=1024)
+    ///         db["doc"] = {"text": "lorem ipsum " * 1000}
+    ///
+    /// Args:
+    ///     threshold (int): compress pickled values of at least this
+    ///         number of bytes, or `None` to stop compressing new values.
+    ///     level (int): zstd compression level.
+    ///
+    /// Raises:
+    ///     ValueError: if the level is invalid, or in raw mode.
+    #[pyo3(signature = (threshold = Some(1024), level = 3))]
+    fn set_value_compression(&mut self, threshold: Option<usize>, level: i32) -> PyResult<()> {
+        self.codec.set_compression(threshold, level)
+    }
+
+    /// Optionally disable WAL or sync for this write.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, WriteBatch, WriteOptions
+    ///
+    ///         path = "_path_for_rocksdb_storageY1"
+    ///         db = Rdict(path)
+    ///
+    ///         # set write options
+    ///         write_options = WriteOptions()
+    ///         write_options.set_sync(False)
+    ///         write_options.disable_wal(True)
     ///         db.set_write_options(write_options)
     ///
     ///         # write to db
@@ -340,7 +1027,7 @@ impl Rdict {
 
     /// Configure Read Options for all the get operations.
     fn set_read_options(&mut self, read_opt: &ReadOptionsPy, py: Python) -> PyResult<()> {
-        self.read_opt = read_opt.to_read_options(self.opt_py.raw_mode, py)?;
+        self.read_opt = read_opt.to_read_options(self.opt_py.key_codec(), py)?;
         self.read_opt_py = read_opt.clone();
         Ok(())
     }
@@ -356,27 +1043,52 @@ impl Rdict {
 
     /// Get value from key or a list of keys.
     ///
+    /// Notes:
+    ///     A list of keys is read with a single batched MultiGet. If the keys
+    ///     are already sorted in the key order of the database, pass
+    ///     `sorted_input=True` to skip sorting them again. Results are
+    ///     undefined if the keys are not actually sorted. Asynchronous
+    ///     (coroutine) MultiGet is not supported, since RocksDB is built
+    ///     without folly: `ReadOptions.set_async_io` has no effect on it.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         for i in range(10):
+    ///             db[i] = i * i
+    ///         assert db.get([2, 3, 4], sorted_input=True) == [4, 9, 16]
+    ///
     /// Args:
     ///     key: a single key or list of keys.
     ///     default: the default value to return if key not found.
     ///     read_opt: override preset read options
     ///         (or use Rdict.set_read_options to preset a read options used by default).
+    ///     sorted_input: whether a list of keys is already sorted in key order.
+    ///     raw: return the bytes stored, without decoding them. In non-raw
+    ///         mode they start with the type tag of the value, and are
+    ///         compressed, checksummed or encrypted if enabled.
     ///
     /// Returns:
     ///    None or default value if the key does not exist.
     #[inline]
-    #[pyo3(signature = (key, default = None, read_opt = None))]
+    #[pyo3(signature = (key, default = None, read_opt = None, sorted_input = false, raw = false))]
     fn get(
         &self,
         key: &Bound<PyAny>,
         default: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        sorted_input: bool,
+        raw: bool,
         py: Python,
     ) -> PyResult<Option<PyObject>> {
+        let _timer = self.latency.timer(Operation::Get);
         let db = self.get_db()?;
         let read_opt_option = match read_opt {
             None => None,
-            Some(opt) => Some(opt.to_read_options(self.opt_py.raw_mode, py)?),
+            Some(opt) => Some(opt.to_read_options(self.opt_py.key_codec(), py)?),
         };
         let read_opt = match &read_opt_option {
             None => &self.read_opt,
@@ -397,16 +1109,19 @@ impl Rdict {
                     py,
                     read_opt,
                     &self.loads,
+                    &self.codec,
                     &cf,
-                    self.opt_py.raw_mode,
+                    self.opt_py.key_codec(),
+                    sorted_input,
+                    raw,
                 )?
                 .to_object(py),
             ));
         }
-        let key_bytes = encode_key(key, self.opt_py.raw_mode)?;
+        let key_bytes = encode_key(key, self.opt_py.key_codec())?;
         let value_result = db
-            .get_pinned_cf_opt(&cf, key_bytes, read_opt)
-            .map_err(|e| PyException::new_err(e.to_string()))?;
+            .get_pinned_cf_opt(&cf, &key_bytes, read_opt)
+            .map_err(to_py_err)?;
         match value_result {
             None => {
                 // try to return default value
@@ -416,13 +1131,186 @@ impl Rdict {
                     Ok(None)
                 }
             }
-            Some(slice) => Ok(Some(decode_value(
-                py,
-                slice.as_ref(),
-                &self.loads,
-                self.opt_py.raw_mode,
-            )?)),
+            Some(slice) if raw => Ok(Some(PyBytes::new_bound(py, &slice).to_object(py))),
+            Some(slice) => Ok(Some(self.load_value(py, &key_bytes, slice.as_ref())?)),
+        }
+    }
+
+    /// Read the value of a key into a caller-provided buffer.
+    ///
+    /// Copies the value into a preallocated writable buffer, such as a
+    /// `bytearray` or a `uint8` numpy array, to avoid allocating a new
+    /// object for each value in tight loops. In non-raw mode only
+    /// `bytes` values are supported.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db["key"] = b"value"
+    ///         buffer = bytearray(64)
+    ///         n = db.get_into("key", buffer)
+    ///         assert buffer[:n] == b"value"
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     buffer: a writable C-contiguous buffer of bytes.
+    ///     read_opt: override preset read options
+    ///         (or use Rdict.set_read_options to preset a read options used by default).
+    ///
+    /// Returns:
+    ///     the length of the value written to the beginning of the buffer,
+    ///     or `None` if the key does not exist.
+    #[pyo3(signature = (key, buffer, read_opt = None))]
+    fn get_into(
+        &self,
+        key: &Bound<PyAny>,
+        buffer: &Bound<PyAny>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<Option<usize>> {
+        let db = self.get_db()?;
+        let buffer = PyBuffer::<u8>::get_bound(buffer)?;
+        if buffer.readonly() || !buffer.is_c_contiguous() {
+            return Err(PyValueError::new_err(
+                "buffer must be writable and C-contiguous",
+            ));
+        }
+        let read_opt_option = match read_opt {
+            None => None,
+            Some(opt) => Some(opt.to_read_options(self.opt_py.key_codec(), py)?),
+        };
+        let read_opt = match &read_opt_option {
+            None => &self.read_opt,
+            Some(opt) => opt,
+        };
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let key = encode_key(key, self.opt_py.key_codec())?;
+        let slice = match db
+            .get_pinned_cf_opt(&cf, &key, read_opt)
+            .map_err(to_py_err)?
+        {
+            None => return Ok(None),
+            Some(slice) => slice,
+        };
+        let slice = self.codec.decode(&key, &slice)?;
+        let value = match (self.opt_py.raw_mode, slice.split_first()) {
+            (true, _) => &slice[..],
+            // strip the type tag of bytes values
+            (false, Some((1, value))) => value,
+            (false, _) => {
+                return Err(PyValueError::new_err(
+                    "only bytes values can be read into a buffer",
+                ))
+            }
+        };
+        if value.len() > buffer.len_bytes() {
+            return Err(PyValueError::new_err(format!(
+                "value of {} bytes does not fit in buffer of {} bytes",
+                value.len(),
+                buffer.len_bytes()
+            )));
+        }
+        // SAFETY: the buffer is writable, C-contiguous and large enough,
+        // and the exporter keeps it alive until `buffer` is released.
+        unsafe {
+            std::ptr::copy_nonoverlapping(value.as_ptr(), buffer.buf_ptr() as *mut u8, value.len());
+        }
+        Ok(Some(value.len()))
+    }
+
+    /// Write the rows of two arrays as key-value pairs, in raw mode.
+    ///
+    /// The i-th row of `keys` is written as a key with the i-th row of
+    /// `values` as value, in a single `WriteBatch` built without the GIL
+    /// and without creating a Python object per row. Rows are the bytes
+    /// along the first axis, such as the items of a one-dimensional
+    /// array of a fixed-width dtype (`">u8"`, `"S16"`), or the rows of
+    /// a two-dimensional `uint8` array.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///         import numpy as np
+    ///
+    ///         db = Rdict("./temp_path", Options(raw_mode=True))
+    ///         keys = np.arange(1000, dtype=">u8")
+    ///         values = np.random.rand(1000, 16).astype("<f4")
+    ///         db.put_numpy(keys, values)
+    ///         assert db[(7).to_bytes(8, "big")] == values[7].tobytes()
+    ///
+    /// Args:
+    ///     keys: a C-contiguous array (or any buffer) of keys.
+    ///     values: a C-contiguous array (or any buffer) of values,
+    ///         with as many rows as `keys`.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///
+    /// Returns:
+    ///     the number of pairs written.
+    #[pyo3(signature = (keys, values, write_opt = None))]
+    fn put_numpy(
+        &self,
+        keys: &Bound<PyAny>,
+        values: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<usize> {
+        let db = self.get_db()?;
+        if !self.opt_py.raw_mode {
+            return Err(PyValueError::new_err("put_numpy requires raw_mode=True"));
+        }
+        let (key_buffer, count, key_width) = buffer_rows(keys)?;
+        let (value_buffer, value_count, value_width) = buffer_rows(values)?;
+        if count != value_count {
+            return Err(PyValueError::new_err(format!(
+                "{count} keys do not match {value_count} values"
+            )));
         }
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        // SAFETY: both buffers are C-contiguous, and held until the end of this call.
+        let (keys, values) = unsafe {
+            (
+                slice::from_raw_parts(key_buffer.buf_ptr() as *const u8, key_buffer.len_bytes()),
+                slice::from_raw_parts(
+                    value_buffer.buf_ptr() as *const u8,
+                    value_buffer.len_bytes(),
+                ),
+            )
+        };
+        py.allow_threads(|| {
+            let mut batch = WriteBatch::default();
+            for i in 0..count {
+                batch.put_cf(
+                    &cf,
+                    &keys[i * key_width..(i + 1) * key_width],
+                    &values[i * value_width..(i + 1) * value_width],
+                );
+            }
+            // a failed write has no effect, so each attempt writes a copy of the batch
+            self.write_with_retry(|| db.write_opt(WriteBatch::from_data(batch.data()), write_opt))
+        })?;
+        Ok(count)
     }
 
     /// Get a wide-column from a key.
@@ -450,7 +1338,7 @@ impl Rdict {
         let db = self.get_db()?;
         let read_opt_option = match read_opt {
             None => None,
-            Some(opt) => Some(opt.to_read_options(self.opt_py.raw_mode, py)?),
+            Some(opt) => Some(opt.to_read_options(self.opt_py.key_codec(), py)?),
         };
         let read_opt = match &read_opt_option {
             None => &self.read_opt,
@@ -463,10 +1351,10 @@ impl Rdict {
             }
             Some(cf) => cf.clone(),
         };
-        let key_bytes = encode_key(key, self.opt_py.raw_mode)?;
+        let key_bytes = encode_key(key, self.opt_py.key_codec())?;
         let column_result = db
-            .get_entity_cf_opt(&cf, key_bytes, read_opt)
-            .map_err(|e| PyException::new_err(e.to_string()))?;
+            .get_entity_cf_opt(&cf, &key_bytes, read_opt)
+            .map_err(to_py_err)?;
         match column_result {
             None => {
                 // try to return default value
@@ -480,7 +1368,7 @@ impl Rdict {
                 let result = PyList::empty_bound(py);
                 for column in columns.iter() {
                     let name = decode_value(py, column.name, &self.loads, self.opt_py.raw_mode)?;
-                    let value = decode_value(py, column.value, &self.loads, self.opt_py.raw_mode)?;
+                    let value = self.load_value(py, &key_bytes, column.value)?;
                     result.append(PyTuple::new_bound(py, [name, value]))?;
                 }
                 Ok(Some(result.to_object(py)))
@@ -507,20 +1395,22 @@ impl Rdict {
         value: &Bound<PyAny>,
         write_opt: Option<&WriteOptionsPy>,
     ) -> PyResult<()> {
+        let _timer = self.latency.timer(Operation::Put);
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
-        let value = encode_value(value, &self.dumps, self.opt_py.raw_mode)?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
+        let value = self.dump_value(&key, value)?;
         let write_opt_option = write_opt.map(WriteOptions::from);
         let write_opt = match &write_opt_option {
             None => &self.write_opt,
             Some(opt) => opt,
         };
-        if let Some(cf) = &self.column_family {
-            db.put_cf_opt(cf, key, value, write_opt)
-        } else {
-            db.put_opt(key, value, write_opt)
-        }
-        .map_err(|e| PyException::new_err(e.to_string()))
+        self.write_with_retry(|| {
+            if let Some(cf) = &self.column_family {
+                db.put_cf_opt(cf, &key, &value, write_opt)
+            } else {
+                db.put_opt(&key, &value, write_opt)
+            }
+        })
     }
 
     /// Insert a wide-column.
@@ -543,7 +1433,7 @@ impl Rdict {
         write_opt: Option<&WriteOptionsPy>,
     ) -> PyResult<()> {
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
         let write_opt_option = write_opt.map(WriteOptions::from);
         let write_opt = match &write_opt_option {
             None => &self.write_opt,
@@ -564,18 +1454,24 @@ impl Rdict {
         let mut names_vec = Vec::with_capacity(names.len());
         let mut values_vec = Vec::with_capacity(values.len());
         for name in names.iter() {
-            names_vec.push(encode_value(name, &self.dumps, self.opt_py.raw_mode)?);
+            names_vec.push(encode_value(
+                name,
+                &self.dumps,
+                self.opt_py.raw_mode,
+                self.opt_py.strict_types,
+            )?);
         }
         for value in values.iter() {
-            values_vec.push(encode_value(value, &self.dumps, self.opt_py.raw_mode)?);
+            values_vec.push(self.dump_value(&key, value)?);
         }
-        db.put_entity_cf_opt(&cf, key, &names_vec, &values_vec, write_opt)
-            .map_err(|e| PyException::new_err(e.to_string()))
+        self.write_with_retry(|| {
+            db.put_entity_cf_opt(&cf, &key, &names_vec, &values_vec, write_opt)
+        })
     }
 
     fn __contains__(&self, key: &Bound<PyAny>) -> PyResult<bool> {
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
         let may_exist = if let Some(cf) = &self.column_family {
             db.key_may_exist_cf_opt(cf, &key[..], &self.read_opt)
         } else {
@@ -592,7 +1488,7 @@ impl Rdict {
                     None => Ok(false),
                     Some(_) => Ok(true),
                 },
-                Err(e) => Err(PyException::new_err(e.to_string())),
+                Err(e) => Err(to_py_err(e)),
             }
         } else {
             Ok(false)
@@ -639,10 +1535,10 @@ impl Rdict {
         py: Python,
     ) -> PyResult<PyObject> {
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
         let read_opt_option = match read_opt {
             None => None,
-            Some(opt) => Some(opt.to_read_options(self.opt_py.raw_mode, py)?),
+            Some(opt) => Some(opt.to_read_options(self.opt_py.key_codec(), py)?),
         };
         let read_opt = match &read_opt_option {
             None => &self.read_opt,
@@ -663,130 +1559,533 @@ impl Rdict {
             let (may, value) = db.key_may_exist_cf_opt_value(&cf, &key[..], read_opt);
             match value {
                 None => Ok((may, py.None()).to_object(py)),
-                Some(dat) => Ok((
-                    may,
-                    decode_value(py, dat.as_ref(), &self.loads, self.opt_py.raw_mode)?,
-                )
-                    .to_object(py)),
+                Some(dat) => Ok((may, self.load_value(py, &key, dat.as_ref())?).to_object(py)),
             }
         }
     }
 
-    fn __delitem__(&self, key: &Bound<PyAny>) -> PyResult<()> {
-        self.delete(key, None)
-    }
-
-    /// Delete entry from the database.
+    /// Atomically update the value of a key with a function.
+    ///
+    /// Reads the current value, calls `func` on it, and writes back
+    /// the result, while holding a lock on the key, so that concurrent
+    /// atomic operations (`update_atomic`, `put_if_absent`, ...) on the
+    /// same key from any thread of this process are serialized.
+    ///
+    /// Notes:
+    ///     Plain writes such as `put` and `delete` do not take the lock.
+    ///     `func` must not run another atomic operation on the same key,
+    ///     which would wait for itself forever.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db.update_atomic("counter", lambda v: v + 1, default=0)
+    ///         assert db["counter"] == 1
     ///
     /// Args:
     ///     key: the key.
+    ///     func: function mapping the current value to the new value.
+    ///     default: the value passed to `func` if the key does not exist.
     ///     write_opt: override preset write options
     ///         (or use Rdict.set_write_options to preset a write options used by default).
-    #[inline]
-    #[pyo3(signature = (key, write_opt = None))]
-    fn delete(&self, key: &Bound<PyAny>, write_opt: Option<&WriteOptionsPy>) -> PyResult<()> {
+    ///
+    /// Returns:
+    ///     the new value.
+    #[pyo3(signature = (key, func, default = None, write_opt = None))]
+    fn update_atomic(
+        &self,
+        key: &Bound<PyAny>,
+        func: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<PyObject> {
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
-
+        let key = encode_key(key, self.opt_py.key_codec())?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
         let write_opt_option = write_opt.map(WriteOptions::from);
         let write_opt = match &write_opt_option {
             None => &self.write_opt,
             Some(opt) => opt,
         };
-        if let Some(cf) = &self.column_family {
-            db.delete_cf_opt(cf, key, write_opt)
-        } else {
-            db.delete_opt(key, write_opt)
-        }
-        .map_err(|e| PyException::new_err(e.to_string()))
+        let _guard = self.lock_key(&cf, &key, py);
+        let current = match db
+            .get_pinned_cf_opt(&cf, &key, &self.read_opt)
+            .map_err(to_py_err)?
+        {
+            Some(slice) => self.load_value(py, &key, slice.as_ref())?,
+            None => default.map_or_else(|| py.None(), |default| default.to_object(py)),
+        };
+        let new_value = func.call1((current,))?;
+        let value = self.dump_value(&key, &new_value)?;
+        self.write_with_retry(|| db.put_cf_opt(&cf, &key, &value, write_opt))?;
+        Ok(new_value.to_object(py))
     }
 
-    /// Reversible for iterating over keys and values.
-    ///
-    /// Examples:
-    ///     ::
-    ///
-    ///         from rocksdict import Rdict, Options, ReadOptions
-    ///
-    ///         path = "_path_for_rocksdb_storage5"
-    ///         db = Rdict(path)
-    ///
-    ///         for i in range(50):
-    ///             db[i] = i ** 2
-    ///
-    ///         iter = db.iter()
-    ///
-    ///         iter.seek_to_first()
+    /// Atomically insert a key only if it does not exist.
     ///
-    ///         j = 0
-    ///         while iter.valid():
-    ///             assert iter.key() == j
-    ///             assert iter.value() == j ** 2
-    ///             print(f"{iter.key()} {iter.value()}")
-    ///             iter.next()
-    ///             j += 1
+    /// Serialized with the other atomic operations on the same key,
+    /// see `update_atomic`.
     ///
-    ///         iter.seek_to_first();
-    ///         assert iter.key() == 0
-    ///         assert iter.value() == 0
-    ///         print(f"{iter.key()} {iter.value()}")
+    /// Example:
+    ///     ::
     ///
-    ///         iter.seek(25)
-    ///         assert iter.key() == 25
-    ///         assert iter.value() == 625
-    ///         print(f"{iter.key()} {iter.value()}")
+    ///         from rocksdict import Rdict
     ///
-    ///         del iter, db
-    ///         Rdict.destroy(path)
+    ///         db = Rdict("./temp_path")
+    ///         assert db.put_if_absent("lease", "worker-1")
+    ///         assert not db.put_if_absent("lease", "worker-2")
+    ///         assert db["lease"] == "worker-1"
     ///
     /// Args:
-    ///     read_opt: ReadOptions
+    ///     key: the key.
+    ///     value: the value.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
     ///
-    /// Returns: Reversible
-    #[pyo3(signature = (read_opt = None))]
-    fn iter(&self, read_opt: Option<&ReadOptionsPy>, py: Python) -> PyResult<RdictIter> {
-        let read_opt: ReadOptionsPy = match read_opt {
-            None => ReadOptionsPy::default(py)?,
-            Some(opt) => opt.clone(),
+    /// Returns:
+    ///     whether the value was written.
+    #[pyo3(signature = (key, value, write_opt = None))]
+    fn put_if_absent(
+        &self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<bool> {
+        let db = self.get_db()?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
+        let value = self.dump_value(&key, value)?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
         };
-
-        RdictIter::new(
-            &self.db,
-            &self.column_family,
-            read_opt,
-            &self.loads,
-            self.opt_py.raw_mode,
-            py,
-        )
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        let _guard = self.lock_key(&cf, &key, py);
+        if db
+            .get_pinned_cf_opt(&cf, &key, &self.read_opt)
+            .map_err(to_py_err)?
+            .is_some()
+        {
+            return Ok(false);
+        }
+        self.write_with_retry(|| db.put_cf_opt(&cf, &key, &value, write_opt))?;
+        Ok(true)
     }
 
-    /// Iterate through all keys and values pairs.
+    /// Atomically replace the value of a key if it equals `expected`.
     ///
-    /// Examples:
+    /// The stored value is decoded and compared with `expected` using
+    /// Python equality. A missing key never matches. Serialized with the
+    /// other atomic operations on the same key, see `update_atomic`.
+    ///
+    /// Example:
     ///     ::
     ///
-    ///         for k, v in db.items():
-    ///             print(f"{k} -> {v}")
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db["state"] = "idle"
+    ///         assert db.compare_and_set("state", "idle", "running")
+    ///         assert not db.compare_and_set("state", "idle", "running")
     ///
     /// Args:
-    ///     backwards: iteration direction, forward if `False`.
+    ///     key: the key.
+    ///     expected: the value the key must currently have.
+    ///     new: the new value.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///
+    /// Returns:
+    ///     whether the value was replaced.
+    #[pyo3(signature = (key, expected, new, write_opt = None))]
+    fn compare_and_set(
+        &self,
+        key: &Bound<PyAny>,
+        expected: &Bound<PyAny>,
+        new: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<bool> {
+        let db = self.get_db()?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
+        let value = self.dump_value(&key, new)?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        let _guard = self.lock_key(&cf, &key, py);
+        if !self.value_equals(db, &cf, &key, expected, py)? {
+            return Ok(false);
+        }
+        self.write_with_retry(|| db.put_cf_opt(&cf, &key, &value, write_opt))?;
+        Ok(true)
+    }
+
+    /// Atomically delete a key if its value equals `expected`.
+    ///
+    /// The stored value is decoded and compared with `expected` using
+    /// Python equality, so that cleanup jobs do not delete a value that
+    /// a concurrent writer has just replaced. Serialized with the other
+    /// atomic operations on the same key, see `update_atomic`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db["lease"] = "worker-1"
+    ///         assert not db.delete_if("lease", "worker-2")
+    ///         assert db.delete_if("lease", "worker-1")
+    ///         assert "lease" not in db
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     expected: the value the key must currently have.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///
+    /// Returns:
+    ///     whether the key was deleted.
+    #[pyo3(signature = (key, expected, write_opt = None))]
+    fn delete_if(
+        &self,
+        key: &Bound<PyAny>,
+        expected: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<bool> {
+        let db = self.get_db()?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        let _guard = self.lock_key(&cf, &key, py);
+        if !self.value_equals(db, &cf, &key, expected, py)? {
+            return Ok(false);
+        }
+        self.write_with_retry(|| db.delete_cf_opt(&cf, &key, write_opt))?;
+        Ok(true)
+    }
+
+    /// Block until `key` exists in the current column family and return its value.
+    ///
+    /// Writes through any `Rdict` handle of the database in this process
+    /// wake up the waiting thread, so that a consumer can wait for a
+    /// producer without polling. The GIL is released while waiting.
+    ///
+    /// Notes:
+    ///     Returns immediately if the key already exists. Writes from other
+    ///     processes are not noticed, except by a secondary instance when
+    ///     `try_catch_up_with_primary` is called.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///         from threading import Thread
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         producer = Thread(target=lambda: db.put("job", "done"))
+    ///         producer.start()
+    ///         assert db.wait_for("job", timeout=10) == "done"
+    ///         producer.join()
+    ///
+    /// Args:
+    ///     key: the key to wait for.
+    ///     timeout: maximum seconds to wait, or `None` to wait forever.
+    ///
+    /// Returns:
+    ///     the value of the key.
+    ///
+    /// Raises:
+    ///     TimeoutError: if the key does not exist before the timeout.
+    #[pyo3(signature = (key, timeout = None))]
+    fn wait_for(&self, key: &Bound<PyAny>, timeout: Option<f64>, py: Python) -> PyResult<PyObject> {
+        let db = self.get_db()?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let deadline = match timeout {
+            None => None,
+            Some(t) if t >= 0.0 && t.is_finite() => {
+                Some(Instant::now() + Duration::from_secs_f64(t))
+            }
+            Some(_) => return Err(PyValueError::new_err("timeout must be non-negative")),
+        };
+        // waiting in slices so that KeyboardInterrupt is handled
+        const WAIT_SLICE: Duration = Duration::from_millis(100);
+        let mut watch = self.db.watch_writes();
+        loop {
+            let value = py
+                .allow_threads(|| {
+                    db.get_pinned_cf_opt(&cf, &key, &self.read_opt)
+                        .map(|v| v.map(|v| v.to_vec()))
+                })
+                .map_err(to_py_err)?;
+            if let Some(value) = value {
+                return self.load_value(py, &key, &value);
+            }
+            let wait = match deadline {
+                None => WAIT_SLICE,
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return Err(PyTimeoutError::new_err("timed out waiting for key"));
+                    }
+                    left.min(WAIT_SLICE)
+                }
+            };
+            py.allow_threads(|| watch.wait(wait));
+            py.check_signals()?;
+        }
+    }
+
+    fn __delitem__(&self, key: &Bound<PyAny>) -> PyResult<()> {
+        self.delete(key, None)
+    }
+
+    /// Delete entry from the database.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    #[inline]
+    #[pyo3(signature = (key, write_opt = None))]
+    fn delete(&self, key: &Bound<PyAny>, write_opt: Option<&WriteOptionsPy>) -> PyResult<()> {
+        let _timer = self.latency.timer(Operation::Delete);
+        let db = self.get_db()?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
+
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        self.write_with_retry(|| {
+            if let Some(cf) = &self.column_family {
+                db.delete_cf_opt(cf, &key, write_opt)
+            } else {
+                db.delete_opt(&key, write_opt)
+            }
+        })
+    }
+
+    /// Reversible for iterating over keys and values.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, ReadOptions
+    ///
+    ///         path = "_path_for_rocksdb_storage5"
+    ///         db = Rdict(path)
+    ///
+    ///         for i in range(50):
+    ///             db[i] = i ** 2
+    ///
+    ///         iter = db.iter()
+    ///
+    ///         iter.seek_to_first()
+    ///
+    ///         j = 0
+    ///         while iter.valid():
+    ///             assert iter.key() == j
+    ///             assert iter.value() == j ** 2
+    ///             print(f"{iter.key()} {iter.value()}")
+    ///             iter.next()
+    ///             j += 1
+    ///
+    ///         iter.seek_to_first();
+    ///         assert iter.key() == 0
+    ///         assert iter.value() == 0
+    ///         print(f"{iter.key()} {iter.value()}")
+    ///
+    ///         iter.seek(25)
+    ///         assert iter.key() == 25
+    ///         assert iter.value() == 625
+    ///         print(f"{iter.key()} {iter.value()}")
+    ///
+    ///         del iter, db
+    ///         Rdict.destroy(path)
+    ///
+    /// Args:
+    ///     read_opt: ReadOptions
+    ///
+    /// Returns: Reversible
+    #[pyo3(signature = (read_opt = None))]
+    fn iter(&self, read_opt: Option<&ReadOptionsPy>, py: Python) -> PyResult<RdictIter> {
+        let read_opt: ReadOptionsPy = match read_opt {
+            None => ReadOptionsPy::default(py)?,
+            Some(opt) => opt.clone(),
+        };
+
+        Ok(RdictIter::new(
+            &self.db,
+            &self.column_family,
+            read_opt,
+            &self.loads,
+            &self.codec,
+            self.opt_py.key_codec(),
+            py,
+        )?
+        .with_latency(&self.latency))
+    }
+
+    /// Get an iterator with default read options from a small pool of
+    /// iterators of this column family.
+    ///
+    /// Creating an iterator dominates the cost of short scans, so
+    /// iterators returned by this method go back to the pool when closed,
+    /// or when garbage collected, and are refreshed to read the latest
+    /// state of the database when reused. A reused iterator is not
+    /// positioned, so seek before reading it.
+    ///
+    /// Up to 8 idle iterators are kept, each pinning the memtables and
+    /// SST files of when it was last refreshed. Closing the database
+    /// drops them.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///
+    ///         def scan(prefix, stop):
+    ///             with db.pooled_iter() as it:
+    ///                 it.seek(prefix)
+    ///                 return it.get_chunk_items(100, stop_key=stop)
+    ///
+    ///         # iterators are reused across calls
+    ///         for user in range(1000):
+    ///             print(scan(f"user:{user}:", f"user:{user};"))
+    ///
+    /// Returns: Reversible
+    fn pooled_iter(&self, py: Python) -> PyResult<RdictIter> {
+        Ok(RdictIter::pooled(
+            &self.iter_pool,
+            &self.db,
+            &self.column_family,
+            &self.loads,
+            &self.codec,
+            self.opt_py.key_codec(),
+            py,
+        )?
+        .with_latency(&self.latency))
+    }
+
+    /// Iterate through all keys and values pairs.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         for k, v in db.items():
+    ///             print(f"{k} -> {v}")
+    ///
+    ///         # iterate over keys in [1, 10]
+    ///         for k, v in db.items(start=1, stop=10, inclusive=(True, True)):
+    ///             print(f"{k} -> {v}")
+    ///
+    /// Args:
+    ///     backwards: iteration direction, forward if `False`.
     ///     from_key: iterate from key, first seek to this key
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     skip: number of entries to skip before the first returned one.
+    ///         Skipping happens in Rust, without decoding and with GIL released.
+    ///     limit: maximum number of entries to return, unlimited if `None`.
+    ///     start: lower bound of keys, unbounded if `None`.
+    ///         Overrides the lower bound of `read_opt`.
+    ///     stop: upper bound of keys, unbounded if `None`.
+    ///         Overrides the upper bound of `read_opt`.
+    ///     inclusive: whether `start` and `stop` are included.
+    ///     raw: yield the bytes stored for keys and values, without
+    ///         decoding them. In non-raw mode they start with type tags,
+    ///         and values are compressed, checksummed or encrypted if
+    ///         enabled. `from_key`, `start` and `stop` are not raw.
+    #[pyo3(signature = (
+        backwards = false,
+        from_key = None,
+        read_opt = None,
+        skip = 0,
+        limit = None,
+        start = None,
+        stop = None,
+        inclusive = (true, false),
+        raw = false
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn items(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        skip: usize,
+        limit: Option<usize>,
+        start: Option<&Bound<PyAny>>,
+        stop: Option<&Bound<PyAny>>,
+        inclusive: (bool, bool),
+        raw: bool,
         py: Python,
     ) -> PyResult<RdictItems> {
-        RdictItems::new(self.iter(read_opt, py)?, backwards, from_key)
+        let items = RdictItems::new(
+            self.range_iter(read_opt, start, stop, inclusive, py)?,
+            backwards,
+            from_key,
+            skip,
+            limit,
+            py,
+        )?;
+        Ok(if raw { items.into_raw() } else { items })
     }
 
     /// Iterate through all keys
     ///
+    /// Keys are read ahead in chunks with the GIL released, and values
+    /// are never read, see `RdictIter.get_chunk_keys`.
+    ///
     /// Examples:
     ///     ::
     ///
@@ -798,15 +2097,45 @@ impl Rdict {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     skip: number of entries to skip before the first returned one.
+    ///         Skipping happens in Rust, without decoding and with GIL released.
+    ///     limit: maximum number of entries to return, unlimited if `None`.
+    ///     start: lower bound of keys, unbounded if `None`.
+    ///         Overrides the lower bound of `read_opt`.
+    ///     stop: upper bound of keys, unbounded if `None`.
+    ///         Overrides the upper bound of `read_opt`.
+    ///     inclusive: whether `start` and `stop` are included.
+    #[pyo3(signature = (
+        backwards = false,
+        from_key = None,
+        read_opt = None,
+        skip = 0,
+        limit = None,
+        start = None,
+        stop = None,
+        inclusive = (true, false)
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn keys(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        skip: usize,
+        limit: Option<usize>,
+        start: Option<&Bound<PyAny>>,
+        stop: Option<&Bound<PyAny>>,
+        inclusive: (bool, bool),
         py: Python,
     ) -> PyResult<RdictKeys> {
-        RdictKeys::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictKeys::new(
+            self.range_iter(read_opt, start, stop, inclusive, py)?,
+            backwards,
+            from_key,
+            skip,
+            limit,
+            py,
+        )
     }
 
     /// Iterate through all values.
@@ -822,15 +2151,45 @@ impl Rdict {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     skip: number of entries to skip before the first returned one.
+    ///         Skipping happens in Rust, without decoding and with GIL released.
+    ///     limit: maximum number of entries to return, unlimited if `None`.
+    ///     start: lower bound of keys, unbounded if `None`.
+    ///         Overrides the lower bound of `read_opt`.
+    ///     stop: upper bound of keys, unbounded if `None`.
+    ///         Overrides the upper bound of `read_opt`.
+    ///     inclusive: whether `start` and `stop` are included.
+    #[pyo3(signature = (
+        backwards = false,
+        from_key = None,
+        read_opt = None,
+        skip = 0,
+        limit = None,
+        start = None,
+        stop = None,
+        inclusive = (true, false)
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn values(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        skip: usize,
+        limit: Option<usize>,
+        start: Option<&Bound<PyAny>>,
+        stop: Option<&Bound<PyAny>>,
+        inclusive: (bool, bool),
         py: Python,
     ) -> PyResult<RdictValues> {
-        RdictValues::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictValues::new(
+            self.range_iter(read_opt, start, stop, inclusive, py)?,
+            backwards,
+            from_key,
+            skip,
+            limit,
+            py,
+        )
     }
 
     /// Iterate through all values as widecolumns
@@ -846,15 +2205,27 @@ impl Rdict {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     skip: number of entries to skip before the first returned one.
+    ///         Skipping happens in Rust, without decoding and with GIL released.
+    ///     limit: maximum number of entries to return, unlimited if `None`.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, skip = 0, limit = None))]
     fn columns(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        skip: usize,
+        limit: Option<usize>,
         py: Python,
     ) -> PyResult<RdictColumns> {
-        RdictColumns::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictColumns::new(
+            self.iter(read_opt, py)?,
+            backwards,
+            from_key,
+            skip,
+            limit,
+            py,
+        )
     }
 
     /// Iterate through all keys and entities pairs.
@@ -871,68 +2242,340 @@ impl Rdict {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     skip: number of entries to skip before the first returned one.
+    ///         Skipping happens in Rust, without decoding and with GIL released.
+    ///     limit: maximum number of entries to return, unlimited if `None`.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, skip = 0, limit = None))]
     fn entities(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        skip: usize,
+        limit: Option<usize>,
         py: Python,
     ) -> PyResult<RdictEntities> {
-        RdictEntities::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictEntities::new(
+            self.iter(read_opt, py)?,
+            backwards,
+            from_key,
+            skip,
+            limit,
+            py,
+        )
     }
 
-    /// Manually flush the current column family.
+    /// Get the key-value pair with the smallest key.
     ///
-    /// Notes:
-    ///     Manually call mem-table flush.
-    ///     It is recommended to call flush() or close() before
-    ///     stopping the python program, to ensure that all written
-    ///     key-value pairs have been flushed to the disk.
+    /// Examples:
+    ///     ::
+    ///
+    ///         db[1] = 1
+    ///         db[2] = 4
+    ///         assert db.first() == (1, 1)
     ///
     /// Args:
-    ///     wait (bool): whether to wait for the flush to finish.
-    #[pyo3(signature = (wait = true))]
-    fn flush(&self, wait: bool, py: Python) -> PyResult<()> {
-        let db = self.get_db()?;
-
-        py.allow_threads(|| {
-            let mut f_opt = FlushOptions::new();
-            f_opt.set_wait(wait);
-            if let Some(cf) = &self.column_family {
-                db.flush_cf_opt(cf, &f_opt)
-            } else {
-                db.flush_opt(&f_opt)
-            }
-        })
-        .map_err(|e| PyException::new_err(e.into_string()))
-    }
-
-    /// Flushes the WAL buffer. If `sync` is set to `true`, also syncs
-    /// the data to disk.
-    #[pyo3(signature = (sync = true))]
-    fn flush_wal(&self, sync: bool, py: Python) -> PyResult<()> {
-        let db = self.get_db()?;
-        py.allow_threads(|| db.flush_wal(sync))
-            .map_err(|e| PyException::new_err(e.into_string()))
+    ///     read_opt: ReadOptions
+    ///
+    /// Returns:
+    ///     A `(key, value)` tuple, or `None` if the column family is empty.
+    #[pyo3(signature = (read_opt = None))]
+    fn first(&self, read_opt: Option<&ReadOptionsPy>, py: Python) -> PyResult<Option<PyObject>> {
+        let mut iter = self.iter(read_opt, py)?;
+        iter.seek_to_first();
+        current_item(&iter, py)
     }
 
-    /// Creates column family with given name and options.
+    /// Get the key-value pair with the largest key.
     ///
-    /// Args:
-    ///     name: name of this column family
+    /// Examples:
+    ///     ::
+    ///
+    ///         db[1] = 1
+    ///         db[2] = 4
+    ///         assert db.last() == (2, 4)
+    ///
+    /// Args:
+    ///     read_opt: ReadOptions
+    ///
+    /// Returns:
+    ///     A `(key, value)` tuple, or `None` if the column family is empty.
+    #[pyo3(signature = (read_opt = None))]
+    fn last(&self, read_opt: Option<&ReadOptionsPy>, py: Python) -> PyResult<Option<PyObject>> {
+        let mut iter = self.iter(read_opt, py)?;
+        iter.seek_to_last();
+        current_item(&iter, py)
+    }
+
+    /// Get the key-value pair at position `index` in key order.
+    ///
+    /// Non-negative indices count from the smallest key, negative
+    /// indices count from the largest key, as with Python lists.
+    /// Iterator stepping happens in Rust, so only the returned
+    /// item is decoded.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         for i in range(10):
+    ///             db[i] = i * i
+    ///         assert db.peekitem() == (9, 81)
+    ///         assert db.peekitem(0) == (0, 0)
+    ///         assert db.peekitem(-2) == (8, 64)
+    ///
+    /// Args:
+    ///     index: position of the item, defaults to `-1` (the last item).
+    ///     read_opt: ReadOptions
+    ///
+    /// Raises:
+    ///     IndexError: if the index is out of range.
+    #[pyo3(signature = (index = -1, read_opt = None))]
+    fn peekitem(
+        &self,
+        index: i64,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let mut iter = self.iter(read_opt, py)?;
+        if index >= 0 {
+            iter.seek_to_first();
+            for _ in 0..index {
+                if !iter.valid() {
+                    break;
+                }
+                iter.next();
+            }
+        } else {
+            iter.seek_to_last();
+            for _ in 0..(-(index + 1)) {
+                if !iter.valid() {
+                    break;
+                }
+                iter.prev();
+            }
+        }
+        current_item(&iter, py)?.ok_or_else(|| PyIndexError::new_err("index out of range"))
+    }
+
+    /// Manually flush the current column family.
+    ///
+    /// Notes:
+    ///     Manually call mem-table flush.
+    ///     It is recommended to call flush() or close() before
+    ///     stopping the python program, to ensure that all written
+    ///     key-value pairs have been flushed to the disk.
+    ///
+    /// Args:
+    ///     wait (bool): whether to wait for the flush to finish.
+    #[pyo3(signature = (wait = true))]
+    fn flush(&self, wait: bool, py: Python) -> PyResult<()> {
+        let db = self.get_db()?;
+
+        py.allow_threads(|| {
+            let mut f_opt = FlushOptions::new();
+            f_opt.set_wait(wait);
+            if let Some(cf) = &self.column_family {
+                db.flush_cf_opt(cf, &f_opt)
+            } else {
+                db.flush_opt(&f_opt)
+            }
+        })
+        .map_err(to_py_err)
+    }
+
+    /// Flush the memtables of all column families.
+    ///
+    /// Unlike `flush`, which only flushes the column family of this
+    /// instance, flushes every column family of the database, such as
+    /// before shutting down cleanly. The column families are flushed
+    /// together, atomically if `Options.set_atomic_flush` is enabled.
+    ///
+    /// Args:
+    ///     wait (bool): whether to wait for the flushes to finish.
+    ///
+    /// Returns:
+    ///     the names of the column families flushed.
+    #[pyo3(signature = (wait = true))]
+    fn flush_all(&self, wait: bool, py: Python) -> PyResult<Vec<String>> {
+        let db = self.get_db()?;
+        let (names, cfs) = self.all_column_families()?;
+        py.allow_threads(|| {
+            let mut f_opt = FlushOptions::new();
+            f_opt.set_wait(wait);
+            db.flush_cfs_opt(&cfs.iter().collect::<Vec<_>>(), &f_opt)
+        })
+        .map_err(to_py_err)?;
+        Ok(names)
+    }
+
+    /// Flush the memtable of the current column family on a timer.
+    ///
+    /// A background thread flushes every `interval_secs` seconds, which
+    /// bounds the data lost on a crash when writing with the WAL disabled.
+    /// Calling it again replaces the previous timer, and `None` stops it.
+    /// The timer also stops when the `Rdict` is closed.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, WriteOptions
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         write_opt = WriteOptions()
+    ///         write_opt.disable_wal = True
+    ///         db.set_write_options(write_opt)
+    ///         # lose at most a minute of writes
+    ///         db.set_auto_flush(60)
+    ///
+    /// Args:
+    ///     interval_secs: seconds between two flushes, or `None` to stop.
+    ///     flush_wal: also flush and sync the WAL buffer before each flush.
+    ///
+    /// Raises:
+    ///     RocksDBError: the last error of the previous timer, if any.
+    #[pyo3(signature = (interval_secs, flush_wal = false))]
+    fn set_auto_flush(
+        &mut self,
+        interval_secs: Option<f64>,
+        flush_wal: bool,
+        py: Python,
+    ) -> PyResult<()> {
+        let interval = match interval_secs {
+            None => None,
+            Some(secs) if secs > 0.0 && secs.is_finite() => Some(Duration::from_secs_f64(secs)),
+            Some(secs) => {
+                return Err(PyValueError::new_err(format!(
+                    "interval_secs must be positive, got {secs}"
+                )))
+            }
+        };
+        let previous = self.auto_flush.take();
+        if let Some(interval) = interval {
+            self.auto_flush = Some(AutoFlush::start(
+                &self.db,
+                self.column_family.clone(),
+                interval,
+                flush_wal,
+            )?);
+        }
+        match previous {
+            None => Ok(()),
+            Some(mut previous) => py.allow_threads(|| previous.stop()),
+        }
+    }
+
+    /// Flushes the WAL buffer. If `sync` is set to `true`, also syncs
+    /// the data to disk.
+    #[pyo3(signature = (sync = true))]
+    fn flush_wal(&self, sync: bool, py: Python) -> PyResult<()> {
+        let db = self.get_db()?;
+        py.allow_threads(|| db.flush_wal(sync)).map_err(to_py_err)
+    }
+
+    /// Reject writes, then flush all column families.
+    ///
+    /// Writes through this instance, its column families, and all other
+    /// handles of the database raise `DbFrozenError` once frozen, and
+    /// writes in progress are waited for, so that the data stays the same
+    /// during a backup or a handover to another process. Reads are not
+    /// affected. The flag is kept across `reopen`.
+    ///
+    /// Notes:
+    ///     RocksDB cannot switch an open database to read-only, so the
+    ///     underlying handle is still opened for writes, and background
+    ///     compactions keep rewriting SST files. Back up a frozen database
+    ///     with `Checkpoint`, or `close` it before copying its files.
+    ///     The database stays frozen if the flush fails.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, DbFrozenError
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db["key"] = "value"
+    ///         db.freeze()
+    ///         try:
+    ///             db["key"] = "other"
+    ///         except DbFrozenError:
+    ///             pass
+    ///         db.unfreeze()
+    ///
+    /// Raises:
+    ///     NotSupportedError: if the database is opened read-only or as secondary.
+    fn freeze(&self, py: Python) -> PyResult<()> {
+        let db = self.get_db()?;
+        if let AccessTypeInner::ReadOnly { .. } | AccessTypeInner::Secondary { .. } =
+            &self.access_type.0
+        {
+            return Err(NotSupportedError::new_err(
+                "cannot freeze a read-only or secondary database",
+            ));
+        }
+        let (_, cfs) = self.all_column_families()?;
+        py.allow_threads(|| {
+            self.db.set_frozen(true);
+            for cf in &cfs {
+                db.flush_cf(cf)?;
+            }
+            db.flush_wal(true)
+        })
+        .map_err(to_py_err)
+    }
+
+    /// Accept writes again after `freeze`.
+    fn unfreeze(&self) -> PyResult<()> {
+        self.get_db()?;
+        self.db.set_frozen(false);
+        Ok(())
+    }
+
+    /// Whether writes are rejected after `freeze`.
+    fn is_frozen(&self) -> PyResult<bool> {
+        self.get_db()?;
+        Ok(self.db.is_frozen())
+    }
+
+    /// Creates column family with given name and options.
+    ///
+    /// Args:
+    ///     name: name of this column family
     ///     options: Rdict Options for this column family
     ///
     /// Return:
     ///     the newly created column family
     #[pyo3(signature = (name, options = OptionsPy::new(false)))]
-    fn create_column_family(&self, name: &str, options: OptionsPy, py: Python) -> PyResult<Rdict> {
+    fn create_column_family(
+        &self,
+        name: &str,
+        options: OptionsPy,
+        py: Python,
+    ) -> PyResult<Py<Rdict>> {
         let db = self.get_db()?;
-        if options.raw_mode != self.opt_py.raw_mode {
-            return Err(PyException::new_err(format!(
-                "Options should have raw_mode={}",
-                self.opt_py.raw_mode
-            )));
+        let _writing = self.begin_write()?;
+        let default_key_codec = self.key_codecs.read().unwrap().default;
+        // column families share the key codec of the database,
+        // unless set by `Options.set_key_codec`
+        let key_codec = match options.key_codec {
+            Some(key_codec) => key_codec,
+            None if options.raw_mode != default_key_codec.is_raw() => {
+                return Err(PyValueError::new_err(format!(
+                    "Options should have raw_mode={}",
+                    default_key_codec.is_raw()
+                )))
+            }
+            None => default_key_codec,
+        };
+        let mut options = options;
+        if options.key_codec() != key_codec {
+            options.set_key_codec_inner(key_codec);
+        }
+        if key_codec != default_key_codec {
+            self.key_codecs
+                .write()
+                .unwrap()
+                .column_families
+                .insert(name.to_string(), key_codec);
         }
         // write slice_transform info into config file
         if let Some(slice_transform) = options.prefix_extractor {
@@ -942,16 +2585,26 @@ impl Rdict {
                 .insert(name.to_string(), slice_transform);
         }
         self.dump_config()?;
-        db.create_cf(name, &options.inner_opt)
-            .map_err(|e| PyException::new_err(e.to_string()))?;
+        db.create_cf(name, &options.inner_opt).map_err(to_py_err)?;
         self.get_column_family(name, py)
     }
 
     /// Drops the column family with the given name
     fn drop_column_family(&self, name: &str) -> PyResult<()> {
         let db = self.get_db()?;
-        db.drop_cf(name)
-            .map_err(|e| PyException::new_err(e.to_string()))
+        let _writing = self.begin_write()?;
+        db.drop_cf(name).map_err(to_py_err)?;
+        let removed = self
+            .key_codecs
+            .write()
+            .unwrap()
+            .column_families
+            .remove(name)
+            .is_some();
+        if removed {
+            self.dump_config()?;
+        }
+        Ok(())
     }
 
     /// Get a column family Rdict
@@ -962,27 +2615,45 @@ impl Rdict {
     ///
     /// Return:
     ///     the column family Rdict of this name
-    pub fn get_column_family(&self, name: &str, py: Python) -> PyResult<Self> {
+    pub fn get_column_family(&self, name: &str, py: Python) -> PyResult<Py<Self>> {
         let db = self.get_db()?;
-        match unsafe { db.cf_handle_unbounded(name) } {
-            None => Err(PyException::new_err(format!(
+        let cf = unsafe { db.cf_handle_unbounded(name) }.ok_or_else(|| {
+            PyValueError::new_err(format!(
                 "column name `{name}` does not exist, use `create_cf` to creat it",
-            ))),
-            Some(cf) => Ok(Self {
+            ))
+        })?;
+        let opt_py = self.column_family_options(name);
+        let cf = Py::new(
+            py,
+            Self {
                 db: self.db.clone(),
                 write_opt: (&self.write_opt_py).into(),
                 flush_opt: self.flush_opt,
-                read_opt: self.read_opt_py.to_read_options(self.opt_py.raw_mode, py)?,
+                read_opt: self.read_opt_py.to_read_options(opt_py.key_codec(), py)?,
                 loads: self.loads.clone(),
                 dumps: self.dumps.clone(),
                 column_family: Some(cf),
                 write_opt_py: self.write_opt_py.clone(),
                 read_opt_py: self.read_opt_py.clone(),
-                opt_py: self.opt_py.clone(),
+                codec: ValueCodec::new(opt_py.raw_mode, opt_py.value_checksums),
+                opt_py,
                 access_type: self.access_type.clone(),
                 slice_transforms: self.slice_transforms.clone(),
-            }),
-        }
+                key_codecs: self.key_codecs.clone(),
+                retry_policy: self.retry_policy.clone(),
+                cf_rdicts: self.cf_rdicts.clone(),
+                auto_flush: None,
+                latency: self.latency.clone(),
+                jobs: self.jobs.clone(),
+                external: self.external,
+                iter_pool: Arc::default(),
+            },
+        )?;
+        let weak = PyWeakrefReference::new_bound(cf.bind(py).as_any())?.unbind();
+        let mut cf_rdicts = self.cf_rdicts.lock().unwrap();
+        cf_rdicts.retain(|cf| cf.bind(py).upgrade().is_some());
+        cf_rdicts.push(weak);
+        Ok(cf)
     }
 
     /// Use this method to obtain a ColumnFamily instance, which can be used in WriteBatch.
@@ -1010,7 +2681,7 @@ impl Rdict {
             ))),
             Some(cf) => Ok(ColumnFamilyPy {
                 cf,
-                db: self.db.clone(),
+                db: self.db.with_kind("column family handle"),
             }),
         }
     }
@@ -1048,38 +2719,334 @@ impl Rdict {
         Snapshot::new(self, py)
     }
 
+    /// One snapshot of all column families of the database.
+    ///
+    /// Unlike `snapshot`, the returned view reads every column family at
+    /// the same sequence number, see `ConsistentView`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         with db.consistent_view() as view:
+    ///             value = view["default"]["key"]
+    ///             entry = view["index"][value]
+    fn consistent_view(&self) -> PyResult<ConsistentView> {
+        ConsistentView::new(self)
+    }
+
+    /// Create a writer that writes updates to the current column family
+    /// in batches from a background thread.
+    ///
+    /// See `RdictBufferedWriter` for details.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         with db.buffered_writer(max_batch=1000, flush_interval=0.05) as writer:
+    ///             for i in range(10_000):
+    ///                 writer[i] = i
+    ///
+    /// Args:
+    ///     max_batch: maximum number of updates per write batch,
+    ///         also the capacity of the queue.
+    ///     max_bytes: write a batch once its keys and values reach this size.
+    ///     flush_interval: maximum seconds an update waits in a batch.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///
+    /// Returns:
+    ///     an `RdictBufferedWriter` instance.
+    #[pyo3(signature = (
+        max_batch = 10_000,
+        max_bytes = 16 << 20,
+        flush_interval = 0.1,
+        write_opt = None
+    ))]
+    fn buffered_writer(
+        &self,
+        max_batch: usize,
+        max_bytes: usize,
+        flush_interval: f64,
+        write_opt: Option<&WriteOptionsPy>,
+    ) -> PyResult<RdictBufferedWriter> {
+        if !(flush_interval >= 0.0 && flush_interval.is_finite()) {
+            return Err(PyValueError::new_err("flush_interval must be non-negative"));
+        }
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let write_opt = WriteOptions::from(write_opt.unwrap_or(&self.write_opt_py));
+        RdictBufferedWriter::new(
+            &self.db,
+            cf,
+            write_opt,
+            WriterConfig {
+                max_batch: max_batch.max(1),
+                max_bytes,
+                flush_interval: Duration::from_secs_f64(flush_interval),
+            },
+            self.dumps.clone(),
+            self.codec.clone(),
+            self.opt_py.key_codec(),
+            self.opt_py.strict_types,
+        )
+    }
+
+    /// Group writes to the current column family into an atomic block.
+    ///
+    /// Writes to the returned object are buffered and committed together
+    /// when the `with` block exits normally, or discarded if it raises.
+    /// Reads through it see its own pending writes.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         with db.atomic() as tx:
+    ///             tx["a"] = 1
+    ///             tx["b"] = tx["a"] + 1
+    ///             del tx["c"]
+    ///         assert db["b"] == 2
+    ///
+    /// Returns:
+    ///     an `RdictAtomic` instance.
+    fn atomic(slf: &Bound<Self>) -> PyResult<RdictAtomic> {
+        RdictAtomic::new(slf)
+    }
+
     /// Loads a list of external SST files created with SstFileWriter
     /// into the current column family.
     ///
+    /// Files of several column families can be ingested in one call by
+    /// passing a dict mapping column family names to lists of paths.
+    /// All column family names are resolved before any file is ingested.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         db.ingest_external_file({
+    ///             "default": ["./default.sst"],
+    ///             "users": ["./users_0.sst", "./users_1.sst"],
+    ///         })
+    ///
     /// Args:
-    ///     paths: a list a paths
+    ///     paths: a list a paths, or a dict `{cf_name: [paths]}`
     ///     opts: IngestExternalFileOptionsPy instance
+    ///     progress: called with the number of files ingested so far
+    ///         and the total number of files, after each ingestion.
+    ///     chunk_size: ingest the files of each column family in chunks
+    ///         of `chunk_size` files, instead of all together, so that
+    ///         `progress` is called more often. Each chunk is ingested
+    ///         atomically, but not the chunks together.
+    ///
+    /// Returns:
+    ///     a list with a dict for each file, in the order of `paths`:
+    ///
+    ///     - ``path`` (str): the path passed.
+    ///     - ``column_family`` (str): the column family ingested into.
+    ///     - ``name`` (str): name of the SST file in the database, as in
+    ///       `live_files`, or None if it cannot be told apart, for example
+    ///       when already compacted.
+    ///     - ``level`` (int): level the file was ingested to, or None
+    ///       if ``name`` is None.
+    ///     - ``global_seqno`` (int): sequence number assigned to the keys
+    ///       of the ingestion, or 0 if they kept sequence number 0.
+    ///     - ``flushed`` (bool): whether the memtable was flushed because
+    ///       it overlapped the ingested keys.
+    ///
+    /// Notes:
+    ///     Column families of a dict are ingested one after another, in
+    ///     the order of the dict. The C API of RocksDB does not expose
+    ///     atomic ingestion into several column families, so if one of
+    ///     them fails, the files of the previous ones stay ingested.
+    ///
+    ///     The C API of RocksDB does not return the results of an
+    ///     ingestion either. They are read from the live files, the
+    ///     latest sequence number, and the memtable size before and after
+    ///     each ingestion, so concurrent writes and compactions can make
+    ///     them inaccurate. The sequence number of an ingestion is shared
+    ///     by all of its files, including files moved to the bottommost
+    ///     level which keep sequence number 0.
     #[pyo3(signature = (
         paths,
-        opts = Python::with_gil(|py| Py::new(py, IngestExternalFileOptionsPy::new()).unwrap())
+        opts = Python::with_gil(|py| Py::new(py, IngestExternalFileOptionsPy::new()).unwrap()),
+        progress = None,
+        chunk_size = None
     ))]
     fn ingest_external_file(
         &self,
-        paths: Vec<String>,
+        paths: &Bound<PyAny>,
         opts: Py<IngestExternalFileOptionsPy>,
+        progress: Option<&Bound<PyAny>>,
+        chunk_size: Option<usize>,
         py: Python,
-    ) -> PyResult<()> {
+    ) -> PyResult<Vec<PyObject>> {
         let db = self.get_db()?;
+        let _writing = self.begin_write()?;
+        if chunk_size == Some(0) {
+            return Err(PyValueError::new_err("chunk_size must be positive"));
+        }
         let opts = &opts.borrow(py).0;
-        if let Some(cf) = &self.column_family {
-            db.ingest_external_file_cf_opts(cf, opts, paths)
+        let mut ingestions = Vec::new();
+        if let Ok(per_cf) = paths.downcast::<PyDict>() {
+            for (name, cf_paths) in per_cf.iter() {
+                let name = name.extract::<String>()?;
+                let cf = self.get_column_family_handle(&name)?.cf;
+                ingestions.push((name, cf, cf_paths.extract::<Vec<String>>()?));
+            }
         } else {
-            db.ingest_external_file_opts(opts, paths)
+            let cf = match &self.column_family {
+                None => {
+                    self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                        .cf
+                }
+                Some(cf) => cf.clone(),
+            };
+            ingestions.push((self.column_family_name(), cf, paths.extract()?));
+        }
+        let total: usize = ingestions.iter().map(|(_, _, paths)| paths.len()).sum();
+        let mut results = Vec::with_capacity(total);
+        for (name, cf, cf_paths) in ingestions {
+            let chunk_size = chunk_size.unwrap_or(cf_paths.len()).max(1);
+            for chunk in cf_paths.chunks(chunk_size) {
+                let ingested = py.allow_threads(|| ingest_files(db, &cf, &name, opts, chunk));
+                self.db.notify_writes();
+                for (path, file) in chunk.iter().zip(ingested.map_err(to_py_err)?) {
+                    let result = PyDict::new_bound(py);
+                    result.set_item("path", path)?;
+                    result.set_item("column_family", &name)?;
+                    result.set_item("name", file.name)?;
+                    result.set_item("level", file.level)?;
+                    result.set_item("global_seqno", file.global_seqno)?;
+                    result.set_item("flushed", file.flushed)?;
+                    results.push(result.to_object(py));
+                }
+                if let Some(progress) = progress {
+                    progress.call1((results.len(), total))?;
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Copy the entries of `src` into the current column family, from a
+    /// snapshot of `src` and through SST files.
+    ///
+    /// Takes a snapshot of `src`, streams the keys between `begin` and
+    /// `end` into temporary SST files of about `file_size` bytes, and
+    /// ingests them all at once, so that `src` can keep serving reads
+    /// and writes meanwhile and `dst` sees either none or all of the
+    /// entries. This can re-shard a database online, or merge several
+    /// databases into one.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         src = Rdict("./temp_src")
+    ///         dst = Rdict("./temp_dst")
+    ///         for i in range(100):
+    ///             src[i] = i
+    ///         # move [50, 100) to another shard
+    ///         assert dst.ingest_from(src, 50) == 50
+    ///         src.delete_range(50, None)
+    ///
+    /// Args:
+    ///     src (Rdict): source database or column family, with the same
+    ///         `raw_mode`.
+    ///     begin: included, `None` for unbounded.
+    ///     end: excluded, `None` for unbounded.
+    ///     file_size (int): size of each SST file written before ingestion.
+    ///
+    /// Returns:
+    ///     the number of entries ingested.
+    ///
+    /// Raises:
+    ///     ValueError: if `src` and the current column family do not use
+    ///         the same compression, checksums and encryption of values,
+    ///         since values are copied as stored. Encrypted values can
+    ///         only be copied between column families of the same name.
+    ///
+    /// Notes:
+    ///     Keys of the current column family that are not in `src` are
+    ///     kept. Wide columns other than the default column are not copied.
+    #[pyo3(signature = (src, begin = None, end = None, file_size = 64 * 1024 * 1024))]
+    fn ingest_from(
+        &self,
+        src: &Rdict,
+        begin: Option<&Bound<PyAny>>,
+        end: Option<&Bound<PyAny>>,
+        file_size: u64,
+        py: Python,
+    ) -> PyResult<u64> {
+        if src.opt_py.key_codec() != self.opt_py.key_codec() {
+            return Err(PyValueError::new_err(
+                "src and dst must have the same raw_mode and key codec",
+            ));
         }
-        .map_err(|e| PyException::new_err(e.to_string()))
+        if file_size == 0 {
+            return Err(PyValueError::new_err("file_size must be positive"));
+        }
+        if !src.codec.same_stages(&self.codec) {
+            return Err(PyValueError::new_err(
+                "src and dst must have the same value checksums, compression and encryption, \
+                 and encrypted values must stay in column families of the same name",
+            ));
+        }
+        let key_codec = self.opt_py.key_codec();
+        let src_db = src.get_db()?;
+        let db = self.get_db()?;
+        let _writing = self.begin_write()?;
+        let src_cf = match &src.column_family {
+            None => src.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?.cf,
+            Some(cf) => cf.clone(),
+        };
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let from = match begin {
+            Some(begin) if !begin.is_none() => Some(Vec::from(encode_key(begin, key_codec)?)),
+            _ => None,
+        };
+        let to = match end {
+            Some(end) if !end.is_none() => Some(Vec::from(encode_key(end, key_codec)?)),
+            _ => None,
+        };
+        let sst_opt = self.opt_py.inner_opt.clone();
+        let dir = TempDir::create("rocksdict-ingest-from")?;
+        let result = py.allow_threads(|| {
+            let (files, count) =
+                write_snapshot_files(src_db, &src_cf, from, to, &sst_opt, dir.path(), file_size)?;
+            if !files.is_empty() {
+                let mut ingest_opt = IngestExternalFileOptions::default();
+                ingest_opt.set_move_files(true);
+                db.ingest_external_file_cf_opts(&cf, &ingest_opt, files)?;
+            }
+            Ok::<_, rocksdb::Error>(count)
+        });
+        self.db.notify_writes();
+        result.map_err(to_py_err)
     }
 
     /// Tries to catch up with the primary by reading as much as possible from the
     /// log files.
     pub fn try_catch_up_with_primary(&self) -> PyResult<()> {
         let db = self.get_db()?;
-        db.try_catch_up_with_primary()
-            .map_err(|e| PyException::new_err(e.to_string()))
+        self.write_with_retry(|| db.try_catch_up_with_primary())
     }
 
     /// Request stopping background work, if wait is true wait until it's done.
@@ -1097,6 +3064,10 @@ impl Rdict {
     /// Args:
     ///     write_batch: WriteBatch instance. This instance will be consumed.
     ///     write_opt: use default value if not provided.
+    ///
+    /// Raises:
+    ///     ValueError: if the batch has another key codec, or no value
+    ///         checksums while `Options.set_value_checksums` is set.
     #[pyo3(signature = (write_batch, write_opt = None))]
     pub fn write(
         &self,
@@ -1115,31 +3086,485 @@ impl Rdict {
                 ))
             };
         }
+        if self.opt_py.key_codec() != write_batch.key_codec {
+            return Err(PyValueError::new_err(format!(
+                "must set key_codec=\"{}\" for WriteBatch",
+                self.opt_py.key_codec().name()
+            )));
+        }
+        if self.codec.has_checksums() && !write_batch.codec.has_checksums() {
+            return Err(PyValueError::new_err(
+                "must set value_checksums=True for WriteBatch",
+            ));
+        }
         let write_opt_option = write_opt.map(WriteOptions::from);
         let write_opt = match &write_opt_option {
             None => &self.write_opt,
             Some(opt) => opt,
         };
-        db.write_opt(write_batch.consume()?, write_opt)
-            .map_err(|e| PyException::new_err(e.to_string()))
+        let _writing = self.begin_write()?;
+        let batch = write_batch.consume()?;
+        match &self.retry_policy {
+            None => db.write_opt(batch, write_opt).map_err(to_py_err)?,
+            // a failed write has no effect, so each attempt writes a copy of the batch
+            Some(policy) => with_retry(Some(policy), || {
+                db.write_opt(WriteBatch::from_data(batch.data()), write_opt)
+            })?,
+        }
+        self.db.notify_writes();
+        Ok(())
     }
 
     /// Removes the database entries in the range `["from", "to")` of the current column family.
     ///
+    /// Either bound can be `None`, meaning from the first key or
+    /// to the last key of the column family.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         for i in range(10):
+    ///             db[i] = i
+    ///         db.delete_range(None, 3)
+    ///         db.delete_range(7, None)
+    ///         assert list(db.keys()) == [3, 4, 5, 6]
+    ///
+    /// Notes:
+    ///     In raw mode there is no key larger than all keys, so an open end
+    ///     deletes up to the last key present when the call is made.
+    ///
     /// Args:
-    ///     begin: included
-    ///     end: excluded
+    ///     begin: included, or `None` to delete from the first key
+    ///     end: excluded, or `None` to delete to the last key
     ///     write_opt: WriteOptions
     #[pyo3(signature = (begin, end, write_opt=None))]
     pub fn delete_range(
+        &self,
+        begin: Option<&Bound<PyAny>>,
+        end: Option<&Bound<PyAny>>,
+        write_opt: Option<&WriteOptionsPy>,
+    ) -> PyResult<()> {
+        let db = self.get_db()?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let from = match begin {
+            Some(begin) => encode_key(begin, self.opt_py.key_codec())?,
+            None => EncodedBytes::Owned(Vec::new()),
+        };
+        let to = match (end, max_encoded_key(self.opt_py.key_codec())) {
+            (Some(end), _) => encode_key(end, self.opt_py.key_codec())?,
+            (None, Some(max_key)) => EncodedBytes::Owned(max_key),
+            (None, None) => {
+                let mut iter = db.raw_iterator_cf_opt(&cf, ReadOptions::default());
+                iter.seek_to_last();
+                match iter.key() {
+                    Some(last) => EncodedBytes::Owned(key_successor(last, true)),
+                    None => return iter.status().map_err(to_py_err),
+                }
+            }
+        };
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        self.write_with_retry(|| db.delete_range_cf_opt(&cf, &from, &to, write_opt))
+    }
+
+    /// Purge the range `["begin", "end")` of the current column family
+    /// and reclaim its disk space.
+    ///
+    /// Notes:
+    ///     This combines the usual recipe for dropping a large range of keys
+    ///     into a single call: first delete the SST files that are entirely
+    ///     within the range (`DeleteFilesInRange`), then write a range tombstone
+    ///     (`delete_range`) for the keys left in partially covered files and
+    ///     memtables, and finally (if `compact=True`) run a manual compaction
+    ///     over the range with bottommost level compaction forced, so that
+    ///     the tombstones and the data they cover are physically removed.
+    ///
+    /// Args:
+    ///     begin: included
+    ///     end: excluded
+    ///     compact: whether to compact the range after deletion.
+    ///     write_opt: WriteOptions
+    ///
+    /// Returns:
+    ///     The number of bytes of live SST files reclaimed in the current column family.
+    #[pyo3(signature = (begin, end, compact = true, write_opt = None))]
+    pub fn truncate_range(
         &self,
         begin: &Bound<PyAny>,
         end: &Bound<PyAny>,
+        compact: bool,
         write_opt: Option<&WriteOptionsPy>,
-    ) -> PyResult<()> {
+        py: Python,
+    ) -> PyResult<u64> {
+        let db = self.get_db()?;
+        let from = encode_key(begin, self.opt_py.key_codec())?;
+        let to = encode_key(end, self.opt_py.key_codec())?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        let _writing = self.begin_write()?;
+        py.allow_threads(|| {
+            let live_sst_size = || {
+                db.property_int_value_cf(&cf, "rocksdb.live-sst-files-size")
+                    .map(|size| size.unwrap_or(0))
+            };
+            let size_before = live_sst_size()?;
+            db.delete_file_in_range_cf(&cf, &from, &to)?;
+            db.delete_range_cf_opt(&cf, &from, &to, write_opt)?;
+            if compact {
+                let mut compact_opt = CompactOptions::default();
+                compact_opt.set_bottommost_level_compaction(BottommostLevelCompaction::Force);
+                db.compact_range_cf_opt(&cf, Some(&from), Some(&to), &compact_opt);
+            }
+            let size_after = live_sst_size()?;
+            Ok::<_, rocksdb::Error>(size_before.saturating_sub(size_after))
+        })
+        .map_err(to_py_err)
+    }
+
+    /// Count the keys in the range `["begin", "end")` of the current column family.
+    ///
+    /// Keys are counted in Rust with the GIL released, without decoding
+    /// keys or values, which is much faster than counting `db.keys()`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         for i in range(100):
+    ///             db[i] = i
+    ///         assert db.count_range(10, 20) == 10
+    ///         assert db.count_range(None, None) == 100
+    ///
+    /// Args:
+    ///     begin: included, or `None` to count from the first key.
+    ///     end: excluded, or `None` to count to the last key.
+    ///     read_opt: ReadOptions
+    ///
+    /// Returns:
+    ///     the number of keys in the range.
+    #[pyo3(signature = (begin = None, end = None, read_opt = None))]
+    fn count_range(
+        &self,
+        begin: Option<&Bound<PyAny>>,
+        end: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<u64> {
+        let db = self.get_db()?;
+        let key_codec = self.opt_py.key_codec();
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let mut read_opt = match read_opt {
+            None => self.read_opt_py.to_read_options(key_codec, py)?,
+            Some(opt) => opt.to_read_options(key_codec, py)?,
+        };
+        read_opt.set_total_order_seek(true);
+        if let Some(end) = end {
+            read_opt.set_iterate_upper_bound(encode_key(end, key_codec)?.to_vec());
+        }
+        let begin = match begin {
+            Some(begin) => Some(encode_key(begin, key_codec)?),
+            None => None,
+        };
+        py.allow_threads(|| {
+            let mut iter = db.raw_iterator_cf_opt(&cf, read_opt);
+            match &begin {
+                Some(begin) => iter.seek(begin),
+                None => iter.seek_to_first(),
+            }
+            let mut count = 0;
+            while iter.valid() {
+                count += 1;
+                iter.next();
+            }
+            iter.status().map(|_| count)
+        })
+        .map_err(to_py_err)
+    }
+
+    /// Verify the checksums of the values in a range of keys.
+    ///
+    /// Scans the range `["begin", "end")` of the current column family
+    /// with the GIL released, without decoding values with `loads`.
+    /// Encrypted values are only verified if the encryption key is set.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_value_checksums(True)
+    ///         db = Rdict("./temp_path", opt)
+    ///         for i in range(10):
+    ///             db[i] = i
+    ///         report = db.verify_values(begin=5)
+    ///         assert report == {"checked": 5, "without_checksum": 0, "corrupted": []}
+    ///
+    /// Args:
+    ///     begin: included, or `None` to start from the first key.
+    ///     end: excluded, or `None` to end at the last key.
+    ///
+    /// Returns:
+    ///     a dict with the number of `checked` values, the number of values
+    ///     `without_checksum`, such as values written before checksums were
+    ///     enabled, and the list of keys with `corrupted` values.
+    #[pyo3(signature = (begin = None, end = None))]
+    fn verify_values(
+        &self,
+        begin: Option<&Bound<PyAny>>,
+        end: Option<&Bound<PyAny>>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let db = self.get_db()?;
+        let key_codec = self.opt_py.key_codec();
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let mut read_opt = ReadOptions::default();
+        read_opt.fill_cache(false);
+        read_opt.set_total_order_seek(true);
+        if let Some(end) = end {
+            read_opt.set_iterate_upper_bound(encode_key(end, key_codec)?.to_vec());
+        }
+        let begin = match begin {
+            Some(begin) => Some(encode_key(begin, key_codec)?),
+            None => None,
+        };
+        let codec = &self.codec;
+        let (checked, without_checksum, corrupted) = py
+            .allow_threads(|| {
+                let mut iter = db.raw_iterator_cf_opt(&cf, read_opt);
+                match &begin {
+                    Some(begin) => iter.seek(begin),
+                    None => iter.seek_to_first(),
+                }
+                let mut checked: u64 = 0;
+                let mut without_checksum: u64 = 0;
+                let mut corrupted = Vec::new();
+                while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                    checked += 1;
+                    match codec.verify(key, value) {
+                        Ok(true) => {}
+                        Ok(false) => without_checksum += 1,
+                        Err(_) => corrupted.push(key.to_vec()),
+                    }
+                    iter.next();
+                }
+                iter.status()
+                    .map(|_| (checked, without_checksum, corrupted))
+            })
+            .map_err(to_py_err)?;
+        let corrupted_keys = PyList::empty_bound(py);
+        for key in corrupted {
+            corrupted_keys.append(decode_key(py, &key, &self.loads, key_codec)?)?;
+        }
+        let result = PyDict::new_bound(py);
+        result.set_item("checked", checked)?;
+        result.set_item("without_checksum", without_checksum)?;
+        result.set_item("corrupted", corrupted_keys)?;
+        Ok(result.to_object(py))
+    }
+
+    /// Rewrite the values of a range of keys with a function.
+    ///
+    /// Scans the range `["begin", "end")` of the current column family,
+    /// calls `func` on each value, and writes the results back in
+    /// batches of `batch_size`, so that large ranges can be transformed
+    /// without holding all updates in memory.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         for i in range(10):
+    ///             db[i] = i
+    ///         assert db.map_values(lambda v: v * 10, begin=5) == 5
+    ///         assert db[9] == 90
+    ///
+    /// Notes:
+    ///     Each batch is written atomically, but not the whole range.
+    ///     Values written concurrently by others may be overwritten.
+    ///
+    /// Args:
+    ///     func: function mapping the current value to the new value.
+    ///     begin: included, or `None` to start from the first key.
+    ///     end: excluded, or `None` to end at the last key.
+    ///     batch_size: number of updates per write batch.
+    ///     write_opt: WriteOptions
+    ///
+    /// Returns:
+    ///     the number of rewritten values.
+    #[pyo3(signature = (func, begin = None, end = None, batch_size = 1000, write_opt = None))]
+    fn map_values(
+        &self,
+        func: &Bound<PyAny>,
+        begin: Option<&Bound<PyAny>>,
+        end: Option<&Bound<PyAny>>,
+        batch_size: usize,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<u64> {
+        let db = self.get_db()?;
+        let key_codec = self.opt_py.key_codec();
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        let mut read_opt = ReadOptions::default();
+        read_opt.fill_cache(false);
+        read_opt.set_total_order_seek(true);
+        if let Some(end) = end {
+            read_opt.set_iterate_upper_bound(encode_key(end, key_codec)?.to_vec());
+        }
+        let mut iter = db.raw_iterator_cf_opt(&cf, read_opt);
+        match begin {
+            Some(begin) => iter.seek(encode_key(begin, key_codec)?),
+            None => iter.seek_to_first(),
+        }
+        let write = |batch: &WriteBatch| {
+            // a failed write has no effect, so each attempt writes a copy of the batch
+            self.write_with_retry(|| db.write_opt(WriteBatch::from_data(batch.data()), write_opt))
+        };
+        let batch_size = batch_size.max(1);
+        let mut batch = WriteBatch::default();
+        let mut count = 0;
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            let value = self.load_value(py, key, value)?;
+            let new_value = func.call1((value,))?;
+            let new_value = self.dump_value(key, &new_value)?;
+            batch.put_cf(&cf, key, new_value);
+            count += 1;
+            if batch.len() >= batch_size {
+                write(&batch)?;
+                batch.clear();
+            }
+            iter.next();
+        }
+        iter.status().map_err(to_py_err)?;
+        if !batch.is_empty() {
+            write(&batch)?;
+        }
+        Ok(count)
+    }
+
+    /// Delete all keys of the current column family matching the given filters.
+    ///
+    /// The scan and the batched deletes run in Rust. Without `predicate`,
+    /// values are never decoded and the GIL is released for the whole scan.
+    /// All given filters must match for a key to be deleted.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db["session:1"] = "expired"
+    ///         db["session:2"] = "active"
+    ///         db["user:1"] = "expired"
+    ///         assert db.delete_where(key_prefix="session:", value_filter="expired") == 1
+    ///         assert db.delete_where(lambda k, v: v == "expired") == 1
+    ///
+    /// Notes:
+    ///     In non-raw mode, `key_prefix` must be a `str` or `bytes`, and
+    ///     only matches keys of the same type. `value_filter` matches
+    ///     values with the same encoding, so it is reliable for `str`,
+    ///     `bytes`, `int`, `float` and `bool` values.
+    ///     `predicate` may write to the database, or freeze it, in which
+    ///     case the batches deleted so far are kept and `DbFrozenError`
+    ///     is raised.
+    ///
+    /// Args:
+    ///     predicate: a function `(key, value) -> bool`, called with
+    ///         the decoded key and value.
+    ///     key_prefix: only delete keys starting with this prefix.
+    ///     value_filter: only delete keys with this value.
+    ///     batch_size: number of deletes per write batch.
+    ///     write_opt: WriteOptions
+    ///
+    /// Returns:
+    ///     the number of deleted keys.
+    ///
+    /// Raises:
+    ///     DbFrozenError: if the database is frozen.
+    #[pyo3(signature = (
+        predicate = None,
+        key_prefix = None,
+        value_filter = None,
+        batch_size = 1000,
+        write_opt = None
+    ))]
+    fn delete_where(
+        &self,
+        predicate: Option<&Bound<PyAny>>,
+        key_prefix: Option<&Bound<PyAny>>,
+        value_filter: Option<&Bound<PyAny>>,
+        batch_size: usize,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<u64> {
         let db = self.get_db()?;
-        let from = encode_key(begin, self.opt_py.raw_mode)?;
-        let to = encode_key(end, self.opt_py.raw_mode)?;
+        let raw_mode = self.opt_py.raw_mode;
+        let key_codec = self.opt_py.key_codec();
+        let prefix = match key_prefix {
+            None => EncodedBytes::Owned(Vec::new()),
+            Some(prefix) => {
+                let prefix = encode_key(prefix, key_codec)?;
+                // int keys are not ordered bytewise
+                if !raw_mode && !matches!(prefix.first(), Some(1) | Some(2)) {
+                    return Err(PyValueError::new_err("key_prefix must be str or bytes"));
+                }
+                prefix
+            }
+        };
+        let value_filter = match value_filter {
+            None => None,
+            Some(value) => Some(encode_value(value, &self.dumps, raw_mode, false)?),
+        };
         let cf = match &self.column_family {
             None => {
                 self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
@@ -1152,8 +3577,57 @@ impl Rdict {
             None => &self.write_opt,
             Some(opt) => opt,
         };
-        db.delete_range_cf_opt(&cf, from, to, write_opt)
-            .map_err(|e| PyException::new_err(e.to_string()))
+        let codec = &self.codec;
+        let value_matches = |key: &[u8], value: &[u8]| -> PyResult<bool> {
+            match &value_filter {
+                None => Ok(true),
+                Some(filter) => Ok(codec.decode(key, value)?[..] == filter[..]),
+            }
+        };
+        let batch_size = batch_size.max(1);
+        let retry_policy = self.retry_policy.as_ref();
+        // fail early if frozen, but hold off `freeze` only while writing each
+        // batch, so that `predicate` can freeze or write to the database
+        drop(self.begin_write()?);
+        let db_ref = &self.db;
+        let begin_write = || {
+            db_ref
+                .begin_write()
+                .ok_or_else(|| DbFrozenError::new_err("database is frozen"))
+        };
+        let deleted = match predicate {
+            None => py.allow_threads(|| {
+                delete_matching(
+                    db,
+                    &cf,
+                    &prefix,
+                    batch_size,
+                    write_opt,
+                    retry_policy,
+                    begin_write,
+                    |k, v| value_matches(k, v),
+                )
+            }),
+            Some(predicate) => delete_matching(
+                db,
+                &cf,
+                &prefix,
+                batch_size,
+                write_opt,
+                retry_policy,
+                begin_write,
+                |k, v| {
+                    if !value_matches(k, v)? {
+                        return Ok(false);
+                    }
+                    let key = decode_key(py, k, &self.loads, key_codec)?;
+                    let value = self.load_value(py, k, v)?;
+                    predicate.call1((key, value))?.is_truthy()
+                },
+            ),
+        };
+        self.db.notify_writes();
+        deleted
     }
 
     /// Flush memory to disk, and drop the current column family.
@@ -1166,21 +3640,40 @@ impl Rdict {
     ///     (cf handle) instances, iterator instances such as`RdictIter`,
     ///     `RdictItems`, `RdictKeys`, `RdictValues` can all keep RocksDB
     ///     alive. `del` or `close` all associated instances mentioned
-    ///     above to actually shut down RocksDB.
+    ///     above to actually shut down RocksDB, or use `force=True`.
+    ///     `open_handles()` reports the instances still holding it.
     ///
-    fn close(&mut self, py: Python) -> PyResult<()> {
+    /// Args:
+    ///     force: also release all iterators and snapshots of the
+    ///         database, which raise `DbClosedError` afterwards.
+    ///         Column family `Rdict` and `ColumnFamily` instances
+    ///         are not released.
+    #[pyo3(signature = (force = false))]
+    fn close(&mut self, force: bool, py: Python) -> PyResult<()> {
+        // stop the auto flush timer first, since it holds the database
+        let auto_flush = match self.auto_flush.take() {
+            None => Ok(()),
+            Some(mut auto_flush) => py.allow_threads(|| auto_flush.stop()),
+        };
         // do not flush if readonly
         if let AccessTypeInner::ReadOnly { .. } | AccessTypeInner::Secondary { .. } =
             &self.access_type.0
         {
             py.allow_threads(|| {
+                if force {
+                    self.db.release_handles();
+                }
+                self.iter_pool.clear();
                 drop(self.column_family.take());
                 self.db.close();
             });
-            return Ok(());
+            return auto_flush;
         }
 
         let (flush_wal_result, flush_result) = py.allow_threads(|| {
+            if force {
+                self.db.release_handles();
+            }
             let f_opt = &self.flush_opt;
             let db = self.get_db()?;
 
@@ -1190,53 +3683,365 @@ impl Rdict {
             } else {
                 db.flush_opt(&f_opt.into())
             };
+            self.iter_pool.clear();
             drop(self.column_family.take());
             self.db.close();
 
             Ok::<_, PyErr>((flush_wal_result, flush_result))
         })?;
         match (flush_result, flush_wal_result) {
-            (Ok(_), Ok(_)) => Ok(()),
-            (Err(e), Ok(_)) => Err(PyException::new_err(e.to_string())),
-            (Ok(_), Err(e)) => Err(PyException::new_err(e.to_string())),
-            (Err(e), Err(wal_e)) => Err(PyException::new_err(format!("{e}; {wal_e}"))),
+            (Ok(_), Ok(_)) => auto_flush,
+            (Err(e), Ok(_)) => Err(to_py_err(e)),
+            (Ok(_), Err(e)) => Err(to_py_err(e)),
+            (Err(e), Err(wal_e)) => Err(status_to_py_err(format!("{e}; {wal_e}"))),
         }
     }
 
-    /// Return current database path.
-    fn path(&self) -> PyResult<String> {
-        Ok(self
-            .get_db()?
-            .path()
-            .as_os_str()
-            .to_string_lossy()
-            .to_string())
-    }
-
-    /// Runs a manual compaction on the Range of keys given for the current Column Family.
-    #[pyo3(signature = (begin, end, compact_opt = Python::with_gil(|py| Py::new(py, CompactOptionsPy::default()).unwrap())))]
-    fn compact_range(
-        &self,
-        begin: &Bound<PyAny>,
-        end: &Bound<PyAny>,
-        compact_opt: Py<CompactOptionsPy>,
+    /// Flush and close the database, and open it again with new options.
+    ///
+    /// This instance and the column family `Rdict` instances created
+    /// from it stay valid and use the reopened database, so options that
+    /// cannot be changed by `set_options` do not require rebuilding all
+    /// references to the database. Iterators and snapshots are released,
+    /// and raise `DbClosedError` afterwards.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         cf = db.create_column_family("cf", Options())
+    ///         opt = Options()
+    ///         opt.set_max_open_files(100)
+    ///         db.reopen(opt)
+    ///         cf["key"] = "value"
+    ///
+    /// Args:
+    ///     options: options to reopen the database with, or `None` to keep
+    ///         the current options. Column families keep the options saved
+    ///         in the database.
+    ///     access_type: access type to reopen the database with, or `None`
+    ///         to keep the current access type.
+    ///
+    /// Raises:
+    ///     BusyError: if the database is still referenced by anything else,
+    ///         such as `ColumnFamily` handles or buffered writers, or if a
+    ///         column family `Rdict` is in use by another thread. If the
+    ///         database fails to open, all instances are closed.
+    #[pyo3(signature = (options = None, access_type = None))]
+    fn reopen(
+        slf: &Bound<Self>,
+        options: Option<OptionsPy>,
+        access_type: Option<AccessType>,
         py: Python,
     ) -> PyResult<()> {
-        let db = self.get_db()?;
-        let opt = compact_opt.borrow(py);
-        let opt_ref = opt.deref();
-        let from = if begin.is_none() {
-            None
-        } else {
-            Some(encode_key(begin, self.opt_py.raw_mode)?)
-        };
-        let to = if end.is_none() {
-            None
+        let mut this = slf.try_borrow_mut()?;
+        let path = path_to_str(this.get_db()?.path())?.to_string();
+        let options =
+            options.unwrap_or_else(|| this.column_family_options(DEFAULT_COLUMN_FAMILY_NAME));
+        let raw_mode = this.key_codecs.read().unwrap().default.is_raw();
+        if options.raw_mode != raw_mode {
+            return Err(PyValueError::new_err(format!(
+                "Options should have raw_mode={raw_mode}"
+            )));
+        }
+        let access_type = access_type.unwrap_or_else(|| this.access_type.clone());
+        let cf_rdicts = this
+            .cf_rdicts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|cf| cf.bind(py).upgrade_as::<Rdict>().transpose())
+            .collect::<PyResult<Vec<_>>>()?;
+        let mut others = cf_rdicts
+            .iter()
+            .filter(|cf| !cf.is(slf))
+            .map(|cf| {
+                cf.try_borrow_mut()
+                    .map_err(|_| BusyError::new_err("a column family Rdict is in use"))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        // closed column family Rdicts stay closed
+        others.retain(|cf| cf.db.get().is_some());
+        let cf_count = others.len();
+        let mut rdicts = std::iter::once(&mut *this)
+            .chain(others.iter_mut().map(|cf| &mut **cf))
+            .collect::<Vec<&mut Rdict>>();
+        // auto flush timers hold the database, and are restarted after reopening
+        let timers = py.allow_threads(|| {
+            rdicts
+                .iter_mut()
+                .map(|rdict| {
+                    let mut timer = rdict.auto_flush.take()?;
+                    timer.stop().ok();
+                    Some((timer.interval, timer.flush_wal))
+                })
+                .collect::<Vec<_>>()
+        });
+        py.allow_threads(|| rdicts[0].db.release_handles());
+        let mut in_use = rdicts[0].db.open_handles();
+        if in_use.get("database") == Some(&cf_count) {
+            in_use.remove("database");
+        }
+        if !in_use.is_empty() {
+            restart_auto_flush(&mut rdicts, timers)?;
+            return Err(BusyError::new_err(format!(
+                "cannot reopen, the database is still referenced by {in_use:?}"
+            )));
+        }
+        let cf_names = rdicts
+            .iter()
+            .map(|rdict| {
+                rdict
+                    .column_family
+                    .as_ref()
+                    .map(|_| rdict.column_family_name())
+            })
+            .collect::<Vec<_>>();
+        // do not flush if readonly
+        let flush = !matches!(
+            rdicts[0].access_type.0,
+            AccessTypeInner::ReadOnly { .. } | AccessTypeInner::Secondary { .. }
+        );
+        py.allow_threads(|| {
+            if flush {
+                let db = rdicts[0].get_db()?;
+                db.flush_wal(true).map_err(to_py_err)?;
+                for rdict in rdicts.iter() {
+                    let f_opt = &rdict.flush_opt;
+                    let flushed = match &rdict.column_family {
+                        None => db.flush_opt(&f_opt.into()),
+                        Some(cf) => db.flush_cf_opt(cf, &f_opt.into()),
+                    };
+                    flushed.map_err(to_py_err)?;
+                }
+            }
+            // column family handles must not outlive the database
+            for rdict in rdicts.iter_mut() {
+                drop(rdict.column_family.take());
+            }
+            let mut holders = rdicts
+                .iter_mut()
+                .map(|rdict| &mut rdict.db)
+                .collect::<Vec<_>>();
+            DbReferenceHolder::close_shared(&mut holders);
+            Ok::<_, PyErr>(())
+        })?;
+        let (db, options, prefix_extractors, key_codecs, jobs) = if rdicts[0].external {
+            let (db, options, key_codecs, jobs) =
+                Rdict::open_external_db(&path, raw_mode, Some(options), None, &access_type)?;
+            (db, options, HashMap::new(), key_codecs, jobs)
         } else {
-            Some(encode_key(end, self.opt_py.raw_mode)?)
+            Rdict::open_db(&path, Some(options), None, &access_type)?
         };
-        py.allow_threads(|| {
-            if let Some(cf) = &self.column_family {
+        let mut holders = rdicts
+            .iter_mut()
+            .map(|rdict| &mut rdict.db)
+            .collect::<Vec<_>>();
+        DbReferenceHolder::reopen(&mut holders, db);
+        *rdicts[0].slice_transforms.write().unwrap() = prefix_extractors;
+        *rdicts[0].key_codecs.write().unwrap() = key_codecs;
+        for (rdict, cf_name) in rdicts.iter_mut().zip(cf_names) {
+            rdict.opt_py = options.clone();
+            rdict.jobs = jobs.clone();
+            if let Some(name) = cf_name {
+                let cf = unsafe { rdict.get_db()?.cf_handle_unbounded(&name) };
+                rdict.column_family = Some(cf.ok_or_else(|| {
+                    PyValueError::new_err(format!("column name `{name}` does not exist"))
+                })?);
+                rdict.opt_py = rdict.column_family_options(&name);
+            }
+            rdict.codec.set_checksums(options.value_checksums);
+            rdict.access_type = access_type.clone();
+        }
+        restart_auto_flush(&mut rdicts, timers)
+    }
+
+    /// Count the instances keeping the database open, by kind.
+    ///
+    /// Kinds are `"database"` (`Rdict` instances, including column
+    /// family `Rdict`), `"column family handle"`, `"iterator"`,
+    /// `"snapshot"`, `"consistent view"`, `"buffered writer"`,
+    /// `"updates iterator"`, `"compaction"` for `compact_all` running in
+    /// the background, and `"other"` for internal references such as
+    /// running checkpoints.
+    /// This instance is not counted.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         it = db.iter()
+    ///         assert db.open_handles() == {"iterator": 1}
+    ///         # release the iterator, making it raise DbClosedError
+    ///         db.close(force=True)
+    fn open_handles(&self) -> HashMap<&'static str, usize> {
+        self.db.open_handles()
+    }
+
+    /// Return current database path.
+    fn path(&self) -> PyResult<PathBuf> {
+        Ok(self.get_db()?.path().to_path_buf())
+    }
+
+    /// Copy a consistent snapshot of the whole database into `dst_path`.
+    ///
+    /// The copy is an independent database created from a checkpoint,
+    /// so the source stays online during cloning. SST files are
+    /// hard-linked when `dst_path` is on the same filesystem.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         db = Rdict("./temp_source")
+    ///         db["key"] = "value"
+    ///         db.clone_to("./temp_clone")
+    ///
+    ///         clone = Rdict("./temp_clone")
+    ///         assert clone["key"] == "value"
+    ///
+    /// Args:
+    ///     dst_path (str): path of the new database, must not exist.
+    ///     options (rocksdict.Options): if provided, rewrite the options of
+    ///         the new database by opening it with these options once.
+    ///     column_families (dict): if provided, rewrite the options of the
+    ///         column families of the new database. Must include all
+    ///         column families of the source database.
+    #[pyo3(signature = (dst_path, options = None, column_families = None))]
+    fn clone_to(
+        &self,
+        dst_path: &str,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+        py: Python,
+    ) -> PyResult<()> {
+        if let Some(opt) = &options {
+            if opt.raw_mode != self.opt_py.raw_mode {
+                return Err(PyValueError::new_err(
+                    "cannot change raw_mode when cloning a database",
+                ));
+            }
+        }
+        let checkpoint = CheckpointPy::new(self)?;
+        py.allow_threads(move || checkpoint.create_checkpoint(dst_path.into()))?;
+        if options.is_some() || column_families.is_some() {
+            let mut db = Rdict::new(
+                dst_path,
+                options,
+                column_families,
+                AccessType::read_write(),
+                None,
+                false,
+                py,
+            )?;
+            db.close(py)?;
+        }
+        Ok(())
+    }
+
+    /// Export a consistent snapshot of the whole database as a single tar archive.
+    ///
+    /// A checkpoint is created next to `archive`, packed, and removed
+    /// afterwards. The source stays online during exporting.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_source")
+    ///         db["key"] = "value"
+    ///         db.export_archive("./temp_source.tar.zst")
+    ///
+    ///         # possibly on another machine
+    ///         Rdict.import_archive("./temp_source.tar.zst", "./temp_imported")
+    ///         imported = Rdict("./temp_imported")
+    ///         assert imported["key"] == "value"
+    ///
+    /// Args:
+    ///     archive (str): path of the archive, must not exist.
+    ///         The archive is zstd compressed if it ends with `.zst`.
+    #[pyo3(signature = (archive))]
+    fn export_archive(&self, archive: &str, py: Python) -> PyResult<()> {
+        let archive = PathBuf::from(archive);
+        if archive.exists() {
+            return Err(PyFileExistsError::new_err(format!(
+                "archive {} already exists",
+                archive.display()
+            )));
+        }
+        let checkpoint = CheckpointPy::new(self)?;
+        let checkpoint_dir = sibling_temp_path(&archive, "checkpoint");
+        py.allow_threads(move || {
+            let result = checkpoint
+                .create_checkpoint(checkpoint_dir.clone())
+                .and_then(|_| pack_dir(&checkpoint_dir, &archive).map_err(PyErr::from));
+            fs::remove_dir_all(&checkpoint_dir).ok();
+            result
+        })
+    }
+
+    /// Unpack an archive created by `export_archive` into a new database.
+    ///
+    /// Args:
+    ///     archive (str): path of the archive, either plain or zstd compressed.
+    ///     dst_path (str): path of the new database, must not exist or be empty.
+    #[staticmethod]
+    #[pyo3(signature = (archive, dst_path))]
+    fn import_archive(archive: &str, dst_path: &str, py: Python) -> PyResult<()> {
+        let dst = Path::new(dst_path);
+        if dst.exists() && dst.read_dir()?.next().is_some() {
+            return Err(PyFileExistsError::new_err(format!(
+                "destination {dst_path} is not empty"
+            )));
+        }
+        let result = py
+            .allow_threads(|| unpack(Path::new(archive), dst))
+            .map_err(PyErr::from)
+            .and_then(|_| {
+                if !dst.join("CURRENT").is_file() {
+                    return Err(PyValueError::new_err(format!(
+                        "archive {archive} does not contain a database"
+                    )));
+                }
+                // fail early on an incompatible rocksdict config
+                RocksDictConfig::load_or_default(config_file(dst_path)).map(|_| ())
+            });
+        if result.is_err() {
+            fs::remove_dir_all(dst).ok();
+        }
+        result
+    }
+
+    /// Runs a manual compaction on the Range of keys given for the current Column Family.
+    #[pyo3(signature = (begin, end, compact_opt = Python::with_gil(|py| Py::new(py, CompactOptionsPy::default()).unwrap())))]
+    fn compact_range(
+        &self,
+        begin: &Bound<PyAny>,
+        end: &Bound<PyAny>,
+        compact_opt: Py<CompactOptionsPy>,
+        py: Python,
+    ) -> PyResult<()> {
+        let db = self.get_db()?;
+        let opt = compact_opt.borrow(py);
+        let opt_ref = opt.deref();
+        let from = if begin.is_none() {
+            None
+        } else {
+            Some(encode_key(begin, self.opt_py.key_codec())?)
+        };
+        let to = if end.is_none() {
+            None
+        } else {
+            Some(encode_key(end, self.opt_py.key_codec())?)
+        };
+        py.allow_threads(|| {
+            if let Some(cf) = &self.column_family {
                 db.compact_range_cf_opt(cf, from, to, &opt_ref.0)
             } else {
                 db.compact_range_opt(from, to, &opt_ref.0)
@@ -1244,122 +4049,2643 @@ impl Rdict {
         });
         Ok(())
     }
-
-    /// Set options for the current column family.
-    fn set_options(&self, options: HashMap<String, String>) -> PyResult<()> {
-        let db = self.get_db()?;
-        let options: Vec<(&str, &str)> = options
+
+    /// Compact the whole key range of every column family.
+    ///
+    /// Runs `compact_range(None, None)` on all column families of the
+    /// database, not only the one of this instance, such as in nightly
+    /// maintenance jobs.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db.compact_all(parallel=2)
+    ///
+    /// Args:
+    ///     compact_opt: options of each compaction. The options cannot be
+    ///         changed until the compactions finish.
+    ///     wait: wait for the compactions to finish. Otherwise they run in
+    ///         a background thread, which keeps the database open until
+    ///         they finish, and is reported as `"compaction"` by
+    ///         `open_handles`.
+    ///     parallel: number of column families compacted at the same time.
+    ///         Each compaction is also split into at most
+    ///         `max_subcompactions` subcompactions, see
+    ///         `Options.set_max_subcompactions`.
+    ///
+    /// Returns:
+    ///     the names of the column families compacted.
+    #[pyo3(signature = (compact_opt = None, wait = true, parallel = 1))]
+    fn compact_all(
+        &self,
+        compact_opt: Option<Py<CompactOptionsPy>>,
+        wait: bool,
+        parallel: usize,
+        py: Python,
+    ) -> PyResult<Vec<String>> {
+        let db = self.get_db()?;
+        if parallel == 0 {
+            return Err(PyValueError::new_err("parallel must be at least 1"));
+        }
+        let compact_opt = match compact_opt {
+            Some(opt) => opt,
+            None => Py::new(py, CompactOptionsPy::default())?,
+        };
+        let (names, cfs) = self.all_column_families()?;
+        if wait {
+            let opt = compact_opt.borrow(py);
+            let opt_ref = opt.deref();
+            py.allow_threads(|| compact_column_families(db, &cfs, &opt_ref.0, parallel));
+        } else {
+            let db = self.db.with_kind("compaction");
+            std::thread::Builder::new()
+                .name("rocksdict-compact-all".to_string())
+                .spawn(move || {
+                    let Some(inner) = db.get() else {
+                        return;
+                    };
+                    Python::with_gil(|py| {
+                        let opt = compact_opt.borrow(py);
+                        let opt_ref = opt.deref();
+                        py.allow_threads(|| {
+                            compact_column_families(inner, &cfs, &opt_ref.0, parallel)
+                        });
+                    });
+                })?;
+        }
+        Ok(names)
+    }
+
+    /// Set options for the current column family.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         effective = db.set_options({"write_buffer_size": "64m"})
+    ///         assert effective == {"write_buffer_size": "67108864"}
+    ///
+    /// Args:
+    ///     options: names and values of the options to set.
+    ///
+    /// Returns:
+    ///     the effective values of the options, read back from the latest
+    ///     OPTIONS file, or `None` for options not found there. A
+    ///     `UserWarning` is emitted for values RocksDB changed while
+    ///     setting them, such as sanitized values.
+    ///
+    /// Notes:
+    ///     `set_cf_option_int`, `set_cf_option_float`, and
+    ///     `set_cf_option_bool` check the name and type of an option first.
+    fn set_options(
+        &self,
+        options: HashMap<String, String>,
+        py: Python,
+    ) -> PyResult<HashMap<String, Option<String>>> {
+        let db = self.get_db()?;
+        let pairs: Vec<(&str, &str)> = options
+            .iter()
+            .map(|(opt, v)| (opt.as_str(), v.as_str()))
+            .collect();
+        let result = match &self.column_family {
+            None => db.set_options(&pairs),
+            Some(cf) => db.set_options_cf(cf, &pairs),
+        };
+        result.map_err(to_py_err)?;
+        let section = format!("CFOptions \"{}\"", self.column_family_name());
+        let mut effective = latest_options(db.path(), &section)?;
+        let mut result = HashMap::with_capacity(options.len());
+        for (name, value) in options {
+            let actual = effective.remove(&name);
+            if let Some(actual) = &actual {
+                if !same_option_value(&value, actual) {
+                    let message = format!("option {name} is {actual} instead of {value}");
+                    PyErr::warn_bound(py, &py.get_type_bound::<PyUserWarning>(), &message, 1)?;
+                }
+            }
+            result.insert(name, actual);
+        }
+        Ok(result)
+    }
+
+    /// Set an integer option of the current column family on the open database.
+    ///
+    /// Unlike `set_options`, the option must be one RocksDB can change
+    /// without reopening the database, such as `write_buffer_size`,
+    /// `max_write_buffer_number`, `target_file_size_base`, or
+    /// `level0_slowdown_writes_trigger`, so typos and static options
+    /// fail before anything is changed.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         assert db.set_cf_option_int("max_write_buffer_number", 4) == 4
+    ///         db.set_cf_option_int("write_buffer_size", 128 << 20)
+    ///
+    /// Args:
+    ///     name: name of the option, as in `set_options`.
+    ///     value: new value of the option.
+    ///
+    /// Returns:
+    ///     the effective value of the option, see `set_options`.
+    ///
+    /// Raises:
+    ///     ValueError: if the option cannot be changed on an open database,
+    ///         or the value is negative for an unsigned option.
+    ///     TypeError: if the option is not an integer option, see
+    ///         `set_cf_option_float` and `set_cf_option_bool`.
+    fn set_cf_option_int(&self, name: &str, value: i64, py: Python) -> PyResult<Option<i128>> {
+        let option = dynamic_cf_option(name, "set_cf_option_int")?;
+        if option == DynamicOption::Unsigned && value < 0 {
+            return Err(PyValueError::new_err(format!(
+                "{name} must not be negative, got {value}"
+            )));
+        }
+        let effective = self.set_cf_option(name, value.to_string(), py)?;
+        Ok(effective.and_then(|value| value.parse().ok()))
+    }
+
+    /// Set a float option of the current column family on the open
+    /// database, such as `max_bytes_for_level_multiplier` or
+    /// `memtable_prefix_bloom_size_ratio`, see `set_cf_option_int`.
+    ///
+    /// Args:
+    ///     name: name of the option, as in `set_options`.
+    ///     value: new value of the option.
+    ///
+    /// Returns:
+    ///     the effective value of the option, see `set_options`.
+    fn set_cf_option_float(&self, name: &str, value: f64, py: Python) -> PyResult<Option<f64>> {
+        dynamic_cf_option(name, "set_cf_option_float")?;
+        if !value.is_finite() {
+            return Err(PyValueError::new_err(format!(
+                "{name} must be finite, got {value}"
+            )));
+        }
+        let effective = self.set_cf_option(name, value.to_string(), py)?;
+        Ok(effective.and_then(|value| value.parse().ok()))
+    }
+
+    /// Set a boolean option of the current column family on the open
+    /// database, such as `disable_auto_compactions` or
+    /// `enable_blob_files`, see `set_cf_option_int`.
+    ///
+    /// Args:
+    ///     name: name of the option, as in `set_options`.
+    ///     value: new value of the option.
+    ///
+    /// Returns:
+    ///     the effective value of the option, see `set_options`.
+    fn set_cf_option_bool(&self, name: &str, value: bool, py: Python) -> PyResult<Option<bool>> {
+        dynamic_cf_option(name, "set_cf_option_bool")?;
+        let effective = self.set_cf_option(name, value.to_string(), py)?;
+        Ok(effective.and_then(|value| value.parse().ok()))
+    }
+
+    /// Returns the options of the current column family in effect,
+    /// read from the latest OPTIONS file.
+    ///
+    /// Returns:
+    ///     a dict from option names to values, in the format of
+    ///     `set_options`, such as `{"max_write_buffer_number": "2", ...}`.
+    fn get_options(&self) -> PyResult<HashMap<String, String>> {
+        let db = self.get_db()?;
+        let section = format!("CFOptions \"{}\"", self.column_family_name());
+        Ok(latest_options(db.path(), &section)?)
+    }
+
+    /// Returns the most recent flush and compaction jobs of the database.
+    ///
+    /// Jobs are recorded in memory by an event listener as they begin and
+    /// complete, so that slow-downs can be investigated without parsing
+    /// the info `LOG` file. Each job is a dict with `type` (`"flush"` or
+    /// `"compaction"`), `job`, `cf_name`, `reason` and `start_micros`, and,
+    /// once completed, `end_micros`, `duration_micros` and `status`
+    /// (`"OK"` or the error), together with:
+    ///
+    /// - flushes: `file_path`, `num_entries`, `num_deletions`, `data_size`,
+    ///   `triggered_writes_slowdown` and `triggered_writes_stop`.
+    /// - compactions: `base_input_level`, `output_level`, `num_input_files`,
+    ///   `num_output_files`, `num_input_records`, `num_output_records`,
+    ///   `total_input_bytes` and `total_output_bytes`.
+    ///
+    /// Notes:
+    ///     Only jobs since the database was opened or reopened are
+    ///     recorded, at most the 1000 most recent ones.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db["key"] = "value"
+    ///         db.flush()
+    ///         for job in db.recent_jobs():
+    ///             print(job["type"], job.get("duration_micros"))
+    ///
+    /// Args:
+    ///     limit (int): maximum number of jobs to return.
+    ///
+    /// Returns:
+    ///     a list of jobs, oldest first.
+    #[pyo3(signature = (limit = 100))]
+    fn recent_jobs(&self, limit: usize, py: Python) -> PyResult<PyObject> {
+        self.get_db()?;
+        let result = PyList::empty_bound(py);
+        for job in self.jobs.recent(limit) {
+            result.append(json_to_py(&serde_json::Value::Object(job), py)?)?;
+        }
+        Ok(result.to_object(py))
+    }
+
+    /// Returns the statistics of this database.
+    ///
+    /// Statistics are created when the database is opened, so databases
+    /// opened with the same `Options` do not share tickers and histograms.
+    /// Column families share the statistics of their database.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.enable_statistics()
+    ///         db1 = Rdict("./temp_path_1", opt)
+    ///         db2 = Rdict("./temp_path_2", opt)
+    ///         db1["key"] = "value"
+    ///         # only counts the keys written to db1
+    ///         print(db1.statistics())
+    ///
+    /// Returns:
+    ///     the statistics dump, or `None` if statistics are not
+    ///     enabled with `Options.enable_statistics`.
+    fn statistics(&self) -> PyResult<Option<String>> {
+        self.get_db()?;
+        Ok(self.opt_py.inner_opt.get_statistics())
+    }
+
+    /// Returns the statistics persisted by `Options.set_persist_stats_to_disk`.
+    ///
+    /// Tickers are persisted every `stats_persist_period_sec`, as the
+    /// increase of each ticker since the previous time, so the history
+    /// can be graphed across restarts of the database.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         import time
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.enable_statistics()
+    ///         opt.set_persist_stats_to_disk(True)
+    ///         db = Rdict("./temp_path", opt)
+    ///         last_day = int(time.time()) - 24 * 3600
+    ///         for timestamp, stats in db.stats_history(last_day).items():
+    ///             print(timestamp, stats["rocksdb.bytes.written"])
+    ///
+    /// Args:
+    ///     start_time: first unix timestamp in seconds to return.
+    ///     end_time: unix timestamp in seconds to stop before, all
+    ///         persisted statistics from `start_time` by default.
+    ///
+    /// Returns:
+    ///     a dict from unix timestamps in seconds, in increasing order,
+    ///     to dicts from ticker names to values.
+    ///
+    /// Raises:
+    ///     RocksDBError: if the database has no persisted statistics.
+    #[pyo3(signature = (start_time = 0, end_time = None))]
+    fn stats_history(
+        &self,
+        start_time: u64,
+        end_time: Option<u64>,
+        py: Python,
+    ) -> PyResult<BTreeMap<u64, HashMap<String, u64>>> {
+        let db = self.get_db()?;
+        let cf =
+            unsafe { db.cf_handle_unbounded(STATS_HISTORY_COLUMN_FAMILY) }.ok_or_else(|| {
+                RocksDBError::new_err(
+                    "statistics are not persisted, see Options.set_persist_stats_to_disk",
+                )
+            })?;
+        py.allow_threads(|| {
+            let mut history = BTreeMap::<u64, HashMap<String, u64>>::new();
+            let mut iter = db.raw_iterator_cf_opt(&cf, ReadOptions::default());
+            // keys are `{timestamp:010}#{ticker}`, and values decimal numbers
+            iter.seek(format!("{start_time:010}"));
+            while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                let entry = std::str::from_utf8(key)
+                    .ok()
+                    .and_then(|key| key.split_once('#'))
+                    .and_then(|(time, name)| Some((time.parse::<u64>().ok()?, name)));
+                // version keys of the column family do not have a timestamp
+                let Some((time, name)) = entry else {
+                    break;
+                };
+                if end_time.is_some_and(|end_time| time >= end_time) {
+                    break;
+                }
+                if let Some(value) = std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                {
+                    history
+                        .entry(time)
+                        .or_default()
+                        .insert(name.to_string(), value);
+                }
+                iter.next();
+            }
+            iter.status().map_err(to_py_err)?;
+            Ok(history)
+        })
+    }
+
+    /// Retrieves a RocksDB property by name, for the current column family.
+    fn property_value(&self, name: &str) -> PyResult<Option<String>> {
+        let db = self.get_db()?;
+        match &self.column_family {
+            None => db.property_value(name),
+            Some(cf) => db.property_value_cf(cf, name),
+        }
+        .map_err(to_py_err)
+    }
+
+    /// Retrieves a RocksDB property and casts it to an integer
+    /// (for the current column family).
+    ///
+    /// Full list of properties that return int values could be find
+    /// [here](https://github.com/facebook/rocksdb/blob/08809f5e6cd9cc4bc3958dd4d59457ae78c76660/include/rocksdb/db.h#L654-L689).
+    fn property_int_value(&self, name: &str) -> PyResult<Option<u64>> {
+        let db = self.get_db()?;
+        match &self.column_family {
+            None => db.property_int_value(name),
+            Some(cf) => db.property_int_value_cf(cf, name),
+        }
+        .map_err(to_py_err)
+    }
+
+    /// Read, write and space amplification of this database, in one dict.
+    ///
+    /// Combines the statistics tickers and the properties of the current
+    /// column family that are usually looked up one name at a time.
+    /// Entries read from statistics are `None` unless statistics are
+    /// enabled with `Options.enable_statistics`, and ratios are `None`
+    /// when their denominator is zero.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.enable_statistics()
+    ///         db = Rdict("./temp_path", opt)
+    ///         db["key"] = "value"
+    ///         db.flush()
+    ///         stats = db.io_stats()
+    ///         print(stats["write_amplification"], stats["read_amplification"])
+    ///
+    /// Returns:
+    ///     a dict with the keys:
+    ///
+    ///     - ``bytes_read``, ``bytes_written``: bytes read by point lookups
+    ///       and written by users, from statistics.
+    ///     - ``iter_bytes_read``: bytes read by iterators, from statistics.
+    ///     - ``wal_bytes_written``, ``flush_bytes_written``,
+    ///       ``compaction_bytes_read``, ``compaction_bytes_written``:
+    ///       bytes of background I/O, from statistics.
+    ///     - ``block_cache_hit``, ``block_cache_miss``: block cache lookups,
+    ///       from statistics.
+    ///     - ``write_amplification``: bytes written by flushes and
+    ///       compactions over bytes written by users.
+    ///     - ``read_amplification``: number of sorted runs a point lookup
+    ///       may read, L0 files plus non-empty deeper levels.
+    ///     - ``space_amplification``: size of live SST files over the
+    ///       estimated size of live data.
+    ///     - ``live_sst_bytes``, ``live_data_bytes``, ``memtable_bytes``,
+    ///       ``estimate_num_keys``: from properties.
+    fn io_stats(&self, py: Python) -> PyResult<PyObject> {
+        let db = self.get_db()?;
+        let property = |name: &str| {
+            match &self.column_family {
+                None => db.property_int_value(name),
+                Some(cf) => db.property_int_value_cf(cf, name),
+            }
+            .map_err(to_py_err)
+        };
+        let dump = self.opt_py.inner_opt.get_statistics();
+        let tickers = dump.as_deref().map(statistics_tickers);
+        let ticker = |name: &str| {
+            tickers
+                .as_ref()
+                .map(|t| t.get(name).copied().unwrap_or_default())
+        };
+        let result = PyDict::new_bound(py);
+        for (key, name) in [
+            ("bytes_read", "rocksdb.bytes.read"),
+            ("bytes_written", "rocksdb.bytes.written"),
+            ("iter_bytes_read", "rocksdb.db.iter.bytes.read"),
+            ("wal_bytes_written", "rocksdb.wal.bytes"),
+            ("flush_bytes_written", "rocksdb.flush.write.bytes"),
+            ("compaction_bytes_read", "rocksdb.compact.read.bytes"),
+            ("compaction_bytes_written", "rocksdb.compact.write.bytes"),
+            ("block_cache_hit", "rocksdb.block.cache.hit"),
+            ("block_cache_miss", "rocksdb.block.cache.miss"),
+        ] {
+            result.set_item(key, ticker(name))?;
+        }
+        let storage_written = ticker("rocksdb.flush.write.bytes")
+            .zip(ticker("rocksdb.compact.write.bytes"))
+            .map(|(flush, compaction)| flush + compaction);
+        result.set_item(
+            "write_amplification",
+            ratio(storage_written, ticker("rocksdb.bytes.written")),
+        )?;
+        let mut sorted_runs = 0;
+        for level in 0.. {
+            match property(&format!("rocksdb.num-files-at-level{level}"))? {
+                None => break,
+                Some(files) if level == 0 => sorted_runs += files,
+                Some(files) => sorted_runs += u64::from(files > 0),
+            }
+        }
+        result.set_item("read_amplification", sorted_runs)?;
+        let live_sst = property("rocksdb.live-sst-files-size")?;
+        let live_data = property("rocksdb.estimate-live-data-size")?;
+        result.set_item("space_amplification", ratio(live_sst, live_data))?;
+        result.set_item("live_sst_bytes", live_sst)?;
+        result.set_item("live_data_bytes", live_data)?;
+        result.set_item(
+            "memtable_bytes",
+            property("rocksdb.cur-size-all-mem-tables")?,
+        )?;
+        result.set_item("estimate_num_keys", property("rocksdb.estimate-num-keys")?)?;
+        Ok(result.to_object(py))
+    }
+
+    /// Open file handles and table cache activity of this database.
+    ///
+    /// Helps choose `Options.set_max_open_files` for large databases: a
+    /// table cache too small for the live SST files keeps closing and
+    /// reopening them, which shows up as file opens growing with reads.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.enable_statistics()
+    ///         opt.set_max_open_files(100)
+    ///         db = Rdict("./temp_path", opt)
+    ///         stats = db.file_handle_stats()
+    ///         print(stats["open_sst_files"], stats["file_opens"])
+    ///
+    /// Returns:
+    ///     a dict with the keys:
+    ///
+    ///     - ``max_open_files``: the `max_open_files` option, -1 if unlimited.
+    ///     - ``table_cache_capacity``: number of table files the table
+    ///       cache keeps open, `None` if unlimited.
+    ///     - ``live_sst_files``: number of live SST files of all column
+    ///       families.
+    ///     - ``live_versions``: number of live versions of the LSM tree,
+    ///       from the ``rocksdb.num-live-versions`` property. Old versions
+    ///       kept alive by iterators and snapshots pin their table files.
+    ///     - ``open_files``, ``open_sst_files``: file descriptors of the
+    ///       process opened on files of the database directory, and how
+    ///       many of them are SST files, `None` where ``/proc/self/fd``
+    ///       is not available.
+    ///     - ``file_opens``: table files opened, each on a table cache
+    ///       miss, from the ``rocksdb.no.file.opens`` ticker.
+    ///     - ``file_open_errors``: failures to open table files, from the
+    ///       ``rocksdb.no.file.errors`` ticker.
+    ///     - ``block_cache_hits``, ``block_cache_misses``: lookups of
+    ///       blocks in the block cache, from the ``rocksdb.block.cache.hit``
+    ///       and ``rocksdb.block.cache.miss`` tickers.
+    ///
+    ///     Entries read from tickers are `None` unless statistics are
+    ///     enabled with `Options.enable_statistics`.
+    ///
+    /// Notes:
+    ///     RocksDB has no ticker of table cache hits or evictions.
+    fn file_handle_stats(&self, py: Python) -> PyResult<PyObject> {
+        let db = self.get_db()?;
+        let max_open_files = unsafe {
+            librocksdb_sys::rocksdb_options_get_max_open_files(
+                self.opt_py.inner_opt.inner() as *mut librocksdb_sys::rocksdb_options_t
+            )
+        };
+        let live_sst_files = db.live_files().map_err(to_py_err)?.len();
+        let live_versions = db
+            .property_int_value("rocksdb.num-live-versions")
+            .map_err(to_py_err)?;
+        let (open_files, open_sst_files) = match open_files_in(db.path()) {
+            Some((all, sst)) => (Some(all), Some(sst)),
+            None => (None, None),
+        };
+        let dump = self.opt_py.inner_opt.get_statistics();
+        let tickers = dump.as_deref().map(statistics_tickers);
+        let ticker = |name: &str| {
+            tickers
+                .as_ref()
+                .map(|t| t.get(name).copied().unwrap_or_default())
+        };
+        let result = PyDict::new_bound(py);
+        result.set_item("max_open_files", max_open_files)?;
+        // the table cache leaves room for the files that are not tables
+        result.set_item(
+            "table_cache_capacity",
+            (max_open_files >= 0).then(|| (max_open_files - 10).max(0)),
+        )?;
+        result.set_item("live_sst_files", live_sst_files)?;
+        result.set_item("live_versions", live_versions)?;
+        result.set_item("open_files", open_files)?;
+        result.set_item("open_sst_files", open_sst_files)?;
+        result.set_item("file_opens", ticker("rocksdb.no.file.opens"))?;
+        result.set_item("file_open_errors", ticker("rocksdb.no.file.errors"))?;
+        result.set_item("block_cache_hits", ticker("rocksdb.block.cache.hit"))?;
+        result.set_item("block_cache_misses", ticker("rocksdb.block.cache.miss"))?;
+        Ok(result.to_object(py))
+    }
+
+    /// Start recording the latencies of operations.
+    ///
+    /// Times `get`, `put` and `delete` calls, including their `[]`
+    /// forms, and each item yielded by `items`, `keys`, `values`,
+    /// `columns` and `entities`, in lock-free histograms kept by the
+    /// wrapper. Unlike `Options.enable_statistics`, this does not slow
+    /// down RocksDB itself, and can be turned on and off while the
+    /// database is open. Latencies recorded before are dropped.
+    ///
+    /// Column families of the database share the same histograms.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         db.enable_latency_tracking()
+    ///         for i in range(1000):
+    ///             db[i] = i
+    ///         print(db.latency_stats()["put"]["p99"])
+    fn enable_latency_tracking(&self) -> PyResult<()> {
+        self.get_db()?;
+        self.latency.enable();
+        Ok(())
+    }
+
+    /// Stop recording latencies, keeping those recorded so far.
+    fn disable_latency_tracking(&self) {
+        self.latency.disable();
+    }
+
+    /// Latencies recorded since `Rdict.enable_latency_tracking`.
+    ///
+    /// Args:
+    ///     percentiles: percentiles to report, between 0 and 100.
+    ///
+    /// Returns:
+    ///     a dict from ``"get"``, ``"put"``, ``"delete"`` and ``"scan"`` to
+    ///     dicts of ``count``, ``mean`` and ``max``, and of ``p50``,
+    ///     ``p99.9``, etc. for the requested percentiles. Latencies are in
+    ///     microseconds, within about 6% for percentiles, and are `None`
+    ///     if no operation was recorded.
+    #[pyo3(signature = (percentiles = vec![50.0, 95.0, 99.0]))]
+    fn latency_stats(&self, percentiles: Vec<f64>, py: Python) -> PyResult<PyObject> {
+        if percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            return Err(PyValueError::new_err(
+                "percentiles must be between 0 and 100",
+            ));
+        }
+        let micros = |nanos: u64| nanos as f64 / 1000.0;
+        let result = PyDict::new_bound(py);
+        for op in Operation::ALL {
+            let histogram = self.latency.histogram(op);
+            let count = histogram.count();
+            let stats = PyDict::new_bound(py);
+            stats.set_item("count", count)?;
+            stats.set_item(
+                "mean",
+                (count > 0).then(|| micros(histogram.sum()) / count as f64),
+            )?;
+            stats.set_item("max", (count > 0).then(|| micros(histogram.max())))?;
+            for p in &percentiles {
+                stats.set_item(format!("p{p}"), histogram.percentile(*p).map(micros))?;
+            }
+            result.set_item(op.name(), stats)?;
+        }
+        Ok(result.to_object(py))
+    }
+
+    /// The sequence number of the most recent transaction.
+    fn latest_sequence_number(&self) -> PyResult<u64> {
+        Ok(self.get_db()?.latest_sequence_number())
+    }
+
+    /// Sequence number of the latest update of `key` since `seq_number`.
+    ///
+    /// Scans the updates written to the WAL since `seq_number`, such as
+    /// the last replication checkpoint, for puts, merges and deletions of
+    /// `key` in the current column family, including range deletions
+    /// covering it, so that a sync tool can tell whether the key changed.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         checkpoint = db.latest_sequence_number() + 1
+    ///         db["a"] = 1
+    ///         assert db.latest_sequence_for_key("a", checkpoint) == checkpoint
+    ///         assert db.latest_sequence_for_key("b", checkpoint) is None
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     seq_number (int): the first sequence number to scan.
+    ///
+    /// Returns:
+    ///     the sequence number of the latest update of `key`, or `None`
+    ///     if it was not updated since `seq_number`.
+    ///
+    /// Raises:
+    ///     RocksDBError: if the WAL files since `seq_number` were already
+    ///         deleted, see `Options.set_wal_ttl_seconds`.
+    ///
+    /// Notes:
+    ///     RocksDB does not expose `GetLatestSequenceForKey` in its C API,
+    ///     so the cost grows with the number of updates since `seq_number`.
+    ///     Files added by `ingest_external_file` are not in the WAL, and
+    ///     their keys are not reported.
+    fn latest_sequence_for_key(
+        &self,
+        key: &Bound<PyAny>,
+        seq_number: u64,
+        py: Python,
+    ) -> PyResult<Option<u64>> {
+        let db = self.get_db()?;
+        let key = encode_key(key, self.opt_py.key_codec())?;
+        if seq_number > db.latest_sequence_number() {
+            return Ok(None);
+        }
+        let cf_id = match &self.column_family {
+            None => 0,
+            Some(cf) => unsafe { librocksdb_sys::rocksdb_column_family_handle_get_id(cf.inner()) },
+        };
+        let compare = self.opt_py.key_codec().compare();
+        py.allow_threads(|| latest_key_sequence(db, seq_number, cf_id, &key, compare))
+    }
+
+    /// Ship the updates written to this (primary) database to a replica.
+    ///
+    /// Iterates over the write batches in the WAL starting from the batch
+    /// containing `seq_number`. Feed them to `apply_updates` of a replica,
+    /// which returns the sequence number to continue shipping from.
+    ///
+    /// Notes:
+    ///     WAL files are deleted after flushing by default. Set
+    ///     `Options.set_wal_ttl_seconds` or `Options.set_wal_size_limit_mb`
+    ///     on the primary to keep them long enough for the replica to catch up.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         primary = Rdict("./temp_primary")
+    ///         # the replica must start from a copy of the primary
+    ///         primary.clone_to("./temp_replica")
+    ///         replica = Rdict("./temp_replica")
+    ///
+    ///         seq = primary.latest_sequence_number() + 1
+    ///         primary["key"] = "value"
+    ///         last_seq = replica.apply_updates(primary.ship_updates_since(seq))
+    ///         assert replica["key"] == "value"
+    ///         # next round
+    ///         seq = last_seq + 1
+    ///
+    /// Args:
+    ///     seq_number (int): the first sequence number to ship.
+    ///
+    /// Returns:
+    ///     an iterator of `(sequence_number, batch)` tuples, where `batch` is
+    ///     a serialized write batch (bytes) starting at `sequence_number`.
+    #[pyo3(signature = (seq_number))]
+    fn ship_updates_since(&self, seq_number: u64) -> PyResult<RdictUpdates> {
+        RdictUpdates::new(&self.db, seq_number)
+    }
+
+    /// Atomically apply the updates shipped from a primary database.
+    ///
+    /// All batches are written to this (replica) database in a single
+    /// write. Column families are identified by their ids, so the
+    /// replica must be created from a copy of the primary (for example,
+    /// with `clone_to` or a checkpoint), and column families must only
+    /// be created or dropped through the same copy.
+    ///
+    /// Args:
+    ///     updates: an iterable of `(sequence_number, batch)` tuples,
+    ///         as returned by `ship_updates_since` of the primary.
+    ///     write_opt: use default value if not provided.
+    ///
+    /// Returns:
+    ///     the primary sequence number of the last applied update,
+    ///     or `None` if `updates` is empty.
+    #[pyo3(signature = (updates, write_opt = None))]
+    fn apply_updates(
+        &self,
+        updates: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+    ) -> PyResult<Option<u64>> {
+        let db = self.get_db()?;
+        let mut batches = Vec::new();
+        for update in updates.iter()? {
+            batches.push(update?.extract::<(u64, Vec<u8>)>()?);
+        }
+        let (data, last_seq_number) = combine_batches(&batches)?;
+        if last_seq_number.is_none() {
+            return Ok(None);
+        }
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        self.write_with_retry(|| db.write_opt(WriteBatch::from_data(&data), write_opt))?;
+        Ok(last_seq_number)
+    }
+
+    /// Atomically write a serialized write batch, such as a batch
+    /// shipped by `ship_updates_since` of another instance.
+    ///
+    /// The batch is validated before writing, and must only write to
+    /// column families that exist in this database. Column families are
+    /// identified by their ids, so the batch must come from a copy of
+    /// this database (see `apply_updates`).
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         primary = Rdict("./temp_primary")
+    ///         primary.clone_to("./temp_replica")
+    ///         replica = Rdict("./temp_replica")
+    ///
+    ///         seq = primary.latest_sequence_number() + 1
+    ///         primary["key"] = "value"
+    ///         for _, batch in primary.ship_updates_since(seq):
+    ///             replica.apply_batch_bytes(batch)
+    ///
+    /// Args:
+    ///     data (bytes): the serialized write batch.
+    ///     write_opt: use default value if not provided.
+    ///
+    /// Raises:
+    ///     CorruptionError: if the batch is malformed.
+    ///     ValueError: if the batch writes to a column family missing
+    ///         from this database.
+    #[pyo3(signature = (data, write_opt = None))]
+    fn apply_batch_bytes(&self, data: &[u8], write_opt: Option<&WriteOptionsPy>) -> PyResult<()> {
+        let db = self.get_db()?;
+        let cf_ids = batch_column_family_ids(data)?;
+        if cf_ids.iter().any(|id| *id != 0) {
+            let mut existing = Vec::new();
+            for name in DB::list_cf(&self.opt_py.inner_opt, db.path()).map_err(to_py_err)? {
+                if let Some(cf) = unsafe { db.cf_handle_unbounded(&name) } {
+                    existing.push(unsafe {
+                        librocksdb_sys::rocksdb_column_family_handle_get_id(cf.inner())
+                    });
+                }
+            }
+            let mut missing = cf_ids
+                .into_iter()
+                .filter(|id| !existing.contains(id))
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                missing.sort();
+                return Err(PyValueError::new_err(format!(
+                    "write batch writes to missing column families with ids {missing:?}"
+                )));
+            }
+        }
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        self.write_with_retry(|| db.write_opt(WriteBatch::from_data(data), write_opt))
+    }
+
+    /// Returns a list of all table files with their level, start key and end key
+    fn live_files(&self, py: Python) -> PyResult<PyObject> {
+        let db = self.get_db()?;
+        match db.live_files() {
+            Ok(lfs) => {
+                let result = PyList::empty_bound(py);
+                for lf in lfs {
+                    result.append(display_live_file_dict(
+                        lf,
+                        py,
+                        &self.loads,
+                        self.opt_py.key_codec(),
+                    )?)?
+                }
+                Ok(result.to_object(py))
+            }
+            Err(e) => Err(to_py_err(e)),
+        }
+    }
+
+    /// Returns the crc32c checksums of all live SST files of the database.
+    ///
+    /// Use together with `verify_file_checksums` to validate
+    /// copies of the database end-to-end, for example after
+    /// transferring a checkpoint or an archive to another machine.
+    ///
+    /// Notes:
+    ///     Checksums are computed by reading the files (with the GIL
+    ///     released), since RocksDB C API does not expose the full file
+    ///     checksums stored in the MANIFEST when
+    ///     `Options.set_file_checksum_gen_factory("crc32c")` is set, which
+    ///     are the same values. SST files are immutable, so checksums of a
+    ///     file never change.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Checkpoint
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         Checkpoint(db).create_checkpoint("./temp_checkpoint")
+    ///         checksums = db.get_live_files_checksums()
+    ///         # after copying the checkpoint elsewhere
+    ///         Rdict.verify_file_checksums("./temp_checkpoint", checksums)
+    ///
+    /// Returns:
+    ///     a dict from SST file names (relative to the database path)
+    ///     to hex encoded crc32c checksums.
+    fn get_live_files_checksums(&self, py: Python) -> PyResult<HashMap<String, String>> {
+        let db = self.get_db()?;
+        let db_path = db.path().to_path_buf();
+        let live_files = db.live_files().map_err(to_py_err)?;
+        let checksums = py.allow_threads(|| {
+            live_files
+                .into_iter()
+                .map(|lf| {
+                    let name = lf.name.trim_start_matches('/').to_string();
+                    let crc = file_crc32c(&db_path.join(&name))?;
+                    Ok((name, format_checksum(crc)))
+                })
+                .collect::<std::io::Result<HashMap<_, _>>>()
+        })?;
+        Ok(checksums)
+    }
+
+    /// Verify the files of a database directory against checksums
+    /// returned by `get_live_files_checksums`.
+    ///
+    /// Args:
+    ///     path (str): path of the database (or checkpoint) to verify.
+    ///     checksums (dict): file names and their expected checksums.
+    ///
+    /// Raises:
+    ///     CorruptionError: if any file is missing or has a different checksum.
+    #[staticmethod]
+    #[pyo3(signature = (path, checksums))]
+    fn verify_file_checksums(
+        path: &str,
+        checksums: HashMap<String, String>,
+        py: Python,
+    ) -> PyResult<()> {
+        let mut failures = py.allow_threads(|| {
+            let mut failures = Vec::new();
+            for (name, expected) in &checksums {
+                match file_crc32c(&Path::new(path).join(name)) {
+                    Ok(crc) if format_checksum(crc) == expected.to_lowercase() => {}
+                    Ok(_) => failures.push(format!("{name} (checksum mismatch)")),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        failures.push(format!("{name} (missing)"))
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(failures)
+        })?;
+        if failures.is_empty() {
+            return Ok(());
+        }
+        failures.sort();
+        Err(CorruptionError::new_err(format!(
+            "file checksum verification failed: {}",
+            failures.join(", ")
+        )))
+    }
+
+    /// Returns the live and archived WAL files, sorted by log number.
+    ///
+    /// WAL files are moved to the archive instead of being deleted when
+    /// `Options.set_wal_ttl_seconds` or `Options.set_wal_size_limit_mb`
+    /// is set, so that `ship_updates_since` can read them later.
+    ///
+    /// Returns:
+    ///     a list of dicts with keys `name` (the path relative to the WAL
+    ///     directory), `log_number`, `size`, `archived` and `start_sequence`,
+    ///     the sequence number of the first update in the file, or `None`
+    ///     if the file is empty or compressed.
+    fn get_sorted_wal_files(&self, py: Python) -> PyResult<PyObject> {
+        let db = self.get_db()?;
+        let wal_dir = wal_dir(db.path())?;
+        let files = py.allow_threads(|| sorted_wal_files(&wal_dir))?;
+        let result = PyList::empty_bound(py);
+        for file in files {
+            result.append(display_wal_file_dict(file, py)?)?;
+        }
+        Ok(result.to_object(py))
+    }
+
+    /// Delete the archived WAL files only holding updates before `up_to_seq`.
+    ///
+    /// Use it to trim the WAL archive once all replicas have caught up,
+    /// instead of relying on `Options.set_wal_ttl_seconds` or
+    /// `Options.set_wal_size_limit_mb` alone. `ship_updates_since(up_to_seq)`
+    /// keeps working afterwards. Live WAL files are never deleted.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_wal_ttl_seconds(24 * 3600)
+    ///         db = Rdict("./temp_path", opt)
+    ///         # every replica has applied the updates before `seq`
+    ///         db.purge_wal_files(seq)
+    ///
+    /// Args:
+    ///     up_to_seq (int): the first sequence number to keep.
+    ///
+    /// Returns:
+    ///     the deleted WAL files, as returned by `get_sorted_wal_files`.
+    fn purge_wal_files(&self, up_to_seq: u64, py: Python) -> PyResult<PyObject> {
+        if let AccessTypeInner::ReadOnly { .. } | AccessTypeInner::Secondary { .. } =
+            &self.access_type.0
+        {
+            return Err(NotSupportedError::new_err(
+                "cannot purge WAL files of a read-only or secondary database",
+            ));
+        }
+        let db = self.get_db()?;
+        let wal_dir = wal_dir(db.path())?;
+        let latest_sequence = db.latest_sequence_number();
+        let files = py.allow_threads(|| purge_wal_files(&wal_dir, up_to_seq, latest_sequence))?;
+        let result = PyList::empty_bound(py);
+        for file in files {
+            result.append(display_wal_file_dict(file, py)?)?;
+        }
+        Ok(result.to_object(py))
+    }
+
+    /// Returns the table properties of the current column family,
+    /// aggregated over all of its SST files.
+    ///
+    /// Notes:
+    ///     RocksDB C API does not expose per file table properties,
+    ///     nor user collected properties. Use `live_files` for the
+    ///     size, number of entries, and number of deletions of each file.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         props = db.table_properties()
+    ///         print(props["# entries"], props["raw key size"], props["raw value size"])
+    ///         # properties of SST files at level 0
+    ///         print(db.table_properties(level=0))
+    ///
+    /// Args:
+    ///     level (int): only aggregate SST files at this level.
+    ///
+    /// Returns:
+    ///     a dict from property names (such as `"# entries"`, `"raw key size"`,
+    ///     `"raw value size"`, `"data block size"`) to values.
+    ///     Numeric values are converted to int or float.
+    #[pyo3(signature = (level = None))]
+    fn table_properties(&self, level: Option<usize>, py: Python) -> PyResult<PyObject> {
+        let name = match level {
+            None => "rocksdb.aggregated-table-properties".to_string(),
+            Some(level) => format!("rocksdb.aggregated-table-properties-at-level{level}"),
+        };
+        let properties = self.property_value(&name)?.unwrap_or_default();
+        let result = PyDict::new_bound(py);
+        for property in properties.split(';') {
+            let Some((key, value)) = property.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if key.is_empty() || result.contains(key)? {
+                continue;
+            }
+            if let Ok(value) = value.parse::<i64>() {
+                result.set_item(key, value)?;
+            } else if let Ok(value) = value.parse::<f64>() {
+                result.set_item(key, value)?;
+            } else {
+                result.set_item(key, value)?;
+            }
+        }
+        Ok(result.to_object(py))
+    }
+
+    /// Returns the current write stall state of the current column family.
+    ///
+    /// All values are read from RocksDB properties in one call, which is
+    /// convenient for producers that want to apply backpressure before
+    /// RocksDB starts stalling or stopping writes.
+    ///
+    /// Returns:
+    ///     A dict with the following keys:
+    ///
+    ///     - ``is_write_stopped`` (bool): whether writes are currently stopped.
+    ///     - ``is_write_delayed`` (bool): whether writes are currently delayed.
+    ///     - ``actual_delayed_write_rate`` (int): current delayed write rate
+    ///       in bytes per second, 0 if writes are not delayed.
+    ///     - ``delayed_write_rate`` (int): configured rate writes are delayed to
+    ///       during a stall (see `Options.set_delayed_write_rate`), read from
+    ///       the latest OPTIONS file, or None if it cannot be found.
+    ///     - ``estimate_pending_compaction_bytes`` (int): estimated bytes
+    ///       compaction needs to rewrite to get all levels down to under target size.
+    ///     - ``compaction_pending`` (bool): whether at least one compaction is pending.
+    ///     - ``mem_table_flush_pending`` (bool): whether a memtable flush is pending.
+    ///     - ``num_files_at_level0`` (int): number of files at level 0.
+    ///     - ``num_immutable_mem_table`` (int): number of immutable memtables
+    ///       that have not yet been flushed.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///         import time
+    ///
+    ///         db = Rdict("./temp_stall")
+    ///         for i in range(1000):
+    ///             while db.write_stall_info()["is_write_delayed"]:
+    ///                 time.sleep(0.1)
+    ///             db[i] = i
+    ///         db.close()
+    ///         Rdict.destroy("./temp_stall")
+    fn write_stall_info(&self, py: Python) -> PyResult<PyObject> {
+        let db = self.get_db()?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let int_property = |name: &str| {
+            db.property_int_value_cf(&cf, name)
+                .map(|v| v.unwrap_or(0))
+                .map_err(to_py_err)
+        };
+        let actual_delayed_write_rate = int_property("rocksdb.actual-delayed-write-rate")?;
+        let result = PyDict::new_bound(py);
+        result.set_item(
+            "is_write_stopped",
+            int_property("rocksdb.is-write-stopped")? != 0,
+        )?;
+        result.set_item("is_write_delayed", actual_delayed_write_rate != 0)?;
+        result.set_item("actual_delayed_write_rate", actual_delayed_write_rate)?;
+        let delayed_write_rate = latest_db_option(db.path(), "delayed_write_rate")?
+            .and_then(|rate| rate.parse::<u64>().ok());
+        result.set_item("delayed_write_rate", delayed_write_rate)?;
+        result.set_item(
+            "estimate_pending_compaction_bytes",
+            int_property("rocksdb.estimate-pending-compaction-bytes")?,
+        )?;
+        result.set_item(
+            "compaction_pending",
+            int_property("rocksdb.compaction-pending")? != 0,
+        )?;
+        result.set_item(
+            "mem_table_flush_pending",
+            int_property("rocksdb.mem-table-flush-pending")? != 0,
+        )?;
+        result.set_item(
+            "num_files_at_level0",
+            int_property("rocksdb.num-files-at-level0")?,
+        )?;
+        result.set_item(
+            "num_immutable_mem_table",
+            int_property("rocksdb.num-immutable-mem-table")?,
+        )?;
+        Ok(result.to_object(py))
+    }
+
+    /// Count the keys per key prefix in the current column family.
+    ///
+    /// Keys are bucketed by their first `prefix_len` bytes as stored in
+    /// RocksDB (in non-raw mode this includes the one byte type tag),
+    /// which is the same view a fixed-length `SliceTransform` sees.
+    /// Counts are exact: every key of the column family is scanned, so
+    /// this takes time proportional to the number of keys. The scan
+    /// bypasses the block cache, does not decode any key or value into
+    /// python objects, and releases the GIL.
+    ///
+    /// Useful for choosing `SliceTransform` lengths and detecting hot prefixes.
+    /// For a cheap estimate of the total number of keys, use
+    /// `property_int_value("rocksdb.estimate-num-keys")` instead.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         db = Rdict("./temp_prefix_count", Options(raw_mode=True))
+    ///         for i in range(100):
+    ///             db[b"user:" + str(i).encode()] = b""
+    ///             db[b"item:" + str(i).encode()] = b""
+    ///         counts = db.count_prefixes(5)
+    ///         assert counts == {b"user:": 100, b"item:": 100}
+    ///         db.close()
+    ///         Rdict.destroy("./temp_prefix_count")
+    ///
+    /// Args:
+    ///     prefix_len (int): number of leading bytes forming a prefix bucket.
+    ///         Keys shorter than `prefix_len` form their own bucket.
+    ///
+    /// Returns:
+    ///     A dict mapping prefix (bytes) to key count (int).
+    #[pyo3(signature = (prefix_len))]
+    fn count_prefixes(&self, prefix_len: usize, py: Python) -> PyResult<PyObject> {
+        let db = self.get_db()?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let counts = py
+            .allow_threads(|| {
+                let mut read_opt = ReadOptions::default();
+                read_opt.fill_cache(false);
+                read_opt.set_total_order_seek(true);
+                let mut iter = db.raw_iterator_cf_opt(&cf, read_opt);
+                let mut counts: HashMap<Vec<u8>, u64> = HashMap::new();
+                iter.seek_to_first();
+                while let Some(key) = iter.key() {
+                    let prefix = &key[..key.len().min(prefix_len)];
+                    match counts.get_mut(prefix) {
+                        Some(count) => *count += 1,
+                        None => {
+                            counts.insert(prefix.to_vec(), 1);
+                        }
+                    }
+                    iter.next();
+                }
+                iter.status().map(|_| counts)
+            })
+            .map_err(to_py_err)?;
+        let result = PyDict::new_bound(py);
+        for (prefix, count) in counts {
+            result.set_item(PyBytes::new_bound(py, &prefix), count)?;
+        }
+        Ok(result.to_object(py))
+    }
+
+    /// Warm the block cache with the range `[begin, end)` of the current column family.
+    ///
+    /// Useful for a freshly restarted reader to fill its block cache
+    /// before taking traffic. The GIL is released while prefetching.
+    ///
+    /// Notes:
+    ///     Index and filter blocks are only stored in the block cache if
+    ///     `BlockBasedOptions.set_cache_index_and_filter_blocks` is enabled,
+    ///     otherwise they are loaded when table files are opened.
+    ///     They are prefetched by a point lookup of the smallest key
+    ///     of each table file overlapping the range.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./temp_path")
+    ///         # warm everything at 64MB/s at most
+    ///         db.prefetch_range(None, None, "data|index|filter", 64 * 1024 * 1024)
+    ///
+    /// Args:
+    ///     begin: included, `None` for unbounded.
+    ///     end: excluded, `None` for unbounded.
+    ///     what (str): `|` separated blocks to prefetch, among
+    ///         `data`, `index`, and `filter`.
+    ///     max_bytes_per_sec (int): limit the rate of reading data blocks,
+    ///         measured by the size of keys and values read.
+    ///
+    /// Returns:
+    ///     a dict with the number of `keys` and `bytes` read
+    ///     and the number of table `files` looked up.
+    #[pyo3(signature = (begin, end, what = "data", max_bytes_per_sec = None))]
+    fn prefetch_range(
+        &self,
+        begin: &Bound<PyAny>,
+        end: &Bound<PyAny>,
+        what: &str,
+        max_bytes_per_sec: Option<u64>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let mut prefetch_data = false;
+        let mut prefetch_meta = false;
+        for target in what.split('|') {
+            match target.trim() {
+                "data" => prefetch_data = true,
+                "index" | "filter" => prefetch_meta = true,
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown prefetch target `{other}`, expected data, index or filter"
+                    )))
+                }
+            }
+        }
+        if max_bytes_per_sec == Some(0) {
+            return Err(PyValueError::new_err("max_bytes_per_sec must be positive"));
+        }
+        let db = self.get_db()?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let key_codec = self.opt_py.key_codec();
+        let from = if begin.is_none() {
+            None
+        } else {
+            Some(Vec::from(encode_key(begin, key_codec)?))
+        };
+        let to = if end.is_none() {
+            None
+        } else {
+            Some(Vec::from(encode_key(end, key_codec)?))
+        };
+        let cf_name = self.column_family_name();
+        let compare = key_codec.compare();
+        let (keys, bytes, files) = py
+            .allow_threads(|| {
+                let mut files: u64 = 0;
+                if prefetch_meta {
+                    for lf in db.live_files()? {
+                        let (Some(start_key), Some(end_key)) = (&lf.start_key, &lf.end_key) else {
+                            continue;
+                        };
+                        if lf.column_family_name != cf_name
+                            || from
+                                .as_ref()
+                                .is_some_and(|from| compare(end_key, from) == Ordering::Less)
+                            || to
+                                .as_ref()
+                                .is_some_and(|to| compare(start_key, to) != Ordering::Less)
+                        {
+                            continue;
+                        }
+                        db.get_pinned_cf_opt(&cf, start_key, &ReadOptions::default())?;
+                        files += 1;
+                    }
+                }
+                let mut keys: u64 = 0;
+                let mut bytes: u64 = 0;
+                if prefetch_data {
+                    let started = Instant::now();
+                    let mut read_opt = ReadOptions::default();
+                    if let Some(to) = &to {
+                        read_opt.set_iterate_upper_bound(to.clone());
+                    }
+                    let mut iter = db.raw_iterator_cf_opt(&cf, read_opt);
+                    match &from {
+                        None => iter.seek_to_first(),
+                        Some(from) => iter.seek(from),
+                    }
+                    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                        keys += 1;
+                        bytes += (key.len() + value.len()) as u64;
+                        if let Some(rate) = max_bytes_per_sec {
+                            let expected = Duration::from_secs_f64(bytes as f64 / rate as f64);
+                            let elapsed = started.elapsed();
+                            if expected > elapsed {
+                                std::thread::sleep(expected - elapsed);
+                            }
+                        }
+                        iter.next();
+                    }
+                    iter.status()?;
+                }
+                Ok::<_, rocksdb::Error>((keys, bytes, files))
+            })
+            .map_err(to_py_err)?;
+        let result = PyDict::new_bound(py);
+        result.set_item("keys", keys)?;
+        result.set_item("bytes", bytes)?;
+        result.set_item("files", files)?;
+        Ok(result.to_object(py))
+    }
+
+    /// Create a database under a new unique temporary directory.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict.temp()
+    ///         db["key"] = "value"
+    ///         # the database is removed after closing
+    ///         db.close()
+    ///
+    /// Args:
+    ///     prefix (str): prefix of the temporary directory name.
+    ///     cleanup (bool): remove the database after it is closed or
+    ///         garbage collected, together with all its column families,
+    ///         iterators, and snapshots.
+    ///     options (rocksdict.Options): Rocksdb options object.
+    #[staticmethod]
+    #[pyo3(signature = (prefix = None, cleanup = true, options = None))]
+    fn temp(
+        prefix: Option<&str>,
+        cleanup: bool,
+        options: Option<OptionsPy>,
+        py: Python,
+    ) -> PyResult<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let temp_dir = std::env::temp_dir().join(format!(
+            "{}{}-{}",
+            prefix.unwrap_or("rocksdict-"),
+            std::process::id(),
+            nanos
+        ));
+        let temp_path = path_to_str(&temp_dir)?.to_string();
+        fs::create_dir(&temp_dir)?;
+        let result = Rdict::new(
+            &temp_path,
+            options,
+            None,
+            AccessType::read_write(),
+            None,
+            false,
+            py,
+        );
+        match result {
+            Ok(mut db) => {
+                if cleanup {
+                    db.db.remove_on_close(temp_dir);
+                }
+                Ok(db)
+            }
+            Err(e) => {
+                fs::remove_dir_all(&temp_dir).ok();
+                Err(e)
+            }
+        }
+    }
+
+    /// Open a database described by a URI.
+    ///
+    /// The URI has the form `rocksdict://<path>?<param>=<value>&...`,
+    /// where `rocksdict:///data/store` refers to the absolute path
+    /// `/data/store`, and `rocksdict://./store` to a relative one.
+    /// Paths and values can be percent-encoded. Supported parameters:
+    ///
+    /// - `read_only`: open as read only if `true`.
+    /// - `secondary`: open as secondary, with this secondary path.
+    /// - `ttl`: open with this time to live in seconds.
+    /// - `raw`: open in raw mode if `true`, instead of the mode
+    ///   recorded in the database, or with default Options.
+    /// - `column_families`: comma-separated names of the column
+    ///   families to open, instead of those recorded in the database.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         import os
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict.from_uri("rocksdict:///data/store?read_only=true")
+    ///
+    ///         # or configure the deployment with the environment
+    ///         os.environ["ROCKSDICT_URI"] = "rocksdict:///data/store?ttl=86400"
+    ///         db = Rdict.from_uri()
+    ///
+    /// Args:
+    ///     uri (str): the URI, read from the `ROCKSDICT_URI`
+    ///         environment variable if `None`.
+    ///
+    /// Raises:
+    ///     ValueError: if the URI is malformed, has an unknown parameter,
+    ///         or asks for more than one access type.
+    #[staticmethod]
+    #[pyo3(signature = (uri = None))]
+    fn from_uri(uri: Option<String>, py: Python) -> PyResult<Self> {
+        let uri = match uri {
+            Some(uri) => uri,
+            None => std::env::var(ROCKSDICT_URI_ENV).map_err(|_| {
+                PyValueError::new_err(format!("no uri passed, and {ROCKSDICT_URI_ENV} is not set"))
+            })?,
+        };
+        let (path, params) = parse_uri(&uri)?;
+        let mut access_types = Vec::new();
+        let mut raw_mode = None;
+        let mut column_families = None;
+        for (name, value) in params {
+            match name.as_str() {
+                "read_only" => {
+                    if parse_uri_bool(&name, &value)? {
+                        access_types.push(AccessType::read_only(false));
+                    }
+                }
+                "secondary" => access_types.push(AccessType::secondary(value)),
+                "ttl" => {
+                    let ttl = value.parse().map_err(|_| {
+                        PyValueError::new_err(format!("ttl should be seconds, got `{value}`"))
+                    })?;
+                    access_types.push(AccessType::with_ttl(ttl));
+                }
+                "raw" => raw_mode = Some(parse_uri_bool(&name, &value)?),
+                "column_families" => {
+                    column_families = Some(
+                        value
+                            .split(',')
+                            .filter(|name| !name.is_empty())
+                            .map(str::to_string)
+                            .collect::<Vec<_>>(),
+                    )
+                }
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown uri parameter `{name}`"
+                    )))
+                }
+            }
+        }
+        if access_types.len() > 1 {
+            return Err(PyValueError::new_err(
+                "uri should set at most one of read_only, secondary and ttl",
+            ));
+        }
+        let access_type = access_types.pop().unwrap_or_else(AccessType::read_write);
+        // the options of the column families must have the mode of the database
+        let cf_raw_mode = match raw_mode {
+            Some(raw_mode) => raw_mode,
+            None if config_file(&path).is_file() => {
+                RocksDictConfig::load(config_file(&path))?.raw_mode
+            }
+            None => false,
+        };
+        let column_families = column_families.map(|names| {
+            names
+                .into_iter()
+                .map(|name| (name, OptionsPy::new(cf_raw_mode)))
+                .collect()
+        });
+        Rdict::new(
+            &path,
+            raw_mode.map(OptionsPy::new),
+            column_families,
+            access_type,
+            None,
+            false,
+            py,
+        )
+    }
+
+    /// Open a checkpoint created by `Checkpoint.create_checkpoint`.
+    ///
+    /// Unlike opening the checkpoint with `Rdict(checkpoint_path)`,
+    /// this refuses to open directories that are not checkpoints
+    /// created by rocksdict, or whose rocksdict config is incompatible,
+    /// and it never creates an empty database by accident.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Checkpoint
+    ///
+    ///         # roll back to the latest checkpoint
+    ///         latest = Checkpoint.list("./temp_checkpoints")[-1]
+    ///         db = Rdict.open_checkpoint(latest)
+    ///
+    /// Args:
+    ///     checkpoint_path (str): path of the checkpoint.
+    ///     read_only (bool): open the checkpoint as read only,
+    ///         which leaves the checkpoint unmodified.
+    #[staticmethod]
+    #[pyo3(signature = (checkpoint_path, read_only = true))]
+    fn open_checkpoint(checkpoint_path: &str, read_only: bool, py: Python) -> PyResult<Self> {
+        let config_path = config_file(checkpoint_path);
+        if !Path::new(checkpoint_path).join("CURRENT").is_file() || !config_path.is_file() {
+            return Err(PyValueError::new_err(format!(
+                "{checkpoint_path} is not a rocksdict checkpoint"
+            )));
+        }
+        RocksDictConfig::load(&config_path)?;
+        let access_type = if read_only {
+            AccessType::read_only(false)
+        } else {
+            AccessType::read_write()
+        };
+        let (options, column_families) = OptionsPy::load_latest_inner(
+            checkpoint_path,
+            EnvPy::default()?,
+            false,
+            CachePy::new_lru_cache(DEFAULT_LRU_CACHE_SIZE),
+        )?;
+        Rdict::new(
+            checkpoint_path,
+            Some(options),
+            Some(column_families),
+            access_type,
+            None,
+            false,
+            py,
+        )
+    }
+
+    /// Open a RocksDB database created by other tools, such as RocksDB
+    /// itself or bindings of other languages, which has no
+    /// `rocksdict-config.json`.
+    ///
+    /// Unlike `Rdict(path)`, this never writes `rocksdict-config.json`,
+    /// even when creating column families, never sets the `rocksdict`
+    /// comparator, which fails to open databases using the default
+    /// comparator, and never creates an empty database by accident.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, AccessType
+    ///
+    ///         db = Rdict.open_external("./rocksdb_data")
+    ///         for key, value in db.items():
+    ///             print(key, value)
+    ///         db.close()
+    ///
+    ///         # write to the database
+    ///         db = Rdict.open_external("./rocksdb_data", access_type=AccessType.read_write())
+    ///         db[b"key"] = b"value"
+    ///
+    /// Args:
+    ///     path (str): path of the database.
+    ///     raw_mode (bool): keys and values are bytes. Otherwise, they are
+    ///         encoded by rocksdict, with the `"ordered"` key codec, which
+    ///         orders keys as the default comparator does.
+    ///     options (rocksdict.Options): options to open the database with,
+    ///         read from its latest OPTIONS file if not provided.
+    ///     column_families (dict): options of the column families to open,
+    ///         read from its latest OPTIONS file if not provided.
+    ///     access_type (AccessType): read only by default.
+    #[staticmethod]
+    #[pyo3(signature = (
+        path,
+        raw_mode = true,
+        options = None,
+        column_families = None,
+        access_type = AccessType::read_only(false)
+    ))]
+    fn open_external(
+        path: &str,
+        raw_mode: bool,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+        access_type: AccessType,
+        py: Python,
+    ) -> PyResult<Self> {
+        let (db, options, key_codecs, jobs) =
+            Rdict::open_external_db(path, raw_mode, options, column_families, &access_type)?;
+        Rdict::with_db(
+            db,
+            jobs,
+            options,
+            HashMap::new(),
+            key_codecs,
+            access_type,
+            None,
+            true,
+            py,
+        )
+    }
+
+    /// Delete the database.
+    ///
+    /// Refuses to run while the database is open, either by this
+    /// process or by another process holding its `LOCK` file.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         # list the files that would be removed
+    ///         print(Rdict.destroy("./temp_path", dry_run=True))
+    ///         # only remove the data of column family "cf1"
+    ///         Rdict.destroy("./temp_path", column_family="cf1")
+    ///         # remove the whole database
+    ///         Rdict.destroy("./temp_path")
+    ///
+    /// Args:
+    ///     path (str): path to this database
+    ///     options (rocksdict.Options): Rocksdb options object
+    ///     dry_run (bool): do not remove anything, return the paths of
+    ///         the files that would be removed instead.
+    ///     column_family (str): only destroy the data of this column family
+    ///         by dropping it. With `dry_run`, returns the paths of the live
+    ///         SST files of this column family.
+    ///
+    /// Returns:
+    ///     a list of file paths if `dry_run` is `True`, otherwise `None`.
+    #[staticmethod]
+    #[pyo3(signature = (path, options = OptionsPy::new(false), dry_run = false, column_family = None))]
+    fn destroy(
+        path: &str,
+        options: OptionsPy,
+        dry_run: bool,
+        column_family: Option<&str>,
+        py: Python,
+    ) -> PyResult<Option<Vec<String>>> {
+        ensure_not_in_use(path)?;
+        if let Some(name) = column_family {
+            return destroy_column_family(path, name, dry_run, py);
+        }
+        if dry_run {
+            return list_db_files(path).map(Some);
+        }
+        let inner_opt = options.inner_opt;
+
+        py.allow_threads(|| {
+            fs::remove_file(config_file(path)).ok();
+            DB::destroy(&inner_opt, path)
+        })
+        .map_err(to_py_err)?;
+        Ok(None)
+    }
+
+    /// Repair the database.
+    ///
+    /// Notes:
+    ///     RocksDB repairs every column family with `options`, so
+    ///     the options of all column families must use the same
+    ///     `raw_mode` (which determines the comparator).
+    ///     When `column_families` is provided, the rocksdict config
+    ///     (such as per column family prefix extractors) is rewritten
+    ///     from it after repairing.
+    ///
+    /// Args:
+    ///     path (str): path to this database
+    ///     options (rocksdict.Options): Rocksdb options object.
+    ///         If not provided, load the latest options from the path
+    ///         like opening does, falling back to `Options()`.
+    ///     column_families (dict): (name, options) pairs of column families,
+    ///         loaded from the path together with `options` if not provided.
+    #[staticmethod]
+    #[pyo3(signature = (path, options = None, column_families = None))]
+    fn repair(
+        path: &str,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+        py: Python,
+    ) -> PyResult<()> {
+        let (options, column_families) = match options {
+            Some(opt) => (opt, column_families),
+            None => match OptionsPy::load_latest_inner(
+                path,
+                EnvPy::default()?,
+                false,
+                CachePy::new_lru_cache(DEFAULT_LRU_CACHE_SIZE),
+            ) {
+                Ok((opt, cols)) => (opt, column_families.or(Some(cols))),
+                Err(_) => (OptionsPy::new(false), column_families),
+            },
+        };
+        if let Some(cols) = &column_families {
+            for (name, opt) in cols.iter() {
+                if opt.key_codec.is_none() && opt.raw_mode != options.raw_mode {
+                    return Err(PyValueError::new_err(format!(
+                        "column family `{name}` has raw_mode={}, but options has raw_mode={}",
+                        opt.raw_mode, options.raw_mode
+                    )));
+                }
+            }
+        }
+        let inner_opt = &options.inner_opt;
+        py.allow_threads(|| DB::repair(inner_opt, path))
+            .map_err(to_py_err)?;
+        if column_families.is_some() {
+            let recorded_key_codecs = RocksDictConfig::load_or_default(config_file(path))
+                .map(|config| config.key_codecs())
+                .unwrap_or_else(|_| KeyCodecs::new(options.key_codec()));
+            let prefix_extractors = collect_prefix_extractors(&options, &column_families);
+            RocksDictConfig::new(options.raw_mode, prefix_extractors)
+                .with_key_codecs(&collect_key_codecs(
+                    &options,
+                    &column_families,
+                    &recorded_key_codecs,
+                ))
+                .with_value_checksums(options.value_checksums)
+                .save(config_file(path))?;
+        }
+        Ok(())
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (path, options = OptionsPy::new(false)))]
+    fn list_cf(path: &str, options: OptionsPy) -> PyResult<Vec<String>> {
+        DB::list_cf(&options.inner_opt, path).map_err(to_py_err)
+    }
+}
+
+/// Collect key codecs of the default and other column families, the
+/// codec set by `Options.set_key_codec` taking precedence over `recorded`.
+fn collect_key_codecs(
+    options: &OptionsPy,
+    column_families: &Option<HashMap<String, OptionsPy>>,
+    recorded: &KeyCodecs,
+) -> KeyCodecs {
+    let mut key_codecs = KeyCodecs::new(options.key_codec());
+    for (name, opt) in column_families.iter().flatten() {
+        if name == DEFAULT_COLUMN_FAMILY_NAME {
+            continue;
+        }
+        // written by RocksDB with the bytewise comparator
+        if name == STATS_HISTORY_COLUMN_FAMILY {
+            key_codecs
+                .column_families
+                .insert(name.clone(), KeyCodec::Raw);
+            continue;
+        }
+        let key_codec = opt.key_codec.unwrap_or_else(|| {
+            recorded
+                .column_families
+                .get(name)
+                .copied()
+                .unwrap_or(key_codecs.default)
+        });
+        if key_codec != key_codecs.default {
+            key_codecs.column_families.insert(name.clone(), key_codec);
+        }
+    }
+    key_codecs
+}
+
+/// Collect prefix extractors of the default and other column families.
+fn collect_prefix_extractors(
+    options: &OptionsPy,
+    column_families: &Option<HashMap<String, OptionsPy>>,
+) -> HashMap<String, SliceTransformType> {
+    let mut prefix_extractors = HashMap::new();
+    if let Some(slice_transform) = &options.prefix_extractor {
+        prefix_extractors.insert(
+            DEFAULT_COLUMN_FAMILY_NAME.to_string(),
+            slice_transform.clone(),
+        );
+    }
+    if let Some(cf) = column_families {
+        for (name, opt) in cf.iter() {
+            if let Some(slice_transform) = &opt.prefix_extractor {
+                prefix_extractors.insert(name.clone(), slice_transform.clone());
+            }
+        }
+    }
+    prefix_extractors
+}
+
+/// Copy the key range `[begin, end)` from one database or column family to another.
+///
+/// Entries are streamed in Rust with the GIL released, either with write
+/// batches of `batch_size` entries, or by writing SST files from a
+/// snapshot of the source, as `Rdict.ingest_from` does, which are
+/// ingested into the destination at once.
+///
+/// Notes:
+///     Only the default column of wide-column entities is copied.
+///     Values are copied as stored, so both sides must have the same
+///     key codec and the same value checksums, compression and
+///     encryption settings. Encrypted values are authenticated with
+///     their column family name, so they can only be copied between
+///     column families of the same name. The retry policy of `dst`
+///     applies to its writes.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, copy_range
+///
+///         src = Rdict("./temp_src")
+///         dst = Rdict("./temp_dst")
+///         for i in range(100):
+///             src[i] = i
+///         # move [10, 20) into column family "cf1" of another database
+///         copy_range(src, dst.create_column_family("cf1"), 10, 20)
+///         src.delete_range(10, 20)
+///
+/// Args:
+///     src (Rdict): source database or column family.
+///     dst (Rdict): destination database or column family.
+///     begin: included, `None` for unbounded.
+///     end: excluded, `None` for unbounded.
+///     batch_size (int): number of entries per write batch.
+///     use_ingest (bool): write an SST file and ingest it into `dst`
+///         instead of using write batches. Faster for large ranges.
+///
+/// Returns:
+///     the number of entries copied.
+#[pyfunction]
+#[pyo3(signature = (src, dst, begin = None, end = None, batch_size = 1000, use_ingest = false))]
+pub(crate) fn copy_range(
+    src: &Rdict,
+    dst: &Rdict,
+    begin: Option<&Bound<PyAny>>,
+    end: Option<&Bound<PyAny>>,
+    batch_size: usize,
+    use_ingest: bool,
+    py: Python,
+) -> PyResult<u64> {
+    if src.opt_py.key_codec() != dst.opt_py.key_codec() {
+        return Err(PyValueError::new_err(
+            "src and dst must have the same raw_mode and key codec",
+        ));
+    }
+    if batch_size == 0 {
+        return Err(PyValueError::new_err("batch_size must be positive"));
+    }
+    if !src.codec.same_stages(&dst.codec) {
+        return Err(PyValueError::new_err(
+            "src and dst must have the same value checksums, compression and encryption, \
+             and encrypted values must stay in column families of the same name",
+        ));
+    }
+    let key_codec = src.opt_py.key_codec();
+    let src_db = src.get_db()?;
+    let dst_db = dst.get_db()?;
+    let src_cf = match &src.column_family {
+        None => src.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?.cf,
+        Some(cf) => cf.clone(),
+    };
+    let dst_cf = match &dst.column_family {
+        None => dst.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?.cf,
+        Some(cf) => cf.clone(),
+    };
+    let from = match begin {
+        Some(begin) if !begin.is_none() => Some(Vec::from(encode_key(begin, key_codec)?)),
+        _ => None,
+    };
+    let to = match end {
+        Some(end) if !end.is_none() => Some(Vec::from(encode_key(end, key_codec)?)),
+        _ => None,
+    };
+    let write_opt = WriteOptions::from(&dst.write_opt_py);
+    if use_ingest {
+        let sst_opt = dst.opt_py.inner_opt.clone();
+        let dir = TempDir::create("rocksdict-copy-range")?;
+        return py.allow_threads(|| {
+            let (files, count) = write_snapshot_files(
+                src_db,
+                &src_cf,
+                from,
+                to,
+                &sst_opt,
+                dir.path(),
+                DEFAULT_SST_FILE_SIZE,
+            )
+            .map_err(to_py_err)?;
+            if !files.is_empty() {
+                let mut ingest_opt = IngestExternalFileOptions::default();
+                ingest_opt.set_move_files(true);
+                dst.write_with_retry(|| {
+                    dst_db.ingest_external_file_cf_opts(&dst_cf, &ingest_opt, files.clone())
+                })?;
+            }
+            Ok(count)
+        });
+    }
+    py.allow_threads(|| {
+        let mut read_opt = ReadOptions::default();
+        read_opt.fill_cache(false);
+        if let Some(from) = from {
+            read_opt.set_iterate_lower_bound(from);
+        }
+        if let Some(to) = to {
+            read_opt.set_iterate_upper_bound(to);
+        }
+        let mut iter = src_db.raw_iterator_cf_opt(&src_cf, read_opt);
+        iter.seek_to_first();
+        let mut count: u64 = 0;
+        // a failed write has no effect, so each attempt writes a copy of the batch
+        let write = |batch: &WriteBatch| {
+            dst.write_with_retry(|| {
+                dst_db.write_opt(WriteBatch::from_data(batch.data()), &write_opt)
+            })
+        };
+        let mut batch = WriteBatch::default();
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            batch.put_cf(&dst_cf, key, value);
+            count += 1;
+            if batch.len() >= batch_size {
+                write(&batch)?;
+                batch.clear();
+            }
+            iter.next();
+        }
+        iter.status().map_err(to_py_err)?;
+        if !batch.is_empty() {
+            write(&batch)?;
+        }
+        Ok(count)
+    })
+}
+
+/// Size of the SST files written by `copy_range` before ingestion.
+const DEFAULT_SST_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Write the entries of `cf` between `from` and `to` in a snapshot of `db`
+/// into SST files of about `file_size` bytes under `dir`, returning the
+/// files in key order and the number of entries written.
+fn write_snapshot_files(
+    db: &DB,
+    cf: &Arc<UnboundColumnFamily>,
+    from: Option<Vec<u8>>,
+    to: Option<Vec<u8>>,
+    sst_opt: &rocksdb::Options,
+    dir: &Path,
+    file_size: u64,
+) -> Result<(Vec<PathBuf>, u64), rocksdb::Error> {
+    let snapshot = db.snapshot();
+    let mut read_opt = ReadOptions::default();
+    read_opt.fill_cache(false);
+    read_opt.set_snapshot(&snapshot);
+    if let Some(from) = from {
+        read_opt.set_iterate_lower_bound(from);
+    }
+    if let Some(to) = to {
+        read_opt.set_iterate_upper_bound(to);
+    }
+    let mut iter = db.raw_iterator_cf_opt(cf, read_opt);
+    iter.seek_to_first();
+    let mut files = Vec::new();
+    let mut count: u64 = 0;
+    while iter.valid() {
+        let path = dir.join(format!("{:06}.sst", files.len() + 1));
+        let mut writer = SstFileWriter::create(sst_opt);
+        writer.open(&path)?;
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            writer.put(key, value)?;
+            count += 1;
+            iter.next();
+            if writer.file_size() >= file_size {
+                break;
+            }
+        }
+        writer.finish()?;
+        files.push(path);
+    }
+    iter.status()?;
+    Ok((files, count))
+}
+
+/// Split a database into shards by key boundaries.
+///
+/// Opens the database at `src_path` read-only, which gives a point-in-time
+/// view of it as a checkpoint would, and streams the keys of each shard
+/// into SST files ingested into a new database, so that no entry goes
+/// through Python. Shard `i` gets the keys from `boundaries[i - 1]`
+/// included to `boundaries[i]` excluded, in every column family, with
+/// the boundaries encoded by the key codec of each column family, and
+/// the shards are created with the options of the source database.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, split
+///
+///         db = Rdict("./temp_path")
+///         for i in range(300):
+///             db[i] = i
+///         db.close()
+///         counts = split("./temp_path", ["./shard_0", "./shard_1", "./shard_2"], [100, 200])
+///         assert counts == [100, 100, 100]
+///
+/// Args:
+///     src_path (str): path of the database to split, which is not changed.
+///     dst_paths (List[str]): paths of the shards, which must not exist.
+///     boundaries (list): keys between the shards, in increasing order,
+///         one fewer than `dst_paths`.
+///     file_size (int): size of each SST file written before ingestion.
+///
+/// Returns:
+///     the number of entries of each shard.
+///
+/// Notes:
+///     The source can stay open in this process or another one, but
+///     the writes made to it after the split starts are not copied.
+///     Only the default column of wide-column entities is copied.
+#[pyfunction]
+#[pyo3(signature = (src_path, dst_paths, boundaries, file_size = 64 * 1024 * 1024))]
+pub(crate) fn split(
+    src_path: &str,
+    dst_paths: Vec<String>,
+    boundaries: Vec<Bound<PyAny>>,
+    file_size: u64,
+    py: Python,
+) -> PyResult<Vec<u64>> {
+    if dst_paths.len() != boundaries.len() + 1 {
+        return Err(PyValueError::new_err(
+            "there must be one fewer boundary than dst_paths",
+        ));
+    }
+    if file_size == 0 {
+        return Err(PyValueError::new_err("file_size must be positive"));
+    }
+    for dst_path in &dst_paths {
+        if Path::new(dst_path).exists() {
+            return Err(PyFileExistsError::new_err(format!(
+                "{dst_path} already exists"
+            )));
+        }
+    }
+    let (src_db, options, column_families) = open_read_only(src_path)?;
+    // boundaries are encoded and ordered by the key codec of each column family
+    let mut cf_boundaries = HashMap::new();
+    for (name, cf_opt) in &column_families {
+        let key_codec = cf_opt.key_codec();
+        let compare = key_codec.compare();
+        let encoded = boundaries
             .iter()
-            .map(|(opt, v)| (opt.as_str(), v.as_str()))
-            .collect();
-        match &self.column_family {
-            None => db.set_options(&options),
-            Some(cf) => db.set_options_cf(cf, &options),
+            .map(|key| encode_key(key, key_codec).map(Vec::from))
+            .collect::<PyResult<Vec<_>>>()?;
+        if encoded
+            .windows(2)
+            .any(|pair| compare(&pair[0], &pair[1]) != Ordering::Less)
+        {
+            return Err(PyValueError::new_err(format!(
+                "boundaries must be in increasing key order of column family `{name}`"
+            )));
+        }
+        cf_boundaries.insert(name.clone(), encoded);
+    }
+    let mut dst_options = options.clone();
+    dst_options.inner_opt.create_if_missing(true);
+    dst_options.inner_opt.create_missing_column_families(true);
+    let mut dst_dbs = Vec::with_capacity(dst_paths.len());
+    for dst_path in &dst_paths {
+        let (db, _, _, _, _) = Rdict::open_db(
+            dst_path,
+            Some(dst_options.clone()),
+            Some(column_families.clone()),
+            &AccessType::read_write(),
+        )?;
+        dst_dbs.push(db);
+    }
+    let cf_options = column_families
+        .into_iter()
+        .map(|(name, opt)| {
+            let boundaries = cf_boundaries.remove(&name).unwrap_or_default();
+            (name, opt.inner_opt, boundaries)
+        })
+        .collect::<Vec<_>>();
+    let dir = TempDir::create("rocksdict-split")?;
+    let result = py.allow_threads(|| {
+        let mut counts = vec![0; dst_dbs.len()];
+        for (name, sst_opt, boundaries) in &cf_options {
+            let Some(src_cf) = (unsafe { src_db.cf_handle_unbounded(name) }) else {
+                continue;
+            };
+            for (shard, dst_db) in dst_dbs.iter().enumerate() {
+                let dst_cf = unsafe { dst_db.cf_handle_unbounded(name) }.ok_or_else(|| {
+                    RocksDBError::new_err(format!("column family {name} was not created"))
+                })?;
+                let from = shard.checked_sub(1).map(|i| boundaries[i].clone());
+                let to = boundaries.get(shard).cloned();
+                let shard_dir = dir.path().join(format!("{shard}-{name}"));
+                fs::create_dir_all(&shard_dir)?;
+                let (files, count) = write_snapshot_files(
+                    &src_db, &src_cf, from, to, sst_opt, &shard_dir, file_size,
+                )
+                .map_err(to_py_err)?;
+                if !files.is_empty() {
+                    let mut ingest_opt = IngestExternalFileOptions::default();
+                    ingest_opt.set_move_files(true);
+                    dst_db
+                        .ingest_external_file_cf_opts(&dst_cf, &ingest_opt, files)
+                        .map_err(to_py_err)?;
+                }
+                counts[shard] += count;
+            }
+        }
+        Ok(counts)
+    });
+    drop(dir);
+    if result.is_err() {
+        // do not leave incomplete shards behind
+        drop(dst_dbs);
+        for dst_path in &dst_paths {
+            fs::remove_dir_all(dst_path).ok();
         }
-        .map_err(|e| PyException::new_err(e.to_string()))
     }
+    result
+}
 
-    /// Retrieves a RocksDB property by name, for the current column family.
-    fn property_value(&self, name: &str) -> PyResult<Option<String>> {
-        let db = self.get_db()?;
-        match &self.column_family {
-            None => db.property_value(name),
-            Some(cf) => db.property_value_cf(cf, name),
+/// Merge several databases into a new one.
+///
+/// Copies the entries of every column family of each source into the
+/// database created at `dst_path`, with the options of the first source,
+/// by streaming them into SST files that are ingested with the GIL
+/// released. Sources are opened read-only, so they can stay open in this
+/// process or another one, but the writes made to them meanwhile are not
+/// copied. This consolidates stores, such as one database per day.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, merge_dbs
+///
+///         for day in range(3):
+///             db = Rdict(f"./temp_day_{day}")
+///             db[f"event_{day}"] = day
+///             db.close()
+///         merge_dbs([f"./temp_day_{day}" for day in range(3)], "./temp_all")
+///         db = Rdict("./temp_all")
+///         assert db["event_2"] == 2
+///
+/// Args:
+///     src_paths (List[str]): paths of the databases to merge, oldest first,
+///         which are not changed.
+///     dst_path (str): path of the merged database, which must not exist.
+///     conflict (str): what to do with a key in several sources,
+///         ``"newest"`` to keep its value in the last of them, or
+///         ``"error"`` to raise a `ValueError` naming the key, in which
+///         case the merged database is not created.
+///     file_size (int): size of each SST file written before ingestion.
+///
+/// Returns:
+///     the number of entries copied, counting overwritten ones.
+///
+/// Notes:
+///     A column family must have the same key codec in all sources
+///     having it. Column families missing from the first source are
+///     created with the options of the first source having them. Only the default column
+///     of wide-column entities is copied.
+#[pyfunction]
+#[pyo3(signature = (src_paths, dst_path, conflict = "newest", file_size = 64 * 1024 * 1024))]
+pub(crate) fn merge_dbs(
+    src_paths: Vec<String>,
+    dst_path: &str,
+    conflict: &str,
+    file_size: u64,
+    py: Python,
+) -> PyResult<u64> {
+    let check_conflicts = match conflict {
+        "newest" => false,
+        "error" => true,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "conflict must be \"newest\" or \"error\", not {conflict:?}"
+            )))
         }
-        .map_err(|e| PyException::new_err(e.to_string()))
+    };
+    if src_paths.is_empty() {
+        return Err(PyValueError::new_err("src_paths must not be empty"));
+    }
+    if file_size == 0 {
+        return Err(PyValueError::new_err("file_size must be positive"));
     }
+    if Path::new(dst_path).exists() {
+        return Err(PyFileExistsError::new_err(format!(
+            "{dst_path} already exists"
+        )));
+    }
+    let dir = TempDir::create("rocksdict-merge")?;
+    let result = merge_into(
+        &src_paths,
+        dst_path,
+        check_conflicts,
+        file_size,
+        dir.path(),
+        py,
+    );
+    drop(dir);
+    if result.is_err() {
+        // do not leave an incomplete merge behind
+        fs::remove_dir_all(dst_path).ok();
+    }
+    result
+}
 
-    /// Retrieves a RocksDB property and casts it to an integer
-    /// (for the current column family).
-    ///
-    /// Full list of properties that return int values could be find
-    /// [here](https://github.com/facebook/rocksdb/blob/08809f5e6cd9cc4bc3958dd4d59457ae78c76660/include/rocksdb/db.h#L654-L689).
-    fn property_int_value(&self, name: &str) -> PyResult<Option<u64>> {
-        let db = self.get_db()?;
-        match &self.column_family {
-            None => db.property_int_value(name),
-            Some(cf) => db.property_int_value_cf(cf, name),
+/// Ingest the sources of `merge_dbs` one after another into `dst_path`,
+/// staging SST files under `dir`.
+fn merge_into(
+    src_paths: &[String],
+    dst_path: &str,
+    check_conflicts: bool,
+    file_size: u64,
+    dir: &Path,
+    py: Python,
+) -> PyResult<u64> {
+    // the merged database is created with the options of the first source
+    let (first_db, options, column_families) = open_read_only(&src_paths[0])?;
+    let mut dst_key_codecs = KeyCodecs::new(options.key_codec());
+    for (name, cf_opt) in &column_families {
+        if cf_opt.key_codec() != dst_key_codecs.default {
+            dst_key_codecs
+                .column_families
+                .insert(name.clone(), cf_opt.key_codec());
+        }
+    }
+    let mut dst_options = options.clone();
+    dst_options.inner_opt.create_if_missing(true);
+    dst_options.inner_opt.create_missing_column_families(true);
+    let (dst_db, _, _, _, _) = Rdict::open_db(
+        dst_path,
+        Some(dst_options),
+        Some(column_families.clone()),
+        &AccessType::read_write(),
+    )?;
+    let dst_db = &dst_db;
+    let mut first = Some((first_db, options, column_families));
+    let mut total = 0;
+    for (index, src_path) in src_paths.iter().enumerate() {
+        let (src_db, _, column_families) = match first.take() {
+            Some(source) => source,
+            None => open_read_only(src_path)?,
+        };
+        for (name, cf_opt) in &column_families {
+            let Some(src_cf) = (unsafe { src_db.cf_handle_unbounded(name) }) else {
+                continue;
+            };
+            let key_codec = cf_opt.key_codec();
+            let dst_cf = match unsafe { dst_db.cf_handle_unbounded(name) } {
+                Some(cf) => {
+                    // keys are compared and copied as is, so both sides need the same order
+                    let dst_key_codec = dst_key_codecs.get(name);
+                    if key_codec != dst_key_codec {
+                        return Err(PyValueError::new_err(format!(
+                            "column family `{name}` of {src_path} uses key codec `{}`, \
+                             but the merged database uses key codec `{}`",
+                            key_codec.name(),
+                            dst_key_codec.name()
+                        )));
+                    }
+                    cf
+                }
+                None => {
+                    dst_db
+                        .create_cf(name, &cf_opt.inner_opt)
+                        .map_err(to_py_err)?;
+                    if key_codec != dst_key_codecs.default {
+                        dst_key_codecs
+                            .column_families
+                            .insert(name.clone(), key_codec);
+                        RocksDictConfig::load(config_file(dst_path))?
+                            .with_key_codecs(&dst_key_codecs)
+                            .save(config_file(dst_path))?;
+                    }
+                    unsafe { dst_db.cf_handle_unbounded(name) }.ok_or_else(|| {
+                        RocksDBError::new_err(format!("column family {name} was not created"))
+                    })?
+                }
+            };
+            let compare = key_codec.compare();
+            if check_conflicts {
+                let common = py
+                    .allow_threads(|| first_common_key(&src_db, &src_cf, dst_db, &dst_cf, compare))
+                    .map_err(to_py_err)?;
+                if let Some(key) = common {
+                    let loads = PyModule::import_bound(py, "pickle")?.getattr("loads")?;
+                    let key = decode_key(py, &key, &loads.unbind(), key_codec)?;
+                    return Err(PyValueError::new_err(format!(
+                        "key {} of column family {name} in {src_path} is already in an earlier source",
+                        key.bind(py).repr()?
+                    )));
+                }
+            }
+            let cf_dir = dir.join(format!("{index}-{name}"));
+            fs::create_dir_all(&cf_dir)?;
+            let sst_opt = &cf_opt.inner_opt;
+            total += py
+                .allow_threads(|| {
+                    let (files, count) = write_snapshot_files(
+                        &src_db, &src_cf, None, None, sst_opt, &cf_dir, file_size,
+                    )?;
+                    if !files.is_empty() {
+                        let mut ingest_opt = IngestExternalFileOptions::default();
+                        ingest_opt.set_move_files(true);
+                        dst_db.ingest_external_file_cf_opts(&dst_cf, &ingest_opt, files)?;
+                    }
+                    Ok::<_, rocksdb::Error>(count)
+                })
+                .map_err(to_py_err)?;
         }
-        .map_err(|e| PyException::new_err(e.to_string()))
     }
+    Ok(total)
+}
 
-    /// The sequence number of the most recent transaction.
-    fn latest_sequence_number(&self) -> PyResult<u64> {
-        Ok(self.get_db()?.latest_sequence_number())
+/// The first key of `src_cf` that is also in `dst_cf`, in key order.
+fn first_common_key(
+    src: &DB,
+    src_cf: &Arc<UnboundColumnFamily>,
+    dst: &DB,
+    dst_cf: &Arc<UnboundColumnFamily>,
+    compare: fn(&[u8], &[u8]) -> Ordering,
+) -> Result<Option<Vec<u8>>, rocksdb::Error> {
+    let mut src_iter = src.raw_iterator_cf(src_cf);
+    let mut dst_iter = dst.raw_iterator_cf(dst_cf);
+    src_iter.seek_to_first();
+    dst_iter.seek_to_first();
+    // leapfrog the two iterators to the next key they may share
+    while let (Some(src_key), Some(dst_key)) = (src_iter.key(), dst_iter.key()) {
+        match compare(src_key, dst_key) {
+            Ordering::Less => src_iter.seek(dst_key),
+            Ordering::Greater => dst_iter.seek(src_key),
+            Ordering::Equal => return Ok(Some(src_key.to_vec())),
+        }
     }
+    src_iter.status()?;
+    dst_iter.status()?;
+    Ok(None)
+}
 
-    /// Returns a list of all table files with their level, start key and end key
-    fn live_files(&self, py: Python) -> PyResult<PyObject> {
-        let db = self.get_db()?;
-        match db.live_files() {
-            Ok(lfs) => {
-                let result = PyList::empty_bound(py);
-                for lf in lfs {
-                    result.append(display_live_file_dict(
-                        lf,
-                        py,
-                        &self.loads,
-                        self.opt_py.raw_mode,
-                    )?)?
+/// Copy a database into a new one with another key codec.
+///
+/// The key codec of a database cannot be changed in place, since it
+/// changes the key order. This opens the database at `src_path`
+/// read-only, and writes each entry of every column family into the
+/// database created at `dst_path`, with the same options except for
+/// the key codec. Int and float keys are re-encoded, other keys and
+/// all values are copied as they are. See `Options.set_key_codec`.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, Options, migrate_key_codec
+///
+///         db = Rdict("./temp_path")
+///         db[2**100] = "big"
+///         db[1.5] = "float"
+///         db.close()
+///         migrate_key_codec("./temp_path", "./temp_ordered", "ordered")
+///         opt = Options()
+///         opt.set_key_codec("ordered")
+///         db = Rdict("./temp_ordered", opt)
+///         assert db[2**100] == "big"
+///
+/// Args:
+///     src_path (str): path of the database to migrate, which is not changed.
+///     dst_path (str): path of the migrated database, which must not exist.
+///     key_codec (str): key codec of the migrated database,
+///         `"ordered"` or `"rocksdict"`.
+///     batch_size (int): number of entries written per batch.
+///
+/// Returns:
+///     the number of entries copied.
+///
+/// Notes:
+///     Raw mode databases have no key codec. Column families with a
+///     key codec of their own are copied as they are. The source can stay open
+///     in this process or another one, but the writes made to it after
+///     the migration starts are not copied. Only the default column of
+///     wide-column entities is copied.
+#[pyfunction]
+#[pyo3(signature = (src_path, dst_path, key_codec = "ordered", batch_size = 10000))]
+pub(crate) fn migrate_key_codec(
+    src_path: &str,
+    dst_path: &str,
+    key_codec: &str,
+    batch_size: usize,
+    py: Python,
+) -> PyResult<u64> {
+    let to = match KeyCodec::from_name(key_codec) {
+        Some(codec @ (KeyCodec::Rocksdict | KeyCodec::Ordered)) => codec,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unknown key codec `{key_codec}`, expected `rocksdict` or `ordered`"
+            )))
+        }
+    };
+    if batch_size == 0 {
+        return Err(PyValueError::new_err("batch_size must be positive"));
+    }
+    if Path::new(dst_path).exists() {
+        return Err(PyFileExistsError::new_err(format!(
+            "{dst_path} already exists"
+        )));
+    }
+    let (src_db, options, column_families) = open_read_only(src_path)?;
+    let from = options.key_codec();
+    if from.is_raw() {
+        return Err(PyValueError::new_err(
+            "raw mode databases have no key codec to migrate",
+        ));
+    }
+    if !matches!(from, KeyCodec::Rocksdict | KeyCodec::Ordered) {
+        return Err(PyValueError::new_err(format!(
+            "{src_path} uses key codec `{}`, which cannot be migrated",
+            from.name()
+        )));
+    }
+    if from == to {
+        return Err(PyValueError::new_err(format!(
+            "{src_path} already uses key codec `{key_codec}`"
+        )));
+    }
+    let mut dst_options = options;
+    dst_options.set_key_codec_inner(to);
+    dst_options.inner_opt.create_if_missing(true);
+    dst_options.inner_opt.create_missing_column_families(true);
+    // column families with a key codec of their own keep it
+    let mut dst_column_families = column_families;
+    let mut names = Vec::with_capacity(dst_column_families.len());
+    for (name, cf_opt) in dst_column_families.iter_mut() {
+        let transcode = cf_opt.key_codec() == from;
+        if transcode {
+            cf_opt.set_key_codec_inner(to);
+        }
+        names.push((name.clone(), transcode));
+    }
+    let (dst_db, _, _, _, _) = Rdict::open_db(
+        dst_path,
+        Some(dst_options),
+        Some(dst_column_families),
+        &AccessType::read_write(),
+    )?;
+    let result = py.allow_threads(|| {
+        let mut total = 0;
+        for (name, transcode) in &names {
+            let Some(src_cf) = (unsafe { src_db.cf_handle_unbounded(name) }) else {
+                continue;
+            };
+            let dst_cf = unsafe { dst_db.cf_handle_unbounded(name) }.ok_or_else(|| {
+                RocksDBError::new_err(format!("column family {name} was not created"))
+            })?;
+            let mut read_opt = ReadOptions::default();
+            read_opt.fill_cache(false);
+            let mut iter = src_db.raw_iterator_cf_opt(&src_cf, read_opt);
+            iter.seek_to_first();
+            let mut batch = WriteBatch::default();
+            while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                if *transcode {
+                    batch.put_cf(&dst_cf, transcode_key(key, from, to)?, value);
+                } else {
+                    batch.put_cf(&dst_cf, key, value);
                 }
-                Ok(result.to_object(py))
+                total += 1;
+                if batch.len() >= batch_size {
+                    dst_db
+                        .write(std::mem::take(&mut batch))
+                        .map_err(to_py_err)?;
+                }
+                iter.next();
             }
-            Err(e) => Err(PyException::new_err(e.to_string())),
+            iter.status().map_err(to_py_err)?;
+            dst_db.write(batch).map_err(to_py_err)?;
         }
+        Ok(total)
+    });
+    if result.is_err() {
+        // do not leave an incomplete migration behind
+        drop(dst_db);
+        fs::remove_dir_all(dst_path).ok();
     }
+    result
+}
 
-    /// Delete the database.
-    ///
-    /// Args:
-    ///     path (str): path to this database
-    ///     options (rocksdict.Options): Rocksdb options object
-    #[staticmethod]
-    #[pyo3(signature = (path, options = OptionsPy::new(false)))]
-    fn destroy(path: &str, options: OptionsPy, py: Python) -> PyResult<()> {
-        let inner_opt = options.inner_opt;
-
-        py.allow_threads(|| {
-            fs::remove_file(config_file(path)).ok();
-            DB::destroy(&inner_opt, path)
-        })
-        .map_err(|e| PyException::new_err(e.to_string()))
+/// Open the database at `path` read-only, returning it with its latest
+/// options and those of each of its column families, default included.
+fn open_read_only(path: &str) -> PyResult<(DB, OptionsPy, HashMap<String, OptionsPy>)> {
+    if !Path::new(path).join("CURRENT").is_file() {
+        return Err(PyFileNotFoundError::new_err(format!(
+            "no database found at {path}"
+        )));
     }
+    let (options, mut column_families) = OptionsPy::load_latest_inner(
+        path,
+        EnvPy::default()?,
+        false,
+        CachePy::new_lru_cache(DEFAULT_LRU_CACHE_SIZE),
+    )?;
+    column_families
+        .entry(DEFAULT_COLUMN_FAMILY_NAME.to_string())
+        .or_insert_with(|| options.clone());
+    let (db, _, _, _, _) = Rdict::open_db(
+        path,
+        Some(options.clone()),
+        Some(column_families.clone()),
+        &AccessType::read_only(false),
+    )?;
+    Ok((db, options, column_families))
+}
 
-    /// Repair the database.
-    ///
-    /// Args:
-    ///     path (str): path to this database
-    ///     options (rocksdict.Options): Rocksdb options object
-    #[staticmethod]
-    #[pyo3(signature = (path, options = OptionsPy::new(false)))]
-    fn repair(path: &str, options: OptionsPy, py: Python) -> PyResult<()> {
-        let inner_opt = options.inner_opt;
+/// Raise if the database at `path` is open in this process,
+/// or if its `LOCK` file is held by another process.
+fn ensure_not_in_use(path: &str) -> PyResult<()> {
+    let db_path = Path::new(path);
+    if is_open_in_process(db_path) {
+        return Err(BusyError::new_err(format!(
+            "database at {path} is still open in this process, close it first"
+        )));
+    }
+    let locked = is_locked_by_other_process(db_path)?;
+    if locked {
+        return Err(BusyError::new_err(format!(
+            "database at {path} is locked by another process"
+        )));
+    }
+    Ok(())
+}
 
-        py.allow_threads(|| DB::repair(&inner_opt, path))
-            .map_err(|e| PyException::new_err(e.to_string()))
+/// Paths of all files in the database directory.
+fn list_db_files(path: &str) -> PyResult<Vec<String>> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.path().is_file() {
+            files.push(entry.path().to_string_lossy().into_owned());
+        }
     }
+    files.sort();
+    Ok(files)
+}
 
-    #[staticmethod]
-    #[pyo3(signature = (path, options = OptionsPy::new(false)))]
-    fn list_cf(path: &str, options: OptionsPy) -> PyResult<Vec<String>> {
-        DB::list_cf(&options.inner_opt, path).map_err(|e| PyException::new_err(e.to_string()))
+/// Drop the column family `name`, or list its live SST files if `dry_run`.
+fn destroy_column_family(
+    path: &str,
+    name: &str,
+    dry_run: bool,
+    py: Python,
+) -> PyResult<Option<Vec<String>>> {
+    if name == DEFAULT_COLUMN_FAMILY_NAME {
+        return Err(PyValueError::new_err(
+            "the default column family cannot be destroyed",
+        ));
     }
+    let access_type = if dry_run {
+        AccessType::read_only(false)
+    } else {
+        AccessType::read_write()
+    };
+    let mut db = Rdict::new(path, None, None, access_type, None, false, py)?;
+    let result = if dry_run {
+        db.get_db().and_then(|db| {
+            let live_files = db.live_files().map_err(to_py_err)?;
+            Ok(Some(
+                live_files
+                    .into_iter()
+                    .filter(|lf| lf.column_family_name == name)
+                    .map(|lf| format!("{}{}", path.trim_end_matches('/'), lf.name))
+                    .collect(),
+            ))
+        })
+    } else {
+        db.drop_column_family(name).map(|_| None)
+    };
+    db.close(py)?;
+    result
 }
 
 fn display_live_file_dict(
     lf: LiveFile,
     py: Python,
     pickle_loads: &PyObject,
-    raw_mode: bool,
+    key_codec: KeyCodec,
 ) -> PyResult<PyObject> {
     let result = PyDict::new_bound(py);
     let start_key = match lf.start_key {
         None => py.None(),
-        Some(k) => decode_value(py, &k, pickle_loads, raw_mode)?,
+        Some(k) => decode_key(py, &k, pickle_loads, key_codec)?,
     };
     let end_key = match lf.end_key {
         None => py.None(),
-        Some(k) => decode_value(py, &k, pickle_loads, raw_mode)?,
+        Some(k) => decode_key(py, &k, pickle_loads, key_codec)?,
     };
     result.set_item("name", lf.name)?;
     result.set_item("size", lf.size)?;
@@ -1371,29 +6697,514 @@ fn display_live_file_dict(
     Ok(result.to_object(py))
 }
 
+/// The bytes of a C-contiguous buffer, with the number and width of its
+/// rows along the first axis.
+fn buffer_rows(array: &Bound<PyAny>) -> PyResult<(PyBuffer<u8>, usize, usize)> {
+    let view = PyMemoryView::from_bound(array)?;
+    let shape: Vec<usize> = view.getattr("shape")?.extract()?;
+    let Some(&count) = shape.first() else {
+        return Err(PyValueError::new_err(
+            "arrays must have at least one dimension",
+        ));
+    };
+    if !view.getattr("c_contiguous")?.is_truthy()? {
+        return Err(PyValueError::new_err("arrays must be C-contiguous"));
+    }
+    let bytes = PyBuffer::<u8>::get_bound(&view.call_method1("cast", ("B",))?)?;
+    let width = if count == 0 {
+        0
+    } else {
+        bytes.len_bytes() / count
+    };
+    Ok((bytes, count, width))
+}
+
+/// An SST file ingested by `Rdict.ingest_external_file`.
+struct IngestedFile {
+    name: Option<String>,
+    level: Option<i32>,
+    global_seqno: u64,
+    flushed: bool,
+}
+
+/// Ingest `paths` into `cf` named `cf_name`, telling the ingested files
+/// apart from the live files before and after the ingestion.
+fn ingest_files(
+    db: &DB,
+    cf: &Arc<UnboundColumnFamily>,
+    cf_name: &str,
+    opts: &IngestExternalFileOptions,
+    paths: &[String],
+) -> Result<Vec<IngestedFile>, rocksdb::Error> {
+    let memtable_entries = || {
+        db.property_int_value_cf(cf, "rocksdb.num-entries-active-mem-table")
+            .map(|entries| entries.unwrap_or(0))
+    };
+    let files_before = db
+        .live_files()?
+        .into_iter()
+        .map(|lf| lf.name)
+        .collect::<HashSet<_>>();
+    let entries_before = memtable_entries()?;
+    let sequence_before = db.latest_sequence_number();
+    db.ingest_external_file_cf_opts(cf, opts, paths.to_vec())?;
+    let sequence_after = db.latest_sequence_number();
+    let flushed = entries_before > 0 && memtable_entries()? < entries_before;
+    let global_seqno = if sequence_after > sequence_before {
+        sequence_after
+    } else {
+        0
+    };
+    // ingested files are numbered in the order of `paths`, before
+    // the file of a flush triggered by the ingestion
+    let mut new_files = db
+        .live_files()?
+        .into_iter()
+        .filter(|lf| lf.column_family_name == cf_name && !files_before.contains(&lf.name))
+        .collect::<Vec<_>>();
+    new_files.sort_by_key(|lf| sst_file_number(&lf.name));
+    let mut new_files = new_files.into_iter();
+    let matched = new_files.len() >= paths.len();
+    Ok(paths
+        .iter()
+        .map(|_| {
+            let file = if matched { new_files.next() } else { None };
+            IngestedFile {
+                level: file.as_ref().map(|lf| lf.level),
+                name: file.map(|lf| lf.name),
+                global_seqno,
+                flushed,
+            }
+        })
+        .collect())
+}
+
+/// Compact the whole key range of `cfs`, `parallel` column families at a time.
+fn compact_column_families(
+    db: &DB,
+    cfs: &[Arc<UnboundColumnFamily>],
+    opt: &CompactOptions,
+    parallel: usize,
+) {
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..parallel.min(cfs.len()) {
+            scope.spawn(|| {
+                while let Some(cf) = cfs.get(next.fetch_add(1, AtomicOrdering::Relaxed)) {
+                    db.compact_range_cf_opt(cf, None::<&[u8]>, None::<&[u8]>, opt);
+                }
+            });
+        }
+    });
+}
+
+/// Number of an SST file named like `/000012.sst`.
+fn sst_file_number(name: &str) -> u64 {
+    name.trim_start_matches('/')
+        .trim_end_matches(".sst")
+        .parse()
+        .unwrap_or(u64::MAX)
+}
+
+/// Ticker counts of a statistics dump, from lines like
+/// `rocksdb.bytes.written COUNT : 42`.
+fn statistics_tickers(dump: &str) -> HashMap<&str, u64> {
+    dump.lines()
+        .filter_map(|line| {
+            let (name, count) = line.split_once(" COUNT : ")?;
+            Some((name, count.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Number of file descriptors of this process opened on files under
+/// `dir`, and how many of them are SST files, or `None` if the open
+/// file descriptors cannot be listed.
+fn open_files_in(dir: &Path) -> Option<(usize, usize)> {
+    let dir = dir.canonicalize().ok()?;
+    let mut all = 0;
+    let mut sst = 0;
+    for entry in std::fs::read_dir("/proc/self/fd").ok()?.flatten() {
+        let Ok(target) = std::fs::read_link(entry.path()) else {
+            continue;
+        };
+        if target.starts_with(&dir) {
+            all += 1;
+            if target.extension().is_some_and(|ext| ext == "sst") {
+                sst += 1;
+            }
+        }
+    }
+    Some((all, sst))
+}
+
+fn ratio(numerator: Option<u64>, denominator: Option<u64>) -> Option<f64> {
+    match (numerator, denominator) {
+        (Some(n), Some(d)) if d > 0 => Some(n as f64 / d as f64),
+        _ => None,
+    }
+}
+
+fn display_wal_file_dict(file: WalFile, py: Python) -> PyResult<PyObject> {
+    let result = PyDict::new_bound(py);
+    result.set_item("name", file.name)?;
+    result.set_item("log_number", file.log_number)?;
+    result.set_item("size", file.size)?;
+    result.set_item("archived", file.archived)?;
+    result.set_item("start_sequence", file.start_sequence)?;
+    Ok(result.to_object(py))
+}
+
+/// Decode the item under the iterator cursor as a `(key, value)` tuple,
+/// returning `None` if the iterator is not valid.
+fn current_item(iter: &RdictIter, py: Python) -> PyResult<Option<PyObject>> {
+    if iter.valid() {
+        Ok(Some((iter.key(py)?, iter.value(py)?).to_object(py)))
+    } else {
+        iter.status()?;
+        Ok(None)
+    }
+}
+
+/// Restart the auto flush timers of `rdicts` stopped by `Rdict.reopen`.
+fn restart_auto_flush(
+    rdicts: &mut [&mut Rdict],
+    timers: Vec<Option<(Duration, bool)>>,
+) -> PyResult<()> {
+    for (rdict, timer) in rdicts.iter_mut().zip(timers) {
+        if let Some((interval, flush_wal)) = timer {
+            rdict.auto_flush = Some(AutoFlush::start(
+                &rdict.db,
+                rdict.column_family.clone(),
+                interval,
+                flush_wal,
+            )?);
+        }
+    }
+    Ok(())
+}
+
+/// Read a DB option from the latest OPTIONS file of the database at `path`.
+fn latest_db_option(path: &Path, name: &str) -> std::io::Result<Option<String>> {
+    Ok(latest_options(path, "DBOptions")?.remove(name))
+}
+
+/// Split a `rocksdict://<path>?<query>` URI into its decoded path and parameters.
+fn parse_uri(uri: &str) -> PyResult<(String, Vec<(String, String)>)> {
+    let rest = uri.strip_prefix("rocksdict://").ok_or_else(|| {
+        PyValueError::new_err(format!("uri should start with rocksdict://, got `{uri}`"))
+    })?;
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let path = percent_decode(path)?;
+    if path.is_empty() {
+        return Err(PyValueError::new_err(format!("uri `{uri}` has no path")));
+    }
+    let params = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let (name, value) = param.split_once('=').ok_or_else(|| {
+                PyValueError::new_err(format!("uri parameter `{param}` has no value"))
+            })?;
+            Ok((percent_decode(name)?, percent_decode(value)?))
+        })
+        .collect::<PyResult<_>>()?;
+    Ok((path, params))
+}
+
+fn percent_decode(s: &str) -> PyResult<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let byte = s
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| {
+                        PyValueError::new_err(format!("invalid percent-encoding in `{s}`"))
+                    })?;
+                decoded.push(byte);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded)
+        .map_err(|_| PyValueError::new_err(format!("`{s}` is not valid utf-8 once decoded")))
+}
+
+fn parse_uri_bool(name: &str, value: &str) -> PyResult<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Ok(true),
+        "false" | "0" | "no" => Ok(false),
+        _ => Err(PyValueError::new_err(format!(
+            "{name} should be true or false, got `{value}`"
+        ))),
+    }
+}
+
+/// The WAL directory of the database at `path`, which defaults to `path`.
+fn wal_dir(path: &Path) -> std::io::Result<PathBuf> {
+    Ok(match latest_db_option(path, "wal_dir")? {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => path.to_path_buf(),
+    })
+}
+
+/// Read the options of a section, such as `DBOptions` or `CFOptions "default"`,
+/// from the latest OPTIONS file of the database at `path`.
+pub(crate) fn latest_options(
+    path: &Path,
+    section: &str,
+) -> std::io::Result<HashMap<String, String>> {
+    let mut latest: Option<(u64, PathBuf)> = None;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let number = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix("OPTIONS-"))
+            .and_then(|number| number.parse::<u64>().ok());
+        if let Some(number) = number {
+            if !matches!(&latest, Some((latest, _)) if *latest >= number) {
+                latest = Some((number, entry.path()));
+            }
+        }
+    }
+    let mut options = HashMap::new();
+    let Some((_, options_file)) = latest else {
+        return Ok(options);
+    };
+    let header = format!("[{section}]");
+    let mut in_section = false;
+    for line in fs::read_to_string(options_file)?.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line == header;
+        } else if in_section {
+            if let Some((name, value)) = line.split_once('=') {
+                options.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Whether `requested`, as passed to `set_options`, is the same value as
+/// `actual` read from an OPTIONS file, which spells out sizes and booleans.
+fn same_option_value(requested: &str, actual: &str) -> bool {
+    let requested = requested.trim();
+    // nested options are written in a different format
+    if requested.eq_ignore_ascii_case(actual) || requested.starts_with('{') {
+        return true;
+    }
+    match (option_number(requested), option_number(actual)) {
+        (Some(requested), Some(actual)) => requested == actual,
+        _ => matches!((requested, actual), ("1", "true") | ("0", "false")),
+    }
+}
+
+/// Parse a number of an option value, with an optional `k`, `m`, `g`, or `t` suffix.
+fn option_number(value: &str) -> Option<f64> {
+    let scale = match value.chars().last()?.to_ascii_lowercase() {
+        'k' => 1u64 << 10,
+        'm' => 1 << 20,
+        'g' => 1 << 30,
+        't' => 1 << 40,
+        _ => return value.parse().ok(),
+    };
+    let number: f64 = value[..value.len() - 1].parse().ok()?;
+    Some(number * scale as f64)
+}
+
+/// Value type of a column family option that can be changed on an open database.
+#[derive(Clone, Copy, PartialEq)]
+enum DynamicOption {
+    Unsigned,
+    Signed,
+    Float,
+    Bool,
+}
+
+impl DynamicOption {
+    /// The `Rdict` method setting options of this type.
+    fn setter(self) -> &'static str {
+        match self {
+            DynamicOption::Unsigned | DynamicOption::Signed => "set_cf_option_int",
+            DynamicOption::Float => "set_cf_option_float",
+            DynamicOption::Bool => "set_cf_option_bool",
+        }
+    }
+}
+
+/// Scalar column family options that RocksDB can change on an open
+/// database, from its `MutableCFOptions`.
+const DYNAMIC_CF_OPTIONS: &[(&str, DynamicOption)] = &[
+    ("write_buffer_size", DynamicOption::Unsigned),
+    ("max_write_buffer_number", DynamicOption::Signed),
+    ("arena_block_size", DynamicOption::Unsigned),
+    ("memtable_huge_page_size", DynamicOption::Unsigned),
+    ("memtable_prefix_bloom_size_ratio", DynamicOption::Float),
+    ("memtable_whole_key_filtering", DynamicOption::Bool),
+    ("memtable_protection_bytes_per_key", DynamicOption::Unsigned),
+    ("memtable_max_range_deletions", DynamicOption::Unsigned),
+    ("max_successive_merges", DynamicOption::Unsigned),
+    ("strict_max_successive_merges", DynamicOption::Bool),
+    ("inplace_update_num_locks", DynamicOption::Unsigned),
+    ("experimental_mempurge_threshold", DynamicOption::Float),
+    ("disable_auto_compactions", DynamicOption::Bool),
+    (
+        "soft_pending_compaction_bytes_limit",
+        DynamicOption::Unsigned,
+    ),
+    (
+        "hard_pending_compaction_bytes_limit",
+        DynamicOption::Unsigned,
+    ),
+    ("level0_file_num_compaction_trigger", DynamicOption::Signed),
+    ("level0_slowdown_writes_trigger", DynamicOption::Signed),
+    ("level0_stop_writes_trigger", DynamicOption::Signed),
+    ("max_compaction_bytes", DynamicOption::Unsigned),
+    ("ignore_max_compaction_bytes_for_input", DynamicOption::Bool),
+    ("target_file_size_base", DynamicOption::Unsigned),
+    ("target_file_size_multiplier", DynamicOption::Signed),
+    ("max_bytes_for_level_base", DynamicOption::Unsigned),
+    ("max_bytes_for_level_multiplier", DynamicOption::Float),
+    ("ttl", DynamicOption::Unsigned),
+    ("periodic_compaction_seconds", DynamicOption::Unsigned),
+    ("preclude_last_level_data_seconds", DynamicOption::Unsigned),
+    ("preserve_internal_time_seconds", DynamicOption::Unsigned),
+    ("bottommost_file_compaction_delay", DynamicOption::Unsigned),
+    ("max_sequential_skip_in_iterations", DynamicOption::Unsigned),
+    ("sample_for_compression", DynamicOption::Unsigned),
+    ("paranoid_file_checks", DynamicOption::Bool),
+    ("paranoid_memory_checks", DynamicOption::Bool),
+    ("check_flush_compaction_key_order", DynamicOption::Bool),
+    ("report_bg_io_stats", DynamicOption::Bool),
+    ("block_protection_bytes_per_key", DynamicOption::Unsigned),
+    ("uncache_aggressiveness", DynamicOption::Unsigned),
+    ("enable_blob_files", DynamicOption::Bool),
+    ("min_blob_size", DynamicOption::Unsigned),
+    ("blob_file_size", DynamicOption::Unsigned),
+    ("enable_blob_garbage_collection", DynamicOption::Bool),
+    ("blob_garbage_collection_age_cutoff", DynamicOption::Float),
+    (
+        "blob_garbage_collection_force_threshold",
+        DynamicOption::Float,
+    ),
+    ("blob_compaction_readahead_size", DynamicOption::Unsigned),
+    ("blob_file_starting_level", DynamicOption::Signed),
+];
+
+/// Check that `name` is a dynamic column family option set by `setter`.
+fn dynamic_cf_option(name: &str, setter: &str) -> PyResult<DynamicOption> {
+    let Some((_, option)) = DYNAMIC_CF_OPTIONS
+        .iter()
+        .find(|(option, _)| *option == name)
+    else {
+        return Err(PyValueError::new_err(format!(
+            "{name} is not a column family option that can be changed on an open database, \
+             set it with Options and reopen the database"
+        )));
+    };
+    if option.setter() != setter {
+        return Err(PyTypeError::new_err(format!(
+            "{name} is set by {} instead of {setter}",
+            option.setter()
+        )));
+    }
+    Ok(*option)
+}
+
+/// Delete the keys of `cf` starting with `prefix` for which `matches(key, value)`
+/// holds, writing a batch every `batch_size` deletes.
+#[allow(clippy::too_many_arguments)]
+fn delete_matching(
+    db: &DB,
+    cf: &Arc<UnboundColumnFamily>,
+    prefix: &[u8],
+    batch_size: usize,
+    write_opt: &WriteOptions,
+    retry_policy: Option<&RetryPolicyPy>,
+    begin_write: impl Fn() -> PyResult<WriteGuard>,
+    mut matches: impl FnMut(&[u8], &[u8]) -> PyResult<bool>,
+) -> PyResult<u64> {
+    let write = |batch: &WriteBatch| {
+        let _writing = begin_write()?;
+        // a failed write has no effect, so each attempt writes a copy of the batch
+        with_retry(retry_policy, || {
+            db.write_opt(WriteBatch::from_data(batch.data()), write_opt)
+        })
+    };
+    let mut read_opt = ReadOptions::default();
+    read_opt.fill_cache(false);
+    read_opt.set_total_order_seek(true);
+    let mut iter = db.raw_iterator_cf_opt(cf, read_opt);
+    let mut batch = WriteBatch::default();
+    let mut deleted = 0;
+    iter.seek(prefix);
+    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+        if !key.starts_with(prefix) {
+            break;
+        }
+        if matches(key, value)? {
+            batch.delete_cf(cf, key);
+            deleted += 1;
+            if batch.len() >= batch_size {
+                write(&batch)?;
+                batch.clear();
+            }
+        }
+        iter.next();
+    }
+    iter.status().map_err(to_py_err)?;
+    if !batch.is_empty() {
+        write(&batch)?;
+    }
+    Ok(deleted)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn get_batch_inner<'a>(
     db: &DB,
     key_list: &Bound<PyList>,
     py: Python<'a>,
     read_opt: &ReadOptions,
     loads: &PyObject,
+    codec: &ValueCodec,
     cf: &Arc<UnboundColumnFamily>,
-    raw_mode: bool,
+    key_codec: KeyCodec,
+    sorted_input: bool,
+    raw_values: bool,
 ) -> PyResult<Bound<'a, PyList>> {
     let keys_py = key_list.iter().collect::<Vec<_>>();
-    let mut keys: Vec<Cow<[u8]>> = Vec::with_capacity(key_list.len());
+    let mut keys: Vec<EncodedBytes> = Vec::with_capacity(key_list.len());
     for key in keys_py.iter() {
-        keys.push(encode_key(key, raw_mode)?);
+        keys.push(encode_key(key, key_codec)?);
     }
-    let values = py.allow_threads(|| db.batched_multi_get_cf_opt(cf, &keys, false, read_opt));
+    let values =
+        py.allow_threads(|| db.batched_multi_get_cf_opt(cf, &keys, sorted_input, read_opt));
     let result = PyList::empty_bound(py);
-    for v in values {
+    for (key, v) in keys.iter().zip(values) {
         match v {
             Ok(value) => match value {
                 None => result.append(py.None())?,
-                Some(slice) => result.append(decode_value(py, slice.as_ref(), loads, raw_mode)?)?,
+                Some(slice) if raw_values => result.append(PyBytes::new_bound(py, &slice))?,
+                Some(slice) => {
+                    let value = codec.decode(key, &slice)?;
+                    result.append(decode_value(py, &value, loads, key_codec.is_raw())?)?
+                }
             },
-            Err(e) => return Err(PyException::new_err(e.to_string())),
+            Err(e) => return Err(to_py_err(e)),
         }
     }
     Ok(result)
@@ -1402,6 +7213,7 @@ fn get_batch_inner<'a>(
 impl Drop for Rdict {
     // flush
     fn drop(&mut self) {
+        drop(self.auto_flush.take());
         if let Some(db) = self.db.get() {
             let f_opt = &self.flush_opt;
             let _ = if let Some(cf) = &self.column_family {
@@ -1410,6 +7222,7 @@ impl Drop for Rdict {
                 db.flush_opt(&f_opt.into())
             };
         }
+        self.iter_pool.clear();
         // important, always drop column families first
         // to ensure that CF handles have shorter life than DB.
         drop(self.column_family.take());
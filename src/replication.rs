@@ -0,0 +1,291 @@
+use crate::db_reference::{DbReference, DbReferenceHolder};
+use crate::exceptions::{CorruptionError, DbClosedError};
+use crate::util::error_message;
+use crate::{ffi_try, ffi_try_impl};
+use core::slice;
+use libc::size_t;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::cmp::Ordering;
+use std::ptr::null;
+
+/// Size of the write batch header: 8 bytes sequence number and 4 bytes count.
+const WRITE_BATCH_HEADER_SIZE: usize = 12;
+
+/// Iterator over the write batches in the WAL of a primary database.
+///
+/// Yields `(sequence_number, batch)` tuples, where `batch` is the
+/// serialized `WriteBatch` starting at `sequence_number`.
+/// Created by `Rdict.ship_updates_since`.
+#[pyclass]
+pub(crate) struct RdictUpdates {
+    /// iterator must keep a reference count of DB to keep DB alive.
+    _db: DbReferenceHolder,
+
+    inner: *mut librocksdb_sys::rocksdb_wal_iterator_t,
+
+    started: bool,
+}
+
+impl RdictUpdates {
+    pub(crate) fn new(db: &DbReferenceHolder, seq_number: u64) -> PyResult<Self> {
+        let db_inner = db
+            .get()
+            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))?
+            .inner();
+        let inner = unsafe {
+            ffi_try!(librocksdb_sys::rocksdb_get_updates_since(
+                db_inner,
+                seq_number,
+                null(),
+            ))
+        };
+        Ok(RdictUpdates {
+            _db: db.with_kind("updates iterator"),
+            inner,
+            started: false,
+        })
+    }
+}
+
+#[pymethods]
+impl RdictUpdates {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<(u64, PyObject)>> {
+        unsafe {
+            if self.started {
+                librocksdb_sys::rocksdb_wal_iter_next(self.inner);
+            } else {
+                self.started = true;
+            }
+            if librocksdb_sys::rocksdb_wal_iter_valid(self.inner) == 0 {
+                ffi_try!(librocksdb_sys::rocksdb_wal_iter_status(self.inner));
+                return Ok(None);
+            }
+            let mut seq_number: u64 = 0;
+            let batch = librocksdb_sys::rocksdb_wal_iter_get_batch(self.inner, &mut seq_number);
+            let mut len: size_t = 0;
+            let data = librocksdb_sys::rocksdb_writebatch_data(batch, &mut len);
+            let bytes = PyBytes::new_bound(py, slice::from_raw_parts(data as *const u8, len));
+            librocksdb_sys::rocksdb_writebatch_destroy(batch);
+            Ok(Some((seq_number, bytes.to_object(py))))
+        }
+    }
+}
+
+impl Drop for RdictUpdates {
+    fn drop(&mut self) {
+        unsafe {
+            librocksdb_sys::rocksdb_wal_iter_destroy(self.inner);
+        }
+    }
+}
+
+unsafe impl Send for RdictUpdates {}
+
+/// Concatenate serialized write batches into a single write batch.
+///
+/// Returns the combined batch and the primary sequence number of
+/// the last update it contains.
+pub(crate) fn combine_batches(batches: &[(u64, Vec<u8>)]) -> PyResult<(Vec<u8>, Option<u64>)> {
+    let mut combined = vec![0u8; WRITE_BATCH_HEADER_SIZE];
+    let mut total_count: u32 = 0;
+    let mut last_seq_number = None;
+    for (seq_number, data) in batches {
+        if data.len() < WRITE_BATCH_HEADER_SIZE {
+            return Err(CorruptionError::new_err(format!(
+                "invalid write batch at sequence number {seq_number}"
+            )));
+        }
+        let count = u32::from_le_bytes(data[8..WRITE_BATCH_HEADER_SIZE].try_into().unwrap());
+        total_count += count;
+        combined.extend_from_slice(&data[WRITE_BATCH_HEADER_SIZE..]);
+        last_seq_number = Some(seq_number + count.max(1) as u64 - 1);
+    }
+    combined[8..WRITE_BATCH_HEADER_SIZE].copy_from_slice(&total_count.to_le_bytes());
+    Ok((combined, last_seq_number))
+}
+
+/// Read a varint32 at `pos` of a serialized write batch, advancing `pos`.
+fn read_varint32(data: &[u8], pos: &mut usize) -> Option<u32> {
+    let mut result: u32 = 0;
+    for shift in (0..35).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        result |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+    }
+    None
+}
+
+/// An update of a serialized write batch.
+struct BatchUpdate<'a> {
+    /// id of the column family, `0` for the default column family.
+    cf_id: u32,
+    tag: u8,
+    /// the key and the value, or the range of a range deletion.
+    slices: [&'a [u8]; 2],
+}
+
+impl BatchUpdate<'_> {
+    /// Whether the update writes `key`, comparing keys with `cmp`.
+    fn writes_key(&self, key: &[u8], cmp: fn(&[u8], &[u8]) -> Ordering) -> bool {
+        match self.tag {
+            // range deletion, column family range deletion
+            0xF | 0xE => {
+                cmp(self.slices[0], key) != Ordering::Greater
+                    && cmp(key, self.slices[1]) == Ordering::Less
+            }
+            _ => self.slices[0] == key,
+        }
+    }
+}
+
+/// Read a length prefixed slice at `pos` of a serialized write batch, advancing `pos`.
+fn read_slice<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    let len = read_varint32(data, pos)? as usize;
+    let end = pos.checked_add(len).filter(|end| *end <= data.len())?;
+    let slice = &data[*pos..end];
+    *pos = end;
+    Some(slice)
+}
+
+/// Parse a serialized write batch, returning its updates in order,
+/// each using one sequence number.
+///
+/// Raises an exception if the batch is malformed, or if the number of
+/// updates does not match the count in its header.
+fn batch_updates(data: &[u8]) -> PyResult<Vec<BatchUpdate>> {
+    let invalid = |reason: &str| CorruptionError::new_err(format!("invalid write batch: {reason}"));
+    if data.len() < WRITE_BATCH_HEADER_SIZE {
+        return Err(invalid("too short"));
+    }
+    let expected = u32::from_le_bytes(data[8..WRITE_BATCH_HEADER_SIZE].try_into().unwrap());
+    let mut updates = Vec::with_capacity(expected as usize);
+    let mut pos = WRITE_BATCH_HEADER_SIZE;
+    while pos < data.len() {
+        let tag = data[pos];
+        pos += 1;
+        // (column family, number of slices, counted as an update)
+        let (has_cf, slices, counted) = match tag {
+            // deletion, single deletion
+            0x0 | 0x7 => (false, 1, true),
+            // value, merge, range deletion, blob index, wide column entity,
+            // value with preferred sequence number
+            0x1 | 0x2 | 0xF | 0x11 | 0x16 | 0x18 => (false, 2, true),
+            // column family deletion, single deletion
+            0x4 | 0x8 => (true, 1, true),
+            // column family value, merge, range deletion, blob index,
+            // wide column entity, value with preferred sequence number
+            0x5 | 0x6 | 0xE | 0x10 | 0x17 | 0x19 => (true, 2, true),
+            // log data, end prepare, commit, rollback
+            0x3 | 0xA | 0xB | 0xC => (false, 1, false),
+            // commit with timestamp: the timestamp, then the xid
+            0x15 => (false, 2, false),
+            // begin prepare, noop, begin persisted prepare, begin unprepare
+            0x9 | 0xD | 0x12 | 0x13 => (false, 0, false),
+            _ => return Err(invalid(&format!("unknown record tag {tag:#x}"))),
+        };
+        let cf_id = if has_cf {
+            read_varint32(data, &mut pos).ok_or_else(|| invalid("truncated"))?
+        } else {
+            0
+        };
+        let mut update = BatchUpdate {
+            cf_id,
+            tag,
+            slices: [&[], &[]],
+        };
+        for i in 0..slices {
+            update.slices[i] = read_slice(data, &mut pos).ok_or_else(|| invalid("truncated"))?;
+        }
+        if counted {
+            updates.push(update);
+        }
+    }
+    if updates.len() != expected as usize {
+        return Err(invalid(&format!(
+            "{} updates, but the header has {expected}",
+            updates.len()
+        )));
+    }
+    Ok(updates)
+}
+
+/// Parse a serialized write batch, returning the ids of the column
+/// families it writes to, besides the default column family.
+///
+/// Raises an exception if the batch is malformed, or if the number of
+/// updates does not match the count in its header.
+pub(crate) fn batch_column_family_ids(data: &[u8]) -> PyResult<Vec<u32>> {
+    let mut cf_ids = Vec::new();
+    for update in batch_updates(data)? {
+        if update.cf_id != 0 && !cf_ids.contains(&update.cf_id) {
+            cf_ids.push(update.cf_id);
+        }
+    }
+    Ok(cf_ids)
+}
+
+/// Sequence number of the latest update of `key` in the column family
+/// `cf_id` since `seq_number`, scanning the WAL.
+///
+/// Keys are compared with `cmp`, the comparator of the column family,
+/// to match range deletions.
+pub(crate) fn latest_key_sequence(
+    db: &DbReference,
+    seq_number: u64,
+    cf_id: u32,
+    key: &[u8],
+    cmp: fn(&[u8], &[u8]) -> Ordering,
+) -> PyResult<Option<u64>> {
+    let inner = unsafe {
+        ffi_try!(librocksdb_sys::rocksdb_get_updates_since(
+            db.inner(),
+            seq_number,
+            null(),
+        ))
+    };
+    let mut latest = None;
+    let result = loop {
+        unsafe {
+            if librocksdb_sys::rocksdb_wal_iter_valid(inner) == 0 {
+                break wal_iter_status(inner);
+            }
+            let mut batch_seq: u64 = 0;
+            let batch = librocksdb_sys::rocksdb_wal_iter_get_batch(inner, &mut batch_seq);
+            let mut len: size_t = 0;
+            let data = librocksdb_sys::rocksdb_writebatch_data(batch, &mut len);
+            let scanned =
+                batch_updates(slice::from_raw_parts(data as *const u8, len)).map(|updates| {
+                    for (i, update) in updates.iter().enumerate() {
+                        let seq = batch_seq + i as u64;
+                        if seq >= seq_number && update.cf_id == cf_id && update.writes_key(key, cmp)
+                        {
+                            latest = Some(seq);
+                        }
+                    }
+                });
+            librocksdb_sys::rocksdb_writebatch_destroy(batch);
+            if let Err(e) = scanned {
+                break Err(e);
+            }
+            librocksdb_sys::rocksdb_wal_iter_next(inner);
+        }
+    };
+    unsafe {
+        librocksdb_sys::rocksdb_wal_iter_destroy(inner);
+    }
+    result.map(|_| latest)
+}
+
+/// Error of a WAL iterator that is no longer valid, if any.
+unsafe fn wal_iter_status(inner: *mut librocksdb_sys::rocksdb_wal_iterator_t) -> PyResult<()> {
+    ffi_try!(librocksdb_sys::rocksdb_wal_iter_status(inner));
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use crate::exceptions::to_py_err;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rocksdb::ErrorKind;
+use std::time::Duration;
+
+/// Retry policy for transient RocksDB errors.
+///
+/// Writes (`put`, `put_entity`, `delete`, `delete_range`, `write`) and
+/// `try_catch_up_with_primary` are retried when RocksDB returns a
+/// `Busy` or `TryAgain` status, or rejects a write with
+/// `WriteOptions.no_slowdown` because of a write stall, with exponential
+/// backoff between attempts. The GIL is released while waiting.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, RetryPolicy
+///
+///         db = Rdict("./temp_retry", retry_policy=RetryPolicy(max_attempts=5))
+///
+/// Args:
+///     max_attempts (int): total number of attempts, including the first one.
+///     initial_backoff (float): seconds to wait before the first retry.
+///     max_backoff (float): maximum seconds to wait between attempts.
+///     backoff_multiplier (float): backoff growth factor after each retry.
+#[pyclass(name = "RetryPolicy")]
+#[derive(Clone)]
+pub(crate) struct RetryPolicyPy {
+    #[pyo3(get)]
+    max_attempts: u32,
+    #[pyo3(get)]
+    initial_backoff: f64,
+    #[pyo3(get)]
+    max_backoff: f64,
+    #[pyo3(get)]
+    backoff_multiplier: f64,
+}
+
+#[pymethods]
+impl RetryPolicyPy {
+    #[new]
+    #[pyo3(signature = (
+        max_attempts = 3,
+        initial_backoff = 0.01,
+        max_backoff = 1.0,
+        backoff_multiplier = 2.0
+    ))]
+    fn new(
+        max_attempts: u32,
+        initial_backoff: f64,
+        max_backoff: f64,
+        backoff_multiplier: f64,
+    ) -> PyResult<Self> {
+        if max_attempts == 0 {
+            return Err(PyValueError::new_err("max_attempts must be at least 1"));
+        }
+        if !(initial_backoff >= 0.0 && max_backoff >= 0.0 && backoff_multiplier >= 1.0) {
+            return Err(PyValueError::new_err(
+                "backoffs must be non-negative and backoff_multiplier must be at least 1",
+            ));
+        }
+        Ok(Self {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+            backoff_multiplier,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RetryPolicy(max_attempts={}, initial_backoff={}, max_backoff={}, backoff_multiplier={})",
+            self.max_attempts, self.initial_backoff, self.max_backoff, self.backoff_multiplier
+        )
+    }
+}
+
+#[inline]
+fn is_transient(e: &rocksdb::Error) -> bool {
+    match e.kind() {
+        ErrorKind::Busy | ErrorKind::TryAgain => true,
+        // writes with `no_slowdown` fail instead of waiting for a write stall
+        ErrorKind::Incomplete => e.as_ref().contains("Write stall"),
+        _ => false,
+    }
+}
+
+/// Run `f`, retrying transient errors according to `policy`.
+///
+/// Without a policy, `f` is run exactly once.
+pub(crate) fn with_retry<T>(
+    policy: Option<&RetryPolicyPy>,
+    mut f: impl FnMut() -> Result<T, rocksdb::Error>,
+) -> PyResult<T> {
+    let policy = match policy {
+        None => return f().map_err(to_py_err),
+        Some(policy) => policy,
+    };
+    let mut backoff = policy.initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                let wait = Duration::from_secs_f64(backoff.min(policy.max_backoff));
+                Python::with_gil(|py| py.allow_threads(|| std::thread::sleep(wait)));
+                backoff *= policy.backoff_multiplier;
+                attempt += 1;
+            }
+            result => return result.map_err(to_py_err),
+        }
+    }
+}
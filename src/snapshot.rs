@@ -1,11 +1,12 @@
-use crate::db_reference::{DbReference, DbReferenceHolder};
-use crate::encoder::{decode_value, encode_key};
-use crate::exceptions::DbClosedError;
+use crate::db_reference::{DbReference, DbReferenceHolder, ReleaseHandle};
+use crate::encoder::{decode_value, encode_key, EncodedBytes, KeyCodec, ValueCodec};
+use crate::exceptions::{to_py_err, DbClosedError, RocksDBError};
 use crate::{Rdict, RdictItems, RdictIter, RdictKeys, RdictValues, ReadOptionsPy};
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::{PyKeyError, PyValueError};
 use pyo3::prelude::*;
-use rocksdb::{ReadOptions, UnboundColumnFamily};
-use std::sync::Arc;
+use pyo3::types::PyList;
+use rocksdb::{ReadOptions, UnboundColumnFamily, DEFAULT_COLUMN_FAMILY_NAME};
+use std::sync::{Arc, Mutex, Weak};
 
 /// A consistent view of the database at the point of creation.
 ///
@@ -31,20 +32,103 @@ use std::sync::Arc;
 ///         # but they are still in the snapshot
 ///         for i in range(100):
 ///             assert snapshot[i] == i
+///         assert snapshot.get([0, 1, 100]) == [0, 1, None]
+///         assert snapshot.get(100, "missing") == "missing"
 ///
 ///         # drop the snapshot
-///         del snapshot, db
+///         del snapshot
 ///
+///         # or release it deterministically at the end of a with block
+///         with db.snapshot() as snapshot:
+///             assert snapshot[0] == 0
+///
+///         db.close()
 ///         Rdict.destroy("tmp")
+///
+/// Notes:
+///     A live snapshot keeps the entries it can see, and the SST files
+///     containing them, from being removed. Release it by `close()` or
+///     a with block instead of waiting for the garbage collector.
 #[pyclass]
 pub struct Snapshot {
-    pub(crate) inner: *const librocksdb_sys::rocksdb_snapshot_t,
-    pub(crate) column_family: Option<Arc<UnboundColumnFamily>>,
+    /// The rocksdb snapshot, shared with the database so that a forced close can release it.
+    state: Arc<SnapshotHandle>,
     pub(crate) pickle_loads: PyObject,
-    pub(crate) read_opt: ReadOptions,
-    // decrease db Rc last
-    pub(crate) db: DbReferenceHolder,
+    pub(crate) codec: ValueCodec,
     pub(crate) raw_mode: bool,
+    pub(crate) key_codec: KeyCodec,
+}
+
+/// A rocksdb snapshot with the references it needs, `None` once released.
+struct SnapshotHandle(Mutex<Option<SnapshotState>>);
+
+struct SnapshotState {
+    column_family: Arc<UnboundColumnFamily>,
+    read_opt: ReadOptions,
+    // release the snapshot last
+    snapshot: Arc<RocksSnapshot>,
+}
+
+/// `Send` implementation for `SnapshotState` is safe, because the snapshot is
+/// immutable and only used while holding the lock of its `SnapshotHandle`.
+unsafe impl Send for SnapshotState {}
+
+/// A rocksdb snapshot, shared by the `Snapshot`s of a `ConsistentView`,
+/// and released with the last of them.
+struct RocksSnapshot {
+    inner: *const librocksdb_sys::rocksdb_snapshot_t,
+    // decrease db Rc last
+    db: DbReferenceHolder,
+}
+
+/// The snapshot is immutable, so it can be shared between threads.
+unsafe impl Send for RocksSnapshot {}
+unsafe impl Sync for RocksSnapshot {}
+
+impl RocksSnapshot {
+    fn new(db: &DbReferenceHolder, kind: &'static str) -> PyResult<Self> {
+        let inner = db
+            .get()
+            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))?
+            .inner();
+        Ok(RocksSnapshot {
+            inner: unsafe { librocksdb_sys::rocksdb_create_snapshot(inner) },
+            db: db.with_kind(kind),
+        })
+    }
+
+    fn sequence_number(&self) -> u64 {
+        unsafe { librocksdb_sys::rocksdb_snapshot_get_sequence_number(self.inner) }
+    }
+}
+
+impl Drop for RocksSnapshot {
+    fn drop(&mut self) {
+        if let Some(db) = self.db.get() {
+            unsafe {
+                librocksdb_sys::rocksdb_release_snapshot(db.inner(), self.inner);
+            }
+        }
+    }
+}
+
+impl SnapshotHandle {
+    /// Run `f` on the snapshot, failing if it is already released.
+    ///
+    /// `f` must not call into Python, which could release the snapshot.
+    fn with_state<R>(&self, f: impl FnOnce(&SnapshotState) -> PyResult<R>) -> PyResult<R> {
+        match &*self.0.lock().unwrap() {
+            Some(state) => f(state),
+            None => Err(DbClosedError::new_err("Snapshot already released")),
+        }
+    }
+}
+
+impl ReleaseHandle for SnapshotHandle {
+    fn release(&self) {
+        let state = self.0.lock().unwrap().take();
+        drop(state);
+    }
 }
 
 #[pymethods]
@@ -60,18 +144,20 @@ impl Snapshot {
             None => ReadOptionsPy::default(py)?,
             Some(opt) => opt.clone(),
         };
-        let opt_pointer = read_opt.to_read_opt(self.raw_mode, py)?;
-        unsafe {
-            set_snapshot(opt_pointer.0, self.inner);
-        }
-        RdictIter::new(
-            &self.db,
-            &self.column_family,
-            read_opt,
-            &self.pickle_loads,
-            self.raw_mode,
-            py,
-        )
+        let opt_pointer = read_opt.to_read_opt(self.key_codec, py)?;
+        self.state.with_state(|state| {
+            unsafe {
+                set_snapshot(opt_pointer.0, state.snapshot.inner);
+            }
+            RdictIter::from_read_opt(
+                &state.snapshot.db,
+                &Some(state.column_family.clone()),
+                opt_pointer,
+                &self.pickle_loads,
+                &self.codec,
+                self.key_codec,
+            )
+        })
     }
 
     /// Iterate through all keys and values pairs.
@@ -82,15 +168,27 @@ impl Snapshot {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     skip: number of entries to skip before the first returned one.
+    ///         Skipping happens in Rust, without decoding and with GIL released.
+    ///     limit: maximum number of entries to return, unlimited if `None`.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, skip = 0, limit = None))]
     fn items(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        skip: usize,
+        limit: Option<usize>,
         py: Python,
     ) -> PyResult<RdictItems> {
-        RdictItems::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictItems::new(
+            self.iter(read_opt, py)?,
+            backwards,
+            from_key,
+            skip,
+            limit,
+            py,
+        )
     }
 
     /// Iterate through all keys.
@@ -101,15 +199,27 @@ impl Snapshot {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     skip: number of entries to skip before the first returned one.
+    ///         Skipping happens in Rust, without decoding and with GIL released.
+    ///     limit: maximum number of entries to return, unlimited if `None`.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, skip = 0, limit = None))]
     fn keys(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        skip: usize,
+        limit: Option<usize>,
         py: Python,
     ) -> PyResult<RdictKeys> {
-        RdictKeys::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictKeys::new(
+            self.iter(read_opt, py)?,
+            backwards,
+            from_key,
+            skip,
+            limit,
+            py,
+        )
     }
 
     /// Iterate through all values.
@@ -120,79 +230,387 @@ impl Snapshot {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     skip: number of entries to skip before the first returned one.
+    ///         Skipping happens in Rust, without decoding and with GIL released.
+    ///     limit: maximum number of entries to return, unlimited if `None`.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, skip = 0, limit = None))]
     fn values(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        skip: usize,
+        limit: Option<usize>,
         py: Python,
     ) -> PyResult<RdictValues> {
-        RdictValues::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictValues::new(
+            self.iter(read_opt, py)?,
+            backwards,
+            from_key,
+            skip,
+            limit,
+            py,
+        )
     }
 
-    /// read from snapshot
+    /// read from snapshot, use list of keys for batch get
     fn __getitem__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
-        let db = self.get_db();
-        let key = encode_key(key, self.raw_mode)?;
-        let value_result = if let Some(cf) = &self.column_family {
-            db.get_pinned_cf_opt(cf, &key[..], &self.read_opt)
-        } else {
-            db.get_pinned_opt(&key[..], &self.read_opt)
+        match self.get(key, None, None, false, py)? {
+            Some(v) => Ok(v),
+            None => Err(PyKeyError::new_err(format!("key {key} not found"))),
+        }
+    }
+
+    /// Get value from key or a list of keys in the snapshot.
+    ///
+    /// Args:
+    ///     key: a single key or list of keys.
+    ///     default: the default value to return if key not found.
+    ///     read_opt: override the read options of the snapshot,
+    ///         must have the same `raw_mode` argument. The snapshot
+    ///         is set on them, so reads still see the snapshot.
+    ///     sorted_input: whether a list of keys is already sorted in key order.
+    ///
+    /// Returns:
+    ///    None or default value if the key does not exist.
+    #[pyo3(signature = (key, default = None, read_opt = None, sorted_input = false))]
+    fn get(
+        &self,
+        key: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        sorted_input: bool,
+        py: Python,
+    ) -> PyResult<Option<PyObject>> {
+        let read_opt = match read_opt {
+            None => None,
+            Some(opt) => Some(opt.to_read_options(self.key_codec, py)?),
         };
-        match value_result {
-            Ok(value) => match value {
-                None => Err(PyException::new_err("key not found")),
-                Some(slice) => decode_value(py, slice.as_ref(), &self.pickle_loads, self.raw_mode),
-            },
-            Err(e) => Err(PyException::new_err(e.to_string())),
+        if let Ok(keys) = key.downcast::<PyList>() {
+            let keys_py = keys.iter().collect::<Vec<_>>();
+            let keys = keys_py
+                .iter()
+                .map(|key| encode_key(key, self.key_codec))
+                .collect::<PyResult<Vec<EncodedBytes>>>()?;
+            // wait for a concurrent release without the GIL
+            let values = py.allow_threads(|| {
+                self.state.with_state(|state| {
+                    let read_opt = state.read_opt_or(read_opt.as_ref());
+                    state
+                        .get_db()?
+                        .batched_multi_get_cf_opt(
+                            &state.column_family,
+                            &keys,
+                            sorted_input,
+                            read_opt,
+                        )
+                        .into_iter()
+                        .map(|value| Ok(value.map_err(to_py_err)?.map(|v| v.to_vec())))
+                        .collect::<PyResult<Vec<_>>>()
+                })
+            })?;
+            let result = PyList::empty_bound(py);
+            for (key, value) in keys.iter().zip(values) {
+                match value {
+                    None => result.append(py.None())?,
+                    Some(value) => result.append(self.load_value(py, key, &value)?)?,
+                }
+            }
+            return Ok(Some(result.to_object(py)));
+        }
+        let key = encode_key(key, self.key_codec)?;
+        let value = self.state.with_state(|state| {
+            let read_opt = state.read_opt_or(read_opt.as_ref());
+            let value = state
+                .get_db()?
+                .get_pinned_cf_opt(&state.column_family, &key[..], read_opt)
+                .map_err(to_py_err)?;
+            Ok(value.map(|v| v.to_vec()))
+        })?;
+        match value {
+            None => Ok(default.map(|default| default.to_object(py))),
+            Some(value) => Ok(Some(self.load_value(py, &key, &value)?)),
+        }
+    }
+
+    /// Release the snapshot and its reference to the database.
+    ///
+    /// Reading the snapshot afterwards raises `DbClosedError`.
+    /// Iterators already created from the snapshot stay valid.
+    fn close(&self) {
+        self.state.release();
+    }
+
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type, _exc_value, _traceback))]
+    fn __exit__(
+        &self,
+        _exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+    ) -> bool {
+        self.state.release();
+        false
+    }
+
+    /// Check if a key may exist in the snapshot without doing any IO.
+    ///
+    /// Args:
+    ///     key: Key to check
+    ///     fetch: also return the value if it is found in memory.
+    ///
+    /// Returns:
+    ///     if `fetch = False`,
+    ///         returning True implies that the key may exist.
+    ///         returning False implies that the key definitely does not exist.
+    ///     if `fetch = True`,
+    ///         returning (True, value) implies that the key is found and definitely exist.
+    ///         returning (False, None) implies that the key definitely does not exist.
+    ///         returning (True,  None) implies that the key may exist.
+    #[pyo3(signature = (key, fetch = false))]
+    fn key_may_exist(&self, key: &Bound<PyAny>, fetch: bool, py: Python) -> PyResult<PyObject> {
+        let key = encode_key(key, self.key_codec)?;
+        let (may, value) = self.state.with_state(|state| {
+            let db = state.get_db()?;
+            if !fetch {
+                let may = db.key_may_exist_cf_opt(&state.column_family, &key[..], &state.read_opt);
+                return Ok((may, None));
+            }
+            let (may, value) =
+                db.key_may_exist_cf_opt_value(&state.column_family, &key[..], &state.read_opt);
+            Ok((may, value.map(|v| v.to_vec())))
+        })?;
+        if !fetch {
+            return Ok(may.to_object(py));
+        }
+        match value {
+            None => Ok((may, py.None()).to_object(py)),
+            Some(dat) => Ok((may, self.load_value(py, &key, &dat)?).to_object(py)),
         }
     }
 }
 
 impl Snapshot {
     pub(crate) fn new(rdict: &Rdict, py: Python) -> PyResult<Self> {
-        let db_inner = rdict
-            .db
-            .get()
-            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))?
-            .inner();
-        let snapshot = unsafe { librocksdb_sys::rocksdb_create_snapshot(db_inner) };
-        let r_opt: ReadOptions = rdict
+        let snapshot = Arc::new(RocksSnapshot::new(&rdict.db, "snapshot")?);
+        let column_family = match &rdict.column_family {
+            None => default_column_family(snapshot.get_db()?)?,
+            Some(cf) => cf.clone(),
+        };
+        let read_opt = rdict
             .read_opt_py
-            .to_read_options(rdict.opt_py.raw_mode, py)?;
+            .to_read_options(rdict.opt_py.key_codec(), py)?;
+        Ok(Self::with_snapshot(
+            snapshot,
+            column_family,
+            read_opt,
+            rdict.loads.clone(),
+            rdict.codec.clone(),
+            rdict.opt_py.key_codec(),
+        ))
+    }
+
+    /// A snapshot of `column_family` reading `snapshot`.
+    fn with_snapshot(
+        snapshot: Arc<RocksSnapshot>,
+        column_family: Arc<UnboundColumnFamily>,
+        read_opt: ReadOptions,
+        pickle_loads: PyObject,
+        codec: ValueCodec,
+        key_codec: KeyCodec,
+    ) -> Self {
         unsafe {
-            set_snapshot(r_opt.inner(), snapshot);
+            set_snapshot(read_opt.inner(), snapshot.inner);
+        }
+        let state = Arc::new(SnapshotHandle(Mutex::new(Some(SnapshotState {
+            column_family,
+            read_opt,
+            snapshot: snapshot.clone(),
+        }))));
+        let release: Weak<dyn ReleaseHandle> = Arc::downgrade(&state);
+        snapshot.db.register_release(release);
+        Snapshot {
+            state,
+            pickle_loads,
+            codec,
+            raw_mode: key_codec.is_raw(),
+            key_codec,
         }
-        Ok(Snapshot {
-            inner: snapshot,
-            column_family: rdict.column_family.clone(),
-            pickle_loads: rdict.loads.clone(),
-            read_opt: r_opt,
-            db: rdict.db.clone(),
-            raw_mode: rdict.opt_py.raw_mode,
-        })
     }
 
-    fn get_db(&self) -> &DbReference {
+    /// Decode a value stored at the encoded `key` with the value codec and the loads function.
+    fn load_value(&self, py: Python, key: &[u8], bytes: &[u8]) -> PyResult<PyObject> {
+        decode_value(
+            py,
+            &self.codec.decode(key, bytes)?,
+            &self.pickle_loads,
+            self.raw_mode,
+        )
+    }
+}
+
+impl RocksSnapshot {
+    fn get_db(&self) -> PyResult<&DbReference> {
         self.db
             .get()
-            .expect("Snapshot should never close its DbReference")
+            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))
     }
 }
 
-impl Drop for Snapshot {
-    fn drop(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_release_snapshot(self.get_db().inner(), self.inner);
+impl SnapshotState {
+    fn get_db(&self) -> PyResult<&DbReference> {
+        self.snapshot.get_db()
+    }
+
+    /// `read_opt` with the snapshot set, or the read options of the snapshot.
+    fn read_opt_or<'a>(&'a self, read_opt: Option<&'a ReadOptions>) -> &'a ReadOptions {
+        match read_opt {
+            Some(read_opt) => {
+                unsafe {
+                    set_snapshot(read_opt.inner(), self.snapshot.inner);
+                }
+                read_opt
+            }
+            None => &self.read_opt,
         }
     }
 }
 
-/// `Send` and `Sync` implementations for `SnapshotWithThreadMode` are safe, because `SnapshotWithThreadMode` is
-/// immutable and can be safely shared between threads.
-unsafe impl Send for Snapshot {}
-unsafe impl Sync for Snapshot {}
+/// Handle of the default column family.
+fn default_column_family(db: &DbReference) -> PyResult<Arc<UnboundColumnFamily>> {
+    unsafe { db.cf_handle_unbounded(DEFAULT_COLUMN_FAMILY_NAME) }
+        .ok_or_else(|| RocksDBError::new_err("default column family not found"))
+}
+
+/// One snapshot of all column families of the database.
+///
+/// Created by `Rdict.consistent_view`. The `Snapshot`s of its column
+/// families share the same RocksDB snapshot, so they all read the
+/// database at the same sequence number, such as records and their
+/// index stored in different column families.
+///
+/// Examples:
+///     ::
+///
+///         from rocksdict import Rdict
+///
+///         db = Rdict("tmp")
+///         index = db.create_column_family("index")
+///         db["record"] = "value"
+///         index["value"] = "record"
+///
+///         with db.consistent_view() as view:
+///             records = view["default"]
+///             for value, key in view["index"].items():
+///                 assert records[key] == value
+///
+/// Notes:
+///     The RocksDB snapshot is released once the view and all the
+///     `Snapshot`s created from it are closed.
+#[pyclass]
+pub struct ConsistentView {
+    state: Arc<ViewHandle>,
+    read_opt: ReadOptionsPy,
+    pickle_loads: PyObject,
+    codec: ValueCodec,
+    key_codec: KeyCodec,
+}
+
+/// The snapshot of a `ConsistentView`, `None` once released.
+struct ViewHandle(Mutex<Option<Arc<RocksSnapshot>>>);
+
+impl ReleaseHandle for ViewHandle {
+    fn release(&self) {
+        let snapshot = self.0.lock().unwrap().take();
+        drop(snapshot);
+    }
+}
+
+#[pymethods]
+impl ConsistentView {
+    /// A `Snapshot` of the column family `name`, reading the same
+    /// RocksDB snapshot as the other column families of the view.
+    ///
+    /// Args:
+    ///     name: name of the column family.
+    #[pyo3(signature = (name = DEFAULT_COLUMN_FAMILY_NAME))]
+    fn column_family(&self, name: &str, py: Python) -> PyResult<Snapshot> {
+        let snapshot = self.snapshot()?;
+        let column_family =
+            unsafe { snapshot.get_db()?.cf_handle_unbounded(name) }.ok_or_else(|| {
+                PyValueError::new_err(format!("column family `{name}` does not exist"))
+            })?;
+        let read_opt = self.read_opt.to_read_options(self.key_codec, py)?;
+        Ok(Snapshot::with_snapshot(
+            snapshot,
+            column_family,
+            read_opt,
+            self.pickle_loads.clone(),
+            self.codec.clone(),
+            self.key_codec,
+        ))
+    }
+
+    /// Same as `column_family(name)`.
+    fn __getitem__(&self, name: &str, py: Python) -> PyResult<Snapshot> {
+        self.column_family(name, py)
+    }
+
+    /// The sequence number the view reads the database at.
+    fn sequence_number(&self) -> PyResult<u64> {
+        Ok(self.snapshot()?.sequence_number())
+    }
+
+    /// Release the view and its reference to the database.
+    ///
+    /// `Snapshot`s already created from the view stay valid.
+    fn close(&self) {
+        self.state.release();
+    }
+
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type, _exc_value, _traceback))]
+    fn __exit__(
+        &self,
+        _exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+    ) -> bool {
+        self.state.release();
+        false
+    }
+}
+
+impl ConsistentView {
+    pub(crate) fn new(rdict: &Rdict) -> PyResult<Self> {
+        let snapshot = Arc::new(RocksSnapshot::new(&rdict.db, "consistent view")?);
+        let state = Arc::new(ViewHandle(Mutex::new(Some(snapshot.clone()))));
+        let release: Weak<dyn ReleaseHandle> = Arc::downgrade(&state);
+        snapshot.db.register_release(release);
+        Ok(ConsistentView {
+            state,
+            read_opt: rdict.read_opt_py.clone(),
+            pickle_loads: rdict.loads.clone(),
+            codec: rdict.codec.clone(),
+            key_codec: rdict.opt_py.key_codec(),
+        })
+    }
+
+    fn snapshot(&self) -> PyResult<Arc<RocksSnapshot>> {
+        self.state
+            .0
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| DbClosedError::new_err("ConsistentView already released"))
+    }
+}
 
 #[inline]
 pub(crate) unsafe fn set_snapshot(
@@ -1,18 +1,38 @@
-use crate::encoder::{encode_key, encode_value};
+use crate::encoder::{encode_key, encode_value, KeyCodec, ValueCodec};
+use crate::options::{BlockBasedOptionsPy, DBCompressionTypePy};
 use crate::util::{error_message, to_cpath};
 use crate::{ffi_try, ffi_try_impl, OptionsPy};
 use libc::{self, c_char, size_t};
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::PyResult;
-use rocksdb::Options;
+use rocksdb::{BlockBasedOptions, ChecksumType, DBCompressionType, Options};
+use std::cmp::Ordering;
 use std::ffi::CString;
+use std::path::PathBuf;
 
 /// SstFileWriter is used to create sst files that can be added to database later
 /// All keys in files generated by SstFileWriter will have sequence number = 0.
 ///
 /// Args:
-///     options: this options must have the same `raw_mode` as the Rdict DB.
+///     options: this options must have the same `raw_mode`, key codec
+///         and value checksums as the Rdict DB.
+///     compat: `"rocksdb-7"` or `"legacy"` to write files readable by
+///         older RocksDB versions and bindings, see `SstFileWriter.__init__`.
+///     table_options: BlockBasedOptions of the generated files.
+///     compression: compression type of the generated files.
+///     block_size: approximate size of the data blocks of the generated files.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import SstFileWriter, Options, DBCompressionType
+///
+///         # bulk generated files are compacted harder than the serving DB
+///         writer = SstFileWriter(Options(),
+///                                compression=DBCompressionType.zstd(),
+///                                block_size=64 * 1024)
+///         writer.open("./bulk.sst")
 #[pyclass(name = "SstFileWriter")]
 #[allow(dead_code)]
 pub struct SstFileWriterPy {
@@ -20,6 +40,9 @@ pub struct SstFileWriterPy {
     opts: Options,
     dumps: PyObject,
     raw_mode: bool,
+    key_codec: KeyCodec,
+    codec: ValueCodec,
+    strict_types: bool,
 }
 
 unsafe impl Send for SstFileWriterPy {}
@@ -48,23 +71,106 @@ impl Default for EnvOptions {
 impl SstFileWriterPy {
     /// Initializes SstFileWriter with given DB options.
     ///
+    /// The table and compression settings default to those of `options`,
+    /// and can be set for the generated files only by `table_options`,
+    /// `compression` and `block_size`, without changing the options of
+    /// the database that ingests them.
+    ///
     /// Args:
-    ///     options: this options must have the same `raw_mode` as the Rdict DB.
+    ///     options: this options must have the same `raw_mode`, key codec
+    ///         and value checksums as the Rdict DB. Values are written with
+    ///         checksums if set by `Options.set_value_checksums`.
+    ///     compat: constrain the file format so that the generated files
+    ///         can be ingested by other RocksDB versions and bindings.
+    ///         `"rocksdb-7"` writes format_version 5 with crc32c checksums
+    ///         and snappy compression (RocksDB >= 6.6), and `"legacy"` writes
+    ///         format_version 2 with crc32c checksums and no compression
+    ///         (RocksDB >= 3.10, whatever compression libraries are built in).
+    ///     table_options: BlockBasedOptions used instead of the table
+    ///         factory of `options`. Cannot be combined with `compat` or
+    ///         `block_size`, which build their own table options.
+    ///     compression: compression type of all generated blocks, which
+    ///         overrides the compression of `options` and of `compat`.
+    ///     block_size: approximate size of the data blocks in bytes.
+    ///
+    /// Notes:
+    ///     With `compat` or `block_size`, the block based table settings
+    ///     of `options` are replaced by the defaults, together with the
+    ///     requested block size, format_version and checksum type.
+    ///     Values are neither compressed nor encrypted, whatever
+    ///     `Rdict.set_value_compression` and `Rdict.set_value_encryption`.
     #[new]
-    #[pyo3(signature = (options = OptionsPy::new(false)))]
-    fn create(options: OptionsPy, py: Python) -> PyResult<Self> {
+    #[pyo3(signature = (
+        options = OptionsPy::new(false),
+        compat = None,
+        table_options = None,
+        compression = None,
+        block_size = None
+    ))]
+    fn create(
+        options: OptionsPy,
+        compat: Option<&str>,
+        table_options: Option<PyRef<BlockBasedOptionsPy>>,
+        compression: Option<PyRef<DBCompressionTypePy>>,
+        block_size: Option<usize>,
+        py: Python,
+    ) -> PyResult<Self> {
         let env_options = EnvOptions::default();
         let raw_mode = options.raw_mode;
-        let options = &options.inner_opt;
-        let writer = Self::create_raw(options, &env_options);
+        let key_codec = options.key_codec();
+        let codec = ValueCodec::new(raw_mode, options.value_checksums);
+        let strict_types = options.strict_types;
+        let mut options = options.inner_opt;
+        let profile = compat.map(CompatProfile::parse).transpose()?;
+        match (table_options, &profile, block_size) {
+            (Some(_), Some(_), _) => {
+                return Err(PyValueError::new_err(
+                    "`compat` cannot be combined with `table_options`",
+                ))
+            }
+            (Some(_), None, Some(_)) => {
+                return Err(PyValueError::new_err(
+                    "set the block size on `table_options` instead of `block_size`",
+                ))
+            }
+            (Some(table_options), None, None) => {
+                options.set_block_based_table_factory(&table_options.0)
+            }
+            (None, None, None) => {}
+            (None, profile, block_size) => {
+                let mut table_opts = BlockBasedOptions::default();
+                if let Some(size) = block_size {
+                    table_opts.set_block_size(size);
+                }
+                if let Some(profile) = profile {
+                    table_opts.set_format_version(profile.format_version);
+                    table_opts.set_checksum_type(ChecksumType::CRC32c);
+                }
+                options.set_block_based_table_factory(&table_opts);
+            }
+        }
+        let compression = match compression {
+            Some(compression) => Some(compression.0),
+            None => profile.map(|profile| profile.compression),
+        };
+        if let Some(compression) = compression {
+            // the writer picks bottommost or per level compression over the default one
+            options.set_compression_type(compression);
+            options.set_bottommost_compression_type(compression);
+            options.set_compression_per_level(&[]);
+        }
+        let writer = Self::create_raw(&options, &env_options);
         let pickle = PyModule::import_bound(py, "pickle")?.to_object(py);
         let pickle_dumps = pickle.getattr(py, "dumps")?;
 
         Ok(Self {
             inner: writer,
-            opts: options.clone(),
+            opts: options,
             dumps: pickle_dumps,
             raw_mode,
+            key_codec,
+            codec,
+            strict_types,
         })
     }
 
@@ -74,7 +180,7 @@ impl SstFileWriterPy {
     }
 
     /// Prepare SstFileWriter to write into file located at "file_path".
-    fn open(&self, path: &str) -> PyResult<()> {
+    fn open(&self, path: PathBuf) -> PyResult<()> {
         let cpath = to_cpath(path)?;
         self.open_raw(&cpath)
     }
@@ -92,17 +198,116 @@ impl SstFileWriterPy {
     /// Adds a Put key with value to currently opened file
     /// REQUIRES: key is after any previously added key according to comparator.
     fn __setitem__(&mut self, key: &Bound<PyAny>, value: &Bound<PyAny>) -> PyResult<()> {
-        let key = encode_key(key, self.raw_mode)?;
-        let value = encode_value(value, &self.dumps, self.raw_mode)?;
+        let key = encode_key(key, self.key_codec)?;
+        let value = self.codec.encode(
+            &key,
+            encode_value(value, &self.dumps, self.raw_mode, self.strict_types)?,
+        )?;
         self.setitem_raw(&key, &value)
     }
 
     /// Adds a deletion key to currently opened file
     /// REQUIRES: key is after any previously added key according to comparator.
     fn __delitem__(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key(key, self.key_codec)?;
         self.delitem_raw(&key)
     }
+
+    /// Add the `(key, value)` pairs of an iterable, sorted by key.
+    ///
+    /// Pairs are encoded in chunks of `chunk_size`, which are written
+    /// without holding the GIL. Keys are checked to be in strictly
+    /// increasing order, and the pairs before an unordered key are
+    /// written before raising.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import SstFileWriter, Options
+    ///
+    ///         writer = SstFileWriter(Options())
+    ///         writer.open("./bulk.sst")
+    ///         writer.write_sorted(((i, i * i) for i in range(1_000_000)),
+    ///                             progress=lambda n: print(f"{n} written"))
+    ///         writer.finish()
+    ///
+    /// Args:
+    ///     pairs: an iterable of `(key, value)` pairs, sorted by key.
+    ///     progress: called with the number of pairs written so far
+    ///         after each chunk.
+    ///     chunk_size: number of pairs per chunk.
+    ///
+    /// Returns:
+    ///     the number of pairs written.
+    ///
+    /// Raises:
+    ///     ValueError: if a key is not after the previous key.
+    #[pyo3(signature = (pairs, progress = None, chunk_size = 10000))]
+    fn write_sorted(
+        &mut self,
+        pairs: &Bound<PyAny>,
+        progress: Option<&Bound<PyAny>>,
+        chunk_size: usize,
+        py: Python,
+    ) -> PyResult<u64> {
+        if chunk_size == 0 {
+            return Err(PyValueError::new_err("chunk_size must be positive"));
+        }
+        let compare = self.key_codec.compare();
+        let mut pairs = pairs.iter()?;
+        let mut chunk: Vec<(Vec<u8>, Vec<u8>)> = Vec::with_capacity(chunk_size);
+        let mut last_key: Option<Vec<u8>> = None;
+        let mut written: u64 = 0;
+        loop {
+            let mut error = None;
+            for pair in pairs.by_ref().take(chunk_size) {
+                let encoded = pair.and_then(|pair| {
+                    let (key, value) = pair.extract::<(Bound<PyAny>, Bound<PyAny>)>()?;
+                    let key = Vec::from(encode_key(&key, self.key_codec)?);
+                    let value =
+                        encode_value(&value, &self.dumps, self.raw_mode, self.strict_types)?;
+                    let value = Vec::from(self.codec.encode(&key, value)?);
+                    Ok((key, value))
+                });
+                let (key, value) = match encoded {
+                    Ok(encoded) => encoded,
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                };
+                let previous = chunk.last().map(|(key, _)| key).or(last_key.as_ref());
+                if let Some(previous) = previous {
+                    if compare(previous, &key) != Ordering::Less {
+                        error = Some(PyValueError::new_err(format!(
+                            "keys must be in strictly increasing order, \
+                             the key at position {} is not",
+                            written + chunk.len() as u64
+                        )));
+                        break;
+                    }
+                }
+                chunk.push((key, value));
+            }
+            if chunk.is_empty() {
+                return error.map_or(Ok(written), Err);
+            }
+            py.allow_threads(|| {
+                chunk
+                    .iter()
+                    .try_for_each(|(key, value)| self.setitem_raw(key, value))
+            })?;
+            written += chunk.len() as u64;
+            last_key = chunk.pop().map(|(key, _)| key);
+            chunk.clear();
+            if let Some(progress) = progress {
+                progress.call1((written,))?;
+            }
+            if let Some(error) = error {
+                return Err(error);
+            }
+        }
+    }
 }
 
 impl SstFileWriterPy {
@@ -168,6 +373,30 @@ impl SstFileWriterPy {
     }
 }
 
+/// Table format and compression readable by a target RocksDB version.
+struct CompatProfile {
+    format_version: i32,
+    compression: DBCompressionType,
+}
+
+impl CompatProfile {
+    fn parse(compat: &str) -> PyResult<Self> {
+        let (format_version, compression) = match compat {
+            "rocksdb-7" => (5, DBCompressionType::Snappy),
+            "legacy" => (2, DBCompressionType::None),
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown compat profile `{compat}`, expected \"rocksdb-7\" or \"legacy\""
+                )))
+            }
+        };
+        Ok(CompatProfile {
+            format_version,
+            compression,
+        })
+    }
+}
+
 impl Drop for SstFileWriterPy {
     fn drop(&mut self) {
         unsafe {
@@ -0,0 +1,1093 @@
+use crate::db_reference::register_open;
+use crate::encoder::{decode_value, encode_key, encode_value, KeyCodec, ValueCodec};
+use crate::exceptions::{to_py_err, DbClosedError, NotSupportedError, TransactionConflictError};
+use crate::options::{OptionsPy, SliceTransformType, WriteOptionsPy};
+use crate::rdict::{config_file, Rdict, RocksDictConfig};
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use rocksdb::{
+    BoundColumnFamily, ErrorKind, MultiThreaded, OptimisticTransactionDB,
+    OptimisticTransactionOptions, ReadOptions, Transaction, TransactionDB, TransactionDBOptions,
+    TransactionOptions, WriteOptions, DEFAULT_COLUMN_FAMILY_NAME,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+type TxnDB = TransactionDB<MultiThreaded>;
+type OptimisticTxnDB = OptimisticTransactionDB<MultiThreaded>;
+
+/// The database of a `TransactionDb`, by concurrency control.
+enum TxnDbInner {
+    /// Transactions lock the keys they write, with the lock
+    /// settings of each transaction taken from the options.
+    Pessimistic(TxnDB, TransactionDbOptionsPy),
+    /// Transactions check for conflicting writes when committing.
+    Optimistic(OptimisticTxnDB),
+}
+
+/// A transaction of a `TxnDbInner`.
+enum TxnInner<'db> {
+    Pessimistic(Transaction<'db, TxnDB>),
+    Optimistic(Transaction<'db, OptimisticTxnDB>),
+}
+
+/// Evaluate `$body` with `$db` bound to the database of either mode.
+macro_rules! match_db {
+    ($inner:expr, $db:ident => $body:expr) => {
+        match $inner {
+            TxnDbInner::Pessimistic($db, _) => $body,
+            TxnDbInner::Optimistic($db) => $body,
+        }
+    };
+}
+
+/// Evaluate `$body` with `$txn` bound to the transaction of either mode.
+macro_rules! match_txn {
+    ($inner:expr, $txn:ident => $body:expr) => {
+        match $inner {
+            TxnInner::Pessimistic($txn) => $body,
+            TxnInner::Optimistic($txn) => $body,
+        }
+    };
+}
+
+impl TxnDbInner {
+    fn cf_handle(&self, name: &str) -> PyResult<Arc<BoundColumnFamily<'_>>> {
+        match_db!(self, db => db.cf_handle(name)).ok_or_else(|| missing_cf_error(name))
+    }
+
+    /// Begin a transaction, taking a snapshot at its start if `set_snapshot`.
+    fn transaction(&self, write_opt: &WriteOptions, set_snapshot: bool) -> TxnInner<'_> {
+        match self {
+            TxnDbInner::Pessimistic(db, txn_db_opt) => {
+                let mut txn_opt = TransactionOptions::from(txn_db_opt);
+                txn_opt.set_snapshot(set_snapshot);
+                TxnInner::Pessimistic(db.transaction_opt(write_opt, &txn_opt))
+            }
+            TxnDbInner::Optimistic(db) => {
+                let mut txn_opt = OptimisticTransactionOptions::default();
+                txn_opt.set_snapshot(set_snapshot);
+                TxnInner::Optimistic(db.transaction_opt(write_opt, &txn_opt))
+            }
+        }
+    }
+}
+
+/// Lock settings of a `TransactionDb`, passed when opening it.
+///
+/// Timeouts are in milliseconds. A transaction waiting longer than
+/// its timeout for a lock raises `TimedOutError`, so that long-running
+/// transactions cannot block each other forever.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import TransactionDb, TransactionDbOptions
+///
+///         txn_db_opt = TransactionDbOptions()
+///         txn_db_opt.txn_lock_timeout = 100
+///         txn_db_opt.deadlock_detect = True
+///         txn_db_opt.expiration = 60_000
+///         db = TransactionDb("./temp_path", txn_db_options=txn_db_opt)
+#[pyclass(name = "TransactionDbOptions")]
+#[derive(Clone, Copy)]
+pub(crate) struct TransactionDbOptionsPy {
+    /// Wait timeout of transactions acquiring a lock.
+    /// No waiting is done if 0, and there is no timeout if negative.
+    ///
+    /// Default: 1000
+    #[pyo3(get, set)]
+    txn_lock_timeout: i64,
+
+    /// Wait timeout of writes outside transactions, such as `TransactionDb.put`,
+    /// acquiring a lock. No waiting is done if 0, and there is no timeout if negative.
+    ///
+    /// Default: 1000
+    #[pyo3(get, set)]
+    default_lock_timeout: i64,
+
+    /// Maximum number of keys locked at the same time per column
+    /// family, beyond which locking a key raises `BusyError`.
+    /// There is no limit if not positive.
+    ///
+    /// Default: -1
+    #[pyo3(get, set)]
+    max_num_locks: i64,
+
+    /// Number of sub-tables of the lock table of each column family,
+    /// each with its own mutex, to lock keys concurrently.
+    ///
+    /// Default: 16
+    #[pyo3(get, set)]
+    num_stripes: usize,
+
+    /// Check whether a transaction would deadlock before waiting for
+    /// a lock, raising `BusyError` instead of waiting until timed out.
+    ///
+    /// Default: false
+    #[pyo3(get, set)]
+    deadlock_detect: bool,
+
+    /// Number of traversals made by deadlock detection.
+    ///
+    /// Default: 50
+    #[pyo3(get, set)]
+    deadlock_detect_depth: i64,
+
+    /// Duration after which a transaction fails to commit, and its
+    /// locks can be taken by other writers, so that a forgotten
+    /// transaction does not hold its locks forever. No expiration if negative.
+    ///
+    /// Default: -1
+    #[pyo3(get, set)]
+    expiration: i64,
+}
+
+#[pymethods]
+impl TransactionDbOptionsPy {
+    #[new]
+    pub fn new() -> Self {
+        TransactionDbOptionsPy {
+            txn_lock_timeout: 1000,
+            default_lock_timeout: 1000,
+            max_num_locks: -1,
+            num_stripes: 16,
+            deadlock_detect: false,
+            deadlock_detect_depth: 50,
+            expiration: -1,
+        }
+    }
+}
+
+impl From<&TransactionDbOptionsPy> for TransactionDBOptions {
+    fn from(opt: &TransactionDbOptionsPy) -> Self {
+        let mut txn_db_opt = TransactionDBOptions::default();
+        txn_db_opt.set_txn_lock_timeout(opt.txn_lock_timeout);
+        txn_db_opt.set_default_lock_timeout(opt.default_lock_timeout);
+        txn_db_opt.set_max_num_locks(opt.max_num_locks);
+        txn_db_opt.set_num_stripes(opt.num_stripes);
+        txn_db_opt
+    }
+}
+
+impl From<&TransactionDbOptionsPy> for TransactionOptions {
+    fn from(opt: &TransactionDbOptionsPy) -> Self {
+        let mut txn_opt = TransactionOptions::default();
+        txn_opt.set_deadlock_detect(opt.deadlock_detect);
+        txn_opt.set_deadlock_detect_depth(opt.deadlock_detect_depth);
+        txn_opt.set_expiration(opt.expiration);
+        txn_opt
+    }
+}
+
+/// A database opened in transactional mode.
+///
+/// Writes go through `Transaction` objects from `begin_transaction()`,
+/// which lock the keys they write, see the writes of each other only once
+/// committed, and commit or roll back atomically across column families.
+/// Writes outside transactions, through `put`, `delete` and item
+/// assignment, are committed one by one.
+///
+/// Column families are referred to by name, and must be passed to
+/// `column_families` when the database is reopened, as for `Rdict`.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import TransactionDb, Options
+///
+///         db = TransactionDb("./temp_path")
+///         db.create_column_family("balances")
+///         db["alice"] = 100
+///         with db.begin_transaction() as txn:
+///             txn["alice"] = txn["alice"] - 30
+///             txn.put("alice", 30, column_family="balances")
+///         assert db["alice"] == 70
+///         assert db.get("alice", column_family="balances") == 30
+///         db.close()
+///
+/// Notes:
+///     Writing a key locked by another transaction waits for the lock,
+///     and raises `TimedOutError` if it is not released in time, or
+///     `BusyError` on a deadlock. See `OptimisticTransactionDb` for
+///     workloads with few conflicts.
+#[pyclass(name = "TransactionDb", subclass)]
+pub(crate) struct TransactionDbPy {
+    /// `None` once closed.
+    db: Option<Arc<TxnDbInner>>,
+    path: String,
+    key_codec: KeyCodec,
+    strict_types: bool,
+    value_checksums: bool,
+    prefix_extractors: HashMap<String, SliceTransformType>,
+    loads: PyObject,
+    dumps: PyObject,
+    codec: ValueCodec,
+}
+
+/// A transaction of a `TransactionDb` or `OptimisticTransactionDb`.
+///
+/// Created by `begin_transaction()`. Reads see the
+/// committed writes of the database and the pending writes of this
+/// transaction. Used as a context manager, the transaction is committed
+/// when the block exits normally, and rolled back if it raises an
+/// exception.
+#[pyclass(name = "Transaction")]
+pub(crate) struct TransactionPy {
+    /// `None` once committed or rolled back, declared first
+    /// to be dropped before the database it borrows.
+    txn: Option<TxnInner<'static>>,
+    db: Arc<TxnDbInner>,
+    write_opt: WriteOptions,
+    /// Whether the transaction has a snapshot, which reads are done at.
+    has_snapshot: bool,
+    /// Whether the transaction wrote or read for update any key.
+    tracked_keys: bool,
+    /// Name set by `set_name`, for two-phase commit.
+    name: Option<String>,
+    key_codec: KeyCodec,
+    strict_types: bool,
+    loads: PyObject,
+    dumps: PyObject,
+    codec: ValueCodec,
+}
+
+/// Begin a transaction of `db`, which must outlive it.
+fn begin(db: &Arc<TxnDbInner>, write_opt: &WriteOptions, set_snapshot: bool) -> TxnInner<'static> {
+    let txn = db.transaction(write_opt, set_snapshot);
+    // SAFETY: the transaction borrows the database, which is kept alive by
+    // the `Arc` stored next to it, and is dropped before that `Arc`.
+    unsafe { std::mem::transmute::<TxnInner<'_>, TxnInner<'static>>(txn) }
+}
+
+fn closed_error() -> PyErr {
+    DbClosedError::new_err("DB instance already closed")
+}
+
+fn finished_error() -> PyErr {
+    PyValueError::new_err("transaction already committed or rolled back")
+}
+
+fn optimistic_2pc_error() -> PyErr {
+    NotSupportedError::new_err("two-phase commit is not supported by OptimisticTransactionDb")
+}
+
+fn missing_cf_error(name: &str) -> PyErr {
+    PyValueError::new_err(format!("column family `{name}` does not exist"))
+}
+
+/// Raise `TransactionConflictError` for the errors of a transaction that can be retried.
+fn conflict_error(e: rocksdb::Error) -> PyErr {
+    match e.kind() {
+        ErrorKind::Busy | ErrorKind::TryAgain => TransactionConflictError::new_err(e.into_string()),
+        _ => to_py_err(e),
+    }
+}
+
+impl TransactionDbPy {
+    /// Open the database at `path`, resolving options as `Rdict` does,
+    /// with pessimistic transactions if `txn_db_options` is given.
+    fn open(
+        path: &str,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+        txn_db_options: Option<TransactionDbOptionsPy>,
+        py: Python,
+    ) -> PyResult<Self> {
+        let pickle = PyModule::import_bound(py, "pickle")?.to_object(py);
+        let (options, cfs, prefix_extractors, key_codecs) =
+            Rdict::prepare_open(path, options, column_families)?;
+        // keys are encoded with the key codec of the database in all column families
+        if let Some(name) = key_codecs.column_families.keys().next() {
+            return Err(NotSupportedError::new_err(format!(
+                "column family `{name}` has its own key codec, which TransactionDb does not support"
+            )));
+        }
+        let db = match txn_db_options {
+            None => OptimisticTxnDB::open_cf_descriptors(&options.inner_opt, path, cfs)
+                .map(TxnDbInner::Optimistic),
+            Some(txn_db_opt) => TxnDB::open_cf_descriptors(
+                &options.inner_opt,
+                &TransactionDBOptions::from(&txn_db_opt),
+                path,
+                cfs,
+            )
+            .map(|db| TxnDbInner::Pessimistic(db, txn_db_opt)),
+        }
+        .map_err(to_py_err)?;
+        Ok(TransactionDbPy {
+            db: Some(register_open(Path::new(path), db)),
+            path: path.to_string(),
+            key_codec: options.key_codec(),
+            strict_types: options.strict_types,
+            value_checksums: options.value_checksums,
+            prefix_extractors,
+            loads: pickle.getattr(py, "loads")?,
+            dumps: pickle.getattr(py, "dumps")?,
+            codec: ValueCodec::new(options.raw_mode, options.value_checksums),
+        })
+    }
+
+    fn get_db(&self) -> PyResult<&Arc<TxnDbInner>> {
+        self.db.as_ref().ok_or_else(closed_error)
+    }
+
+    fn dump_value(&self, key: &[u8], value: &Bound<PyAny>) -> PyResult<Vec<u8>> {
+        Ok(self
+            .codec
+            .encode(
+                key,
+                encode_value(
+                    value,
+                    &self.dumps,
+                    self.key_codec.is_raw(),
+                    self.strict_types,
+                )?,
+            )?
+            .to_vec())
+    }
+
+    fn load_value(&self, py: Python, key: &[u8], value: &[u8]) -> PyResult<PyObject> {
+        let value = self.codec.decode(key, value)?;
+        decode_value(py, &value, &self.loads, self.key_codec.is_raw())
+    }
+}
+
+#[pymethods]
+impl TransactionDbPy {
+    /// Create a new transactional database or open an existing one.
+    ///
+    /// Options and column families are resolved as for `Rdict`.
+    ///
+    /// Args:
+    ///     path: path to the database.
+    ///     options: Rdict Options, read from `path` if not provided.
+    ///     column_families: options of the column families to open,
+    ///         read from `path` if not provided.
+    ///     txn_db_options: lock settings, see `TransactionDbOptions`.
+    #[new]
+    #[pyo3(signature = (path, options = None, column_families = None, txn_db_options = None))]
+    fn new(
+        path: &str,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+        txn_db_options: Option<TransactionDbOptionsPy>,
+        py: Python,
+    ) -> PyResult<Self> {
+        let txn_db_options = txn_db_options.unwrap_or_else(TransactionDbOptionsPy::new);
+        TransactionDbPy::open(path, options, column_families, Some(txn_db_options), py)
+    }
+
+    /// Begin a transaction.
+    ///
+    /// Args:
+    ///     write_opt: write options used to commit the transaction.
+    ///     set_snapshot: take a snapshot when the transaction begins,
+    ///         see `Transaction.set_snapshot`.
+    ///
+    /// Returns:
+    ///     a `Transaction`, to commit or roll back.
+    #[pyo3(signature = (write_opt = None, set_snapshot = false))]
+    fn begin_transaction(
+        &self,
+        write_opt: Option<&WriteOptionsPy>,
+        set_snapshot: bool,
+    ) -> PyResult<TransactionPy> {
+        let db = self.get_db()?.clone();
+        let write_opt: WriteOptions = write_opt.map(Into::into).unwrap_or_default();
+        Ok(TransactionPy {
+            txn: Some(begin(&db, &write_opt, set_snapshot)),
+            db,
+            write_opt,
+            has_snapshot: set_snapshot,
+            tracked_keys: false,
+            name: None,
+            key_codec: self.key_codec,
+            strict_types: self.strict_types,
+            loads: self.loads.clone(),
+            dumps: self.dumps.clone(),
+            codec: self.codec.clone(),
+        })
+    }
+
+    /// Recover the transactions prepared but neither committed nor
+    /// rolled back when the database was last closed, see `Transaction.prepare`.
+    ///
+    /// Recovered transactions keep the locks of the keys they wrote, until
+    /// committed or rolled back. Resolve them all before beginning other
+    /// transactions.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import TransactionDb
+    ///
+    ///         db = TransactionDb("./temp_path")
+    ///         for name, txn in db.prepared_transactions().items():
+    ///             if message_queue_committed(name):
+    ///                 txn.commit()
+    ///             else:
+    ///                 txn.rollback()
+    ///
+    /// Returns:
+    ///     the recovered transactions by name. Each is only returned once,
+    ///     by the first call after opening the database.
+    fn prepared_transactions(&self) -> PyResult<HashMap<String, TransactionPy>> {
+        let db = self.get_db()?.clone();
+        let TxnDbInner::Pessimistic(txn_db, _) = &*db else {
+            return Ok(HashMap::new());
+        };
+        let mut prepared = HashMap::new();
+        for txn in txn_db.prepared_transactions() {
+            let name = String::from_utf8_lossy(&txn.get_name().unwrap_or_default()).into_owned();
+            // SAFETY: as in `begin`, the transaction is stored next to the `Arc` of its database.
+            let txn = unsafe {
+                std::mem::transmute::<TxnInner<'_>, TxnInner<'static>>(TxnInner::Pessimistic(txn))
+            };
+            let txn = TransactionPy {
+                txn: Some(txn),
+                db: db.clone(),
+                write_opt: WriteOptions::default(),
+                has_snapshot: false,
+                tracked_keys: true,
+                name: Some(name.clone()),
+                key_codec: self.key_codec,
+                strict_types: self.strict_types,
+                loads: self.loads.clone(),
+                dumps: self.dumps.clone(),
+                codec: self.codec.clone(),
+            };
+            prepared.insert(name, txn);
+        }
+        Ok(prepared)
+    }
+
+    /// Insert a key value, committed on its own.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     value: the value.
+    ///     column_family: name of the column family, the default one if `None`.
+    #[pyo3(signature = (key, value, column_family = None))]
+    fn put(
+        &self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        column_family: Option<&str>,
+        py: Python,
+    ) -> PyResult<()> {
+        let db = self.get_db()?;
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        let value = self.dump_value(&key, value)?;
+        let name = column_family.unwrap_or(DEFAULT_COLUMN_FAMILY_NAME);
+        py.allow_threads(|| {
+            let cf = db.cf_handle(name)?;
+            match_db!(&**db, db => db.put_cf(&cf, key, value)).map_err(to_py_err)
+        })
+    }
+
+    /// Get the committed value of a key.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     default: the default value to return if key not found.
+    ///     column_family: name of the column family, the default one if `None`.
+    #[pyo3(signature = (key, default = None, column_family = None))]
+    fn get(
+        &self,
+        key: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        column_family: Option<&str>,
+        py: Python,
+    ) -> PyResult<Option<PyObject>> {
+        let db = self.get_db()?;
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        let name = column_family.unwrap_or(DEFAULT_COLUMN_FAMILY_NAME);
+        let value = py.allow_threads(|| {
+            let cf = db.cf_handle(name)?;
+            match_db!(&**db, db => db.get_cf(&cf, &key)).map_err(to_py_err)
+        })?;
+        match value {
+            Some(value) => Ok(Some(self.load_value(py, &key, &value)?)),
+            None => Ok(default.map(|default| default.to_object(py))),
+        }
+    }
+
+    /// Delete a key, committed on its own.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     column_family: name of the column family, the default one if `None`.
+    #[pyo3(signature = (key, column_family = None))]
+    fn delete(&self, key: &Bound<PyAny>, column_family: Option<&str>, py: Python) -> PyResult<()> {
+        let db = self.get_db()?;
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        let name = column_family.unwrap_or(DEFAULT_COLUMN_FAMILY_NAME);
+        py.allow_threads(|| {
+            let cf = db.cf_handle(name)?;
+            match_db!(&**db, db => db.delete_cf(&cf, key)).map_err(to_py_err)
+        })
+    }
+
+    fn __setitem__(&self, key: &Bound<PyAny>, value: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.put(key, value, None, py)
+    }
+
+    fn __getitem__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
+        match self.get(key, None, None, py)? {
+            Some(value) => Ok(value),
+            None => Err(PyKeyError::new_err(format!("key {key} not found"))),
+        }
+    }
+
+    fn __delitem__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.delete(key, None, py)
+    }
+
+    fn __contains__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<bool> {
+        Ok(self.get(key, None, None, py)?.is_some())
+    }
+
+    /// Creates column family with given name and options.
+    ///
+    /// Args:
+    ///     name: name of this column family
+    ///     options: Rdict Options for this column family
+    #[pyo3(signature = (name, options = OptionsPy::new(false)))]
+    fn create_column_family(&mut self, name: &str, options: OptionsPy) -> PyResult<()> {
+        let db = self.get_db()?.clone();
+        if options.raw_mode != self.key_codec.is_raw() {
+            return Err(PyValueError::new_err(format!(
+                "Options should have raw_mode={}",
+                self.key_codec.is_raw()
+            )));
+        }
+        // column families share the key codec of the database
+        if options
+            .key_codec
+            .is_some_and(|key_codec| key_codec != self.key_codec)
+        {
+            return Err(NotSupportedError::new_err(format!(
+                "column families of a TransactionDb use key codec `{}`",
+                self.key_codec.name()
+            )));
+        }
+        let mut options = options;
+        if options.key_codec() != self.key_codec {
+            options.set_key_codec_inner(self.key_codec);
+        }
+        // write slice_transform info into config file
+        if let Some(slice_transform) = options.prefix_extractor {
+            self.prefix_extractors
+                .insert(name.to_string(), slice_transform);
+        }
+        RocksDictConfig::new(self.key_codec.is_raw(), self.prefix_extractors.clone())
+            .with_key_codec(self.key_codec)
+            .with_value_checksums(self.value_checksums)
+            .save(config_file(&self.path))?;
+        match_db!(&*db, db => db.create_cf(name, &options.inner_opt)).map_err(to_py_err)
+    }
+
+    /// Close the database.
+    ///
+    /// Notes:
+    ///     Open transactions keep the database alive until
+    ///     they are committed, rolled back or deleted.
+    fn close(&mut self) {
+        self.db = None;
+    }
+
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type, _exc_value, _traceback))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+    ) -> bool {
+        self.close();
+        false
+    }
+}
+
+/// A database opened in optimistic transactional mode.
+///
+/// Unlike `TransactionDb`, transactions do not lock the keys they write,
+/// but fail to commit with `TransactionConflictError` if another write
+/// to one of those keys was committed since they wrote it, so that they
+/// can be retried. This is cheaper when conflicts are rare.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import OptimisticTransactionDb, TransactionConflictError
+///
+///         db = OptimisticTransactionDb("./temp_path")
+///         db["counter"] = 0
+///         while True:
+///             txn = db.begin_transaction()
+///             txn["counter"] = txn["counter"] + 1
+///             try:
+///                 txn.commit()
+///                 break
+///             except TransactionConflictError:
+///                 continue
+///         db.close()
+#[pyclass(name = "OptimisticTransactionDb", extends = TransactionDbPy)]
+pub(crate) struct OptimisticTransactionDbPy;
+
+#[pymethods]
+impl OptimisticTransactionDbPy {
+    /// Create a new optimistic transactional database or open an existing one.
+    ///
+    /// Options and column families are resolved as for `Rdict`.
+    ///
+    /// Args:
+    ///     path: path to the database.
+    ///     options: Rdict Options, read from `path` if not provided.
+    ///     column_families: options of the column families to open,
+    ///         read from `path` if not provided.
+    #[new]
+    #[pyo3(signature = (path, options = None, column_families = None))]
+    fn new(
+        path: &str,
+        options: Option<OptionsPy>,
+        column_families: Option<HashMap<String, OptionsPy>>,
+        py: Python,
+    ) -> PyResult<(Self, TransactionDbPy)> {
+        Ok((
+            OptimisticTransactionDbPy,
+            TransactionDbPy::open(path, options, column_families, None, py)?,
+        ))
+    }
+}
+
+impl TransactionPy {
+    /// Run `f` on the transaction without the GIL, so that other
+    /// threads can release the locks it waits for.
+    fn with_txn<R: Send>(
+        &mut self,
+        py: Python,
+        f: impl FnOnce(&TxnInner<'static>, &TxnDbInner) -> R + Send,
+    ) -> PyResult<R> {
+        let txn = self.txn.take().ok_or_else(finished_error)?;
+        let db = self.db.clone();
+        let (txn, result) = py.allow_threads(move || {
+            let result = f(&txn, &*db);
+            (txn, result)
+        });
+        self.txn = Some(txn);
+        Ok(result)
+    }
+
+    fn dump_value(&self, key: &[u8], value: &Bound<PyAny>) -> PyResult<Vec<u8>> {
+        Ok(self
+            .codec
+            .encode(
+                key,
+                encode_value(
+                    value,
+                    &self.dumps,
+                    self.key_codec.is_raw(),
+                    self.strict_types,
+                )?,
+            )?
+            .to_vec())
+    }
+
+    /// Decode a value read by the transaction, or return `default` if missing.
+    fn load_value(
+        &self,
+        key: &[u8],
+        value: Option<Vec<u8>>,
+        default: Option<&Bound<PyAny>>,
+        py: Python,
+    ) -> PyResult<Option<PyObject>> {
+        match value {
+            Some(value) => Ok(Some(decode_value(
+                py,
+                &self.codec.decode(key, &value)?,
+                &self.loads,
+                self.key_codec.is_raw(),
+            )?)),
+            None => Ok(default.map(|default| default.to_object(py))),
+        }
+    }
+}
+
+#[pymethods]
+impl TransactionPy {
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    /// Commit on normal exit, roll back if an exception was raised.
+    #[pyo3(signature = (exc_type, _exc_value, _traceback))]
+    fn __exit__(
+        &mut self,
+        exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+        py: Python,
+    ) -> PyResult<bool> {
+        if self.txn.is_some() {
+            match exc_type {
+                None => self.commit(py)?,
+                Some(_) => self.rollback(py)?,
+            }
+        }
+        Ok(false)
+    }
+
+    fn __setitem__(
+        &mut self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        py: Python,
+    ) -> PyResult<()> {
+        self.put(key, value, None, py)
+    }
+
+    fn __getitem__(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
+        match self.get(key, None, None, py)? {
+            Some(value) => Ok(value),
+            None => Err(PyKeyError::new_err(format!("key {key} not found"))),
+        }
+    }
+
+    fn __delitem__(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.delete(key, None, py)
+    }
+
+    fn __contains__(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<bool> {
+        Ok(self.get(key, None, None, py)?.is_some())
+    }
+
+    /// Insert a key value in this transaction, locking the key
+    /// in a pessimistic `TransactionDb`.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     value: the value.
+    ///     column_family: name of the column family, the default one if `None`.
+    ///
+    /// Raises:
+    ///     TimedOutError: if the key is locked by another transaction for too long.
+    ///     BusyError: if waiting for the lock would deadlock.
+    #[pyo3(signature = (key, value, column_family = None))]
+    fn put(
+        &mut self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        column_family: Option<&str>,
+        py: Python,
+    ) -> PyResult<()> {
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        let value = self.dump_value(&key, value)?;
+        let name = column_family.unwrap_or(DEFAULT_COLUMN_FAMILY_NAME);
+        self.with_txn(py, |txn, db| {
+            let cf = db.cf_handle(name)?;
+            match_txn!(txn, txn => txn.put_cf(&cf, key, value)).map_err(to_py_err)
+        })??;
+        self.tracked_keys = true;
+        Ok(())
+    }
+
+    /// Get the value of a key, including the pending writes of this transaction,
+    /// at the snapshot of the transaction if set.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     default: the default value to return if key not found.
+    ///     column_family: name of the column family, the default one if `None`.
+    #[pyo3(signature = (key, default = None, column_family = None))]
+    fn get(
+        &mut self,
+        key: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        column_family: Option<&str>,
+        py: Python,
+    ) -> PyResult<Option<PyObject>> {
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        let name = column_family.unwrap_or(DEFAULT_COLUMN_FAMILY_NAME);
+        let has_snapshot = self.has_snapshot;
+        let value = self.with_txn(py, |txn, db| {
+            let cf = db.cf_handle(name)?;
+            match_txn!(txn, txn => {
+                let snapshot = has_snapshot.then(|| txn.snapshot());
+                let mut read_opt = ReadOptions::default();
+                if let Some(snapshot) = &snapshot {
+                    read_opt.set_snapshot(snapshot);
+                }
+                txn.get_cf_opt(&cf, &key, &read_opt)
+            })
+            .map_err(to_py_err)
+        })??;
+        self.load_value(&key, value, default, py)
+    }
+
+    /// Get the value of a key, and make the transaction conflict with
+    /// other writes to the key, for read-modify-write updates.
+    ///
+    /// In a `TransactionDb`, the key is locked until the transaction ends.
+    /// In an `OptimisticTransactionDb`, committing fails if the key was
+    /// written since it was read. With a snapshot, see `set_snapshot`,
+    /// the value is read at the snapshot, and writes since the snapshot
+    /// are conflicts.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import TransactionDb
+    ///
+    ///         db = TransactionDb("./temp_path")
+    ///         with db.begin_transaction() as txn:
+    ///             balance = txn.get_for_update("alice", 0)
+    ///             txn["alice"] = balance + 10
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     default: the default value to return if key not found.
+    ///     column_family: name of the column family, the default one if `None`.
+    ///     exclusive: lock the key exclusively, instead of allowing other
+    ///         transactions to read it for update, in a `TransactionDb`.
+    ///
+    /// Raises:
+    ///     TransactionConflictError: if the key was written since the snapshot,
+    ///         in a `TransactionDb`.
+    ///     TimedOutError: if the key is locked by another transaction for too long.
+    #[pyo3(signature = (key, default = None, column_family = None, exclusive = true))]
+    fn get_for_update(
+        &mut self,
+        key: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        column_family: Option<&str>,
+        exclusive: bool,
+        py: Python,
+    ) -> PyResult<Option<PyObject>> {
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        let name = column_family.unwrap_or(DEFAULT_COLUMN_FAMILY_NAME);
+        let has_snapshot = self.has_snapshot;
+        let value = self.with_txn(py, |txn, db| {
+            let cf = db.cf_handle(name)?;
+            match_txn!(txn, txn => {
+                let snapshot = has_snapshot.then(|| txn.snapshot());
+                let mut read_opt = ReadOptions::default();
+                if let Some(snapshot) = &snapshot {
+                    read_opt.set_snapshot(snapshot);
+                }
+                txn.get_for_update_cf_opt(&cf, &key, exclusive, &read_opt)
+            })
+            .map_err(conflict_error)
+        })??;
+        self.tracked_keys = true;
+        self.load_value(&key, value, default, py)
+    }
+
+    /// Take a snapshot, at which the transaction reads from then on, and
+    /// after which writes by others to keys it writes or reads for update
+    /// are conflicts.
+    ///
+    /// Without a snapshot, conflicts are checked from the time each key
+    /// is first written or read for update, so that values read before
+    /// may be outdated.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import OptimisticTransactionDb
+    ///
+    ///         db = OptimisticTransactionDb("./temp_path")
+    ///         txn = db.begin_transaction()
+    ///         txn.set_snapshot()
+    ///         total = txn.get("a", 0) + txn.get_for_update("b", 0)
+    ///         txn["total"] = total
+    ///         # raises TransactionConflictError if "b" or "total"
+    ///         # were written since the snapshot
+    ///         txn.commit()
+    ///
+    /// Raises:
+    ///     ValueError: if the transaction already wrote or read for update
+    ///         any key, or set a savepoint, use
+    ///         `begin_transaction(set_snapshot=True)` instead.
+    fn set_snapshot(&mut self) -> PyResult<()> {
+        if self.txn.is_none() {
+            return Err(finished_error());
+        }
+        if self.tracked_keys {
+            return Err(PyValueError::new_err(
+                "set_snapshot must be called before the transaction writes or reads \
+                 for update any key, or use begin_transaction(set_snapshot=True)",
+            ));
+        }
+        // a transaction without keys is replaced by one with a snapshot,
+        // which is equivalent to taking the snapshot in place
+        self.txn = None;
+        let txn = begin(&self.db, &self.write_opt, true);
+        if let (Some(name), TxnInner::Pessimistic(txn)) = (&self.name, &txn) {
+            txn.set_name(name.as_bytes()).map_err(to_py_err)?;
+        }
+        self.txn = Some(txn);
+        self.has_snapshot = true;
+        Ok(())
+    }
+
+    /// Delete a key in this transaction, locking the key
+    /// in a pessimistic `TransactionDb`.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     column_family: name of the column family, the default one if `None`.
+    ///
+    /// Raises:
+    ///     TimedOutError: if the key is locked by another transaction for too long.
+    ///     BusyError: if waiting for the lock would deadlock.
+    #[pyo3(signature = (key, column_family = None))]
+    fn delete(
+        &mut self,
+        key: &Bound<PyAny>,
+        column_family: Option<&str>,
+        py: Python,
+    ) -> PyResult<()> {
+        let key = encode_key(key, self.key_codec)?.to_vec();
+        let name = column_family.unwrap_or(DEFAULT_COLUMN_FAMILY_NAME);
+        self.with_txn(py, |txn, db| {
+            let cf = db.cf_handle(name)?;
+            match_txn!(txn, txn => txn.delete_cf(&cf, key)).map_err(to_py_err)
+        })??;
+        self.tracked_keys = true;
+        Ok(())
+    }
+
+    /// Record the state of the transaction, to undo the writes made
+    /// since with `rollback_to_savepoint`.
+    ///
+    /// Savepoints are nested: each call records a new one, and
+    /// `rollback_to_savepoint` goes back to the most recent one.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import TransactionDb
+    ///
+    ///         db = TransactionDb("./temp_path")
+    ///         with db.begin_transaction() as txn:
+    ///             txn["order"] = "created"
+    ///             txn.set_savepoint()
+    ///             try:
+    ///                 txn["stock"] = reserve_stock()
+    ///             except OutOfStock:
+    ///                 # keep the order, undo the reservation
+    ///                 txn.rollback_to_savepoint()
+    fn set_savepoint(&mut self) -> PyResult<()> {
+        let txn = self.txn.as_ref().ok_or_else(finished_error)?;
+        match_txn!(txn, txn => txn.set_savepoint());
+        // savepoints would be lost by replacing the transaction in `set_snapshot`
+        self.tracked_keys = true;
+        Ok(())
+    }
+
+    /// Undo the writes made since the most recent `set_savepoint`,
+    /// and remove that savepoint.
+    ///
+    /// Raises:
+    ///     RocksDBError: if there is no savepoint left.
+    fn rollback_to_savepoint(&mut self, py: Python) -> PyResult<()> {
+        self.with_txn(
+            py,
+            |txn, _| match_txn!(txn, txn => txn.rollback_to_savepoint()),
+        )?
+        .map_err(to_py_err)
+    }
+
+    /// Name the transaction, which is required to `prepare` it.
+    ///
+    /// Args:
+    ///     name: a name unique among the transactions of the database,
+    ///         to find the transaction by in `TransactionDb.prepared_transactions`.
+    ///
+    /// Raises:
+    ///     RocksDBError: if the name is used by another transaction, or
+    ///         the transaction already has a name.
+    ///     NotSupportedError: in an `OptimisticTransactionDb`.
+    fn set_name(&mut self, name: &str) -> PyResult<()> {
+        match self.txn.as_ref().ok_or_else(finished_error)? {
+            TxnInner::Pessimistic(txn) => txn.set_name(name.as_bytes()).map_err(to_py_err)?,
+            TxnInner::Optimistic(_) => return Err(optimistic_2pc_error()),
+        }
+        self.name = Some(name.to_string());
+        Ok(())
+    }
+
+    /// The name set by `set_name`, or `None`.
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    /// Prepare the transaction for two-phase commit.
+    ///
+    /// The writes of a prepared transaction are persisted in the WAL, so
+    /// that the transaction survives a crash or the database being
+    /// closed, and can then be recovered by name with
+    /// `TransactionDb.prepared_transactions`, and committed or rolled
+    /// back. No more writes can be made in a prepared transaction.
+    ///
+    /// This allows committing a transaction together with an external
+    /// system: prepare it, commit in the external system, then commit it.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import TransactionDb
+    ///
+    ///         db = TransactionDb("./temp_path")
+    ///         txn = db.begin_transaction()
+    ///         txn.set_name("order-42")
+    ///         txn["order-42"] = "paid"
+    ///         txn.prepare()
+    ///         message_queue.publish("order-42")
+    ///         txn.commit()
+    ///
+    /// Notes:
+    ///     Transactions use the default `WRITE_COMMITTED` write policy,
+    ///     which writes to the memtable at commit. The `WRITE_PREPARED`
+    ///     policy is not available through the RocksDB C API.
+    ///
+    /// Raises:
+    ///     RocksDBError: if the transaction has no name.
+    ///     NotSupportedError: in an `OptimisticTransactionDb`.
+    fn prepare(&mut self, py: Python) -> PyResult<()> {
+        if let Some(TxnInner::Optimistic(_)) = &self.txn {
+            return Err(optimistic_2pc_error());
+        }
+        self.with_txn(py, |txn, _| match_txn!(txn, txn => txn.prepare()))?
+            .map_err(to_py_err)?;
+        self.tracked_keys = true;
+        Ok(())
+    }
+
+    /// Atomically write the writes of this transaction to the database,
+    /// and release its locks.
+    ///
+    /// Raises:
+    ///     TransactionConflictError: if the transaction conflicts with a
+    ///         write committed since, in an `OptimisticTransactionDb`.
+    ///         Its writes are discarded, and can be retried in a new transaction.
+    fn commit(&mut self, py: Python) -> PyResult<()> {
+        let txn = self.txn.take().ok_or_else(finished_error)?;
+        py.allow_threads(move || match_txn!(txn, txn => txn.commit()))
+            .map_err(conflict_error)
+    }
+
+    /// Discard the writes of this transaction, and release its locks.
+    fn rollback(&mut self, py: Python) -> PyResult<()> {
+        let txn = self.txn.take().ok_or_else(finished_error)?;
+        py.allow_threads(move || match_txn!(txn, txn => txn.rollback()))
+            .map_err(to_py_err)
+    }
+}
@@ -1,8 +1,12 @@
 use libc::{c_char, c_void};
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::{PyException, PyValueError};
 use pyo3::PyResult;
 use std::ffi::{CStr, CString};
-use std::path::Path;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[macro_export]
 macro_rules! ffi_try {
@@ -21,7 +25,7 @@ macro_rules! ffi_try_impl {
         let mut err: *mut ::libc::c_char = ::std::ptr::null_mut();
         let result = $($function)::*($($arg,)* &mut err);
         if !err.is_null() {
-            return Err(PyException::new_err(error_message(err)));
+            return Err($crate::exceptions::status_to_py_err(error_message(err)));
         }
         result
     }};
@@ -41,10 +45,66 @@ pub(crate) unsafe fn from_cstr(ptr: *const c_char) -> String {
 }
 
 pub(crate) fn to_cpath<P: AsRef<Path>>(path: P) -> PyResult<CString> {
-    match CString::new(path.as_ref().to_string_lossy().as_bytes()) {
+    match CString::new(path_bytes(path.as_ref())?) {
         Ok(c) => Ok(c),
         Err(e) => Err(PyException::new_err(format!(
             "Failed to convert path to CString: {e}",
         ))),
     }
 }
+
+/// Bytes of a path as passed to RocksDB: the native bytes on unix,
+/// which need not be valid unicode, and UTF-8 elsewhere.
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> PyResult<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> PyResult<&[u8]> {
+    path_to_str(path).map(str::as_bytes)
+}
+
+/// Borrow a path as a str, failing instead of replacing invalid characters.
+pub(crate) fn path_to_str(path: &Path) -> PyResult<&str> {
+    path.to_str().ok_or_else(|| {
+        PyValueError::new_err(format!("path `{}` is not valid unicode", path.display()))
+    })
+}
+
+/// A directory that is removed when dropped.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create an empty directory named after `prefix` in the system temporary directory.
+    pub(crate) fn create(prefix: &str) -> io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let dir = std::env::temp_dir().join(format!(
+            "{prefix}-{}-{nanos}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&dir)?;
+        Ok(TempDir(dir))
+    }
+
+    /// Remove the existing directory `path` when dropped.
+    pub(crate) fn from_path(path: PathBuf) -> Self {
+        TempDir(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
@@ -0,0 +1,71 @@
+use rocksdb::compaction_filter::{CompactionFilter, Decision};
+use rocksdb::compaction_filter_factory::{CompactionFilterContext, CompactionFilterFactory};
+use std::ffi::CStr;
+
+/// Length of the version suffix of versioned keys, an inverted
+/// big-endian timestamp so that newer versions sort first.
+const VERSION_LEN: usize = 8;
+
+const FACTORY_NAME: &[u8] = b"rocksdict.VersionHistoryFactory\0";
+const FILTER_NAME: &[u8] = b"rocksdict.VersionHistory\0";
+
+/// Create a `VersionHistoryFilter` for each compaction.
+///
+/// Installed by `Options.set_version_history`.
+pub(crate) struct VersionHistoryFactory {
+    pub(crate) max_versions: usize,
+}
+
+impl CompactionFilterFactory for VersionHistoryFactory {
+    type Filter = VersionHistoryFilter;
+
+    fn create(&mut self, _context: CompactionFilterContext) -> Self::Filter {
+        VersionHistoryFilter {
+            max_versions: self.max_versions,
+            key: Vec::new(),
+            versions: 0,
+        }
+    }
+
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(FACTORY_NAME).unwrap()
+    }
+}
+
+/// Drop versions of a key past the newest `max_versions`.
+///
+/// Keys are visited in order within a compaction, so the versions of a
+/// key are adjacent, newest first. A compaction may not see all versions
+/// of a key, and only drops versions with at least `max_versions` newer
+/// versions in the same compaction, so it never drops too many.
+pub(crate) struct VersionHistoryFilter {
+    max_versions: usize,
+    /// key of the versions counted, without the version suffix.
+    key: Vec<u8>,
+    versions: usize,
+}
+
+impl CompactionFilter for VersionHistoryFilter {
+    fn filter(&mut self, _level: u32, key: &[u8], _value: &[u8]) -> Decision {
+        // keys without a version suffix are not versioned
+        let Some(user_key) = key.len().checked_sub(VERSION_LEN).map(|len| &key[..len]) else {
+            return Decision::Keep;
+        };
+        if user_key == self.key.as_slice() {
+            self.versions += 1;
+        } else {
+            self.key.clear();
+            self.key.extend_from_slice(user_key);
+            self.versions = 1;
+        }
+        if self.versions > self.max_versions {
+            Decision::Remove
+        } else {
+            Decision::Keep
+        }
+    }
+
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(FILTER_NAME).unwrap()
+    }
+}
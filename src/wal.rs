@@ -0,0 +1,174 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Size of a WAL block, records do not span blocks unless fragmented.
+const BLOCK_SIZE: usize = 32 * 1024;
+
+/// Size of a record header: crc (4 bytes), length (2 bytes) and type (1 byte).
+const HEADER_SIZE: usize = 7;
+
+/// Recyclable records also store the log number (4 bytes) in the header.
+const RECYCLABLE_HEADER_SIZE: usize = HEADER_SIZE + 4;
+
+/// Name of the directory in the WAL directory holding archived WAL files.
+const ARCHIVE_DIR: &str = "archive";
+
+/// A WAL file, as returned by `Rdict.get_sorted_wal_files`.
+pub(crate) struct WalFile {
+    /// path relative to the WAL directory, such as `/archive/000012.log`.
+    pub(crate) name: String,
+    pub(crate) log_number: u64,
+    pub(crate) size: u64,
+    pub(crate) archived: bool,
+    /// sequence number of the first write batch, `None` if the file is
+    /// empty or compressed.
+    pub(crate) start_sequence: Option<u64>,
+    /// whether write batches are compressed, and their sequence numbers unknown.
+    compressed: bool,
+}
+
+/// List the live and archived WAL files in `wal_dir`, sorted by log number.
+pub(crate) fn sorted_wal_files(wal_dir: &Path) -> io::Result<Vec<WalFile>> {
+    let mut files = list_wal_files(wal_dir, false)?;
+    files.extend(list_wal_files(&wal_dir.join(ARCHIVE_DIR), true)?);
+    files.sort_by_key(|f| f.log_number);
+    Ok(files)
+}
+
+/// Delete the archived WAL files in `wal_dir` only holding updates with
+/// sequence numbers before `up_to_seq`, returning the deleted files.
+///
+/// The updates of a file end before the start sequence of the next file,
+/// or at `latest_sequence` for the last file. Files followed by
+/// compressed files are never deleted.
+pub(crate) fn purge_wal_files(
+    wal_dir: &Path,
+    up_to_seq: u64,
+    latest_sequence: u64,
+) -> io::Result<Vec<WalFile>> {
+    let files = sorted_wal_files(wal_dir)?;
+    let mut purged = Vec::new();
+    let mut next_start = Some(latest_sequence + 1);
+    for file in files.into_iter().rev() {
+        let (start_sequence, compressed) = (file.start_sequence, file.compressed);
+        if file.archived && matches!(next_start, Some(next) if next <= up_to_seq) {
+            match fs::remove_file(wal_dir.join(file.name.trim_start_matches('/'))) {
+                Ok(()) => purged.push(file),
+                // already removed by the TTL or size limit of the WAL archive
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        // empty files do not hold any update
+        if start_sequence.is_some() || compressed {
+            next_start = start_sequence;
+        }
+    }
+    purged.reverse();
+    Ok(purged)
+}
+
+/// Delete the live WAL files in `wal_dir`, keeping archived files,
+/// so that opening the database does not replay them.
+pub(crate) fn delete_live_wal_files(wal_dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(wal_dir)? {
+        let entry = entry?;
+        let is_wal = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_suffix(".log"))
+            .is_some_and(|number| number.parse::<u64>().is_ok());
+        if is_wal && entry.file_type()?.is_file() {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+fn list_wal_files(dir: &Path, archived: bool) -> io::Result<Vec<WalFile>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if archived && e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(log_number) = file_name
+            .to_str()
+            .and_then(|name| name.strip_suffix(".log"))
+            .and_then(|number| number.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        // files are moved to the archive or deleted while listing
+        let (size, start_sequence, compressed) = match read_wal_file(&entry.path()) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let name = if archived {
+            format!("/{ARCHIVE_DIR}/{}", file_name.to_string_lossy())
+        } else {
+            format!("/{}", file_name.to_string_lossy())
+        };
+        files.push(WalFile {
+            name,
+            log_number,
+            size,
+            archived,
+            start_sequence,
+            compressed,
+        });
+    }
+    Ok(files)
+}
+
+/// Read the size, the start sequence and whether a WAL file is compressed.
+fn read_wal_file(path: &Path) -> io::Result<(u64, Option<u64>, bool)> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut block = Vec::with_capacity(BLOCK_SIZE);
+    file.take(BLOCK_SIZE as u64).read_to_end(&mut block)?;
+    Ok(match start_sequence(&block) {
+        Err(Compressed) => (size, None, true),
+        Ok(start_sequence) => (size, start_sequence, false),
+    })
+}
+
+/// Write batches of a WAL file are compressed.
+struct Compressed;
+
+/// Find the sequence number of the first write batch in the first block
+/// of a WAL file, skipping metadata records.
+fn start_sequence(block: &[u8]) -> Result<Option<u64>, Compressed> {
+    let mut offset = 0;
+    while offset + HEADER_SIZE <= block.len() {
+        let length = u16::from_le_bytes([block[offset + 4], block[offset + 5]]) as usize;
+        let record_type = block[offset + 6];
+        let header_size = match record_type {
+            5..=8 | 11 | 131 => RECYCLABLE_HEADER_SIZE,
+            _ => HEADER_SIZE,
+        };
+        let Some(payload) = block.get(offset + header_size..offset + header_size + length) else {
+            // truncated while being written
+            return Ok(None);
+        };
+        match record_type {
+            // zero type is padding or preallocated space
+            0 => return Ok(None),
+            // full or first fragment of a write batch, starting with its sequence number
+            1 | 2 | 5 | 6 => {
+                return Ok(payload
+                    .get(..8)
+                    .map(|seq| u64::from_le_bytes(seq.try_into().unwrap())))
+            }
+            // write batches following a compression type record are compressed
+            9 if payload.iter().take(4).any(|b| *b != 0) => return Err(Compressed),
+            _ => offset += header_size + length,
+        }
+    }
+    Ok(None)
+}
@@ -1,6 +1,9 @@
-use crate::encoder::{encode_key, encode_value};
+use crate::encoder::{
+    encode_key, encode_value, max_encoded_key, EncodedBytes, KeyCodec, ValueCodec,
+};
+use crate::exceptions::to_py_err;
 use crate::ColumnFamilyPy;
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::{PyException, PyValueError};
 use pyo3::prelude::*;
 use rocksdb::WriteBatch;
 
@@ -34,12 +37,23 @@ macro_rules! inner_mut {
 ///
 /// Args:
 ///     raw_mode (bool): make sure that this is consistent with the Rdict.
+///     strict_types (bool): raise `TypeError` instead of pickling values,
+///         see `Options.set_strict_types`.
+///     key_codec (str): key codec of the Rdict in non-raw mode,
+///         see `Options.set_key_codec`. To write into a column family
+///         with its own key codec, use that codec and write the batch
+///         with the column family Rdict.
+///     value_checksums (bool): append checksums to values in non-raw mode,
+///         required by an Rdict with `Options.set_value_checksums`.
 #[pyclass(name = "WriteBatch")]
 pub(crate) struct WriteBatchPy {
     inner: Option<WriteBatch>,
     default_column_family: Option<ColumnFamilyPy>,
     dumps: PyObject,
     pub(crate) raw_mode: bool,
+    pub(crate) key_codec: KeyCodec,
+    pub(crate) codec: ValueCodec,
+    strict_types: bool,
 }
 
 #[pymethods]
@@ -52,15 +66,53 @@ impl WriteBatchPy {
     ///
     /// Args:
     ///     raw_mode (bool): make sure that this is consistent with the Rdict.
+    ///     strict_types (bool): raise `TypeError` instead of pickling values,
+    ///         see `Options.set_strict_types`.
+    ///     key_codec (str): key codec of the Rdict in non-raw mode,
+    ///         see `Options.set_key_codec`. To write into a column family
+    ///         with its own key codec, use that codec and write the batch
+    ///         with the column family Rdict.
+    ///     value_checksums (bool): append checksums to values in non-raw mode,
+    ///         required by an Rdict with `Options.set_value_checksums`.
+    ///
+    /// Notes:
+    ///     Values are neither compressed nor encrypted, whatever
+    ///     `Rdict.set_value_compression` and `Rdict.set_value_encryption`,
+    ///     and the names and values of wide columns have no checksums.
     #[new]
-    #[pyo3(signature = (raw_mode = false))]
-    pub fn default(py: Python, raw_mode: bool) -> PyResult<Self> {
+    #[pyo3(signature = (
+        raw_mode = false,
+        strict_types = false,
+        key_codec = None,
+        value_checksums = false
+    ))]
+    pub fn default(
+        py: Python,
+        raw_mode: bool,
+        strict_types: bool,
+        key_codec: Option<&str>,
+        value_checksums: bool,
+    ) -> PyResult<Self> {
+        let key_codec = match key_codec {
+            None => KeyCodec::from(raw_mode),
+            Some(name) => match KeyCodec::from_name(name) {
+                Some(codec) if codec.is_raw() == raw_mode => codec,
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "key codec `{name}` is not supported with raw_mode={raw_mode}"
+                    )))
+                }
+            },
+        };
         let pickle = PyModule::import_bound(py, "pickle")?.to_object(py);
         Ok(WriteBatchPy {
             inner: Some(WriteBatch::default()),
             default_column_family: None,
             dumps: pickle.getattr(py, "dumps")?,
             raw_mode,
+            key_codec,
+            codec: ValueCodec::new(raw_mode, value_checksums),
+            strict_types,
         })
     }
 
@@ -75,8 +127,11 @@ impl WriteBatchPy {
 
     pub fn __setitem__(&mut self, key: &Bound<PyAny>, value: &Bound<PyAny>) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
-        let value = encode_value(value, &self.dumps, self.raw_mode)?;
+        let key = encode_key(key, self.key_codec)?;
+        let value = self.codec.encode(
+            &key,
+            encode_value(value, &self.dumps, self.raw_mode, self.strict_types)?,
+        )?;
         match &self.default_column_family {
             None => inner.put(key, value),
             Some(cf) => inner.put_cf(&cf.cf, key, value),
@@ -86,7 +141,7 @@ impl WriteBatchPy {
 
     pub fn __delitem__(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key(key, self.key_codec)?;
         match &self.default_column_family {
             None => inner.delete(key),
             Some(cf) => inner.delete_cf(&cf.cf, key),
@@ -139,8 +194,11 @@ impl WriteBatchPy {
         column_family: Option<ColumnFamilyPy>,
     ) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
-        let value = encode_value(value, &self.dumps, self.raw_mode)?;
+        let key = encode_key(key, self.key_codec)?;
+        let value = self.codec.encode(
+            &key,
+            encode_value(value, &self.dumps, self.raw_mode, self.strict_types)?,
+        )?;
         match column_family {
             Some(cf) => inner.put_cf(&cf.cf, key, value),
             None => inner.put(key, value),
@@ -165,7 +223,7 @@ impl WriteBatchPy {
         values: Vec<Bound<PyAny>>,
     ) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key(key, self.key_codec)?;
         let cf = if let Some(cf) = &self.default_column_family {
             cf
         } else {
@@ -181,14 +239,24 @@ impl WriteBatchPy {
         let mut names_vec = Vec::with_capacity(names.len());
         let mut values_vec = Vec::with_capacity(values.len());
         for name in names.iter() {
-            names_vec.push(encode_value(name, &self.dumps, self.raw_mode)?);
+            names_vec.push(encode_value(
+                name,
+                &self.dumps,
+                self.raw_mode,
+                self.strict_types,
+            )?);
         }
         for value in values.iter() {
-            values_vec.push(encode_value(value, &self.dumps, self.raw_mode)?);
+            values_vec.push(encode_value(
+                value,
+                &self.dumps,
+                self.raw_mode,
+                self.strict_types,
+            )?);
         }
         inner
             .put_entity_cf_opt(&cf.cf, key, &names_vec, &values_vec)
-            .map_err(|e| PyException::new_err(e.to_string()))
+            .map_err(to_py_err)
     }
 
     /// Removes the database entry for key. Does nothing if the key was not found.
@@ -202,7 +270,7 @@ impl WriteBatchPy {
         column_family: Option<ColumnFamilyPy>,
     ) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key(key, self.key_codec)?;
         match column_family {
             Some(cf) => inner.delete_cf(&cf.cf, key),
             None => inner.delete(key),
@@ -217,20 +285,32 @@ impl WriteBatchPy {
     ///     including "begin_key" and excluding "end_key". It is not an error if no
     ///     keys exist in the range ["begin_key", "end_key").
     ///
+    ///     `begin` can be `None` to delete from the first key. `end` can be
+    ///     `None` to delete to the last key, except in raw mode, where no key
+    ///     is larger than all keys.
+    ///
     /// Args:
-    ///     begin: begin key
-    ///     end: end key
+    ///     begin: begin key, or `None`
+    ///     end: end key, or `None`
     ///     column_family: override the default column family set by set_default_column_family
     #[pyo3(signature = (begin, end, column_family = None))]
     pub fn delete_range(
         &mut self,
-        begin: &Bound<PyAny>,
-        end: &Bound<PyAny>,
+        begin: Option<&Bound<PyAny>>,
+        end: Option<&Bound<PyAny>>,
         column_family: Option<ColumnFamilyPy>,
     ) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let from = encode_key(begin, self.raw_mode)?;
-        let to = encode_key(end, self.raw_mode)?;
+        let from = match begin {
+            Some(begin) => encode_key(begin, self.key_codec)?,
+            None => EncodedBytes::Owned(Vec::new()),
+        };
+        let to = match end {
+            Some(end) => encode_key(end, self.key_codec)?,
+            None => EncodedBytes::Owned(max_encoded_key(self.key_codec).ok_or_else(|| {
+                PyValueError::new_err("`end` cannot be None for a raw mode WriteBatch")
+            })?),
+        };
         match column_family {
             Some(cf) => inner.delete_range_cf(&cf.cf, from, to),
             None => inner.delete_range(from, to),
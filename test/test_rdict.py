@@ -1,22 +1,71 @@
 import unittest
 from rocksdict import (
     AccessType,
+    ReadOptions,
     Rdict,
     Options,
     PlainTableFactoryOptions,
     SliceTransform,
     CuckooTableOptions,
     DbClosedError,
+    DbFrozenError,
+    RocksDBError,
+    CorruptionError,
+    RetryPolicy,
+    TransactionDb,
+    TransactionDbOptions,
+    OptimisticTransactionDb,
+    TransactionConflictError,
+    BusyError,
+    TimedOutError,
+    NotSupportedError,
+    copy_range,
+    split,
+    merge_dbs,
+    migrate_key_codec,
+    build_info,
     WriteBatch,
-    Checkpoint
+    SstFileWriter,
+    BlockBasedOptions,
+    DBCompressionType,
+    Checkpoint,
+    MemtableFactory,
+    BlockBasedIndexType,
+    Cache,
+    WriteOptions,
+    WriteBufferManager,
+)
+from rocksdict.serve import RdictServer, Client, MAX_FRAME_BYTES, read_frame
+from rocksdict.tuner import AutoTuner
+from rocksdict.versioned import VersionedRdict
+from rocksdict.keys import (
+    u32_be,
+    u64_be,
+    i32_be,
+    i64_be,
+    from_u32_be,
+    from_u64_be,
+    from_i32_be,
+    from_i64_be,
 )
 from random import randint, random, getrandbits
 import os
+from pathlib import Path
+import shutil
 import gc
 import sys
 import platform
+import socket
+import struct
 from json import loads, dumps
 from subprocess import Popen
+from threading import Thread
+import time
+
+try:
+    import numpy as np
+except ImportError:
+    np = None
 
 
 TEST_INT_RANGE_UPPER = 999999
@@ -148,6 +197,33 @@ class TestIterBytes(unittest.TestCase):
         ref_list.sort()
         self.assertEqual([k for k in self.test_dict.keys(from_key=key)], ref_list)
 
+    def test_skip_limit(self):
+        assert self.ref_dict is not None
+        assert self.test_dict is not None
+        ref_list = sorted(self.ref_dict.keys())
+        self.assertEqual(
+            [k for k in self.test_dict.keys(skip=100, limit=50)], ref_list[100:150]
+        )
+        self.assertEqual(
+            [k for k, _ in self.test_dict.items(backwards=True, skip=10, limit=5)],
+            ref_list[::-1][10:15],
+        )
+        self.assertEqual([k for k in self.test_dict.keys(skip=len(ref_list))], [])
+        self.assertEqual(len([v for v in self.test_dict.values(limit=0)]), 0)
+
+    def test_start_stop(self):
+        assert self.ref_dict is not None
+        assert self.test_dict is not None
+        ref_list = sorted(self.ref_dict.keys())
+        start, stop = ref_list[10], ref_list[20]
+        self.assertEqual(
+            [k for k in self.test_dict.keys(start=start, stop=stop)], ref_list[10:20]
+        )
+        self.assertEqual(
+            [k for k in self.test_dict.keys(start=start, stop=stop, inclusive=(False, True))],
+            ref_list[11:21],
+        )
+
     def test_seek_backward_key(self):
         assert self.ref_dict is not None
         assert self.test_dict is not None
@@ -240,6 +316,31 @@ class TestIterInt(unittest.TestCase):
         ref_list.sort()
         self.assertEqual([k for k in self.test_dict.keys(from_key=key)], ref_list)
 
+    def test_start_stop(self):
+        assert self.ref_dict is not None
+        assert self.test_dict is not None
+        ref_keys = sorted(self.ref_dict.keys())
+        start, stop = ref_keys[10], ref_keys[100]
+        for inclusive in [(True, False), (True, True), (False, False), (False, True)]:
+            ref_list = [
+                k
+                for k in ref_keys
+                if (start <= k if inclusive[0] else start < k)
+                and (k <= stop if inclusive[1] else k < stop)
+            ]
+            self.assertEqual(
+                [k for k in self.test_dict.keys(start=start, stop=stop, inclusive=inclusive)],
+                ref_list,
+            )
+            self.assertEqual(
+                [k for k, _ in self.test_dict.items(start=start, stop=stop, inclusive=inclusive, backwards=True)],
+                ref_list[::-1],
+            )
+        self.assertEqual(
+            [v for v in self.test_dict.values(stop=stop)],
+            [self.ref_dict[k] for k in ref_keys if k < stop],
+        )
+
     def test_seek_backward_key(self):
         assert self.ref_dict is not None
         assert self.test_dict is not None
@@ -250,6 +351,18 @@ class TestIterInt(unittest.TestCase):
             [k for k in self.test_dict.keys(from_key=key, backwards=True)], ref_list
         )
 
+    def test_iterate_lower_bound(self):
+        assert self.ref_dict is not None
+        assert self.test_dict is not None
+        key = randint(0, TEST_INT_RANGE_UPPER - 1)
+        read_opt = ReadOptions()
+        read_opt.set_iterate_lower_bound(key)
+        ref_list = sorted(k for k in self.ref_dict.keys() if k >= key)
+        # only a lower bound, the end of the iteration stays unbounded
+        self.assertEqual(list(self.test_dict.keys(read_opt=read_opt)), ref_list)
+        self.assertEqual(list(self.test_dict.keys(backwards=True, read_opt=read_opt)),
+                         ref_list[::-1])
+
     @classmethod
     def tearDownClass(cls):
         assert cls.test_dict is not None
@@ -325,6 +438,15 @@ class TestInt(unittest.TestCase):
             [self.ref_dict[k] for k in keys] + [None] * 3,
         )
 
+    def test_get_batch_sorted(self):
+        assert self.ref_dict is not None
+        assert self.test_dict is not None
+        keys = sorted(self.ref_dict.keys())[:100]
+        self.assertEqual(
+            self.test_dict.get(keys, sorted_input=True),
+            [self.ref_dict[k] for k in keys],
+        )
+
     @classmethod
     def tearDownClass(cls):
         assert cls.opt is not None
@@ -332,521 +454,3356 @@ class TestInt(unittest.TestCase):
         Rdict.destroy(cls.path, cls.opt)
 
 
-class TestBigInt(unittest.TestCase):
+class TestTruncateRange(unittest.TestCase):
     test_dict = None
     opt = None
-    path = "./temp_big_int"
+    path = "./temp_truncate_range"
 
     @classmethod
     def setUpClass(cls) -> None:
         cls.opt = Options()
-        cls.opt.create_if_missing(True)
-        cls.opt.set_plain_table_factory(PlainTableFactoryOptions())
-        cls.opt.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
         cls.test_dict = Rdict(cls.path, cls.opt)
 
-    def test_big_int(self):
+    def test_truncate_range(self):
         assert self.test_dict is not None
-        key = 13456436145354564353464754615223435465543
-        value = 3456321456543245657643253647543212425364342343564
-        self.test_dict[key] = value
-        self.assertTrue(key in self.test_dict)
-        self.assertEqual(self.test_dict[key], value)
-        self.test_dict[key] = True
-        self.assertTrue(self.test_dict[key])
-        self.test_dict[key] = False
-        self.assertFalse(self.test_dict[key])
-        del self.test_dict[key]
-        self.assertFalse(key in self.test_dict)
+        for i in range(10000):
+            self.test_dict[i] = i
+        self.test_dict.flush()
+        reclaimed = self.test_dict.truncate_range(1000, 9000)
+        # compacting the range rewrites the file without the deleted keys
+        self.assertGreater(reclaimed, 0)
+        self.assertNotIn(1000, self.test_dict)
+        self.assertNotIn(8999, self.test_dict)
+        self.assertIsNone(self.test_dict.get(5000))
+        self.assertEqual(
+            [k for k in self.test_dict.keys()],
+            list(range(1000)) + list(range(9000, 10000)),
+        )
 
     @classmethod
     def tearDownClass(cls):
         assert cls.test_dict is not None
         cls.test_dict.close()
-        assert cls.opt is not None
         gc.collect()
-        Rdict.destroy(cls.path, cls.opt)
+        Rdict.destroy(cls.path, Options())
 
 
-class TestFloat(unittest.TestCase):
+class TestIntrospection(unittest.TestCase):
     test_dict = None
-    ref_dict = None
-    opt = None
-    path = "./temp_float"
+    path = "./temp_introspection"
 
     @classmethod
     def setUpClass(cls) -> None:
-        cls.opt = Options()
-        cls.opt.create_if_missing(True)
-        cls.test_dict = Rdict(cls.path, cls.opt)
-        cls.ref_dict = dict()
+        cls.test_dict = Rdict(cls.path)
+        for i in range(1000):
+            cls.test_dict[i] = i
+        cls.test_dict.flush()
 
-    def test_add_float(self):
+    def test_write_stall_info(self):
         assert self.test_dict is not None
-        assert self.ref_dict is not None
-        for i in range(10000):
-            key = random()
-            value = random()
-            self.ref_dict[key] = value
-            self.test_dict[key] = value
-
-        compare_dicts(self, self.ref_dict, self.test_dict)
+        info = self.test_dict.write_stall_info()
+        self.assertFalse(info["is_write_stopped"])
+        self.assertGreaterEqual(info["num_files_at_level0"], 1)
+        self.assertEqual(info["num_immutable_mem_table"], 0)
+        self.assertGreaterEqual(info["estimate_pending_compaction_bytes"], 0)
+
+    def test_delayed_write_rate(self):
+        path = "./temp_delayed_write_rate"
+        opt = Options()
+        opt.set_delayed_write_rate(8 * 1024 * 1024)
+        test_dict = Rdict(path, opt)
+        self.assertEqual(test_dict.write_stall_info()["delayed_write_rate"], 8 * 1024 * 1024)
+        test_dict.close()
+        Rdict.destroy(path)
 
-    def test_delete_float(self):
+    def test_table_properties(self):
         assert self.test_dict is not None
-        assert self.ref_dict is not None
-        for i in range(5000):
-            keys = [k for k in self.ref_dict.keys()]
-            key = keys[randint(0, len(self.ref_dict) - 1)]
-            del self.ref_dict[key]
-            del self.test_dict[key]
-
-        compare_dicts(self, self.ref_dict, self.test_dict)
+        props = self.test_dict.table_properties()
+        self.assertEqual(props["# entries"], 1000)
+        self.assertGreater(props["raw key size"], 0)
+        self.assertGreater(props["raw value size"], 0)
+        self.assertEqual(self.test_dict.table_properties(level=0)["# entries"], 1000)
 
-    def test_reopen(self):
+    def test_count_prefixes(self):
         assert self.test_dict is not None
-        assert self.ref_dict is not None
-        self.test_dict.close()
-        gc.collect()
-        test_dict = Rdict(self.path, self.opt)
-        compare_dicts(self, self.ref_dict, test_dict)
+        counts = self.test_dict.count_prefixes(1)
+        # non-raw int keys all share the same type tag byte
+        self.assertEqual(sum(counts.values()), 1000)
+        self.assertEqual(len(counts), 1)
+
+    def test_count_prefixes_distribution(self):
+        path = "./temp_prefix_distribution"
+        db = Rdict(path, Options(raw_mode=True))
+        real = {b"a:": 1000, b"b:": 300, b"c:": 37}
+        for prefix, count in real.items():
+            for i in range(count):
+                db[prefix + i.to_bytes(4, "big")] = b""
+        self.assertEqual(db.count_prefixes(2), real)
+        # counts are exact across the memtable and table files
+        db.flush()
+        db[b"c:extra"] = b""
+        del db[b"a:" + (0).to_bytes(4, "big")]
+        self.assertEqual(db.count_prefixes(2), {b"a:": 999, b"b:": 300, b"c:": 38})
+        db[b"d"] = b""
+        self.assertEqual(db.count_prefixes(2)[b"d"], 1)
+        db.close()
+        Rdict.destroy(path)
 
-    def test_get_batch(self):
+    def test_prefetch_range(self):
         assert self.test_dict is not None
-        assert self.ref_dict is not None
-        keys = list(self.ref_dict.keys())[:100]
-        self.assertEqual(
-            self.test_dict[keys + ["no such key"] * 3],
-            [self.ref_dict[k] for k in keys] + [None] * 3,
-        )
+        stats = self.test_dict.prefetch_range(100, 200)
+        self.assertEqual(stats["keys"], 100)
+        self.assertGreater(stats["bytes"], 0)
+        self.assertEqual(stats["files"], 0)
+        stats = self.test_dict.prefetch_range(None, None, "data|index|filter", 1024 * 1024)
+        self.assertEqual(stats["keys"], 1000)
+        self.assertEqual(stats["files"], 1)
+        # numeric order of int keys is respected when matching files
+        self.assertEqual(self.test_dict.prefetch_range(1000, None, "index")["files"], 0)
+        self.assertRaises(ValueError, lambda: self.test_dict.prefetch_range(None, None, "all"))
 
     @classmethod
     def tearDownClass(cls):
-        assert cls.opt is not None
+        assert cls.test_dict is not None
+        cls.test_dict.close()
         gc.collect()
-        Rdict.destroy(cls.path, cls.opt)
+        Rdict.destroy(cls.path)
 
 
-class TestBytes(unittest.TestCase):
-    test_dict = None
-    ref_dict = None
-    opt = None
-    path = "./temp_bytes"
+class TestStatistics(unittest.TestCase):
+    path1 = "./temp_statistics_1"
+    path2 = "./temp_statistics_2"
+
+    @staticmethod
+    def keys_written(stats):
+        for line in stats.splitlines():
+            if line.startswith("rocksdb.number.keys.written "):
+                return int(line.split()[-1])
+        raise ValueError("ticker not found")
+
+    def test_statistics(self):
+        opt = Options()
+        opt.enable_statistics()
+        db1 = Rdict(self.path1, opt)
+        db2 = Rdict(self.path2, opt)
+        for i in range(10):
+            db1[i] = i
+        db2[0] = 0
+        self.assertEqual(self.keys_written(db1.statistics()), 10)
+        self.assertEqual(self.keys_written(db2.statistics()), 1)
+        db1.close()
+        db2.close()
+        db3 = Rdict(self.path1, Options())
+        self.assertIsNone(db3.statistics())
+        db3.close()
+
+    def test_persist_stats_to_disk(self):
+        path = "./temp_statistics_persisted"
+        opt = Options()
+        opt.enable_statistics()
+        opt.set_stats_persist_period_sec(1)
+        opt.set_persist_stats_to_disk(True)
+        db = Rdict(path, opt)
+        history = {}
+        deadline = time.time() + 10
+        while not history and time.time() < deadline:
+            db[0] = 0
+            time.sleep(0.5)
+            history = db.stats_history()
+        self.assertTrue(history)
+        timestamp, stats = next(iter(history.items()))
+        self.assertIn("rocksdb.number.keys.written", stats)
+        self.assertEqual(db.stats_history(end_time=timestamp), {})
+        db.close()
+        # persisted statistics survive restarts
+        db = Rdict(path)
+        self.assertIn(timestamp, db.stats_history())
+        db.close()
+        Rdict.destroy(path)
+        db = Rdict(self.path1)
+        self.assertRaisesRegex(RocksDBError, "^statistics are not persisted", db.stats_history)
+        db.close()
+
+    def test_io_stats(self):
+        opt = Options()
+        opt.enable_statistics()
+        db = Rdict(self.path2, opt)
+        for i in range(100):
+            db[i] = "v" * 100
+        db.flush()
+        stats = db.io_stats()
+        self.assertGreater(stats["bytes_written"], 0)
+        self.assertGreater(stats["flush_bytes_written"], 0)
+        self.assertGreater(stats["write_amplification"], 0)
+        self.assertGreaterEqual(stats["read_amplification"], 1)
+        self.assertGreater(stats["live_sst_bytes"], 0)
+        db.close()
+        db = Rdict(self.path2, Options())
+        stats = db.io_stats()
+        self.assertIsNone(stats["bytes_written"])
+        self.assertIsNone(stats["write_amplification"])
+        self.assertGreaterEqual(stats["read_amplification"], 1)
+        db.close()
+
+    def test_file_handle_stats(self):
+        opt = Options()
+        opt.enable_statistics()
+        # the smallest table cache, of 10 files
+        opt.set_max_open_files(20)
+        opt.set_disable_auto_compactions(True)
+        db = Rdict(self.path1, opt)
+        for i in range(12):
+            db[i] = i
+            db.flush()
+        self.assertEqual(db[0], 0)
+        stats = db.file_handle_stats()
+        self.assertEqual(stats["max_open_files"], 20)
+        self.assertEqual(stats["table_cache_capacity"], 10)
+        self.assertEqual(stats["live_sst_files"], 12)
+        self.assertGreaterEqual(stats["live_versions"], 1)
+        self.assertEqual(stats["file_open_errors"], 0)
+        self.assertNotIn("table_cache_evictions", stats)
+        if sys.platform.startswith("linux"):
+            self.assertGreaterEqual(stats["open_files"], stats["open_sst_files"])
+            self.assertLessEqual(stats["open_sst_files"], 10)
+        # reading 12 files in turn through 10 cached ones reopens each of them
+        before = db.file_handle_stats()
+        for i in range(12):
+            self.assertEqual(db[i], i)
+        after = db.file_handle_stats()
+        self.assertGreaterEqual(after["file_opens"] - before["file_opens"], 12)
+        self.assertGreater(after["block_cache_misses"], before["block_cache_misses"])
+        # blocks read again are found in the block cache
+        self.assertEqual(db[11], 11)
+        self.assertGreater(db.file_handle_stats()["block_cache_hits"], after["block_cache_hits"])
+        # an iterator pins the current version while a flush creates a new one
+        it = db.iter()
+        db[12] = 12
+        db.flush()
+        self.assertGreater(db.file_handle_stats()["live_versions"], stats["live_versions"])
+        del it
+        db.close()
+        # tickers are None without statistics
+        db = Rdict(self.path1)
+        stats = db.file_handle_stats()
+        self.assertIsNone(stats["file_opens"])
+        self.assertIsNone(stats["block_cache_hits"])
+        db.close()
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.opt = Options()
-        cls.opt.create_if_missing(True)
-        # for the moment do not use CuckooTable on windows
-        if not sys.platform.startswith("win"):
-            cls.opt.set_cuckoo_table_factory(CuckooTableOptions())
-            cls.opt.set_allow_mmap_reads(True)
-            cls.opt.set_allow_mmap_writes(True)
-        cls.test_dict = Rdict(cls.path, cls.opt)
-        cls.ref_dict = dict()
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path1)
+        Rdict.destroy(cls.path2)
+
+
+class TestLatencyTracking(unittest.TestCase):
+    path = "./temp_latency_tracking"
+
+    def test_latency_tracking(self):
+        db = Rdict(self.path)
+        db[0] = 0
+        stats = db.latency_stats()
+        self.assertEqual(stats["put"]["count"], 0)
+        self.assertIsNone(stats["put"]["p50"])
+        db.enable_latency_tracking()
+        cf = db.create_column_family("cf")
+        for i in range(100):
+            db[i] = i
+            cf[i] = i
+        for i in range(10):
+            self.assertEqual(db[i], i)
+        del db[0]
+        self.assertEqual(len(list(db.items())), 99)
+        db.disable_latency_tracking()
+        db[0] = 0
+        stats = db.latency_stats(percentiles=[50, 99.9])
+        self.assertEqual(stats["put"]["count"], 200)
+        self.assertEqual(stats["get"]["count"], 10)
+        self.assertEqual(stats["delete"]["count"], 1)
+        # the last call finds the end of the iteration
+        self.assertEqual(stats["scan"]["count"], 100)
+        put = stats["put"]
+        self.assertLessEqual(put["p50"], put["p99.9"])
+        self.assertLessEqual(put["p99.9"], put["max"])
+        self.assertGreater(put["mean"], 0)
+        with self.assertRaises(ValueError):
+            db.latency_stats(percentiles=[101])
+        del cf
+        db.close()
 
-    @unittest.skipIf(platform.python_implementation() == "PyPy", reason="sys.getrefcount() not available in PyPy")
-    def test_add_bytes(self):
-        assert self.test_dict is not None
-        assert self.ref_dict is not None
-        for i in range(10000):
-            key = randbytes(10)
-            value = randbytes(20)
-            self.assertEqual(sys.getrefcount(key), 2)
-            self.assertEqual(sys.getrefcount(value), 2)
-            self.test_dict[key] = value
-            # rdict does not increase ref_count
-            self.assertEqual(sys.getrefcount(key), 2)
-            self.assertEqual(sys.getrefcount(value), 2)
-            self.ref_dict[key] = value
-            self.assertEqual(sys.getrefcount(key), 3)
-            self.assertEqual(sys.getrefcount(value), 3)
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
 
-        compare_dicts(self, self.ref_dict, self.test_dict)
 
-    @unittest.skipIf(platform.python_implementation() == "PyPy", reason="sys.getrefcount() not available in PyPy")
-    def test_delete_bytes(self):
-        assert self.test_dict is not None
-        assert self.ref_dict is not None
-        for i in range(5000):
-            keys = [k for k in self.ref_dict.keys()]
-            key = keys[randint(0, len(self.ref_dict) - 1)]
-            # key + ref_dict + keys + sys.getrefcount -> 4
-            self.assertEqual(sys.getrefcount(key), 4)
-            del self.test_dict[key]
-            self.assertEqual(sys.getrefcount(key), 4)
-            del self.ref_dict[key]
-            self.assertEqual(sys.getrefcount(key), 3)
+class TestCompressionPerLevel(unittest.TestCase):
+    path = "./temp_compression_per_level"
 
-        compare_dicts(self, self.ref_dict, self.test_dict)
+    def test_compression_per_level(self):
+        self.assertTrue(Options.compression_supported(DBCompressionType.none()))
+        if Options.compression_supported(DBCompressionType.snappy()):
+            last, last_name = DBCompressionType.snappy(), "kSnappyCompression"
+        else:
+            last, last_name = DBCompressionType.none(), "kNoCompression"
+        opt = Options()
+        opt.set_num_levels(4)
+        opt.set_compression_per_level([DBCompressionType.none(), last])
+        self.assertRaises(
+            ValueError,
+            lambda: opt.set_compression_per_level([DBCompressionType.none()] * 5),
+        )
+        db = Rdict(self.path, opt)
+        db["key"] = "value"
+        db.close()
+        with open(self.options_file()) as f:
+            options = f.read()
+        self.assertIn(
+            f"compression_per_level=kNoCompression:{last_name}:{last_name}:{last_name}",
+            options,
+        )
 
-    def test_reopen(self):
-        assert self.test_dict is not None
-        assert self.ref_dict is not None
-        self.test_dict.close()
-        test_dict = Rdict(self.path, self.opt)
-        compare_dicts(self, self.ref_dict, test_dict)
+    def options_file(self):
+        names = [n for n in os.listdir(self.path) if n.startswith("OPTIONS-")]
+        return os.path.join(self.path, max(names, key=lambda n: int(n.split("-")[1])))
 
-    def test_get_batch(self):
-        assert self.test_dict is not None
-        assert self.ref_dict is not None
-        keys = list(self.ref_dict.keys())[:100]
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestBuildInfo(unittest.TestCase):
+    def test_build_info(self):
+        info = build_info()
+        self.assertRegex(info["rocksdb_version"], r"^\d+\.\d+\.\d+$")
+        self.assertIn("none", info["compression_types"])
         self.assertEqual(
-            self.test_dict[keys + ["no such key"] * 3],
-            [self.ref_dict[k] for k in keys] + [None] * 3,
+            "snappy" in info["compression_types"],
+            Options.compression_supported(DBCompressionType.snappy()),
         )
+        self.assertIsInstance(info["io_uring"], bool)
+        self.assertIsInstance(info["jemalloc"], bool)
+        self.assertIn("-", info["target"])
+
+
+class TestTransactionDb(unittest.TestCase):
+    path = "./temp_transaction_db"
+    db = None
+
+    @classmethod
+    def setUpClass(cls):
+        cls.db = TransactionDb(cls.path)
+        cls.db.create_column_family("balances")
+
+    def test_commit(self):
+        assert self.db is not None
+        with self.db.begin_transaction() as txn:
+            txn["commit"] = [1, 2]
+            txn.put("commit", 3, column_family="balances")
+            self.assertEqual(txn["commit"], [1, 2])
+            # uncommitted writes are invisible outside the transaction
+            self.assertNotIn("commit", self.db)
+        self.assertEqual(self.db["commit"], [1, 2])
+        self.assertEqual(self.db.get("commit", column_family="balances"), 3)
+        self.assertRaises(Exception, txn.commit)
+
+    def test_rollback(self):
+        assert self.db is not None
+        self.db["rollback"] = 1
+        txn = self.db.begin_transaction()
+        txn["rollback"] = 2
+        txn.delete("rollback", column_family="balances")
+        txn.rollback()
+        self.assertEqual(self.db["rollback"], 1)
+        with self.assertRaises(KeyError):
+            with self.db.begin_transaction() as txn:
+                txn["rollback"] = 3
+                txn["missing"]
+        self.assertEqual(self.db["rollback"], 1)
+
+    def test_lock_timeout(self):
+        assert self.db is not None
+        first = self.db.begin_transaction()
+        second = self.db.begin_transaction()
+        first["locked"] = 1
+        self.assertRaises(TimedOutError, lambda: second.put("locked", 2))
+        first.commit()
+        second.rollback()
+        self.assertEqual(self.db["locked"], 1)
+
+    def test_get_for_update(self):
+        assert self.db is not None
+        self.db["balance"] = 100
+        first = self.db.begin_transaction()
+        second = self.db.begin_transaction()
+        # the key is locked until the first transaction ends
+        balance = first.get_for_update("balance")
+        self.assertRaises(TimedOutError, lambda: second.get_for_update("balance"))
+        first["balance"] = balance + 10
+        first.commit()
+        # then the second one reads the committed value
+        self.assertEqual(second.get_for_update("balance"), 110)
+        second["balance"] = 0
+        second.commit()
+        self.assertEqual(self.db["balance"], 0)
+
+        # shared locks allow other reads for update, but not writes
+        first = self.db.begin_transaction()
+        second = self.db.begin_transaction()
+        self.assertEqual(first.get_for_update("balance", exclusive=False), 0)
+        self.assertEqual(second.get_for_update("balance", exclusive=False), 0)
+        self.assertRaises(TimedOutError, lambda: second.put("balance", 1))
+        first.rollback()
+        second.rollback()
+
+        # with a snapshot, writes committed since are conflicts
+        txn = self.db.begin_transaction(set_snapshot=True)
+        self.db["balance"] = 50
+        self.assertRaises(TransactionConflictError, lambda: txn.get_for_update("balance"))
+        txn.rollback()
+        self.assertEqual(self.db["balance"], 50)
+
+    def test_txn_db_options(self):
+        txn_db_opt = TransactionDbOptions()
+        self.assertEqual(txn_db_opt.txn_lock_timeout, 1000)
+        self.assertFalse(txn_db_opt.deadlock_detect)
+        # fail at once instead of waiting for the lock
+        txn_db_opt.txn_lock_timeout = 0
+        txn_db_opt.deadlock_detect = True
+        path = self.path + "_txn_db_options"
+        db = TransactionDb(path, txn_db_options=txn_db_opt)
+        first = db.begin_transaction()
+        second = db.begin_transaction()
+        first["locked"] = 1
+        self.assertRaises(TimedOutError, lambda: second.put("locked", 2))
+        second.rollback()
+        first.commit()
+        self.assertEqual(db["locked"], 1)
+        db.close()
+        Rdict.destroy(path)
+
+    def test_savepoint(self):
+        assert self.db is not None
+        txn = self.db.begin_transaction()
+        # no savepoint to roll back to
+        self.assertRaises(Exception, txn.rollback_to_savepoint)
+        txn["savepoint_kept"] = 1
+        txn.set_savepoint()
+        txn["savepoint_undone"] = 2
+        txn["savepoint_kept"] = 3
+        txn.rollback_to_savepoint()
+        self.assertEqual(txn["savepoint_kept"], 1)
+        self.assertNotIn("savepoint_undone", txn)
+        # the savepoint was removed by the rollback
+        self.assertRaises(Exception, txn.rollback_to_savepoint)
+        txn.commit()
+        self.assertEqual(self.db["savepoint_kept"], 1)
+        self.assertNotIn("savepoint_undone", self.db)
+
+    def test_two_phase_commit(self):
+        path = self.path + "_2pc"
+        db = TransactionDb(path)
+        txn = db.begin_transaction()
+        self.assertIsNone(txn.name)
+        txn["prepared"] = 1
+        # only named transactions can be prepared
+        self.assertRaises(Exception, txn.prepare)
+        txn.set_name("first")
+        self.assertEqual(txn.name, "first")
+        txn.prepare()
+        del txn
+        db.close()
+        gc.collect()
+        db = TransactionDb(path)
+        self.assertNotIn("prepared", db)
+        prepared = db.prepared_transactions()
+        self.assertEqual(list(prepared), ["first"])
+        prepared["first"].commit()
+        self.assertEqual(db["prepared"], 1)
+        self.assertEqual(db.prepared_transactions(), {})
+        db.close()
+        Rdict.destroy(path)
+
+    def test_destroy_while_open(self):
+        path = self.path + "_destroy"
+        config = os.path.join(path, "rocksdict-config.json")
+        for open_db in (TransactionDb, OptimisticTransactionDb):
+            db = open_db(path)
+            txn = db.begin_transaction()
+            self.assertRaises(BusyError, Rdict.destroy, path)
+            self.assertRaises(BusyError, lambda: Rdict(path, skip_wal_recovery=True))
+            self.assertTrue(os.path.exists(config))
+            db.close()
+            # the open transaction keeps the database open
+            self.assertRaises(BusyError, Rdict.destroy, path)
+            txn.rollback()
+            del txn
+            gc.collect()
+        Rdict.destroy(path)
+        self.assertFalse(os.path.exists(config))
+
+    def test_reopen(self):
+        assert self.db is not None
+        self.db["reopen"] = "value"
+        self.db.close()
+        self.assertRaises(DbClosedError, lambda: self.db["reopen"])
+        db = TransactionDb(self.path)
+        self.assertEqual(db["reopen"], "value")
+        self.assertEqual(db.get("missing", "default", column_family="balances"), "default")
+        type(self).db = db
 
     @classmethod
     def tearDownClass(cls):
-        assert cls.opt is not None
+        if cls.db is not None:
+            cls.db.close()
         gc.collect()
-        Rdict.destroy(cls.path, cls.opt)
+        Rdict.destroy(cls.path)
 
 
-class TestString(unittest.TestCase):
-    test_dict = None
-    opt = None
-    path = "./temp_string"
+class TestOptimisticTransactionDb(unittest.TestCase):
+    path = "./temp_optimistic_transaction_db"
+    db = None
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.opt = Options()
-        cls.opt.create_if_missing(True)
-        cls.test_dict = Rdict(cls.path, cls.opt)
+    def setUpClass(cls):
+        cls.db = OptimisticTransactionDb(cls.path)
+
+    def test_commit(self):
+        assert self.db is not None
+        self.assertIsInstance(self.db, TransactionDb)
+        with self.db.begin_transaction() as txn:
+            txn["commit"] = 1
+            self.assertNotIn("commit", self.db)
+        self.assertEqual(self.db["commit"], 1)
+
+    def test_conflict(self):
+        assert self.db is not None
+        self.db["counter"] = 0
+        first = self.db.begin_transaction()
+        second = self.db.begin_transaction()
+        first["counter"] = first["counter"] + 1
+        # no lock is taken, so both transactions can write the key
+        second["counter"] = second["counter"] + 1
+        first.commit()
+        with self.assertRaises(TransactionConflictError) as cm:
+            second.commit()
+        self.assertIsInstance(cm.exception, BusyError)
+        self.assertEqual(self.db["counter"], 1)
+        self.assertRaises(Exception, second.commit)
+
+    def test_no_two_phase_commit(self):
+        assert self.db is not None
+        txn = self.db.begin_transaction()
+        self.assertRaises(Exception, txn.set_name, "name")
+        self.assertRaises(Exception, txn.prepare)
+        self.assertEqual(self.db.prepared_transactions(), {})
+        txn.rollback()
+
+    def test_rollback(self):
+        assert self.db is not None
+        txn = self.db.begin_transaction()
+        txn["rollback"] = 1
+        txn.rollback()
+        self.assertNotIn("rollback", self.db)
+
+    def test_get_for_update(self):
+        assert self.db is not None
+        self.db["balance"] = 100
+        first = self.db.begin_transaction()
+        second = self.db.begin_transaction()
+        # a read for update conflicts with writes committed since
+        balance = first.get_for_update("balance")
+        second["balance"] = second["balance"] - 50
+        second.commit()
+        first["balance"] = balance + 10
+        self.assertRaises(TransactionConflictError, first.commit)
+        self.assertEqual(self.db["balance"], 50)
+        self.assertEqual(self.db.begin_transaction().get_for_update("missing", 0), 0)
+
+    def test_set_snapshot(self):
+        assert self.db is not None
+        self.db["snapshot"] = 1
+        txn = self.db.begin_transaction()
+        txn.set_snapshot()
+        self.db["snapshot"] = 2
+        # reads are done at the snapshot
+        self.assertEqual(txn["snapshot"], 1)
+        self.assertEqual(txn.get_for_update("snapshot"), 1)
+        self.assertRaises(TransactionConflictError, txn.commit)
+        txn = self.db.begin_transaction(set_snapshot=True)
+        self.assertEqual(txn.get_for_update("snapshot"), 2)
+        self.assertRaises(Exception, txn.set_snapshot)
+        txn["snapshot"] = 3
+        txn.commit()
+        self.assertEqual(self.db["snapshot"], 3)
 
-    def test_string(self):
-        assert self.test_dict is not None
-        self.test_dict["Guangdong"] = "Shenzhen"
-        self.test_dict["Sichuan"] = "Changsha"
-        # overwrite
-        self.test_dict["Sichuan"] = "Chengdu"
-        self.test_dict["Beijing"] = "Beijing"
-        del self.test_dict["Beijing"]
+    @classmethod
+    def tearDownClass(cls):
+        if cls.db is not None:
+            cls.db.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
 
-        # assertions
-        self.assertFalse("Beijing" in self.test_dict)
-        self.assertEqual(self.test_dict["Sichuan"], "Chengdu")
-        self.assertEqual(self.test_dict["Guangdong"], "Shenzhen")
+
+class TestDeleteRangeOpenEnded(unittest.TestCase):
+    path = "./temp_delete_range_open_ended"
+    raw_path = "./temp_delete_range_open_ended_raw"
+
+    def test_open_ended(self):
+        test_dict = Rdict(self.path)
+        for i in range(-5, 10):
+            test_dict[i] = i
+        test_dict["str"] = "str"
+        test_dict.delete_range(None, 0)
+        self.assertEqual(list(test_dict.keys())[:2], [0, 1])
+        test_dict.delete_range(5, None)
+        self.assertEqual(list(test_dict.keys()), [0, 1, 2, 3, 4])
+        wb = WriteBatch()
+        wb.delete_range(None, 2)
+        wb.delete_range(3, None)
+        test_dict.write(wb)
+        self.assertEqual(list(test_dict.keys()), [2])
+        test_dict.close()
+
+    def test_open_ended_raw(self):
+        test_dict = Rdict(self.raw_path, Options(raw_mode=True))
+        for key in [b"", b"a", b"b", b"c", b"\xff\xff"]:
+            test_dict[key] = key
+        test_dict.delete_range(None, b"b")
+        test_dict.delete_range(b"c", None)
+        self.assertEqual(list(test_dict.keys()), [b"b"])
+        wb = WriteBatch(raw_mode=True)
+        self.assertRaises(ValueError, lambda: wb.delete_range(b"a", None))
+        test_dict.close()
 
     @classmethod
     def tearDownClass(cls):
-        assert cls.test_dict is not None
-        cls.test_dict.close()
-        assert cls.opt is not None
         gc.collect()
-        Rdict.destroy(cls.path, cls.opt)
+        Rdict.destroy(cls.path)
+        Rdict.destroy(cls.raw_path, Options(raw_mode=True))
 
 
-class TestWideColumnsRaw(unittest.TestCase):
+class TestWaitFor(unittest.TestCase):
+    path = "./temp_wait_for"
+
+    def test_wait_for(self):
+        test_dict = Rdict(self.path)
+        test_dict["ready"] = 1
+        self.assertEqual(test_dict.wait_for("ready", timeout=0), 1)
+        self.assertRaises(TimeoutError, lambda: test_dict.wait_for("missing", timeout=0.05))
+
+        def produce():
+            time.sleep(0.2)
+            wb = WriteBatch()
+            wb.put("job", "done")
+            test_dict.write(wb)
+
+        producer = Thread(target=produce)
+        producer.start()
+        self.assertEqual(test_dict.wait_for("job", timeout=10), "done")
+        producer.join()
+        test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestAtomicOps(unittest.TestCase):
+    path = "./temp_atomic_ops"
     test_dict = None
-    opt = None
-    path = "./temp_wide_columns_raw"
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.opt = Options(True)
-        cls.opt.create_if_missing(True)
-        cls.test_dict = Rdict(cls.path, cls.opt)
+    def setUpClass(cls):
+        cls.test_dict = Rdict(cls.path)
 
-    def test_put_wide_columns(self):
+    def test_update_atomic(self):
         assert self.test_dict is not None
-        self.test_dict.put_entity(key=b"Guangdong", names=[b"language", b"city"], values=[b"Cantonese", b"Shenzhen"])
-        self.test_dict.put_entity(key=b"Sichuan", names=[b"language", b"city"], values=[b"Mandarin", b"Chengdu"])
+        test_dict = self.test_dict
+
+        def increment(v):
+            time.sleep(0)
+            return v + 1
+
+        def work():
+            for _ in range(200):
+                test_dict.update_atomic("counter", increment, default=0)
+
+        workers = [Thread(target=work) for _ in range(4)]
+        for worker in workers:
+            worker.start()
+        for worker in workers:
+            worker.join()
+        self.assertEqual(test_dict["counter"], 800)
+        self.assertEqual(test_dict.update_atomic("counter", lambda v: v * 2), 1600)
+
+    def test_put_if_absent(self):
+        assert self.test_dict is not None
+        test_dict = self.test_dict
+        winners = []
+
+        def work(i):
+            if test_dict.put_if_absent("lease", i):
+                winners.append(i)
+
+        workers = [Thread(target=work, args=(i,)) for i in range(8)]
+        for worker in workers:
+            worker.start()
+        for worker in workers:
+            worker.join()
+        self.assertEqual(len(winners), 1)
+        self.assertEqual(test_dict["lease"], winners[0])
+
+    def test_compare_and_set(self):
+        assert self.test_dict is not None
+        test_dict = self.test_dict
+        self.assertFalse(test_dict.compare_and_set("state", "idle", "running"))
+        self.assertNotIn("state", test_dict)
+        test_dict["state"] = "idle"
+        self.assertTrue(test_dict.compare_and_set("state", "idle", "running"))
+        self.assertFalse(test_dict.compare_and_set("state", "idle", "running"))
+        self.assertEqual(test_dict["state"], "running")
+        test_dict["list"] = [1, 2]
+        self.assertTrue(test_dict.compare_and_set("list", [1, 2], [3]))
+        self.assertEqual(test_dict["list"], [3])
+
+    def test_delete_if(self):
+        assert self.test_dict is not None
+        test_dict = self.test_dict
+        self.assertFalse(test_dict.delete_if("owner", "a"))
+        test_dict["owner"] = "a"
+        self.assertFalse(test_dict.delete_if("owner", "b"))
+        self.assertEqual(test_dict["owner"], "a")
+        self.assertTrue(test_dict.delete_if("owner", "a"))
+        self.assertNotIn("owner", test_dict)
+
+    def test_atomic(self):
+        assert self.test_dict is not None
+        test_dict = self.test_dict
+        test_dict["alice"] = 100
+        test_dict["carol"] = 1
+        with test_dict.atomic() as tx:
+            tx["alice"] = tx["alice"] - 30
+            tx["bob"] = tx.get("bob", 0) + 30
+            del tx["carol"]
+            self.assertEqual(tx["bob"], 30)
+            self.assertNotIn("carol", tx)
+            self.assertNotIn("bob", test_dict)
+        self.assertEqual(test_dict["alice"], 70)
+        self.assertEqual(test_dict["bob"], 30)
+        self.assertNotIn("carol", test_dict)
+
+        def fail():
+            with test_dict.atomic() as tx:
+                tx["alice"] = 0
+                raise RuntimeError("abort")
+
+        self.assertRaises(RuntimeError, fail)
+        self.assertEqual(test_dict["alice"], 70)
+
+    @classmethod
+    def tearDownClass(cls):
+        if cls.test_dict is not None:
+            cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestChunkReads(unittest.TestCase):
+    path = "./temp_chunk_reads"
+
+    def test_get_chunk_keys(self):
+        path = self.path + "_keys"
+        db = Rdict(path)
+        for i in range(100):
+            db[i] = b"x" * 1000
+        it = db.iter()
+        it.seek_to_first()
+        self.assertEqual(it.get_chunk_keys(60), list(range(60)))
+        self.assertEqual(it.get_chunk_keys(60), list(range(60, 100)))
+        self.assertEqual(it.get_chunk_keys(60), [])
+        it.seek_to_last()
+        self.assertEqual(it.get_chunk_keys(3, backwards=True), [99, 98, 97])
+        it.close()
+        self.assertRaises(DbClosedError, it.get_chunk_keys)
+        # keys() reads keys in growing chunks
+        self.assertEqual(list(db.keys()), list(range(100)))
+        self.assertEqual(list(db.keys(backwards=True, skip=10, limit=50)),
+                         list(range(89, 39, -1)))
+        keys = db.keys(from_key=50)
+        self.assertEqual(next(keys), 50)
+        keys.close()
+        self.assertEqual(list(keys), [])
+        db.close()
+        Rdict.destroy(path)
+
+    def test_get_chunk_items(self):
+        path = self.path + "_items"
+        db = Rdict(path)
+        for i in range(100):
+            db[i] = b"x" * 1000
+        it = db.iter()
+        it.seek(10)
+        self.assertEqual(it.get_chunk_items(2), [(10, b"x" * 1000), (11, b"x" * 1000)])
+        # the chunk ends before stop_key, where the iterator stays
+        self.assertEqual([k for k, _ in it.get_chunk_items(100, stop_key=20)],
+                         list(range(12, 20)))
+        self.assertEqual(it.get_chunk_items(100, stop_key=20), [])
+        self.assertEqual(it.key(), 20)
+        # about 1000 bytes per item
+        self.assertEqual(len(it.get_chunk_items(100, max_bytes=3500)), 3)
+        # the first item is read even if larger than max_bytes
+        self.assertEqual(len(it.get_chunk_items(100, max_bytes=10)), 1)
+        self.assertEqual(it.key(), 24)
+        self.assertEqual([k for k, _ in it.get_chunk_items(100, backwards=True, stop_key=21)],
+                         [24, 23, 22])
+        self.assertEqual(it.get_chunk_keys(100, stop_key=25), [21, 22, 23, 24])
+        it.close()
+        self.assertRaises(DbClosedError, it.get_chunk_items)
+        db.close()
+        Rdict.destroy(path)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path + "_keys")
+        Rdict.destroy(cls.path + "_items")
+
+
+@unittest.skipIf(np is None, "numpy is not installed")
+class TestChunkKeysNumpy(unittest.TestCase):
+    path = "./temp_chunk_keys_np"
+
+    def test_get_chunk_keys_np(self):
+        assert np is not None
+        opt = Options(raw_mode=True)
+        test_dict = Rdict(self.path, opt)
+        for i in range(100):
+            test_dict[i.to_bytes(8, "big")] = b"v"
+        it = test_dict.iter()
+        it.seek_to_first()
+        self.assertTrue(np.array_equal(it.get_chunk_keys_np(64), np.arange(64)))
+        self.assertTrue(np.array_equal(it.get_chunk_keys_np(64), np.arange(64, 100)))
+        self.assertEqual(len(it.get_chunk_keys_np(64)), 0)
+        it.seek_to_last()
+        self.assertTrue(np.array_equal(it.get_chunk_keys_np(10, backwards=True),
+                                       np.arange(99, 89, -1)))
+        it.seek_to_first()
+        self.assertRaises(ValueError, lambda: it.get_chunk_keys_np(1, dtype="<u4"))
+        del it
+        test_dict.close()
+
+        # keys of the u64_be codec are stored as big-endian integers
+        path = self.path + "_np_u64"
+        opt = Options()
+        opt.set_key_codec("u64_be")
+        db = Rdict(path, opt)
+        for i in range(10):
+            db[i] = i
+        it = db.iter()
+        it.seek_to_first()
+        self.assertTrue(np.array_equal(it.get_chunk_keys_np(), np.arange(10)))
+        del it
+        db.close()
+        Rdict.destroy(path)
+
+        # only bytes keys of the rocksdict codec are supported
+        path = self.path + "_np_bytes"
+        db = Rdict(path)
+        db[b"\x00\x01"] = 1
+        db["ab"] = 2
+        it = db.iter()
+        it.seek_to_first()
+        self.assertTrue(np.array_equal(it.get_chunk_keys_np(1, dtype=">u2"), [1]))
+        self.assertRaises(ValueError, lambda: it.get_chunk_keys_np(1, dtype=">u2"))
+        del it
+        db.close()
+        Rdict.destroy(path)
+
+    def test_put_numpy(self):
+        assert np is not None
+        db = Rdict(self.path, Options(raw_mode=True))
+        keys = np.arange(1000, 1100, dtype=">u8")
+        values = np.arange(200, dtype="<f4").reshape(100, 2)
+        self.assertEqual(db.put_numpy(keys, values), 100)
+        self.assertEqual(db[(1007).to_bytes(8, "big")], values[7].tobytes())
+        self.assertEqual(db.put_numpy(np.array([b"a", b"b"], dtype="S1"),
+                                      np.zeros((2, 0), dtype="u1")), 2)
+        self.assertEqual(db[b"b"], b"")
+        self.assertRaises(ValueError, db.put_numpy, keys, values[:10])
+        self.assertRaises(ValueError, db.put_numpy, keys, values[:, 0])
+        db.close()
+        db = Rdict(self.path + "_typed")
+        self.assertRaises(ValueError, db.put_numpy, keys, values)
+        db.close()
+        Rdict.destroy(self.path + "_typed")
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path, Options(raw_mode=True))
+
+
+class TestGetInto(unittest.TestCase):
+    path = "./temp_get_into"
+
+    def test_get_into(self):
+        test_dict = Rdict(self.path)
+        test_dict["key"] = b"value"
+        test_dict["str"] = "value"
+        buffer = bytearray(8)
+        self.assertEqual(test_dict.get_into("key", buffer), 5)
+        self.assertEqual(buffer[:5], b"value")
+        self.assertIsNone(test_dict.get_into("missing", buffer))
+        self.assertRaises(ValueError, lambda: test_dict.get_into("key", bytearray(4)))
+        self.assertRaises(ValueError, lambda: test_dict.get_into("str", buffer))
+        self.assertRaises(ValueError, lambda: test_dict.get_into("key", b"readonly"))
+        test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestRawRead(unittest.TestCase):
+    path = "./temp_raw_read"
+
+    def test_raw_read(self):
+        db = Rdict(self.path)
+        db["a"] = 1
+        db["b"] = "text"
+        db["c"] = b"bytes"
+        self.assertEqual(db.get("a", raw=True), b"\x03\x01")
+        self.assertEqual(db.get(["b", "missing"], raw=True), [b"\x02text", None])
+        self.assertEqual(db.get("missing", "default", raw=True), "default")
+        self.assertEqual(list(db.items(raw=True)),
+                         [(b"\x02a", b"\x03\x01"),
+                          (b"\x02b", b"\x02text"),
+                          (b"\x02c", b"\x01bytes")])
+        # bounds are given as keys of the database
+        self.assertEqual(list(db.items(raw=True, start="b", stop="c")),
+                         [(b"\x02b", b"\x02text")])
+        self.assertEqual(list(db.items(raw=True, from_key="c")),
+                         [(b"\x02c", b"\x01bytes")])
+        self.assertEqual(db["a"], 1)
+        db.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestBufferedWriter(unittest.TestCase):
+    path = "./temp_buffered_writer"
+
+    def test_buffered_writer(self):
+        test_dict = Rdict(self.path)
+        with test_dict.buffered_writer(max_batch=100, flush_interval=10) as writer:
+            for i in range(1000):
+                writer[i] = i
+            writer.flush()
+            self.assertEqual(test_dict[999], 999)
+            del writer[0]
+        self.assertNotIn(0, test_dict)
+        self.assertEqual(test_dict.count_range(), 999)
+        self.assertRaises(Exception, lambda: writer.put(1, 1))
+
+        writer = test_dict.buffered_writer(max_batch=1000, flush_interval=0.01)
+        writer["late"] = "value"
+        self.assertEqual(test_dict.wait_for("late", timeout=10), "value")
+        writer.close()
+        test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestAutoTuner(unittest.TestCase):
+    path = "./temp_auto_tuner"
+
+    def test_bounds_applied(self):
+        db = Rdict(self.path)
+        tuner = AutoTuner(db, interval=0.01, max_write_buffer_number=(3, 6))
+        self.assertEqual(db.get_options()["max_write_buffer_number"], "3")
+        self.assertEqual([(c.option, c.old, c.new) for c in tuner.changes],
+                         [("max_write_buffer_number", 2, 3)])
+        with tuner:
+            for i in range(1000):
+                db[i] = i
+        self.assertRaises(ValueError, AutoTuner, db, max_write_buffer_number=(4, 3))
+        db.close()
+
+    def test_cache_grows(self):
+        cache = Cache(1024 * 1024)
+        table = BlockBasedOptions()
+        table.set_block_cache(cache)
+        opt = Options()
+        opt.set_block_based_table_factory(table)
+        opt.enable_statistics()
+        db = Rdict(self.path, opt)
+        for i in range(1000):
+            db[i] = i
+        db.flush()
+        # a hit rate above 1 always grows the cache
+        tuner = AutoTuner(db, cache=cache, cache_capacity=(1 << 20, 4 << 20),
+                          target_cache_hit_rate=1.1)
+        for _ in range(3):
+            for i in range(1000):
+                self.assertEqual(db[i], i)
+            tuner.step()
+        self.assertEqual([(c.old, c.new) for c in tuner.changes],
+                         [(1 << 20, 3 << 19), (3 << 19, 9 << 18), (9 << 18, 27 << 17)])
+        db.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestServe(unittest.TestCase):
+    path = "./temp_serve"
+
+    def test_serve(self):
+        test_dict = Rdict(self.path)
+        for i in range(10):
+            test_dict[i] = i * i
+        test_dict["bytes"] = b"\x00\x01"
+        test_dict.close()
+
+        server = RdictServer(self.path, port=0)
+        thread = Thread(target=server.serve_forever, daemon=True)
+        thread.start()
+        try:
+            with Client("127.0.0.1", server.port) as client:
+                self.assertEqual(client.get(3), 9)
+                self.assertIsNone(client.get(100))
+                self.assertEqual(client.get("bytes"), b"\x00\x01")
+                self.assertEqual(client.multi_get([1, 2, 100]), [1, 4, None])
+                self.assertEqual(client.scan(5, 8), [(5, 25), (6, 36), (7, 49)])
+                self.assertEqual(client.scan(start=0, limit=2), [(0, 0), (1, 1)])
+                self.assertRaises(RuntimeError, lambda: client._call({"op": "put"}))
+        finally:
+            server.shutdown()
+            server.server_close()
+            thread.join()
+
+    def test_serve_limits(self):
+        test_dict = Rdict(self.path)
+        for i in range(10):
+            test_dict[i] = i * i
+        test_dict.close()
+
+        server = RdictServer(self.path, port=0, max_scan_items=3)
+        thread = Thread(target=server.serve_forever, daemon=True)
+        thread.start()
+        try:
+            with Client("127.0.0.1", server.port) as client:
+                # a single request returns at most max_scan_items pairs
+                self.assertEqual(len(client._call({"op": "scan", "limit": None})), 3)
+                self.assertEqual(len(client._call({"op": "scan", "limit": 100})), 3)
+                # the client continues the scan from the last key
+                self.assertEqual(client.scan(), [(i, i * i) for i in range(10)])
+                self.assertEqual(client.scan(2, limit=5), [(i, i * i) for i in range(2, 7)])
+            with socket.create_connection(("127.0.0.1", server.port)) as sock:
+                stream = sock.makefile("rwb")
+                stream.write(struct.pack(">I", MAX_FRAME_BYTES + 1))
+                stream.flush()
+                response = read_frame(stream)
+                self.assertFalse(response["ok"])
+                self.assertIn("exceeds", response["error"])
+                # the connection is closed after the error
+                self.assertIsNone(read_frame(stream))
+                stream.close()
+        finally:
+            server.shutdown()
+            server.server_close()
+            thread.join()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestCountRange(unittest.TestCase):
+    path = "./temp_count_range"
+
+    def test_count_range(self):
+        test_dict = Rdict(self.path)
+        for i in range(100):
+            test_dict[i] = i
+        test_dict["str"] = "str"
+        self.assertEqual(test_dict.count_range(10, 20), 10)
+        self.assertEqual(test_dict.count_range(None, 50), 51)
+        self.assertEqual(test_dict.count_range(90), 10)
+        self.assertEqual(test_dict.count_range(), 101)
+        self.assertEqual(test_dict.count_range(20, 10), 0)
+        test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestMapValues(unittest.TestCase):
+    path = "./temp_map_values"
+
+    def test_map_values(self):
+        test_dict = Rdict(self.path)
+        for i in range(100):
+            test_dict[i] = i
+        self.assertEqual(test_dict.map_values(lambda v: v * 10, begin=10, end=20, batch_size=3), 10)
+        self.assertEqual(test_dict[9], 9)
+        self.assertEqual(test_dict[10], 100)
+        self.assertEqual(test_dict[19], 190)
+        self.assertEqual(test_dict[20], 20)
+        self.assertEqual(test_dict.map_values(str), 100)
+        self.assertEqual(test_dict[99], "99")
+        test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestDeleteWhere(unittest.TestCase):
+    path = "./temp_delete_where"
+
+    def test_delete_where(self):
+        test_dict = Rdict(self.path)
+        for i in range(100):
+            test_dict[f"session:{i}"] = "expired" if i % 2 == 0 else "active"
+            test_dict[f"user:{i}"] = "expired"
+            test_dict[i] = i
+        self.assertEqual(
+            test_dict.delete_where(key_prefix="session:", value_filter="expired", batch_size=7), 50
+        )
+        self.assertNotIn("session:0", test_dict)
+        self.assertIn("session:1", test_dict)
+        self.assertIn("user:0", test_dict)
+        self.assertEqual(test_dict.delete_where(lambda k, v: isinstance(k, int) and v >= 10), 90)
+        self.assertEqual(list(test_dict.keys(start=0)), list(range(10)))
+        self.assertEqual(test_dict.delete_where(value_filter="expired"), 100)
+        self.assertRaises(ValueError, lambda: test_dict.delete_where(key_prefix=1))
+
+        # the predicate can write to the database, or freeze it
+        for i in range(10):
+            test_dict[i] = i
+
+        def log_odd(k, v):
+            if isinstance(k, int) and v % 2:
+                test_dict[f"log:{k}"] = v
+                return True
+            return False
+
+        self.assertEqual(test_dict.delete_where(log_odd), 5)
+        self.assertEqual(test_dict.get([1, 2, "log:1"]), [None, 2, 1])
+
+        def freeze(k, v):
+            test_dict.freeze()
+            return True
+
+        self.assertRaises(DbFrozenError, test_dict.delete_where, freeze, "log:")
+        test_dict.unfreeze()
+        self.assertIn("log:1", test_dict)
+        test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestRecentJobs(unittest.TestCase):
+    path = "./temp_recent_jobs"
+
+    def test_recent_jobs(self):
+        test_dict = Rdict(self.path)
+        for round in range(3):
+            for i in range(100):
+                test_dict[i] = round
+            test_dict.flush()
+        test_dict.compact_range(None, None)
+        jobs = test_dict.recent_jobs()
+        flushes = [job for job in jobs if job["type"] == "flush"]
+        compactions = [job for job in jobs if job["type"] == "compaction"]
+        self.assertEqual(len(flushes), 3)
+        for flush in flushes:
+            self.assertEqual(flush["cf_name"], "default")
+            self.assertEqual(flush["status"], "OK")
+            self.assertEqual(flush["num_entries"], 100)
+            self.assertGreaterEqual(flush["end_micros"], flush["start_micros"])
+        self.assertGreaterEqual(len(compactions), 1)
+        self.assertGreaterEqual(compactions[-1]["duration_micros"], 0)
+        self.assertEqual(compactions[-1]["num_output_records"], 100)
+        self.assertEqual(len(test_dict.recent_jobs(limit=1)), 1)
+        # jobs of other databases are not recorded
+        other = Rdict(self.path + "_other")
+        other[0] = 0
+        other.flush()
+        self.assertEqual(len(other.recent_jobs()), 1)
+        self.assertEqual(len(test_dict.recent_jobs()), len(jobs))
+        other.close()
+        # reopening starts a new history
+        test_dict.reopen()
+        self.assertEqual(test_dict.recent_jobs(), [])
+        test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+        Rdict.destroy(cls.path + "_other")
+
+
+class TestCompactOnDeletion(unittest.TestCase):
+    path = "./temp_compact_on_deletion"
+
+    def test_compact_on_deletion(self):
+        opt = Options()
+        opt.add_compact_on_deletion_collector_factory(16, 8, 0.5)
+        test_dict = Rdict(self.path, opt)
+        # a control database without the collector
+        control = Rdict(self.path + "_control")
+        for db in (test_dict, control):
+            for i in range(1000):
+                db[i] = i
+            db.flush()
+            for i in range(900):
+                del db[i]
+            db.flush()
+        self.assertEqual([k for k in test_dict.keys()], list(range(900, 1000)))
+        # the file full of tombstones is compacted away in the background
+        deadline = time.time() + 10
+        while test_dict.table_properties()["# deletions"] > 0 and time.time() < deadline:
+            time.sleep(0.05)
+        props = test_dict.table_properties()
+        self.assertEqual(props["# deletions"], 0)
+        self.assertEqual(props["# entries"], 100)
+        self.assertEqual(test_dict.property_int_value("rocksdb.num-files-at-level0"), 0)
+        reasons = [job["reason"] for job in test_dict.recent_jobs() if job["type"] == "compaction"]
+        self.assertIn("FilesMarkedForCompaction", reasons)
+        # without the collector, both files stay in level 0 with their tombstones
+        props = control.table_properties()
+        self.assertEqual(props["# deletions"], 900)
+        self.assertEqual(props["# entries"], 1900)
+        self.assertEqual(control.property_int_value("rocksdb.num-files-at-level0"), 2)
+        self.assertEqual([k for k in control.keys()], list(range(900, 1000)))
+        control.close()
+        test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+        Rdict.destroy(cls.path + "_control")
+
+
+class TestFirstLast(unittest.TestCase):
+    test_dict = None
+    path = "./temp_first_last"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_first_last(self):
+        assert self.test_dict is not None
+        self.assertIsNone(self.test_dict.first())
+        self.assertIsNone(self.test_dict.last())
+        self.assertRaises(IndexError, lambda: self.test_dict.peekitem())
+        for i in range(10):
+            self.test_dict[i] = i * i
+        self.assertEqual(self.test_dict.first(), (0, 0))
+        self.assertEqual(self.test_dict.last(), (9, 81))
+        self.assertEqual(self.test_dict.peekitem(), (9, 81))
+        self.assertEqual(self.test_dict.peekitem(3), (3, 9))
+        self.assertEqual(self.test_dict.peekitem(-2), (8, 64))
+        self.assertRaises(IndexError, lambda: self.test_dict.peekitem(10))
+        self.assertRaises(IndexError, lambda: self.test_dict.peekitem(-11))
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestBigInt(unittest.TestCase):
+    test_dict = None
+    opt = None
+    path = "./temp_big_int"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options()
+        cls.opt.create_if_missing(True)
+        cls.opt.set_plain_table_factory(PlainTableFactoryOptions())
+        cls.opt.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
+        cls.test_dict = Rdict(cls.path, cls.opt)
+
+    def test_big_int(self):
+        assert self.test_dict is not None
+        key = 13456436145354564353464754615223435465543
+        value = 3456321456543245657643253647543212425364342343564
+        self.test_dict[key] = value
+        self.assertTrue(key in self.test_dict)
+        self.assertEqual(self.test_dict[key], value)
+        self.test_dict[key] = True
+        self.assertTrue(self.test_dict[key])
+        self.test_dict[key] = False
+        self.assertFalse(self.test_dict[key])
+        del self.test_dict[key]
+        self.assertFalse(key in self.test_dict)
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        assert cls.opt is not None
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestFloat(unittest.TestCase):
+    test_dict = None
+    ref_dict = None
+    opt = None
+    path = "./temp_float"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options()
+        cls.opt.create_if_missing(True)
+        cls.test_dict = Rdict(cls.path, cls.opt)
+        cls.ref_dict = dict()
+
+    def test_add_float(self):
+        assert self.test_dict is not None
+        assert self.ref_dict is not None
+        for i in range(10000):
+            key = random()
+            value = random()
+            self.ref_dict[key] = value
+            self.test_dict[key] = value
+
+        compare_dicts(self, self.ref_dict, self.test_dict)
+
+    def test_delete_float(self):
+        assert self.test_dict is not None
+        assert self.ref_dict is not None
+        for i in range(5000):
+            keys = [k for k in self.ref_dict.keys()]
+            key = keys[randint(0, len(self.ref_dict) - 1)]
+            del self.ref_dict[key]
+            del self.test_dict[key]
+
+        compare_dicts(self, self.ref_dict, self.test_dict)
+
+    def test_reopen(self):
+        assert self.test_dict is not None
+        assert self.ref_dict is not None
+        self.test_dict.close()
+        gc.collect()
+        test_dict = Rdict(self.path, self.opt)
+        compare_dicts(self, self.ref_dict, test_dict)
+
+    def test_get_batch(self):
+        assert self.test_dict is not None
+        assert self.ref_dict is not None
+        keys = list(self.ref_dict.keys())[:100]
+        self.assertEqual(
+            self.test_dict[keys + ["no such key"] * 3],
+            [self.ref_dict[k] for k in keys] + [None] * 3,
+        )
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.opt is not None
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestBytes(unittest.TestCase):
+    test_dict = None
+    ref_dict = None
+    opt = None
+    path = "./temp_bytes"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options()
+        cls.opt.create_if_missing(True)
+        # for the moment do not use CuckooTable on windows
+        if not sys.platform.startswith("win"):
+            cls.opt.set_cuckoo_table_factory(CuckooTableOptions())
+            cls.opt.set_allow_mmap_reads(True)
+            cls.opt.set_allow_mmap_writes(True)
+        cls.test_dict = Rdict(cls.path, cls.opt)
+        cls.ref_dict = dict()
+
+    @unittest.skipIf(platform.python_implementation() == "PyPy", reason="sys.getrefcount() not available in PyPy")
+    def test_add_bytes(self):
+        assert self.test_dict is not None
+        assert self.ref_dict is not None
+        for i in range(10000):
+            key = randbytes(10)
+            value = randbytes(20)
+            self.assertEqual(sys.getrefcount(key), 2)
+            self.assertEqual(sys.getrefcount(value), 2)
+            self.test_dict[key] = value
+            # rdict does not increase ref_count
+            self.assertEqual(sys.getrefcount(key), 2)
+            self.assertEqual(sys.getrefcount(value), 2)
+            self.ref_dict[key] = value
+            self.assertEqual(sys.getrefcount(key), 3)
+            self.assertEqual(sys.getrefcount(value), 3)
+
+        compare_dicts(self, self.ref_dict, self.test_dict)
+
+    @unittest.skipIf(platform.python_implementation() == "PyPy", reason="sys.getrefcount() not available in PyPy")
+    def test_delete_bytes(self):
+        assert self.test_dict is not None
+        assert self.ref_dict is not None
+        for i in range(5000):
+            keys = [k for k in self.ref_dict.keys()]
+            key = keys[randint(0, len(self.ref_dict) - 1)]
+            # key + ref_dict + keys + sys.getrefcount -> 4
+            self.assertEqual(sys.getrefcount(key), 4)
+            del self.test_dict[key]
+            self.assertEqual(sys.getrefcount(key), 4)
+            del self.ref_dict[key]
+            self.assertEqual(sys.getrefcount(key), 3)
+
+        compare_dicts(self, self.ref_dict, self.test_dict)
+
+    def test_reopen(self):
+        assert self.test_dict is not None
+        assert self.ref_dict is not None
+        self.test_dict.close()
+        test_dict = Rdict(self.path, self.opt)
+        compare_dicts(self, self.ref_dict, test_dict)
+
+    def test_get_batch(self):
+        assert self.test_dict is not None
+        assert self.ref_dict is not None
+        keys = list(self.ref_dict.keys())[:100]
+        self.assertEqual(
+            self.test_dict[keys + ["no such key"] * 3],
+            [self.ref_dict[k] for k in keys] + [None] * 3,
+        )
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.opt is not None
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestString(unittest.TestCase):
+    test_dict = None
+    opt = None
+    path = "./temp_string"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options()
+        cls.opt.create_if_missing(True)
+        cls.test_dict = Rdict(cls.path, cls.opt)
+
+    def test_string(self):
+        assert self.test_dict is not None
+        self.test_dict["Guangdong"] = "Shenzhen"
+        self.test_dict["Sichuan"] = "Changsha"
+        # overwrite
+        self.test_dict["Sichuan"] = "Chengdu"
+        self.test_dict["Beijing"] = "Beijing"
+        del self.test_dict["Beijing"]
+
+        # assertions
+        self.assertFalse("Beijing" in self.test_dict)
+        self.assertEqual(self.test_dict["Sichuan"], "Chengdu")
+        self.assertEqual(self.test_dict["Guangdong"], "Shenzhen")
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        assert cls.opt is not None
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestRawBuffer(unittest.TestCase):
+    test_dict = None
+    path = "./temp_raw_buffer"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path, Options(raw_mode=True))
+
+    def test_buffer_protocol(self):
+        assert self.test_dict is not None
+        data = bytearray(b"key1value1key2value2")
+        view = memoryview(data)
+        self.test_dict[view[0:4]] = view[4:10]
+        self.test_dict.put(bytearray(b"key2"), view[14:20])
+        self.assertEqual(self.test_dict[b"key1"], b"value1")
+        self.assertEqual(self.test_dict[memoryview(b"key2")], b"value2")
+        self.assertTrue(bytearray(b"key1") in self.test_dict)
+        # non-contiguous buffers are copied
+        self.test_dict[b"key3"] = view[::2]
+        self.assertEqual(self.test_dict[b"key3"], bytes(view[::2]))
+        self.assertRaises(KeyError, lambda: self.test_dict["key1"])
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestRawIntKeys(unittest.TestCase):
+    test_dict = None
+    path = "./temp_raw_int_keys"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path, Options(raw_mode=True))
+
+    def test_i64_keys_sorted(self):
+        assert self.test_dict is not None
+        ints = [randint(-(1 << 63), (1 << 63) - 1) for _ in range(1000)] + [0, -1, 1]
+        for i in ints:
+            self.test_dict[i64_be(i)] = u64_be(abs(i))
+        self.assertEqual(
+            [from_i64_be(k) for k in self.test_dict.keys()], sorted(set(ints))
+        )
+        self.assertEqual(from_u64_be(self.test_dict[i64_be(-1)]), 1)
+
+    def test_round_trip(self):
+        for i in [0, 1, (1 << 32) - 1]:
+            self.assertEqual(from_u32_be(u32_be(i)), i)
+        for i in [-(1 << 31), -1, 0, (1 << 31) - 1]:
+            self.assertEqual(from_i32_be(i32_be(i)), i)
+        self.assertLess(i32_be(-1), i32_be(0))
+        self.assertRaises(Exception, lambda: i64_be(1 << 63))
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestWideColumnsRaw(unittest.TestCase):
+    test_dict = None
+    opt = None
+    path = "./temp_wide_columns_raw"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options(True)
+        cls.opt.create_if_missing(True)
+        cls.test_dict = Rdict(cls.path, cls.opt)
+
+    def test_put_wide_columns(self):
+        assert self.test_dict is not None
+        self.test_dict.put_entity(key=b"Guangdong", names=[b"language", b"city"], values=[b"Cantonese", b"Shenzhen"])
+        self.test_dict.put_entity(key=b"Sichuan", names=[b"language", b"city"], values=[b"Mandarin", b"Chengdu"])
+        self.assertEqual(self.test_dict[b"Guangdong"], b"")
+        self.assertEqual(self.test_dict.get_entity(b"Guangdong"), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
+        self.assertEqual(self.test_dict[b"Sichuan"], b"")
+        self.assertEqual(self.test_dict.get_entity(b"Sichuan"), [(b"city", b"Chengdu"), (b"language", b"Mandarin")])
+        # overwrite
+        self.test_dict.put_entity(key=b"Sichuan", names=[b"language", b"city"], values=[b"Sichuanhua", b"Chengdu"])
+        self.test_dict[b"Beijing"] = b"Beijing"
+
+        # assertions
+        self.assertEqual(self.test_dict[b"Beijing"], b"Beijing")
+        self.assertEqual(self.test_dict.get_entity(b"Beijing"), [(b"", b"Beijing")])
+        self.assertEqual(self.test_dict[b"Guangdong"], b"")
+        self.assertEqual(self.test_dict.get_entity(b"Guangdong"), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
+        self.assertEqual(self.test_dict[b"Sichuan"], b"")
+        self.assertEqual(self.test_dict.get_entity(b"Sichuan"), [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")])
+
+        # iterator
+        it = self.test_dict.iter()
+        it.seek_to_first()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), b"Beijing")
+        self.assertEqual(it.value(), b"Beijing")
+        self.assertEqual(it.columns(), [(b"", b"Beijing")])
+        it.next()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), b"Guangdong")
+        self.assertEqual(it.value(), b"")
+        self.assertEqual(it.columns(), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
+        it.next()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), b"Sichuan")
+        self.assertEqual(it.value(), b"")
+        self.assertEqual(it.columns(), [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")])
+
+        # iterators
+        expected = [
+            (b"Beijing", [(b"", b"Beijing")]),
+            (b"Guangdong", [(b"city", b"Shenzhen"), (b"language", b"Cantonese")]),
+            (b"Sichuan", [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")]),
+        ]
+        for i, (key, entity) in enumerate(self.test_dict.entities()):
+            self.assertEqual(key, expected[i][0])
+            self.assertEqual(entity, expected[i][1])
+
+        self.assertEqual(
+            [c for c in self.test_dict.columns()],
+            [
+                [(b"", b"Beijing")],
+                [(b"city", b"Shenzhen"), (b"language", b"Cantonese")],
+                [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")],
+            ]
+        )
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        assert cls.opt is not None
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestWriteBatchWideColumnsRaw(unittest.TestCase):
+    test_dict = None
+    opt = None
+    path = "./temp_write_batch_wide_columns_raw"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options(True)
+        cls.opt.create_if_missing(True)
+        cls.test_dict = Rdict(cls.path, cls.opt)
+
+    def test_put_wide_columns(self):
+        assert self.test_dict is not None
+        write_batch = WriteBatch(raw_mode=True)
+        default_cf_handle = self.test_dict.get_column_family_handle("default")
+        write_batch.set_default_column_family(default_cf_handle)
+        write_batch.put_entity(key=b"Guangdong", names=[b"language", b"city"], values=[b"Cantonese", b"Shenzhen"])
+        write_batch.put_entity(key=b"Sichuan", names=[b"language", b"city"], values=[b"Mandarin", b"Chengdu"])
+        # overwrite
+        write_batch.put_entity(key=b"Sichuan", names=[b"language", b"city"], values=[b"Sichuanhua", b"Chengdu"])
+        write_batch[b"Beijing"] = b"Beijing"
+
+        self.test_dict.write(write_batch)
+
+        # assertions
+        self.assertEqual(self.test_dict[b"Beijing"], b"Beijing")
+        self.assertEqual(self.test_dict.get_entity(b"Beijing"), [(b"", b"Beijing")])
         self.assertEqual(self.test_dict[b"Guangdong"], b"")
         self.assertEqual(self.test_dict.get_entity(b"Guangdong"), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
         self.assertEqual(self.test_dict[b"Sichuan"], b"")
-        self.assertEqual(self.test_dict.get_entity(b"Sichuan"), [(b"city", b"Chengdu"), (b"language", b"Mandarin")])
+        self.assertEqual(self.test_dict.get_entity(b"Sichuan"), [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")])
+
+        # iterator
+        it = self.test_dict.iter()
+        it.seek_to_first()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), b"Beijing")
+        self.assertEqual(it.value(), b"Beijing")
+        self.assertEqual(it.columns(), [(b"", b"Beijing")])
+        it.next()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), b"Guangdong")
+        self.assertEqual(it.value(), b"")
+        self.assertEqual(it.columns(), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
+        it.next()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), b"Sichuan")
+        self.assertEqual(it.value(), b"")
+        self.assertEqual(it.columns(), [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")])
+
+        # iterators
+        expected = [
+            (b"Beijing", [(b"", b"Beijing")]),
+            (b"Guangdong", [(b"city", b"Shenzhen"), (b"language", b"Cantonese")]),
+            (b"Sichuan", [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")]),
+        ]
+        for i, (key, entity) in enumerate(self.test_dict.entities()):
+            self.assertEqual(key, expected[i][0])
+            self.assertEqual(entity, expected[i][1])
+
+        self.assertEqual(
+            [c for c in self.test_dict.columns()],
+            [
+                [(b"", b"Beijing")],
+                [(b"city", b"Shenzhen"), (b"language", b"Cantonese")],
+                [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")],
+            ]
+        )
+
+        del write_batch
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        assert cls.opt is not None
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestWideColumns(unittest.TestCase):
+    test_dict = None
+    opt = None
+    path = "./temp_wide_columns"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options()
+        cls.opt.create_if_missing(True)
+        cls.test_dict = Rdict(cls.path, cls.opt)
+
+    def test_put_wide_columns(self):
+        assert self.test_dict is not None
+        self.test_dict.put_entity(key="Guangdong", names=["language", "city", "population"], values=["Cantonese", "Shenzhen", 1.27])
+        self.test_dict.put_entity(key="Sichuan", names=["language", "city"], values=["Mandarin", "Chengdu"])
+        self.assertEqual(self.test_dict["Guangdong"], "")
+        self.assertEqual(self.test_dict.get_entity("Guangdong"), [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)])
+        self.assertEqual(self.test_dict["Sichuan"], "")
+        self.assertEqual(self.test_dict.get_entity("Sichuan"), [("city", "Chengdu"), ("language", "Mandarin")])
         # overwrite
-        self.test_dict.put_entity(key=b"Sichuan", names=[b"language", b"city"], values=[b"Sichuanhua", b"Chengdu"])
-        self.test_dict[b"Beijing"] = b"Beijing"
+        self.test_dict.put_entity(key="Sichuan", names=["language", "city"], values=["Sichuanhua", "Chengdu"])
+        self.test_dict["Beijing"] = "Beijing"
+
+        # assertions
+        self.assertEqual(self.test_dict["Beijing"], "Beijing")
+        self.assertEqual(self.test_dict.get_entity("Beijing"), [("", "Beijing")])
+        self.assertEqual(self.test_dict["Guangdong"], "")
+        self.assertEqual(self.test_dict.get_entity("Guangdong"), [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)])
+        self.assertEqual(self.test_dict["Sichuan"], "")
+        self.assertEqual(self.test_dict.get_entity("Sichuan"), [("city", "Chengdu"), ("language", "Sichuanhua")])
+
+        it = self.test_dict.iter()
+        it.seek_to_first()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), "Beijing")
+        self.assertEqual(it.value(), "Beijing")
+        self.assertEqual(it.columns(), [("", "Beijing")])
+        it.next()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), "Guangdong")
+        self.assertEqual(it.value(), "")
+        self.assertEqual(it.columns(), [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)])
+        it.next()
+        self.assertTrue(it.valid())
+        self.assertEqual(it.key(), "Sichuan")
+        self.assertEqual(it.value(), "")
+        self.assertEqual(it.columns(), [("city", "Chengdu"), ("language", "Sichuanhua")])
+
+        # iterators
+        expected = [
+            ("Beijing", [("", "Beijing")]),
+            ("Guangdong", [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)]),
+            ("Sichuan", [("city", "Chengdu"), ("language", "Sichuanhua")]),
+        ]
+        for i, (key, entity) in enumerate(self.test_dict.entities()):
+            self.assertEqual(key, expected[i][0])
+            self.assertEqual(entity, expected[i][1])
+
+        self.assertEqual(
+            [c for c in self.test_dict.columns()],
+            [
+                [("", "Beijing")],
+                [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)],
+                [("city", "Chengdu"), ("language", "Sichuanhua")],
+            ]
+        )
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        assert cls.opt is not None
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestColumnFamiliesDefaultOpts(unittest.TestCase):
+    test_dict = None
+    path = "./column_families"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_column_families(self):
+        assert self.test_dict is not None
+        ds = self.test_dict.create_column_family(name="string")
+        di = self.test_dict.create_column_family(name="integer")
+
+        for i in range(1000):
+            di[i] = i * i
+            ds[str(i)] = str(i * i)
+
+        self.test_dict["ok"] = True
+
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+        # reopen
+        self.test_dict = Rdict(self.path)
+        ds = self.test_dict.get_column_family("string")
+        di = self.test_dict.get_column_family("integer")
+        assert self.test_dict["ok"]
+        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
+        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestColumnFamiliesDefaultOptsCreate(unittest.TestCase):
+    cfs = None
+    test_dict = None
+    path = "./column_families_create"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.cfs = {"string": Options(), "integer": Options()}
+        opt = Options()
+        opt.create_missing_column_families(True)
+        cls.test_dict = Rdict(cls.path, options=opt, column_families=cls.cfs)
+
+    def test_column_families_create(self):
+        assert self.test_dict is not None
+        ds = self.test_dict.get_column_family(name="string")
+        di = self.test_dict.get_column_family(name="integer")
+
+        for i in range(1000):
+            di[i] = i * i
+            ds[str(i)] = str(i * i)
+
+        self.test_dict["ok"] = True
+
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+        # reopen
+        self.test_dict = Rdict(self.path)
+        ds = self.test_dict.get_column_family("string")
+        di = self.test_dict.get_column_family("integer")
+        assert self.test_dict["ok"]
+        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
+        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestColumnFamiliesCustomOpts(unittest.TestCase):
+    cfs = None
+    test_dict = None
+    path = "./column_families_custom_options"
 
-        # assertions
-        self.assertEqual(self.test_dict[b"Beijing"], b"Beijing")
-        self.assertEqual(self.test_dict.get_entity(b"Beijing"), [(b"", b"Beijing")])
-        self.assertEqual(self.test_dict[b"Guangdong"], b"")
-        self.assertEqual(self.test_dict.get_entity(b"Guangdong"), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
-        self.assertEqual(self.test_dict[b"Sichuan"], b"")
-        self.assertEqual(self.test_dict.get_entity(b"Sichuan"), [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")])
+    @classmethod
+    def setUpClass(cls) -> None:
+        plain_opts = Options()
+        plain_opts.create_missing_column_families(True)
+        plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
+        plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
+        cls.cfs = {"string": Options(), "integer": plain_opts}
+        cls.test_dict = Rdict(cls.path, options=plain_opts, column_families=cls.cfs)
+
+    def test_column_families_custom_options_auto_reopen(self):
+        assert self.test_dict is not None
+        ds = self.test_dict.get_column_family(name="string")
+        di = self.test_dict.get_column_family(name="integer")
+
+        for i in range(1000):
+            di[i] = i * i
+            ds[str(i)] = str(i * i)
+
+        self.test_dict["ok"] = True
+
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+        # reopen
+        self.test_dict = Rdict(self.path)
+        ds = self.test_dict.get_column_family("string")
+        di = self.test_dict.get_column_family("integer")
+        assert self.test_dict["ok"]
+        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
+        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestColumnFamiliesCustomOptionsCreate(unittest.TestCase):
+    cfs = None
+    test_dict = None
+    plain_opts = None
+    path = "./column_families_custom_options_create"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.plain_opts = Options()
+        cls.plain_opts.create_missing_column_families(True)
+        cls.plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
+        cls.plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
+        cls.test_dict = Rdict(cls.path, options=cls.plain_opts, column_families=cls.cfs)
+
+    def test_column_families_custom_options_auto_reopen(self):
+        assert self.test_dict is not None
+        ds = self.test_dict.create_column_family(name="string")
+        assert self.plain_opts is not None
+        di = self.test_dict.create_column_family(
+            name="integer", options=self.plain_opts
+        )
+
+        for i in range(1000):
+            di[i] = i * i
+            ds[str(i)] = str(i * i)
+
+        self.test_dict["ok"] = True
+
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+        # reopen
+        self.test_dict = Rdict(self.path)
+        ds = self.test_dict.get_column_family("string")
+        di = self.test_dict.get_column_family("integer")
+        assert self.test_dict["ok"]
+        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
+        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestColumnFamiliesCustomOptionsCreateReopenOverride(unittest.TestCase):
+    cfs = None
+    test_dict = None
+    plain_opts = None
+    path = "./column_families_custom_options_create_reopen_override"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.plain_opts = Options()
+        cls.plain_opts.create_missing_column_families(True)
+        cls.plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
+        cls.plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
+        cls.test_dict = Rdict(cls.path, options=cls.plain_opts)
+
+    def test_column_families_custom_options_auto_reopen_override(self):
+        assert self.test_dict is not None
+        ds = self.test_dict.create_column_family(name="string")
+        assert self.plain_opts is not None
+        di = self.test_dict.create_column_family(
+            name="integer", options=self.plain_opts
+        )
+
+        for i in range(1000):
+            di[i] = i * i
+            ds[str(i)] = str(i * i)
+
+        self.test_dict["ok"] = True
+
+        ds.close()
+        di.close()
+        self.test_dict.close()
+
+        # reopen
+        gc.collect()
+        old_opts, old_cols = Options.load_latest(self.path)
+        old_opts.create_missing_column_families(True)
+        old_cols["bytes"] = self.plain_opts
+        self.test_dict = Rdict(self.path, options=old_opts, column_families=old_cols)
+        ds = self.test_dict.get_column_family("string")
+        di = self.test_dict.get_column_family("integer")
+        db = self.test_dict.get_column_family("bytes")
+        db[b"great"] = b"hello world"
+        assert self.test_dict["ok"]
+        assert db[b"great"] == b"hello world"
+        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
+        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
+        ds.close()
+        di.close()
+        db.close()
+        self.test_dict.close()
+
+        # reopen again auto read config
+        gc.collect()
+        self.test_dict = Rdict(self.path)
+        ds = self.test_dict.get_column_family("string")
+        di = self.test_dict.get_column_family("integer")
+        db = self.test_dict.get_column_family("bytes")
+        db[b"great"] = b"hello world"
+        assert self.test_dict["ok"]
+        assert db[b"great"] == b"hello world"
+        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
+        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
+        ds.close()
+        di.close()
+        db.close()
+        self.test_dict.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestLoadOptionsFromFile(unittest.TestCase):
+    path = "./temp_load_options_from_file"
+
+    def test_load_from_file(self):
+        test_dict = Rdict(self.path, Options(raw_mode=True))
+        test_dict[b"a"] = b"b"
+        test_dict.set_options({"write_buffer_size": "1048576"})
+        # RocksDB only keeps the two most recent OPTIONS files
+        test_dict.create_column_family("cf")
+        test_dict.close()
+        gc.collect()
+
+        options_files = sorted(
+            (f for f in os.listdir(self.path) if f.startswith("OPTIONS-")),
+            key=lambda f: int(f.split("-")[1]),
+        )
+        self.assertGreaterEqual(len(options_files), 2)
+        opts, cols = Options.load_from_file(os.path.join(self.path, options_files[0]))
+        self.assertNotIn("cf", cols)
+        opts, cols = Options.load_from_file(os.path.join(self.path, options_files[-1]))
+        self.assertIn("cf", cols)
+        test_dict = Rdict(self.path, opts, cols)
+        self.assertEqual(test_dict[b"a"], b"b")
+        test_dict.close()
+
+        self.assertRaises(
+            Exception,
+            lambda: Options.load_from_file(os.path.join(self.path, "OPTIONS-no-such-file")),
+        )
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestOptionValidation(unittest.TestCase):
+    path = "./temp_option_validation"
+
+    def test_validate(self):
+        opt = Options()
+        opt.validate()
+        opt.set_memtable_factory(MemtableFactory.vector())
+        self.assertRaisesRegex(ValueError, "allow_concurrent_memtable_write", opt.validate)
+        opt.set_allow_concurrent_memtable_write(False)
+        opt.validate()
+
+        table = BlockBasedOptions()
+        table.set_partition_filters(True)
+        opt.set_block_based_table_factory(table)
+        self.assertRaisesRegex(ValueError, "two_level_index_search", opt.validate)
+        table.set_index_type(BlockBasedIndexType.two_level_index_search())
+        opt.set_block_based_table_factory(table)
+        opt.validate()
+        opt.set_plain_table_factory(PlainTableFactoryOptions())
+        self.assertRaisesRegex(ValueError, "PlainTable", opt.validate)
+
+    def test_set_options_effective_values(self):
+        db = Rdict(self.path)
+        effective = db.set_options({"write_buffer_size": "64m",
+                                    "disable_auto_compactions": "true"})
+        self.assertEqual(effective, {"write_buffer_size": "67108864",
+                                     "disable_auto_compactions": "true"})
+        cf = db.create_column_family("cf", Options())
+        self.assertEqual(cf.set_options({"max_write_buffer_number": "3"}),
+                         {"max_write_buffer_number": "3"})
+        cf.close()
+        db.close()
+
+    def test_set_cf_option_typed(self):
+        path = self.path + "_typed"
+        db = Rdict(path)
+        self.assertEqual(db.set_cf_option_int("max_write_buffer_number", 4), 4)
+        self.assertEqual(db.set_cf_option_int("write_buffer_size", 32 << 20), 32 << 20)
+        self.assertEqual(db.set_cf_option_float("max_bytes_for_level_multiplier", 8.0), 8.0)
+        self.assertTrue(db.set_cf_option_bool("disable_auto_compactions", True))
+        self.assertEqual(db.get_options()["max_write_buffer_number"], "4")
+        # static and misspelled options are rejected before setting anything
+        self.assertRaises(ValueError, db.set_cf_option_int, "num_levels", 4)
+        self.assertRaises(ValueError, db.set_cf_option_int, "write_bufer_size", 1)
+        self.assertRaises(ValueError, db.set_cf_option_int, "write_buffer_size", -1)
+        self.assertRaises(TypeError, db.set_cf_option_int, "disable_auto_compactions", 1)
+        self.assertRaises(TypeError, db.set_cf_option_bool, "write_buffer_size", True)
+        db.close()
+        Rdict.destroy(path)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestVersionedConfig(unittest.TestCase):
+    path = "./temp_versioned_config"
+
+    def config_path(self):
+        return os.path.join(self.path, "rocksdict-config.json")
+
+    def test_legacy_config_migrated(self):
+        test_dict = Rdict(self.path)
+        test_dict[1] = 1
+        test_dict.close()
+        gc.collect()
+        with open(self.config_path(), "w") as f:
+            f.write(dumps({"raw_mode": False, "prefix_extractors": {}}))
+        test_dict = Rdict(self.path)
+        self.assertEqual(test_dict[1], 1)
+        test_dict.close()
+        gc.collect()
+        with open(self.config_path()) as f:
+            config = loads(f.read())
+        self.assertEqual(config["version"], 1)
+        self.assertEqual(config["key_codec"], "rocksdict")
+
+    def test_newer_config_rejected(self):
+        test_dict = Rdict(self.path)
+        test_dict.close()
+        gc.collect()
+        with open(self.config_path()) as f:
+            config = loads(f.read())
+        config["version"] = 999
+        config["unknown_field"] = "ignored"
+        with open(self.config_path(), "w") as f:
+            f.write(dumps(config))
+        self.assertRaises(Exception, lambda: Rdict(self.path))
+        # the config must not be overwritten by the failed open
+        with open(self.config_path()) as f:
+            self.assertEqual(loads(f.read())["version"], 999)
+        config["version"] = 1
+        with open(self.config_path(), "w") as f:
+            f.write(dumps(config))
+        Rdict(self.path).close()
+
+    def tearDown(self):
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestRepair(unittest.TestCase):
+    path = "./temp_repair"
+
+    def test_repair_column_families(self):
+        cf_opts = Options()
+        cf_opts.set_prefix_extractor(SliceTransform.create_fixed_prefix(3))
+        test_dict = Rdict(self.path)
+        cf = test_dict.create_column_family("prefixed", cf_opts)
+        test_dict[1] = 1
+        cf["abc1"] = 1
+        cf.close()
+        test_dict.close()
+        gc.collect()
+
+        Rdict.repair(self.path, Options(), {"prefixed": cf_opts})
+        self.assertRaises(
+            Exception,
+            lambda: Rdict.repair(self.path, Options(), {"prefixed": Options(raw_mode=True)}),
+        )
+        # repair with options loaded from the path
+        Rdict.repair(self.path)
+
+        test_dict = Rdict(self.path)
+        cf = test_dict.get_column_family("prefixed")
+        self.assertEqual(test_dict[1], 1)
+        self.assertEqual(cf["abc1"], 1)
+        cf.close()
+        test_dict.close()
+
+    def test_best_efforts_recovery(self):
+        path = self.path + "_best_efforts"
+        opt = Options()
+        opt.set_disable_auto_compactions(True)
+        db = Rdict(path, opt)
+        db["old"] = 1
+        db.flush()
+        db["new"] = 2
+        db.flush()
+        db.close()
+        # lose the newest SST file, as in an incomplete copy
+        newest = max(f for f in os.listdir(path) if f.endswith(".sst"))
+        os.remove(os.path.join(path, newest))
+        self.assertRaises(Exception, Rdict, path, opt)
+
+        opt.set_best_efforts_recovery(True)
+        db = Rdict(path, opt)
+        self.assertEqual(db["old"], 1)
+        self.assertNotIn("new", db)
+        db.close()
+        Rdict.destroy(path)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestCopyRange(unittest.TestCase):
+    src_path = "./temp_copy_range_src"
+    dst_path = "./temp_copy_range_dst"
+
+    def test_copy_range(self):
+        src = Rdict(self.src_path)
+        dst = Rdict(self.dst_path)
+        cf = dst.create_column_family("cf1", Options())
+        for i in range(1000):
+            src[i] = str(i)
+
+        self.assertEqual(copy_range(src, dst, 100, 200, batch_size=7), 100)
+        self.assertEqual([k for k in dst.keys()], list(range(100, 200)))
+        self.assertEqual(copy_range(src, cf, 500, None, use_ingest=True), 500)
+        self.assertEqual(cf[999], "999")
+        self.assertEqual(len([k for k in cf.keys()]), 500)
+        self.assertEqual(copy_range(src, cf, 2000, 3000, use_ingest=True), 0)
+        self.assertRaises(
+            Exception,
+            lambda: copy_range(src, Rdict.temp(options=Options(raw_mode=True))),
+        )
+
+        cf.close()
+        dst.close()
+        src.close()
+
+    def test_copy_range_value_codecs(self):
+        src = Rdict.temp()
+        dst = Rdict.temp()
+        for i in range(10):
+            src[i] = str(i)
+        # values are copied as stored, so the value codecs must match
+        dst.set_value_compression(threshold=16)
+        self.assertRaises(ValueError, copy_range, src, dst)
+        dst.set_value_compression(threshold=None)
+        key = os.urandom(32)
+        src.set_value_encryption(key)
+        self.assertRaises(ValueError, copy_range, src, dst)
+        dst.set_value_encryption(os.urandom(32))
+        self.assertRaises(ValueError, copy_range, src, dst)
+        dst.set_value_encryption(key)
+        src[10] = "encrypted"
+        self.assertEqual(copy_range(src, dst), 11)
+        self.assertEqual(dst[10], "encrypted")
+
+        # writes of copy_range wake up wait_for callers
+        def copy():
+            time.sleep(0.2)
+            src[20] = "late"
+            copy_range(src, dst, 20, 21)
+
+        copier = Thread(target=copy)
+        copier.start()
+        self.assertEqual(dst.wait_for(20, timeout=10), "late")
+        copier.join()
+        dst.close()
+        src.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.src_path)
+        Rdict.destroy(cls.dst_path)
+
+
+class TestIngestFrom(unittest.TestCase):
+    src_path = "./temp_ingest_from_src"
+    dst_path = "./temp_ingest_from_dst"
+
+    def test_ingest_from(self):
+        src = Rdict(self.src_path)
+        dst = Rdict(self.dst_path)
+        for i in range(1000):
+            src[i] = str(i) * 10
+        dst[-1] = "kept"
+        self.assertEqual(dst.ingest_from(src, 500, file_size=1024), 500)
+        self.assertEqual(dst[-1], "kept")
+        self.assertEqual(dst[999], "999" * 10)
+        self.assertNotIn(499, dst)
+        self.assertGreater(len(dst.live_files()), 1)
+        cf = dst.create_column_family("cf", Options())
+        self.assertEqual(cf.ingest_from(src, None, 10), 10)
+        self.assertEqual(list(cf.keys()), list(range(10)))
+        self.assertEqual(dst.ingest_from(src, 2000, 3000), 0)
+        self.assertRaises(
+            Exception,
+            lambda: Rdict.temp(options=Options(raw_mode=True)).ingest_from(src),
+        )
+        self.assertRaises(ValueError, lambda: dst.ingest_from(src, file_size=0))
+        cf.close()
+        dst.close()
+        src.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.src_path)
+        Rdict.destroy(cls.dst_path)
+
+
+class TestSplit(unittest.TestCase):
+    path = "./temp_split"
+    shard_paths = ["./temp_split_0", "./temp_split_1", "./temp_split_2"]
+
+    def test_split(self):
+        db = Rdict(self.path)
+        cf = db.create_column_family("cf", Options())
+        for i in range(300):
+            db[i] = i
+            cf[i] = str(i)
+        del cf
+        db.close()
+        self.assertRaises(ValueError, lambda: split(self.path, self.shard_paths, [100]))
+        self.assertRaises(
+            ValueError, lambda: split(self.path, self.shard_paths, [200, 100])
+        )
+        self.assertEqual(split(self.path, self.shard_paths, [100, 200]), [200, 200, 200])
+        for i, shard_path in enumerate(self.shard_paths):
+            shard = Rdict(shard_path)
+            self.assertEqual(list(shard.keys()), list(range(i * 100, (i + 1) * 100)))
+            shard_cf = shard.get_column_family("cf")
+            self.assertEqual(shard_cf[i * 100], str(i * 100))
+            del shard_cf
+            shard.close()
+        # the shards already exist
+        self.assertRaises(
+            Exception, lambda: split(self.path, self.shard_paths, [100, 200])
+        )
+
+    def test_split_key_codecs(self):
+        path = self.path + "_codecs"
+        shard_paths = [p + "_codecs" for p in self.shard_paths]
+        db = Rdict(path)
+        u64_opt = Options()
+        u64_opt.set_key_codec("u64_be")
+        ids = db.create_column_family("ids", u64_opt)
+        for i in range(300):
+            db[i] = i
+            ids[i] = i
+        del ids
+        db.close()
+        self.assertEqual(split(path, shard_paths, [100, 200]), [200, 200, 200])
+        for i, shard_path in enumerate(shard_paths):
+            shard = Rdict(shard_path)
+            expected = list(range(i * 100, (i + 1) * 100))
+            self.assertEqual(list(shard.keys()), expected)
+            # the boundaries are encoded by the key codec of each column family
+            shard_ids = shard.get_column_family("ids")
+            self.assertEqual(list(shard_ids.keys()), expected)
+            del shard_ids
+            shard.close()
+        gc.collect()
+        for p in [path] + shard_paths:
+            Rdict.destroy(p)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        for path in [cls.path] + cls.shard_paths:
+            Rdict.destroy(path)
+
+
+class TestMergeDbs(unittest.TestCase):
+    src_paths = ["./temp_merge_dbs_0", "./temp_merge_dbs_1"]
+    dst_path = "./temp_merge_dbs_dst"
+    error_path = "./temp_merge_dbs_error"
+
+    def test_merge_dbs(self):
+        for day, src_path in enumerate(self.src_paths):
+            db = Rdict(src_path)
+            cf = db.create_column_family(f"day_{day}", Options())
+            for i in range(100):
+                db[day * 50 + i] = day
+                cf[i] = i
+            del cf
+            db.close()
+        self.assertRaises(ValueError, lambda: merge_dbs(self.src_paths, self.dst_path, "oldest"))
+        with self.assertRaises(ValueError) as cm:
+            merge_dbs(self.src_paths, self.error_path, conflict="error")
+        self.assertIn("50", str(cm.exception))
+        self.assertFalse(os.path.exists(self.error_path))
+        self.assertEqual(merge_dbs(self.src_paths, self.dst_path), 400)
+        db = Rdict(self.dst_path)
+        self.assertEqual(list(db.keys()), list(range(150)))
+        # later sources win
+        self.assertEqual(db[49], 0)
+        self.assertEqual(db[50], 1)
+        for day in range(2):
+            cf = db.get_column_family(f"day_{day}")
+            self.assertEqual(cf[99], 99)
+            del cf
+        db.close()
+
+    def test_merge_key_codecs(self):
+        src_paths = [p + "_codecs" for p in self.src_paths] + ["./temp_merge_dbs_codecs_2"]
+        dst_path = self.dst_path + "_codecs"
+        u64_opt = Options()
+        u64_opt.set_key_codec("u64_be")
+        for index, src_path in enumerate(src_paths):
+            db = Rdict(src_path)
+            # "ids" uses another key codec than the database, except in the last source
+            ids = db.create_column_family("ids", u64_opt if index < 2 else Options())
+            for i in range(index * 10, index * 10 + 20):
+                ids[i] = index
+            del ids
+            if index == 1:
+                # only in the second source
+                extra = db.create_column_family("extra", u64_opt)
+                extra[2**64 - 1] = "max"
+                del extra
+            db.close()
+        with self.assertRaises(ValueError) as cm:
+            merge_dbs(src_paths, dst_path, conflict="error")
+        self.assertIn("key 10 of column family ids", str(cm.exception))
+        with self.assertRaises(ValueError) as cm:
+            merge_dbs(src_paths, dst_path)
+        self.assertIn("key codec", str(cm.exception))
+        self.assertFalse(os.path.exists(dst_path))
+        self.assertEqual(merge_dbs(src_paths[:2], dst_path), 41)
+        db = Rdict(dst_path)
+        ids = db.get_column_family("ids")
+        self.assertEqual(list(ids.keys()), list(range(30)))
+        self.assertEqual(ids[15], 1)
+        extra = db.get_column_family("extra")
+        self.assertEqual(list(extra.items()), [(2**64 - 1, "max")])
+        del ids, extra
+        db.close()
+        gc.collect()
+        for path in src_paths + [dst_path]:
+            Rdict.destroy(path)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        for path in cls.src_paths + [cls.dst_path, cls.error_path]:
+            Rdict.destroy(path)
+
+
+class TestIngestPerColumnFamily(unittest.TestCase):
+    path = "./temp_ingest_per_cf"
+    sst_dir = "./temp_ingest_per_cf_sst"
+
+    def write_sst(self, name, items):
+        path = os.path.join(self.sst_dir, name)
+        writer = SstFileWriter()
+        writer.open(path)
+        for k, v in items:
+            writer[k] = v
+        writer.finish()
+        return path
+
+    def test_ingest_per_cf(self):
+        os.makedirs(self.sst_dir, exist_ok=True)
+        db = Rdict(self.path)
+        cf = db.create_column_family("cf1", Options())
+        default_sst = self.write_sst("default.sst", [(i, i) for i in range(10)])
+        cf_sst_0 = self.write_sst("cf_0.sst", [(i, str(i)) for i in range(5)])
+        cf_sst_1 = self.write_sst("cf_1.sst", [(i, str(i)) for i in range(5, 10)])
+
+        db.ingest_external_file({"default": [default_sst], "cf1": [cf_sst_0, cf_sst_1]})
+        self.assertEqual(list(db.items()), [(i, i) for i in range(10)])
+        self.assertEqual(list(cf.items()), [(i, str(i)) for i in range(10)])
+
+        # unknown column families fail before anything is ingested
+        other_sst = self.write_sst("other.sst", [(100, 100)])
+        with self.assertRaises(Exception):
+            db.ingest_external_file({"default": [other_sst], "missing": [other_sst]})
+        self.assertNotIn(100, db)
+
+        cf.close()
+        db.close()
 
-        # iterator
-        it = self.test_dict.iter()
-        it.seek_to_first()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), b"Beijing")
-        self.assertEqual(it.value(), b"Beijing")
-        self.assertEqual(it.columns(), [(b"", b"Beijing")])
-        it.next()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), b"Guangdong")
-        self.assertEqual(it.value(), b"")
-        self.assertEqual(it.columns(), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
-        it.next()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), b"Sichuan")
-        self.assertEqual(it.value(), b"")
-        self.assertEqual(it.columns(), [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")])
+    def test_ingest_results(self):
+        os.makedirs(self.sst_dir, exist_ok=True)
+        db = Rdict(self.path)
+        paths = [self.write_sst(f"results_{i}.sst", [(f"results_{i}_{j}", j) for j in range(10)])
+                 for i in range(3)]
+        # overlaps the first file in the memtable
+        db["results_0_5"] = -1
+        progress = []
+        results = db.ingest_external_file(paths, progress=lambda *p: progress.append(p),
+                                          chunk_size=2)
+        self.assertEqual(progress, [(2, 3), (3, 3)])
+        self.assertEqual([r["path"] for r in results], paths)
+        self.assertEqual({r["column_family"] for r in results}, {"default"})
+        self.assertTrue(results[0]["flushed"])
+        self.assertGreater(results[0]["global_seqno"], 0)
+        self.assertFalse(results[2]["flushed"])
+        live_files = {f["name"]: f["level"] for f in db.live_files()}
+        for result in results:
+            self.assertEqual(live_files[result["name"]], result["level"])
+        self.assertEqual(db["results_0_5"], 5)
+        self.assertRaises(ValueError, db.ingest_external_file, paths, chunk_size=0)
+        db.close()
 
-        # iterators
-        expected = [
-            (b"Beijing", [(b"", b"Beijing")]),
-            (b"Guangdong", [(b"city", b"Shenzhen"), (b"language", b"Cantonese")]),
-            (b"Sichuan", [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")]),
-        ]
-        for i, (key, entity) in enumerate(self.test_dict.entities()):
-            self.assertEqual(key, expected[i][0])
-            self.assertEqual(entity, expected[i][1])
+    def test_sst_compat(self):
+        os.makedirs(self.sst_dir, exist_ok=True)
+        db = Rdict(self.path)
+        for compat in ["rocksdb-7", "legacy"]:
+            path = os.path.join(self.sst_dir, f"{compat}.sst")
+            writer = SstFileWriter(compat=compat)
+            writer.open(path)
+            writer[compat] = compat
+            writer.finish()
+            db.ingest_external_file([path])
+            self.assertEqual(db[compat], compat)
+        self.assertRaises(ValueError, lambda: SstFileWriter(compat="rocksdb-1"))
+        db.close()
 
-        self.assertEqual(
-            [c for c in self.test_dict.columns()],
-            [
-                [(b"", b"Beijing")],
-                [(b"city", b"Shenzhen"), (b"language", b"Cantonese")],
-                [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")],
-            ]
+    def test_sst_table_options(self):
+        os.makedirs(self.sst_dir, exist_ok=True)
+        db = Rdict(self.path)
+        table_options = BlockBasedOptions()
+        table_options.set_block_size(4096)
+        writers = [
+            SstFileWriter(compression=DBCompressionType.none(), block_size=1024),
+            SstFileWriter(table_options=table_options),
+            SstFileWriter(compat="legacy", block_size=1024),
+        ]
+        for i, writer in enumerate(writers):
+            path = os.path.join(self.sst_dir, f"table_{i}.sst")
+            writer.open(path)
+            for j in range(100):
+                writer[f"table_{i}_{j:03}"] = "v" * 100
+            writer.finish()
+            db.ingest_external_file([path])
+            self.assertEqual(db[f"table_{i}_099"], "v" * 100)
+        self.assertRaises(
+            ValueError,
+            lambda: SstFileWriter(compat="legacy", table_options=table_options),
         )
+        self.assertRaises(
+            ValueError,
+            lambda: SstFileWriter(table_options=table_options, block_size=1024),
+        )
+        db.close()
+
+    def test_sst_write_sorted(self):
+        os.makedirs(self.sst_dir, exist_ok=True)
+        db = Rdict(self.path)
+        path = os.path.join(self.sst_dir, "sorted.sst")
+        writer = SstFileWriter()
+        writer.open(path)
+        progress = []
+        # int keys are ordered numerically
+        written = writer.write_sorted(((i, i * i) for i in range(-50, 50)),
+                                      progress=progress.append, chunk_size=30)
+        self.assertEqual(written, 100)
+        self.assertEqual(progress, [30, 60, 90, 100])
+        writer.finish()
+        db.ingest_external_file([path])
+        self.assertEqual(db[-7], 49)
+
+        # pairs before an unordered key are written
+        path = os.path.join(self.sst_dir, "unordered.sst")
+        writer = SstFileWriter()
+        writer.open(path)
+        with self.assertRaises(ValueError):
+            writer.write_sorted([(100, 1), (102, 2), (101, 3)])
+        # keys added by an earlier call are checked by RocksDB
+        self.assertRaises(RocksDBError, writer.write_sorted, [(102, 2)])
+        self.assertRaises(ValueError, writer.write_sorted, [], chunk_size=0)
+        writer.finish()
+        db.ingest_external_file([path])
+        self.assertEqual(db[102], 2)
+        self.assertNotIn(101, db)
+        db.close()
 
     @classmethod
     def tearDownClass(cls):
-        assert cls.test_dict is not None
-        cls.test_dict.close()
-        assert cls.opt is not None
         gc.collect()
-        Rdict.destroy(cls.path, cls.opt)
+        Rdict.destroy(cls.path)
+        shutil.rmtree(cls.sst_dir, ignore_errors=True)
+
+
+class TestReplication(unittest.TestCase):
+    primary_path = "./temp_replication_primary"
+    replica_path = "./temp_replication_replica"
+
+    def test_ship_and_apply_updates(self):
+        primary = Rdict(self.primary_path)
+        cf = primary.create_column_family("cf1", Options())
+        primary["init"] = 0
+        primary.clone_to(self.replica_path)
+        replica = Rdict(self.replica_path)
+        replica_cf = replica.get_column_family("cf1")
+        self.assertIsNone(replica.apply_updates([]))
+
+        seq = primary.latest_sequence_number() + 1
+        for i in range(10):
+            primary[i] = i
+        wb = WriteBatch()
+        wb.set_default_column_family(primary.get_column_family_handle("cf1"))
+        wb.put("in_cf", 1)
+        wb.delete("missing")
+        primary.write(wb)
+        del primary["init"]
+
+        updates = list(primary.ship_updates_since(seq))
+        self.assertEqual(updates[0][0], seq)
+        last_seq = replica.apply_updates(updates)
+        self.assertEqual(last_seq, primary.latest_sequence_number())
+        for i in range(10):
+            self.assertEqual(replica[i], i)
+        self.assertEqual(replica_cf["in_cf"], 1)
+        self.assertNotIn("init", replica)
+
+        replica_cf.close()
+        replica.close()
+        cf.close()
+        primary.close()
+
+    def test_apply_batch_bytes(self):
+        primary = Rdict(self.primary_path + "_batch")
+        primary["init"] = 0
+        primary.clone_to(self.replica_path + "_batch")
+        replica = Rdict(self.replica_path + "_batch")
+        cf = primary.create_column_family("cf_batch", Options())
+
+        seq = primary.latest_sequence_number() + 1
+        primary["key"] = "value"
+        cf["key"] = "cf_value"
+        batches = [batch for _, batch in primary.ship_updates_since(seq)]
+        replica.apply_batch_bytes(batches[0])
+        self.assertEqual(replica["key"], "value")
+        # the column family does not exist in the replica
+        self.assertRaises(Exception, replica.apply_batch_bytes, batches[1])
+        self.assertRaises(Exception, replica.apply_batch_bytes, b"invalid")
+        self.assertRaises(Exception, replica.apply_batch_bytes, batches[0][:-1])
+
+        replica.close()
+        cf.close()
+        primary.close()
+
+    @staticmethod
+    def wal_batches(path: str) -> list:
+        # the write batches logged in the WAL files of a database
+        batches = []
+        for name in sorted(f for f in os.listdir(path) if f.endswith(".log")):
+            data = Path(path, name).read_bytes()
+            pos, fragments = 0, b""
+            while pos + 7 <= len(data):
+                # a block trailer shorter than a record header is padding
+                if 32768 - pos % 32768 < 7:
+                    pos += 32768 - pos % 32768
+                    continue
+                length = int.from_bytes(data[pos + 4:pos + 6], "little")
+                record_type = data[pos + 6]
+                payload = data[pos + 7:pos + 7 + length]
+                pos += 7 + length
+                if record_type == 0:
+                    break
+                if record_type in (1, 2):
+                    fragments = payload
+                elif record_type in (3, 4):
+                    fragments += payload
+                if record_type in (1, 4):
+                    batches.append(fragments)
+        return batches
+
+    def test_apply_two_phase_commit_batches(self):
+        txn_db = TransactionDb(self.primary_path + "_2pc")
+        txn = txn_db.begin_transaction()
+        txn.set_name("replicated")
+        txn["prepared"] = "value"
+        txn.prepare()
+        txn.commit()
+        replica = Rdict(self.replica_path + "_2pc")
+        # begin prepare, the put and end prepare, then the commit marker
+        batches = self.wal_batches(self.primary_path + "_2pc")
+        self.assertGreaterEqual(len(batches), 2)
+        for batch in batches:
+            replica.apply_batch_bytes(batch)
+        self.assertEqual(replica["prepared"], "value")
+
+        def record(tag: int, *slices: bytes) -> bytes:
+            return bytes([tag]) + b"".join(bytes([len(s)]) + s for s in slices)
+
+        # begin persisted prepare and commit with timestamp
+        prepared = (bytes(8) + (1).to_bytes(4, "little") + record(0x12)
+                    + record(0x1, b"\x02persisted", b"\x02value") + record(0xA, b"xid"))
+        committed = bytes(12) + record(0x15, bytes(8), b"xid")
+        replica.apply_batch_bytes(prepared)
+        replica.apply_batch_bytes(committed)
+        self.assertEqual(replica["persisted"], "value")
+        replica.close()
+        txn_db.close()
+
+    def test_latest_sequence_for_key(self):
+        db = Rdict(self.primary_path + "_seq")
+        cf = db.create_column_family("cf_seq", Options())
+        seq = db.latest_sequence_number() + 1
+        db[1] = "a"
+        db[2] = "b"
+        cf[1] = "c"
+        self.assertEqual(db.latest_sequence_for_key(1, seq), seq)
+        self.assertEqual(db.latest_sequence_for_key(2, seq), seq + 1)
+        self.assertEqual(cf.latest_sequence_for_key(1, seq), seq + 2)
+        self.assertIsNone(cf.latest_sequence_for_key(2, seq))
+        self.assertIsNone(db.latest_sequence_for_key(1, seq + 1))
+        self.assertIsNone(db.latest_sequence_for_key(1, seq + 100))
+        db.delete_range(0, 2)
+        self.assertEqual(db.latest_sequence_for_key(1, seq),
+                         db.latest_sequence_number())
+        self.assertEqual(db.latest_sequence_for_key(2, seq), seq + 1)
+        cf.close()
+        db.close()
 
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.primary_path)
+        Rdict.destroy(cls.replica_path)
+        Rdict.destroy(cls.primary_path + "_batch")
+        Rdict.destroy(cls.replica_path + "_batch")
+        Rdict.destroy(cls.primary_path + "_seq")
+        Rdict.destroy(cls.primary_path + "_2pc")
+        Rdict.destroy(cls.replica_path + "_2pc")
+
+
+class TestSkipWalRecovery(unittest.TestCase):
+    path = "./temp_skip_wal_recovery"
+
+    def test_skip_wal_recovery(self):
+        db = Rdict(self.path)
+        db["flushed"] = 1
+        db.flush()
+        db["unflushed"] = 2
+        db.flush_wal()
+        # copies of the open database, as left by a crash
+        shutil.copytree(self.path, self.path + "_replay")
+        shutil.copytree(self.path, self.path + "_skip")
+        self.assertRaises(Exception, Rdict, self.path, skip_wal_recovery=True)
+        db.close()
 
-class TestWriteBatchWideColumnsRaw(unittest.TestCase):
-    test_dict = None
-    opt = None
-    path = "./temp_write_batch_wide_columns_raw"
+        replayed = Rdict(self.path + "_replay")
+        self.assertEqual(replayed["unflushed"], 2)
+        replayed.close()
+        skipped = Rdict(self.path + "_skip", skip_wal_recovery=True)
+        self.assertEqual(skipped["flushed"], 1)
+        self.assertNotIn("unflushed", skipped)
+        skipped["new"] = 3
+        skipped.close()
+        skipped = Rdict(self.path + "_skip")
+        self.assertEqual(skipped["new"], 3)
+        skipped.close()
+        self.assertRaises(ValueError, Rdict, self.path, skip_wal_recovery=True,
+                          access_type=AccessType.read_only())
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.opt = Options(True)
-        cls.opt.create_if_missing(True)
-        cls.test_dict = Rdict(cls.path, cls.opt)
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+        Rdict.destroy(cls.path + "_replay")
+        Rdict.destroy(cls.path + "_skip")
 
-    def test_put_wide_columns(self):
-        assert self.test_dict is not None
-        write_batch = WriteBatch(raw_mode=True)
-        default_cf_handle = self.test_dict.get_column_family_handle("default")
-        write_batch.set_default_column_family(default_cf_handle)
-        write_batch.put_entity(key=b"Guangdong", names=[b"language", b"city"], values=[b"Cantonese", b"Shenzhen"])
-        write_batch.put_entity(key=b"Sichuan", names=[b"language", b"city"], values=[b"Mandarin", b"Chengdu"])
-        # overwrite
-        write_batch.put_entity(key=b"Sichuan", names=[b"language", b"city"], values=[b"Sichuanhua", b"Chengdu"])
-        write_batch[b"Beijing"] = b"Beijing"
 
-        self.test_dict.write(write_batch)
+class TestWalArchive(unittest.TestCase):
+    path = "./temp_wal_archive"
 
-        # assertions
-        self.assertEqual(self.test_dict[b"Beijing"], b"Beijing")
-        self.assertEqual(self.test_dict.get_entity(b"Beijing"), [(b"", b"Beijing")])
-        self.assertEqual(self.test_dict[b"Guangdong"], b"")
-        self.assertEqual(self.test_dict.get_entity(b"Guangdong"), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
-        self.assertEqual(self.test_dict[b"Sichuan"], b"")
-        self.assertEqual(self.test_dict.get_entity(b"Sichuan"), [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")])
+    def test_sorted_and_purge_wal_files(self):
+        opt = Options()
+        opt.set_wal_ttl_seconds(3600)
+        db = Rdict(self.path, opt)
+        for i in range(10):
+            db[i] = i
+        seq = db.latest_sequence_number() + 1
+        db.flush()
+        db[10] = 10
+        # obsolete WAL files are archived in the background after flushing
+        for _ in range(50):
+            archived = [f for f in db.get_sorted_wal_files() if f["archived"]]
+            if archived:
+                break
+            time.sleep(0.1)
+        self.assertEqual(archived[0]["start_sequence"], 1)
+        self.assertTrue(archived[0]["name"].startswith("/archive/"))
+        self.assertGreater(archived[0]["size"], 0)
+        files = db.get_sorted_wal_files()
+        self.assertEqual([f["log_number"] for f in files],
+                         sorted(f["log_number"] for f in files))
+        self.assertFalse(files[-1]["archived"])
+
+        self.assertEqual(db.purge_wal_files(seq - 1), [])
+        purged = db.purge_wal_files(seq)
+        self.assertEqual([f["name"] for f in purged], [f["name"] for f in archived])
+        self.assertFalse(any(f["archived"] for f in db.get_sorted_wal_files()))
+        self.assertEqual([u[0] for u in db.ship_updates_since(seq)], [seq])
+        db.close()
 
-        # iterator
-        it = self.test_dict.iter()
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestSnapshot(unittest.TestCase):
+    path = "./temp_snapshot"
+
+    def test_snapshot_reads(self):
+        db = Rdict(self.path)
+        for i in range(10):
+            db[i] = i
+        snapshot = db.snapshot()
+        for i in range(5):
+            del db[i]
+        db[10] = 10
+
+        self.assertEqual(snapshot[0], 0)
+        self.assertRaises(KeyError, lambda: snapshot[10])
+        self.assertEqual(snapshot[[0, 5, 10]], [0, 5, None])
+        self.assertEqual(snapshot.get([4, 3], sorted_input=False), [4, 3])
+        self.assertEqual(snapshot.get(10, "missing"), "missing")
+        self.assertIsNone(snapshot.get(10))
+        self.assertEqual(snapshot.get(0, read_opt=ReadOptions()), 0)
+        self.assertEqual(snapshot.get(10, -1, ReadOptions()), -1)
+        with self.assertRaisesRegex(KeyError, "10"):
+            snapshot[10]
+        self.assertTrue(snapshot.key_may_exist(0))
+        may_exist, value = snapshot.key_may_exist(0, fetch=True)
+        self.assertTrue(may_exist)
+        self.assertIn(value, [0, None])
+        # iterators read the snapshot, not the live database
+        self.assertEqual(list(snapshot.keys()), list(range(10)))
+        self.assertEqual(list(db.keys()), list(range(5, 11)))
+
+        del snapshot
+        db.close()
+
+    def test_snapshot_context_manager(self):
+        db = Rdict(self.path)
+        db["key"] = "old"
+        with db.snapshot() as snapshot:
+            db["key"] = "new"
+            self.assertEqual(snapshot["key"], "old")
+            iterator = snapshot.iter()
+        self.assertRaises(DbClosedError, lambda: snapshot["key"])
+        self.assertRaises(DbClosedError, lambda: snapshot.get("key"))
+        self.assertRaises(DbClosedError, lambda: snapshot.iter())
+        # iterators created before the release stay valid
+        iterator.seek("key")
+        self.assertEqual(iterator.value(), "old")
+        del iterator
+        snapshot.close()
+        # the released snapshot no longer keeps the database open
+        db.close()
+        Rdict(self.path).close()
+
+    def test_consistent_view(self):
+        db = Rdict(self.path + "_view")
+        index = db.create_column_family("index")
+        db["record"] = "v1"
+        index["v1"] = "record"
+        view = db.consistent_view()
+        sequence = view.sequence_number()
+        wb = WriteBatch()
+        wb.put("record", "v2")
+        wb.delete("v1", index.get_column_family_handle("index"))
+        wb.put("v2", "record", index.get_column_family_handle("index"))
+        db.write(wb)
+        self.assertEqual(view.sequence_number(), sequence)
+        records, entries = view["default"], view.column_family("index")
+        self.assertEqual(dict(entries.items()), {"v1": "record"})
+        self.assertEqual(records[entries["v1"]], "v1")
+        self.assertEqual(dict(index.items()), {"v2": "record"})
+        self.assertRaises(Exception, view.column_family, "missing")
+        self.assertEqual(db.open_handles()["consistent view"], 1)
+        view.close()
+        self.assertRaises(DbClosedError, view.column_family)
+        # snapshots of a closed view stay valid
+        self.assertEqual(records["record"], "v1")
+        del records, entries, index, wb
+        gc.collect()
+        self.assertEqual(db.open_handles(), {})
+        db.close()
+        Rdict.destroy(self.path + "_view")
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestIterClose(unittest.TestCase):
+    path = "./temp_iter_close"
+
+    def test_iter_close(self):
+        db = Rdict(self.path)
+        for i in range(10):
+            db[i] = i
+        with db.iter() as it:
+            it.seek_to_first()
+            self.assertEqual(it.key(), 0)
+        self.assertFalse(it.valid())
+        self.assertIsNone(it.key())
         it.seek_to_first()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), b"Beijing")
-        self.assertEqual(it.value(), b"Beijing")
-        self.assertEqual(it.columns(), [(b"", b"Beijing")])
-        it.next()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), b"Guangdong")
-        self.assertEqual(it.value(), b"")
-        self.assertEqual(it.columns(), [(b"city", b"Shenzhen"), (b"language", b"Cantonese")])
         it.next()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), b"Sichuan")
-        self.assertEqual(it.value(), b"")
-        self.assertEqual(it.columns(), [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")])
+        self.assertFalse(it.valid())
+        self.assertRaises(DbClosedError, it.status)
 
-        # iterators
-        expected = [
-            (b"Beijing", [(b"", b"Beijing")]),
-            (b"Guangdong", [(b"city", b"Shenzhen"), (b"language", b"Cantonese")]),
-            (b"Sichuan", [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")]),
-        ]
-        for i, (key, entity) in enumerate(self.test_dict.entities()):
-            self.assertEqual(key, expected[i][0])
-            self.assertEqual(entity, expected[i][1])
+        items = db.items()
+        self.assertEqual(next(items), (0, 0))
+        items.close()
+        self.assertEqual(list(items), [])
+        with db.keys(from_key=5) as keys:
+            self.assertEqual(list(keys), [5, 6, 7, 8, 9])
 
-        self.assertEqual(
-            [c for c in self.test_dict.columns()],
-            [
-                [(b"", b"Beijing")],
-                [(b"city", b"Shenzhen"), (b"language", b"Cantonese")],
-                [(b"city", b"Chengdu"), (b"language", b"Sichuanhua")],
-            ]
-        )
+        # closed iterators no longer keep the database open
+        db.close()
+        Rdict(self.path).close()
+
+    def test_force_close(self):
+        db = Rdict(self.path)
+        db[0] = 0
+        it = db.iter()
+        snapshot = db.snapshot()
+        cf = db.get_column_family_handle("default")
+        self.assertEqual(db.open_handles(),
+                         {"iterator": 1, "snapshot": 1, "column family handle": 1})
+        del cf
+        db.close(force=True)
+        self.assertFalse(it.valid())
+        self.assertRaises(DbClosedError, it.status)
+        self.assertRaises(DbClosedError, snapshot.get, 0)
+        # the database is actually closed
+        db = Rdict(self.path)
+        self.assertEqual(db[0], 0)
+        self.assertEqual(db.open_handles(), {})
+        db.close()
 
-        del write_batch
+    def test_pooled_iter(self):
+        path = self.path + "_pool"
+        db = Rdict(path)
+        db[0] = 0
+        with db.pooled_iter() as it:
+            it.seek_to_first()
+            self.assertEqual(it.key(), 0)
+        self.assertFalse(it.valid())
+        self.assertRaises(DbClosedError, it.status)
+        # the closed iterator is kept in the pool
+        self.assertEqual(db.open_handles(), {"iterator": 1})
+        db[1] = 1
+        it = db.pooled_iter()
+        self.assertEqual(db.open_handles(), {"iterator": 1})
+        # refreshed to read the latest writes
+        it.seek_to_first()
+        self.assertEqual(it.get_chunk_keys(), [0, 1])
+        other = db.pooled_iter()
+        self.assertEqual(db.open_handles(), {"iterator": 2})
+        # garbage collected iterators also return to the pool
+        del it, other
+        gc.collect()
+        self.assertEqual(db.open_handles(), {"iterator": 2})
+        # closing the database drops idle iterators
+        db.close()
+        db = Rdict(path)
+        self.assertEqual(db[1], 1)
+        db.close()
+        Rdict.destroy(path)
 
     @classmethod
     def tearDownClass(cls):
-        assert cls.test_dict is not None
-        cls.test_dict.close()
-        assert cls.opt is not None
         gc.collect()
-        Rdict.destroy(cls.path, cls.opt)
+        Rdict.destroy(cls.path)
 
 
-class TestWideColumns(unittest.TestCase):
-    test_dict = None
-    opt = None
-    path = "./temp_wide_columns"
+class TestReopen(unittest.TestCase):
+    path = "./temp_reopen"
+
+    def test_reopen(self):
+        db = Rdict(self.path)
+        cf = db.create_column_family("cf", Options())
+        db["key"] = "value"
+        cf["key"] = "cf_value"
+        it = db.iter()
+        opt = Options()
+        opt.set_max_open_files(100)
+        db.reopen(opt)
+        # iterators are released
+        self.assertRaises(DbClosedError, it.status)
+        self.assertEqual(db["key"], "value")
+        self.assertEqual(cf["key"], "cf_value")
+        cf["key2"] = "cf_value2"
+        db.reopen(access_type=AccessType.read_only())
+        self.assertEqual(cf["key2"], "cf_value2")
+        with self.assertRaises(Exception):
+            db["key3"] = "value3"
+        db.reopen(access_type=AccessType.read_write())
+        db["key3"] = "value3"
+        cf.close()
+        db.close()
+
+    def test_reopen_referenced(self):
+        db = Rdict(self.path)
+        handle = db.get_column_family_handle("default")
+        self.assertRaises(Exception, db.reopen)
+        # still open after a failed reopen
+        db["key"] = "value"
+        del handle
+        db.reopen()
+        self.assertEqual(db["key"], "value")
+        db.close()
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.opt = Options()
-        cls.opt.create_if_missing(True)
-        cls.test_dict = Rdict(cls.path, cls.opt)
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
 
-    def test_put_wide_columns(self):
-        assert self.test_dict is not None
-        self.test_dict.put_entity(key="Guangdong", names=["language", "city", "population"], values=["Cantonese", "Shenzhen", 1.27])
-        self.test_dict.put_entity(key="Sichuan", names=["language", "city"], values=["Mandarin", "Chengdu"])
-        self.assertEqual(self.test_dict["Guangdong"], "")
-        self.assertEqual(self.test_dict.get_entity("Guangdong"), [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)])
-        self.assertEqual(self.test_dict["Sichuan"], "")
-        self.assertEqual(self.test_dict.get_entity("Sichuan"), [("city", "Chengdu"), ("language", "Mandarin")])
-        # overwrite
-        self.test_dict.put_entity(key="Sichuan", names=["language", "city"], values=["Sichuanhua", "Chengdu"])
-        self.test_dict["Beijing"] = "Beijing"
 
-        # assertions
-        self.assertEqual(self.test_dict["Beijing"], "Beijing")
-        self.assertEqual(self.test_dict.get_entity("Beijing"), [("", "Beijing")])
-        self.assertEqual(self.test_dict["Guangdong"], "")
-        self.assertEqual(self.test_dict.get_entity("Guangdong"), [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)])
-        self.assertEqual(self.test_dict["Sichuan"], "")
-        self.assertEqual(self.test_dict.get_entity("Sichuan"), [("city", "Chengdu"), ("language", "Sichuanhua")])
+class TestValueEncryption(unittest.TestCase):
+    path = "./temp_value_encryption"
+
+    def test_value_encryption(self):
+        key = os.urandom(32)
+        db = Rdict(self.path)
+        cf = db.create_column_family("secrets", Options())
+        cf["plain"] = "written before"
+        cf.set_value_encryption(key)
+        cf["str"] = "secret"
+        cf["int"] = 42
+        cf["obj"] = {"a": [1, 2]}
+        self.assertEqual(cf["plain"], "written before")
+        self.assertEqual(cf["str"], "secret")
+        self.assertEqual(cf.get(["int", "obj", "missing"]), [42, {"a": [1, 2]}, None])
+        self.assertEqual(dict(cf.items()), {
+            "plain": "written before", "str": "secret", "int": 42, "obj": {"a": [1, 2]}})
+        with cf.snapshot() as snapshot:
+            self.assertEqual(snapshot["str"], "secret")
+        self.assertRaises(ValueError, cf.set_value_encryption, b"short")
+        cf.close()
+        db.close()
 
-        it = self.test_dict.iter()
-        it.seek_to_first()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), "Beijing")
-        self.assertEqual(it.value(), "Beijing")
-        self.assertEqual(it.columns(), [("", "Beijing")])
-        it.next()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), "Guangdong")
-        self.assertEqual(it.value(), "")
-        self.assertEqual(it.columns(), [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)])
-        it.next()
-        self.assertTrue(it.valid())
-        self.assertEqual(it.key(), "Sichuan")
-        self.assertEqual(it.value(), "")
-        self.assertEqual(it.columns(), [("city", "Chengdu"), ("language", "Sichuanhua")])
+        db = Rdict(self.path)
+        cf = db.get_column_family("secrets")
+        # the key is not stored
+        self.assertRaises(Exception, cf.__getitem__, "str")
+        cf.set_value_encryption(os.urandom(32))
+        self.assertRaises(Exception, cf.__getitem__, "str")
+        cf.set_value_encryption(key)
+        self.assertEqual(cf["str"], "secret")
+        cf.close()
+        db.close()
 
-        # iterators
-        expected = [
-            ("Beijing", [("", "Beijing")]),
-            ("Guangdong", [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)]),
-            ("Sichuan", [("city", "Chengdu"), ("language", "Sichuanhua")]),
-        ]
-        for i, (key, entity) in enumerate(self.test_dict.entities()):
-            self.assertEqual(key, expected[i][0])
-            self.assertEqual(entity, expected[i][1])
+    def test_bound_to_key_and_column_family(self):
+        key = os.urandom(32)
+        db = Rdict(self.path + "_bound")
+        db.set_value_encryption(key)
+        seq = db.latest_sequence_number() + 1
+        db["a"] = "secret"
+        [(_, batch)] = db.ship_updates_since(seq)
+        # replay the encrypted value of "a" at key "b"
+        db.apply_batch_bytes(batch[:12] + batch[12:].replace(b"\x02a", b"\x02b", 1))
+        self.assertEqual(db["a"], "secret")
+        self.assertRaises(CorruptionError, db.__getitem__, "b")
+        # encrypted values cannot be copied to another column family name
+        cf = db.create_column_family("other", Options())
+        cf.set_value_encryption(key)
+        self.assertRaises(ValueError, copy_range, db, cf)
+        self.assertRaises(ValueError, cf.ingest_from, db)
+        other = Rdict.temp()
+        other.set_value_encryption(key)
+        self.assertEqual(copy_range(db, other, "a", "a\x00"), 1)
+        self.assertEqual(other["a"], "secret")
+        other.close()
+        cf.close()
+        db.close()
 
-        self.assertEqual(
-            [c for c in self.test_dict.columns()],
-            [
-                [("", "Beijing")],
-                [("city", "Shenzhen"), ("language", "Cantonese"), ("population", 1.27)],
-                [("city", "Chengdu"), ("language", "Sichuanhua")],
-            ]
-        )
+    def test_raw_mode(self):
+        opt = Options(raw_mode=True)
+        db = Rdict(self.path + "_raw", opt)
+        self.assertRaises(ValueError, db.set_value_encryption, os.urandom(32))
+        db.close()
 
     @classmethod
     def tearDownClass(cls):
-        assert cls.test_dict is not None
-        cls.test_dict.close()
-        assert cls.opt is not None
         gc.collect()
-        Rdict.destroy(cls.path, cls.opt)
-
+        Rdict.destroy(cls.path)
+        Rdict.destroy(cls.path + "_bound")
+        Rdict.destroy(cls.path + "_raw", Options(raw_mode=True))
+
+
+class TestValueCompression(unittest.TestCase):
+    path = "./temp_value_compression"
+
+    def test_value_compression(self):
+        db = Rdict(self.path)
+        compressed = db.create_column_family("compressed", Options())
+        plain = db.create_column_family("plain", Options())
+        value = {"text": "lorem ipsum " * 10000}
+        compressed.set_value_compression(threshold=1024)
+        compressed["big"] = value
+        compressed["small"] = {"a": 1}
+        compressed["str"] = "x" * 10000
+        plain["big"] = value
+        size = "rocksdb.cur-size-active-mem-table"
+        self.assertLess(compressed.property_int_value(size) * 2, plain.property_int_value(size))
+        self.assertEqual(compressed["big"], value)
+        self.assertEqual(compressed["small"], {"a": 1})
+        self.assertEqual(compressed["str"], "x" * 10000)
+        # compressed values are still readable after disabling compression
+        compressed.set_value_compression(None)
+        self.assertEqual(compressed["big"], value)
+        # together with encryption
+        compressed.set_value_compression()
+        compressed.set_value_encryption(os.urandom(32))
+        compressed["encrypted"] = value
+        self.assertEqual(compressed.get(["big", "encrypted"]), [value, value])
+        self.assertRaises(ValueError, compressed.set_value_compression, 1024, 100)
+        compressed.close()
+        plain.close()
+        db.close()
 
-class TestColumnFamiliesDefaultOpts(unittest.TestCase):
-    test_dict = None
-    path = "./column_families"
+    def test_raw_mode(self):
+        db = Rdict(self.path + "_raw", Options(raw_mode=True))
+        self.assertRaises(ValueError, db.set_value_compression)
+        db.close()
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.test_dict = Rdict(cls.path)
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+        Rdict.destroy(cls.path + "_raw", Options(raw_mode=True))
 
-    def test_column_families(self):
-        assert self.test_dict is not None
-        ds = self.test_dict.create_column_family(name="string")
-        di = self.test_dict.create_column_family(name="integer")
 
-        for i in range(1000):
-            di[i] = i * i
-            ds[str(i)] = str(i * i)
+class TestValueChecksums(unittest.TestCase):
+    path = "./temp_value_checksums"
 
-        self.test_dict["ok"] = True
+    @staticmethod
+    def serialized_put(key: bytes, value: bytes) -> bytes:
+        # a write batch with a single put of already encoded bytes
+        header = bytes(8) + (1).to_bytes(4, "little")
+        return header + b"\x01" + bytes([len(key)]) + key + bytes([len(value)]) + value
 
-        ds.close()
-        di.close()
-        self.test_dict.close()
+    def test_value_checksums(self):
+        opt = Options()
+        opt.set_value_checksums(True)
+        db = Rdict(self.path, opt)
+        for i in range(10):
+            db[i] = {"i": i}
+        # a str value without checksum, and one with a wrong checksum
+        db.apply_batch_bytes(self.serialized_put(b"\x02plain", b"\x02text"))
+        db.apply_batch_bytes(self.serialized_put(b"\x02bad", b"\x42text\x00\x00\x00\x00"))
+        self.assertEqual(db[3], {"i": 3})
+        self.assertEqual(db["plain"], "text")
+        self.assertRaises(CorruptionError, db.__getitem__, "bad")
+        self.assertEqual(db.verify_values(),
+                         {"checked": 12, "without_checksum": 1, "corrupted": ["bad"]})
+        self.assertEqual(db.verify_values(begin=5, end=8),
+                         {"checked": 3, "without_checksum": 0, "corrupted": []})
+        db.close()
 
-        # reopen
-        self.test_dict = Rdict(self.path)
-        ds = self.test_dict.get_column_family("string")
-        di = self.test_dict.get_column_family("integer")
-        assert self.test_dict["ok"]
-        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
-        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
-        ds.close()
-        di.close()
-        self.test_dict.close()
+        # the setting is recorded in the rocksdict config
+        db = Rdict(self.path)
+        del db["bad"]
+        db["new"] = "value"
+        self.assertEqual(db.verify_values(begin="a", end="z"),
+                         {"checked": 2, "without_checksum": 1, "corrupted": []})
+        db.close()
+
+    def test_bulk_writes(self):
+        opt = Options()
+        opt.set_value_checksums(True)
+        db = Rdict(self.path + "_bulk", opt)
+        # batches must append checksums like the database
+        self.assertRaises(ValueError, db.write, WriteBatch())
+        batch = WriteBatch(value_checksums=True)
+        batch["batch"] = "value"
+        batch.put("put", [1, 2])
+        db.write(batch)
+        sst_path = self.path + "_bulk.sst"
+        writer = SstFileWriter(opt)
+        writer.open(sst_path)
+        writer["sst"] = "value"
+        writer.write_sorted([("sst_sorted", 1)])
+        writer.finish()
+        db.ingest_external_file([sst_path])
+        self.assertEqual(db["put"], [1, 2])
+        self.assertEqual(db["sst_sorted"], 1)
+        self.assertEqual(db.verify_values(),
+                         {"checked": 4, "without_checksum": 0, "corrupted": []})
+        db.close()
 
     @classmethod
     def tearDownClass(cls):
         gc.collect()
         Rdict.destroy(cls.path)
+        Rdict.destroy(cls.path + "_bulk")
+        if os.path.exists(cls.path + "_bulk.sst"):
+            os.remove(cls.path + "_bulk.sst")
 
 
-class TestColumnFamiliesDefaultOptsCreate(unittest.TestCase):
-    cfs = None
-    test_dict = None
-    path = "./column_families_create"
+class TestStrictTypes(unittest.TestCase):
+    path = "./temp_strict_types"
+
+    def test_strict_types(self):
+        opt = Options()
+        opt.set_strict_types(True)
+        db = Rdict(self.path, opt)
+        db["str"] = "value"
+        db["int"] = 10**30
+        db["float"] = 1.5
+        db["bool"] = True
+        db["bytes"] = b"bytes"
+        self.assertRaises(TypeError, db.__setitem__, "list", [1, 2, 3])
+        self.assertNotIn("list", db)
+        cf = db.create_column_family("cf", opt)
+        self.assertRaises(TypeError, cf.put, "dict", {"a": 1})
+        cf.close()
+        batch = WriteBatch(strict_types=True)
+        self.assertRaises(TypeError, batch.put, "set", {1})
+        # values written before are still read
+        self.assertEqual(db["int"], 10**30)
+        db.close()
+        # not recorded in the database
+        db = Rdict(self.path)
+        db["list"] = [1, 2, 3]
+        self.assertEqual(db["list"], [1, 2, 3])
+        db.close()
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.cfs = {"string": Options(), "integer": Options()}
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestOrderedKeyCodec(unittest.TestCase):
+    path = "./temp_ordered_keys"
+    legacy_path = "./temp_ordered_keys_legacy"
+    migrated_path = "./temp_ordered_keys_migrated"
+
+    def test_key_order(self):
         opt = Options()
-        opt.create_missing_column_families(True)
-        cls.test_dict = Rdict(cls.path, options=opt, column_families=cls.cfs)
+        opt.set_key_codec("ordered")
+        db = Rdict(self.path, opt)
+        ints = [-(2**200), -(2**64), -256, -255, -1, 0, 1, 255, 256, 2**64, 2**200]
+        floats = [float("-inf"), -1e300, -1.5, -0.0, 0.0, 1e-300, 1.5, float("inf")]
+        keys = [b"a", "a", *ints, *floats, False, True]
+        for i, key in enumerate(reversed(keys)):
+            db[key] = i
+        self.assertEqual(list(db.keys()), keys)
+        self.assertEqual(list(db.keys(backwards=True)), keys[::-1])
+        self.assertEqual(db[2**200], len(keys) - 1 - keys.index(2**200))
+        self.assertEqual(list(db.keys(from_key=-1)), keys[keys.index(-1):])
+        db.close()
+        # the key codec is restored from the config
+        db = Rdict(self.path)
+        self.assertEqual(list(db.keys()), keys)
+        self.assertRaises(ValueError, Options(raw_mode=True).set_key_codec, "ordered")
+        self.assertRaises(ValueError, opt.set_key_codec, "unknown")
+        batch = WriteBatch()
+        self.assertRaises(Exception, db.write, batch)
+        batch = WriteBatch(key_codec="ordered")
+        batch[-(2**70)] = "batch"
+        db.write(batch)
+        self.assertEqual(next(db.keys()), -(2**70))
+        db.close()
+        # cannot be opened as a raw mode database
+        self.assertRaises(Exception, Rdict, self.path, Options(raw_mode=True))
+
+    def test_migrate(self):
+        db = Rdict(self.legacy_path)
+        for i in range(-300, 300):
+            db[i] = i
+        db[1.5] = "float"
+        db["str"] = [1, 2]
+        cf = db.create_column_family("cf")
+        cf[2**100] = "big"
+        cf.close()
+        db.close()
+        count = migrate_key_codec(self.legacy_path, self.migrated_path, batch_size=7)
+        self.assertEqual(count, 603)
+        self.assertRaises(Exception, migrate_key_codec, self.legacy_path, self.migrated_path)
+        db = Rdict(self.migrated_path)
+        self.assertEqual(list(db.keys())[1:601], list(range(-300, 300)))
+        self.assertEqual(db[1.5], "float")
+        self.assertEqual(db["str"], [1, 2])
+        self.assertEqual(db.get_column_family("cf")[2**100], "big")
+        db.close()
 
-    def test_column_families_create(self):
-        assert self.test_dict is not None
-        ds = self.test_dict.get_column_family(name="string")
-        di = self.test_dict.get_column_family(name="integer")
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+        Rdict.destroy(cls.legacy_path)
+        Rdict.destroy(cls.migrated_path)
 
-        for i in range(1000):
-            di[i] = i * i
-            ds[str(i)] = str(i * i)
 
-        self.test_dict["ok"] = True
+class TestOpenExternal(unittest.TestCase):
+    path = "./temp_open_external"
 
-        ds.close()
-        di.close()
-        self.test_dict.close()
+    def config_path(self):
+        return os.path.join(self.path, "rocksdict-config.json")
 
-        # reopen
-        self.test_dict = Rdict(self.path)
-        ds = self.test_dict.get_column_family("string")
-        di = self.test_dict.get_column_family("integer")
-        assert self.test_dict["ok"]
-        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
-        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
-        ds.close()
-        di.close()
-        self.test_dict.close()
+    def test_open_external(self):
+        db = Rdict(self.path, Options(raw_mode=True))
+        db[b"key"] = b"value"
+        db.close()
+        # databases created by other tools have no rocksdict config
+        os.remove(self.config_path())
+        db = Rdict.open_external(self.path)
+        self.assertEqual(list(db.items()), [(b"key", b"value")])
+        self.assertRaises(Exception, db.put, b"other", b"value")
+        db.close()
+        db = Rdict.open_external(self.path, access_type=AccessType.read_write())
+        db[b"other"] = b"value"
+        cf = db.create_column_family("cf", Options(raw_mode=True))
+        cf[b"cf_key"] = b"cf_value"
+        cf.close()
+        db.close()
+        self.assertFalse(os.path.exists(self.config_path()))
+        db = Rdict.open_external(self.path)
+        self.assertEqual(db.get_column_family("cf")[b"cf_key"], b"cf_value")
+        db.close()
+        self.assertRaises(Exception, Rdict.open_external, self.path + "_missing")
+        self.assertFalse(os.path.exists(self.path + "_missing"))
 
     @classmethod
     def tearDownClass(cls):
@@ -854,45 +3811,139 @@ class TestColumnFamiliesDefaultOptsCreate(unittest.TestCase):
         Rdict.destroy(cls.path)
 
 
-class TestColumnFamiliesCustomOpts(unittest.TestCase):
-    cfs = None
-    test_dict = None
-    path = "./column_families_custom_options"
+class TestColumnFamilyKeyCodecs(unittest.TestCase):
+    path = "./temp_cf_key_codecs"
+
+    def test_key_codecs(self):
+        db = Rdict(self.path)
+        raw_opt = Options()
+        raw_opt.set_key_codec("raw")
+        raw = db.create_column_family("raw", raw_opt)
+        raw[b"key"] = b"value"
+        self.assertRaises(Exception, raw.put, "key", b"value")
+        u64_opt = Options()
+        u64_opt.set_key_codec("u64_be")
+        u64 = db.create_column_family("u64", u64_opt)
+        for i in [3, 1, 2**64 - 1, 256, 2]:
+            u64[i] = str(i)
+        self.assertRaises(ValueError, u64.put, -1, "negative")
+        self.assertRaises(TypeError, u64.put, "1", "str")
+        pair_opt = Options()
+        pair_opt.set_key_codec("composite")
+        pairs = db.create_column_family("pairs", pair_opt)
+        keys = [(), (b"a\x00",), ("a",), ("a", -5), ("a", 2), ("a", 10), ("a\x00", 1), ("b", 1.5, True)]
+        for key in reversed(keys):
+            pairs[key] = 1
+        self.assertRaises(TypeError, pairs.put, "a", 1)
+        db[1] = [1]
+        for cf in [raw, u64, pairs]:
+            cf.close()
+        db.close()
+        with open(os.path.join(self.path, "rocksdict-config.json")) as f:
+            config = loads(f.read())
+        self.assertEqual(
+            config["column_family_key_codecs"],
+            {"raw": "raw", "u64": "u64_be", "pairs": "composite"},
+        )
+        # the key codecs of column families are restored from the config
+        db = Rdict(self.path)
+        self.assertEqual(db[1], [1])
+        self.assertEqual(list(db.get_column_family("raw").items()), [(b"key", b"value")])
+        self.assertEqual(list(db.get_column_family("u64").keys()), [1, 2, 3, 256, 2**64 - 1])
+        self.assertEqual(list(db.get_column_family("pairs").keys()), keys)
+        db.drop_column_family("pairs")
+        db.close()
+        with open(os.path.join(self.path, "rocksdict-config.json")) as f:
+            config = loads(f.read())
+        self.assertEqual(config["column_family_key_codecs"], {"raw": "raw", "u64": "u64_be"})
 
     @classmethod
-    def setUpClass(cls) -> None:
-        plain_opts = Options()
-        plain_opts.create_missing_column_families(True)
-        plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
-        plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
-        cls.cfs = {"string": Options(), "integer": plain_opts}
-        cls.test_dict = Rdict(cls.path, options=plain_opts, column_families=cls.cfs)
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
 
-    def test_column_families_custom_options_auto_reopen(self):
-        assert self.test_dict is not None
-        ds = self.test_dict.get_column_family(name="string")
-        di = self.test_dict.get_column_family(name="integer")
 
-        for i in range(1000):
-            di[i] = i * i
-            ds[str(i)] = str(i * i)
+class TestVersionedRdict(unittest.TestCase):
+    path = "./temp_versioned"
+
+    def test_versions(self):
+        db = VersionedRdict(self.path, max_versions=3)
+        for ts in range(1, 6):
+            db.put("key", ts * 10, ts=ts)
+        db.put(b"key", b"bytes", ts=1)
+        db.put("key\x00", "longer", ts=1)
+        self.assertEqual(db["key"], 50)
+        self.assertEqual(db.get("key", as_of=4), 40)
+        # versions past max_versions are not read
+        self.assertEqual(db.get("key", as_of=2), None)
+        self.assertEqual([(v.timestamp, v.value) for v in db.history("key")],
+                         [(5, 50), (4, 40), (3, 30)])
+        self.assertEqual(db[b"key"], b"bytes")
+        self.assertEqual(db["key\x00"], "longer")
+        db.delete("key", ts=6)
+        self.assertNotIn("key", db)
+        self.assertRaises(KeyError, db.__getitem__, "key")
+        self.assertEqual(db.get("key", as_of=5), 50)
+        self.assertEqual(db.history("key")[0], (6, None, True))
+        self.assertRaises(KeyError, db.__getitem__, "missing")
+        self.assertRaises(TypeError, db.put, 1, 1)
+        db.compact()
+        db.close()
 
-        self.test_dict["ok"] = True
+        # older versions are dropped by compaction
+        raw = Rdict(self.path, Options(raw_mode=True))
+        self.assertEqual(len([k for k in raw.keys() if k.startswith(b"skey\x00\x00")]), 3)
+        raw.close()
+        self.assertRaises(ValueError, Options().set_version_history, 3)
+        self.assertRaises(ValueError, Options(raw_mode=True).set_version_history, 0)
 
-        ds.close()
-        di.close()
-        self.test_dict.close()
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path, Options(raw_mode=True))
+
+
+class TestFreeze(unittest.TestCase):
+    path = "./temp_freeze"
+
+    def test_freeze(self):
+        db = Rdict(self.path)
+        db["a"] = 1
+        cf = db.create_column_family("cf")
+        cf["b"] = 2
+        writer = db.buffered_writer()
+        db.freeze()
+        self.assertTrue(db.is_frozen())
+        self.assertEqual(db.property_int_value("rocksdb.num-entries-active-mem-table"), 0)
+        # writes through all handles are rejected, reads are not
+        self.assertRaises(DbFrozenError, db.__setitem__, "a", 2)
+        self.assertRaises(DbFrozenError, cf.__delitem__, "b")
+        wb = WriteBatch()
+        wb.put("c", 3)
+        self.assertRaises(DbFrozenError, db.write, wb)
+        self.assertRaises(DbFrozenError, db.delete_range, None, None)
+        self.assertRaises(DbFrozenError, db.create_column_family, "other")
+        writer["d"] = 4
+        self.assertRaises(DbFrozenError, writer.flush)
+        self.assertEqual(db["a"], 1)
+        self.assertEqual(cf["b"], 2)
+        self.assertRaises(DbFrozenError, writer.close)
+        # the flag is kept across reopen
+        del cf
+        gc.collect()
+        db.reopen()
+        self.assertTrue(db.is_frozen())
+        self.assertRaises(DbFrozenError, db.put, "a", 2)
+        db.unfreeze()
+        db["a"] = 2
+        self.assertEqual(db["a"], 2)
+        db.close()
 
-        # reopen
-        self.test_dict = Rdict(self.path)
-        ds = self.test_dict.get_column_family("string")
-        di = self.test_dict.get_column_family("integer")
-        assert self.test_dict["ok"]
-        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
-        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
-        ds.close()
-        di.close()
-        self.test_dict.close()
+    def test_freeze_read_only(self):
+        Rdict(self.path).close()
+        db = Rdict(self.path, access_type=AccessType.read_only())
+        self.assertRaises(Exception, db.freeze)
+        db.close()
 
     @classmethod
     def tearDownClass(cls):
@@ -900,48 +3951,89 @@ class TestColumnFamiliesCustomOpts(unittest.TestCase):
         Rdict.destroy(cls.path)
 
 
-class TestColumnFamiliesCustomOptionsCreate(unittest.TestCase):
-    cfs = None
-    test_dict = None
-    plain_opts = None
-    path = "./column_families_custom_options_create"
+class TestFlushAll(unittest.TestCase):
+    path = "./temp_flush_all"
+
+    def test_flush_all(self):
+        db = Rdict(self.path)
+        cf = db.create_column_family("other")
+        db["a"] = 1
+        cf["b"] = 2
+        self.assertEqual(sorted(cf.flush_all()), ["default", "other"])
+        for handle in (db, cf):
+            self.assertEqual(
+                handle.property_int_value("rocksdb.num-entries-active-mem-table"), 0)
+            self.assertEqual(
+                handle.property_int_value("rocksdb.num-files-at-level0"), 1)
+        del cf
+        db.close()
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.plain_opts = Options()
-        cls.plain_opts.create_missing_column_families(True)
-        cls.plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
-        cls.plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
-        cls.test_dict = Rdict(cls.path, options=cls.plain_opts, column_families=cls.cfs)
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
 
-    def test_column_families_custom_options_auto_reopen(self):
-        assert self.test_dict is not None
-        ds = self.test_dict.create_column_family(name="string")
-        assert self.plain_opts is not None
-        di = self.test_dict.create_column_family(
-            name="integer", options=self.plain_opts
-        )
 
-        for i in range(1000):
-            di[i] = i * i
-            ds[str(i)] = str(i * i)
+class TestCompactAll(unittest.TestCase):
+    path = "./temp_compact_all"
+
+    def test_compact_all(self):
+        db = Rdict(self.path)
+        cfs = [db.create_column_family(f"cf{i}") for i in range(3)]
+        for cf in [db] + cfs:
+            for i in range(100):
+                cf[i] = i
+            cf.flush()
+            for i in range(100):
+                cf[i] = -i
+            cf.flush()
+        self.assertEqual(sorted(cfs[0].compact_all(parallel=2)),
+                         ["cf0", "cf1", "cf2", "default"])
+        for cf in [db] + cfs:
+            self.assertEqual(cf.property_int_value("rocksdb.num-files-at-level0"), 0)
+            self.assertEqual(cf[7], -7)
+        self.assertRaises(ValueError, db.compact_all, parallel=0)
+        db.compact_all(wait=False)
+        for _ in range(100):
+            if "compaction" not in db.open_handles():
+                break
+            time.sleep(0.1)
+        self.assertNotIn("compaction", db.open_handles())
+        del cfs
+        db.close()
 
-        self.test_dict["ok"] = True
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
 
-        ds.close()
-        di.close()
-        self.test_dict.close()
 
-        # reopen
-        self.test_dict = Rdict(self.path)
-        ds = self.test_dict.get_column_family("string")
-        di = self.test_dict.get_column_family("integer")
-        assert self.test_dict["ok"]
-        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
-        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
-        ds.close()
-        di.close()
-        self.test_dict.close()
+class TestAutoFlush(unittest.TestCase):
+    path = "./temp_auto_flush"
+
+    def test_auto_flush(self):
+        db = Rdict(self.path)
+        write_opt = WriteOptions()
+        write_opt.disable_wal = True
+        db.set_write_options(write_opt)
+        for i in range(100):
+            db[i] = i
+        self.assertEqual(db.property_int_value("rocksdb.num-entries-active-mem-table"), 100)
+        db.set_auto_flush(0.05)
+        time.sleep(0.5)
+        self.assertEqual(db.property_int_value("rocksdb.num-entries-active-mem-table"), 0)
+        self.assertRaises(ValueError, db.set_auto_flush, 0)
+        self.assertRaises(ValueError, db.set_auto_flush, -1.0)
+        # the timer is kept across reopen
+        db.reopen()
+        db[100] = 100
+        time.sleep(0.5)
+        self.assertEqual(db.property_int_value("rocksdb.num-entries-active-mem-table"), 0)
+        db.set_auto_flush(None)
+        db.close()
+        db = Rdict(self.path)
+        self.assertEqual(db[100], 100)
+        db.close()
 
     @classmethod
     def tearDownClass(cls):
@@ -949,77 +4041,211 @@ class TestColumnFamiliesCustomOptionsCreate(unittest.TestCase):
         Rdict.destroy(cls.path)
 
 
-class TestColumnFamiliesCustomOptionsCreateReopenOverride(unittest.TestCase):
-    cfs = None
-    test_dict = None
-    plain_opts = None
-    path = "./column_families_custom_options_create_reopen_override"
+class TestTemp(unittest.TestCase):
+    def test_temp(self):
+        test_dict = Rdict.temp(prefix="rocksdict-test-")
+        path = test_dict.path()
+        self.assertTrue(os.path.basename(path).startswith("rocksdict-test-"))
+        cf = test_dict.create_column_family("cf1", Options())
+        test_dict["key"] = "value"
+        cf["key"] = "cf_value"
+        test_dict.close()
+        # still referenced by the column family
+        self.assertTrue(os.path.exists(path))
+        self.assertEqual(cf["key"], "cf_value")
+        cf.close()
+        del test_dict, cf
+        gc.collect()
+        self.assertFalse(os.path.exists(path))
+
+    def test_temp_no_cleanup(self):
+        test_dict = Rdict.temp(cleanup=False, options=Options(raw_mode=True))
+        path = test_dict.path()
+        test_dict[b"key"] = b"value"
+        test_dict.close()
+        del test_dict
+        gc.collect()
+        test_dict = Rdict(path)
+        self.assertEqual(test_dict[b"key"], b"value")
+        test_dict.close()
+        del test_dict
+        gc.collect()
+        Rdict.destroy(path)
+        shutil.rmtree(path, ignore_errors=True)
+
+
+class TestFromUri(unittest.TestCase):
+    path = "./temp_from_uri"
+
+    def test_from_uri(self):
+        db = Rdict.from_uri(f"rocksdict://{self.path}?raw=true&column_families=cf1")
+        db[b"key"] = b"value"
+        db.get_column_family("cf1")[b"key"] = b"cf_value"
+        db.close()
+        del db
+        gc.collect()
+        os.environ["ROCKSDICT_URI"] = f"rocksdict://{self.path}?read_only=1"
+        try:
+            db = Rdict.from_uri()
+        finally:
+            del os.environ["ROCKSDICT_URI"]
+        self.assertEqual(db[b"key"], b"value")
+        self.assertEqual(db.get_column_family("cf1")[b"key"], b"cf_value")
+        self.assertRaises(Exception, lambda: db.put(b"key", b"other"))
+        db.close()
+
+    def test_invalid_uri(self):
+        self.assertRaises(ValueError, lambda: Rdict.from_uri("file:///tmp/db"))
+        self.assertRaises(
+            ValueError, lambda: Rdict.from_uri(f"rocksdict://{self.path}?unknown=1")
+        )
+        self.assertRaises(
+            ValueError,
+            lambda: Rdict.from_uri(f"rocksdict://{self.path}?read_only=true&ttl=10"),
+        )
 
     @classmethod
-    def setUpClass(cls) -> None:
-        cls.plain_opts = Options()
-        cls.plain_opts.create_missing_column_families(True)
-        cls.plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
-        cls.plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
-        cls.test_dict = Rdict(cls.path, options=cls.plain_opts)
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
 
-    def test_column_families_custom_options_auto_reopen_override(self):
-        assert self.test_dict is not None
-        ds = self.test_dict.create_column_family(name="string")
-        assert self.plain_opts is not None
-        di = self.test_dict.create_column_family(
-            name="integer", options=self.plain_opts
+
+class TestDestroy(unittest.TestCase):
+    path = "./temp_destroy"
+
+    def test_destroy(self):
+        test_dict = Rdict(self.path)
+        cf = test_dict.create_column_family("cf1", Options())
+        for i in range(100):
+            test_dict[i] = i
+            cf[i] = i
+        cf.flush()
+        test_dict.flush()
+        # refuses to run while the database is open
+        self.assertRaises(Exception, lambda: Rdict.destroy(self.path, dry_run=True))
+        cf.close()
+        test_dict.close()
+        gc.collect()
+
+        files = Rdict.destroy(self.path, dry_run=True)
+        self.assertTrue(any(f.endswith("CURRENT") for f in files))
+        self.assertTrue(all(os.path.exists(f) for f in files))
+        cf_files = Rdict.destroy(self.path, dry_run=True, column_family="cf1")
+        self.assertEqual(len(cf_files), 1)
+        self.assertTrue(cf_files[0].endswith(".sst"))
+
+        self.assertIsNone(Rdict.destroy(self.path, column_family="cf1"))
+        self.assertNotIn("cf1", Rdict.list_cf(self.path))
+        self.assertRaises(
+            Exception,
+            lambda: Rdict.destroy(self.path, column_family="default"),
         )
+        test_dict = Rdict(self.path)
+        self.assertEqual(test_dict[99], 99)
+        test_dict.close()
+        gc.collect()
 
-        for i in range(1000):
-            di[i] = i * i
-            ds[str(i)] = str(i * i)
+        self.assertIsNone(Rdict.destroy(self.path))
+        self.assertFalse(os.path.exists(os.path.join(self.path, "CURRENT")))
 
-        self.test_dict["ok"] = True
 
-        ds.close()
-        di.close()
-        self.test_dict.close()
+class TestExceptions(unittest.TestCase):
+    path = "./temp_exceptions"
 
-        # reopen
+    def test_rocksdb_error(self):
+        opt = Options()
+        opt.create_if_missing(False)
+        with self.assertRaises(RocksDBError):
+            Rdict("./temp_exceptions_does_not_exist", opt)
+        test_dict = Rdict(self.path)
+        test_dict.close()
         gc.collect()
-        old_opts, old_cols = Options.load_latest(self.path)
-        old_opts.create_missing_column_families(True)
-        old_cols["bytes"] = self.plain_opts
-        self.test_dict = Rdict(self.path, options=old_opts, column_families=old_cols)
-        ds = self.test_dict.get_column_family("string")
-        di = self.test_dict.get_column_family("integer")
-        db = self.test_dict.get_column_family("bytes")
-        db[b"great"] = b"hello world"
-        assert self.test_dict["ok"]
-        assert db[b"great"] == b"hello world"
-        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
-        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
-        ds.close()
-        di.close()
+        # opening twice fails on LOCK
+        test_dict = Rdict(self.path)
+        with self.assertRaises(RocksDBError):
+            Rdict(self.path)
+        test_dict.close()
+
+    def test_retry_policy(self):
+        self.assertRaises(ValueError, lambda: RetryPolicy(max_attempts=0))
+        self.assertRaises(ValueError, lambda: RetryPolicy(backoff_multiplier=0.5))
+        policy = RetryPolicy(max_attempts=5, initial_backoff=0.001)
+        self.assertEqual(policy.max_attempts, 5)
+        test_dict = Rdict(self.path, retry_policy=policy)
+        test_dict[1] = 1
+        wb = WriteBatch()
+        wb.put(2, 2)
+        test_dict.write(wb)
+        test_dict.delete_range(1, 2)
+        self.assertEqual({k: v for k, v in test_dict.items()}, {2: 2})
+        cf = test_dict.create_column_family("cf")
+        cf[3] = 3
+        self.assertEqual(cf[3], 3)
+        cf.close()
+        test_dict.close()
+
+    def test_retry_write_stall(self):
+        # a write buffer manager shared with another database, whose
+        # memtable holds the memory, stalls writes until it is flushed
+        opt = Options()
+        opt.set_write_buffer_manager(WriteBufferManager.new_write_buffer_manager(1024 * 1024, True))
+        holder = Rdict(self.path + "_holder", opt)
+        holder["big"] = b"x" * (2 * 1024 * 1024)
+        w_opt = WriteOptions()
+        w_opt.no_slowdown = True
+        # without a retry policy, the stall is raised at once
+        db = Rdict(self.path + "_stalled", opt)
+        db.set_write_options(w_opt)
+        self.assertRaisesRegex(RocksDBError, "Write stall", db.put, 0, 0)
+        db.close()
+        # attempts stop after max_attempts while the stall is held
+        policy = RetryPolicy(max_attempts=3, initial_backoff=0.1, max_backoff=1.0)
+        db = Rdict(self.path + "_stalled", opt, retry_policy=policy)
+        db.set_write_options(w_opt)
+        start = time.time()
+        self.assertRaisesRegex(RocksDBError, "Write stall", db.put, 0, 0)
+        # two backoffs of 0.1 and 0.2 seconds between the three attempts
+        self.assertGreaterEqual(time.time() - start, 0.3)
+        self.assertLess(time.time() - start, 1.3)
+        self.assertNotIn(0, db)
+        db.close()
+        # the write succeeds once the stall is released
+        policy = RetryPolicy(max_attempts=100, initial_backoff=0.01, max_backoff=0.05)
+        db = Rdict(self.path + "_stalled", opt, retry_policy=policy)
+        db.set_write_options(w_opt)
+        flusher = Thread(target=lambda: (time.sleep(0.3), holder.flush()))
+        start = time.time()
+        flusher.start()
+        db[0] = 0
+        self.assertGreaterEqual(time.time() - start, 0.3)
+        flusher.join()
+        self.assertEqual(db[0], 0)
+        db.close()
+        holder.close()
+        for suffix in ("_holder", "_stalled"):
+            Rdict.destroy(self.path + suffix)
+
+    def test_typed_errors(self):
+        path = self.path + "_typed"
+        db = Rdict(path)
+        db["key"] = "value"
+        # the database is still open
+        self.assertRaises(BusyError, lambda: Rdict(path, skip_wal_recovery=True))
+        self.assertRaises(CorruptionError, db.apply_batch_bytes, b"invalid")
+        self.assertRaises(ValueError, db.reopen, Options(raw_mode=True))
         db.close()
-        self.test_dict.close()
-
-        # reopen again auto read config
         gc.collect()
-        self.test_dict = Rdict(self.path)
-        ds = self.test_dict.get_column_family("string")
-        di = self.test_dict.get_column_family("integer")
-        db = self.test_dict.get_column_family("bytes")
-        db[b"great"] = b"hello world"
-        assert self.test_dict["ok"]
-        assert db[b"great"] == b"hello world"
-        compare_dicts(self, {i: i**2 for i in range(1000)}, di)
-        compare_dicts(self, {str(i): str(i**2) for i in range(1000)}, ds)
-        ds.close()
-        di.close()
+        db = Rdict(path, access_type=AccessType.read_only())
+        self.assertRaises(NotSupportedError, db.freeze)
         db.close()
-        self.test_dict.close()
+        gc.collect()
+        Rdict.destroy(path)
 
     @classmethod
     def tearDownClass(cls):
         gc.collect()
         Rdict.destroy(cls.path)
+        Rdict.destroy("./temp_exceptions_does_not_exist")
 
 
 class TestIntWithSecondary(unittest.TestCase):
@@ -1119,6 +4345,11 @@ class TestIntWithSecondary(unittest.TestCase):
 class TestCheckpoint(unittest.TestCase):
     test_dict = None
     checkpoint_path = "./temp_checkpoint"
+    clone_path = "./temp_checkpoint_clone"
+    catalog_path = "./temp_checkpoint_catalog"
+    checksum_path = "./temp_checkpoint_checksum"
+    archive_path = "./temp_checkpoint_archive.tar"
+    imported_path = "./temp_checkpoint_imported"
     path = "./temp_checkpoint_db"
     opt = None
 
@@ -1149,6 +4380,129 @@ class TestCheckpoint(unittest.TestCase):
 
         checkpoint_dict.close()
 
+    def test_checkpoint_catalog(self):
+        assert self.test_dict is not None
+        self.test_dict["catalog"] = 1
+        checkpoint = Checkpoint(self.test_dict)
+        checkpoint.create_checkpoint(os.path.join(self.catalog_path, "2"))
+        checkpoint.create_checkpoint(os.path.join(self.catalog_path, "1"))
+        del checkpoint
+        os.makedirs(os.path.join(self.catalog_path, "not_a_checkpoint"))
+
+        checkpoints = Checkpoint.list(self.catalog_path)
+        self.assertEqual(
+            checkpoints,
+            [os.path.join(self.catalog_path, "1"), os.path.join(self.catalog_path, "2")],
+        )
+        self.assertRaises(
+            Exception,
+            lambda: Rdict.open_checkpoint(os.path.join(self.catalog_path, "not_a_checkpoint")),
+        )
+        checkpoint_dict = Rdict.open_checkpoint(checkpoints[-1])
+        self.assertEqual(checkpoint_dict["catalog"], 1)
+        self.assertRaises(Exception, lambda: checkpoint_dict.put("catalog", 2))
+        checkpoint_dict.close()
+        del checkpoint_dict
+        gc.collect()
+        for path in checkpoints:
+            Rdict.destroy(path)
+
+    def test_unicode_checkpoint_path(self):
+        assert self.test_dict is not None
+        self.test_dict["unicode"] = "ünïcode"
+        root = Path(self.catalog_path + "_ünïcode_数据")
+        checkpoint = Checkpoint(self.test_dict)
+        checkpoint.create_checkpoint(root / "检查点")
+        del checkpoint
+
+        checkpoints = Checkpoint.list(root)
+        self.assertEqual(checkpoints, [str(root / "检查点")])
+        checkpoint_dict = Rdict.open_checkpoint(checkpoints[0])
+        self.assertEqual(checkpoint_dict["unicode"], "ünïcode")
+        self.assertEqual(checkpoint_dict.path(), checkpoints[0])
+        checkpoint_dict.close()
+        del checkpoint_dict
+        gc.collect()
+        Rdict.destroy(checkpoints[0])
+        shutil.rmtree(root, ignore_errors=True)
+
+    def test_file_checksums(self):
+        assert self.test_dict is not None
+        self.test_dict["checksum"] = 1
+        self.test_dict.flush()
+        checksums = self.test_dict.get_live_files_checksums()
+        self.assertGreater(len(checksums), 0)
+        self.assertTrue(all(name.endswith(".sst") for name in checksums))
+        self.test_dict.clone_to(self.checksum_path)
+        Rdict.verify_file_checksums(self.checksum_path, checksums)
+
+        name = sorted(checksums)[0]
+        with open(os.path.join(self.checksum_path, name), "r+b") as f:
+            f.seek(0)
+            byte = f.read(1)
+            f.seek(0)
+            f.write(bytes([byte[0] ^ 0xFF]))
+        with self.assertRaises(CorruptionError):
+            Rdict.verify_file_checksums(self.checksum_path, checksums)
+        os.remove(os.path.join(self.checksum_path, name))
+        self.assertRaises(
+            CorruptionError,
+            lambda: Rdict.verify_file_checksums(self.checksum_path, checksums),
+        )
+        shutil.rmtree(self.checksum_path)
+
+    def test_file_checksum_gen_factory(self):
+        opt = Options()
+        self.assertRaises(ValueError, opt.set_file_checksum_gen_factory, "md5")
+        opt.set_file_checksum_gen_factory("crc32c")
+        path = self.checksum_path + "_factory"
+        db = Rdict(path, opt)
+        db["checksum"] = 1
+        db.flush()
+        checksums = db.get_live_files_checksums()
+        db.close()
+        Rdict.verify_file_checksums(path, checksums)
+        opt.set_file_checksum_gen_factory(None)
+        db = Rdict(path, opt)
+        self.assertEqual(db["checksum"], 1)
+        db.close()
+        Rdict.destroy(path)
+
+    def test_clone_to(self):
+        assert self.test_dict is not None
+        self.test_dict["clone"] = "source"
+        opt = Options()
+        opt.set_write_buffer_size(1024 * 1024)
+        self.test_dict.clone_to(self.clone_path, opt)
+        self.assertRaises(
+            Exception,
+            lambda: self.test_dict.clone_to(self.clone_path + "_raw", Options(raw_mode=True)),
+        )
+
+        # the clone is independent of the source
+        self.test_dict["clone"] = "changed"
+        clone_dict = Rdict(self.clone_path)
+        self.assertEqual(clone_dict["clone"], "source")
+        clone_dict.close()
+
+    def test_archive(self):
+        assert self.test_dict is not None
+        self.test_dict["archive"] = "source"
+        for archive in [self.archive_path, self.archive_path + ".zst"]:
+            self.test_dict.export_archive(archive)
+            self.assertRaises(Exception, lambda: self.test_dict.export_archive(archive))
+            Rdict.import_archive(archive, self.imported_path)
+            self.assertRaises(
+                Exception,
+                lambda: Rdict.import_archive(archive, self.imported_path),
+            )
+            imported_dict = Rdict(self.imported_path)
+            self.assertEqual(imported_dict["archive"], "source")
+            imported_dict.close()
+            gc.collect()
+            Rdict.destroy(self.imported_path)
+            os.remove(archive)
+
     @classmethod
     def tearDownClass(cls):
         assert cls.test_dict is not None
@@ -1157,6 +4511,8 @@ class TestCheckpoint(unittest.TestCase):
         gc.collect()
         Rdict.destroy(cls.path, cls.opt)
         Rdict.destroy(cls.checkpoint_path, cls.opt)
+        Rdict.destroy(cls.clone_path, cls.opt)
+        shutil.rmtree(cls.catalog_path, ignore_errors=True)
 
 
 class TestCheckpointRaw(unittest.TestCase):